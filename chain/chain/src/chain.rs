@@ -275,6 +275,10 @@ pub struct Chain {
     /// impossible to have non-empty state patch on non-sandbox builds.
     pending_state_patch: SandboxStatePatch,
 
+    /// Chain heads the sandbox can be reverted to.
+    #[cfg(feature = "sandbox")]
+    sandbox_snapshots: crate::sandbox::SandboxSnapshots,
+
     /// Used to store state parts already requested along with elapsed time
     /// to create the parts. This information is used for debugging
     pub(crate) requested_state_parts: StateRequestTracker,
@@ -385,6 +389,8 @@ impl Chain {
             last_time_head_updated: clock.now(),
             invalid_blocks: LruCache::new(NonZeroUsize::new(INVALID_CHUNKS_POOL_SIZE).unwrap()),
             pending_state_patch: Default::default(),
+            #[cfg(feature = "sandbox")]
+            sandbox_snapshots: Default::default(),
             requested_state_parts: StateRequestTracker::new(),
             snapshot_callbacks: None,
             resharding_config: MutableConfigValue::new(
@@ -598,6 +604,8 @@ impl Chain {
             apply_chunks_spawner,
             last_time_head_updated: clock.now(),
             pending_state_patch: Default::default(),
+            #[cfg(feature = "sandbox")]
+            sandbox_snapshots: Default::default(),
             requested_state_parts: StateRequestTracker::new(),
             snapshot_callbacks,
             resharding_config: chain_config.resharding_config,
@@ -2056,10 +2064,28 @@ impl Chain {
         let epoch_id = block.header().epoch_id();
         let shard_uid = self.epoch_manager.shard_id_to_uid(shard_id, epoch_id)?;
 
+        // The state of the oldest sandbox snapshot must stay available until
+        // the snapshot is dropped, so flat storage and memtrie are not advanced
+        // past its final block.
+        #[cfg(feature = "sandbox")]
+        let pinned_snapshot = self
+            .sandbox_snapshots
+            .oldest_live(self.sandbox_min_snapshot_final_height(block.hash()));
+
         // Update flat storage.
         let flat_storage_manager = self.runtime_adapter.get_flat_storage_manager();
         if flat_storage_manager.get_flat_storage_for_shard(shard_uid).is_some() {
             if let Some(new_flat_head) = self.get_new_flat_storage_head(block, shard_id)? {
+                #[cfg(feature = "sandbox")]
+                let new_flat_head = match pinned_snapshot {
+                    Some(snapshot)
+                        if self.get_block_header(&new_flat_head)?.height()
+                            > snapshot.final_head.height =>
+                    {
+                        snapshot.final_head.last_block_hash
+                    }
+                    _ => new_flat_head,
+                };
                 flat_storage_manager.update_flat_storage_for_shard(shard_uid, new_flat_head)?;
             }
         }
@@ -2070,6 +2096,9 @@ impl Chain {
         if last_final_block != &CryptoHash::default() {
             let header = self.chain_store.get_block_header(last_final_block).unwrap();
             if let Some(prev_height) = header.prev_height() {
+                #[cfg(feature = "sandbox")]
+                let prev_height = pinned_snapshot
+                    .map_or(prev_height, |snapshot| prev_height.min(snapshot.final_prev_height));
                 tries.delete_memtrie_roots_up_to_height(shard_uid, prev_height);
             }
         }
@@ -3254,8 +3283,7 @@ impl Chain {
     }
 
     /// Returns execution status based on the list of currently existing outcomes
-    fn get_execution_status(
-        &self,
+    pub(crate) fn get_execution_status(
         outcomes: &[ExecutionOutcomeWithIdView],
         transaction_hash: &CryptoHash,
     ) -> FinalExecutionStatus {
@@ -3320,7 +3348,7 @@ impl Chain {
     ) -> Result<FinalExecutionOutcomeView, Error> {
         let mut outcomes = Vec::new();
         self.get_recursive_transaction_results(&mut outcomes, transaction_hash, true)?;
-        let status = self.get_execution_status(&outcomes, transaction_hash);
+        let status = Self::get_execution_status(&outcomes, transaction_hash);
         let receipts_outcome = outcomes.split_off(1);
         let transaction = self.chain_store.get_transaction(transaction_hash)?.ok_or_else(|| {
             Error::DBNotFoundErr(format!("Transaction {} is not found", transaction_hash))
@@ -3352,7 +3380,7 @@ impl Chain {
             )));
         }
 
-        let status = self.get_execution_status(&outcomes, transaction_hash);
+        let status = Self::get_execution_status(&outcomes, transaction_hash);
         let receipts_outcome = outcomes.split_off(1);
         let transaction_outcome = outcomes.pop().unwrap();
         Ok(FinalExecutionOutcomeView { status, transaction, transaction_outcome, receipts_outcome })
@@ -4617,6 +4645,105 @@ impl Chain {
     pub fn patch_state_in_progress(&self) -> bool {
         !self.pending_state_patch.is_empty()
     }
+
+    /// Lowest final block height of the sandbox snapshots which are kept.
+    ///
+    /// Snapshots whose final block is less than an epoch above the GC stop
+    /// height expire, so that the flat head they hold back is released before
+    /// garbage collection removes the flat storage deltas following it.
+    #[cfg(feature = "sandbox")]
+    fn sandbox_min_snapshot_final_height(&self, block_hash: &CryptoHash) -> BlockHeight {
+        self.runtime_adapter.get_gc_stop_height(block_hash) + self.epoch_length
+    }
+
+    #[cfg(feature = "sandbox")]
+    fn sandbox_drop_expired_snapshots(&mut self) -> Result<(), Error> {
+        let head = self.head()?;
+        let min_final_height = self.sandbox_min_snapshot_final_height(&head.last_block_hash);
+        self.sandbox_snapshots.drop_expired(min_final_height);
+        Ok(())
+    }
+
+    /// Remembers the current chain head so that the chain can be reverted to
+    /// it with `sandbox_revert`, together with the block time shift of the
+    /// client. Returns the id of the snapshot and the head.
+    ///
+    /// Until the snapshot is dropped, flat storage heads and memtrie roots
+    /// are not moved past its final block, so that its state stays available.
+    /// Snapshots expire about an epoch before garbage collection reaches
+    /// their final block, and at most 100 snapshots are kept.
+    #[cfg(feature = "sandbox")]
+    pub fn sandbox_snapshot(
+        &mut self,
+        time_offset: Duration,
+        accrued_fastforward_delta: BlockHeightDelta,
+    ) -> Result<(u64, Tip), Error> {
+        self.sandbox_drop_expired_snapshots()?;
+        let head = self.head()?;
+        let final_head = self.final_head()?;
+        let final_header = self.get_block_header(&final_head.last_block_hash)?;
+        let final_prev_height = final_header.prev_height().unwrap_or(final_header.height());
+        let id = self.sandbox_snapshots.insert(crate::sandbox::SandboxSnapshot {
+            head: head.clone(),
+            final_head,
+            final_prev_height,
+            time_offset,
+            accrued_fastforward_delta,
+        });
+        info!(target: "chain", id, height = head.height, hash = ?head.last_block_hash, "Sandbox snapshot taken");
+        Ok((id, head))
+    }
+
+    /// Resets the chain head to the block of the given snapshot and returns
+    /// the snapshot.
+    ///
+    /// Blocks produced after the snapshot become a fork which is garbage
+    /// collected as usual, and snapshots taken after the given one are
+    /// dropped. The given snapshot itself is kept, so the chain can be
+    /// reverted to it again.
+    #[cfg(feature = "sandbox")]
+    pub fn sandbox_revert(
+        &mut self,
+        snapshot_id: u64,
+    ) -> Result<crate::sandbox::SandboxSnapshot, Error> {
+        if self.blocks_in_processing.len() > 0 {
+            return Err(Error::Other(
+                "Cannot revert while blocks are being processed, try again later".to_string(),
+            ));
+        }
+        self.sandbox_drop_expired_snapshots()?;
+        let snapshot = self
+            .sandbox_snapshots
+            .get(snapshot_id)
+            .ok_or_else(|| {
+                Error::Other(format!("Unknown or expired sandbox snapshot {}", snapshot_id))
+            })?
+            .clone();
+        if !self.block_exists(&snapshot.head.last_block_hash)? {
+            return Err(Error::Other(format!(
+                "Block of sandbox snapshot {} was garbage collected",
+                snapshot_id
+            )));
+        }
+
+        let mut chain_store_update = self.chain_store.store_update();
+        chain_store_update.save_head(&snapshot.head)?;
+        chain_store_update.save_final_head(&snapshot.final_head)?;
+        chain_store_update.commit()?;
+
+        self.sandbox_snapshots.drop_after(snapshot_id);
+        self.pending_state_patch.clear();
+        info!(target: "chain", snapshot_id, height = snapshot.head.height, hash = ?snapshot.head.last_block_hash, "Reverted to sandbox snapshot");
+        Ok(snapshot)
+    }
+
+    /// Drops the given sandbox snapshot, releasing the flat storage and
+    /// memtrie state held back for it. Returns whether the snapshot existed.
+    #[cfg(feature = "sandbox")]
+    pub fn sandbox_drop_snapshot(&mut self, snapshot_id: u64) -> Result<bool, Error> {
+        self.sandbox_drop_expired_snapshots()?;
+        Ok(self.sandbox_snapshots.remove(snapshot_id))
+    }
 }
 
 /// Epoch sync specific functions.
@@ -5,6 +5,7 @@ pub use doomslug::{Doomslug, DoomslugBlockProductionReadiness, DoomslugThreshold
 pub use lightclient::{create_light_client_block_view, get_epoch_block_producers_view};
 pub use near_chain_primitives::{self, Error};
 pub use near_primitives::receipt::ReceiptResult;
+pub use sandbox::SandboxSnapshot;
 pub use store::{ChainStore, ChainStoreAccess, ChainStoreUpdate};
 pub use store_validator::{ErrorMessage, StoreValidator};
 pub use types::{Block, BlockHeader, BlockStatus, ChainGenesis, Provenance};
@@ -25,6 +26,8 @@ pub mod missing_chunks;
 pub mod orphan;
 pub mod resharding;
 pub mod runtime;
+#[cfg(feature = "sandbox")]
+mod sandbox;
 mod state_request_tracker;
 pub mod state_snapshot_actor;
 pub mod stateless_validation;
@@ -1,9 +1,10 @@
 use crate::types::{
     ApplyChunkBlockContext, ApplyChunkResult, ApplyChunkShardContext, ApplyResultForResharding,
-    PrepareTransactionsBlockContext, PrepareTransactionsChunkContext, PrepareTransactionsLimit,
-    PreparedTransactions, RuntimeAdapter, RuntimeStorageConfig, StorageDataSource, Tip,
+    BlockHeader, PrepareTransactionsBlockContext, PrepareTransactionsChunkContext,
+    PrepareTransactionsLimit, PreparedTransactions, RuntimeAdapter, RuntimeStorageConfig,
+    StorageDataSource, Tip,
 };
-use crate::Error;
+use crate::{Chain, Error};
 use borsh::BorshDeserialize;
 use errors::FromStateViewerErrors;
 use near_async::time::{Duration, Instant};
@@ -18,9 +19,10 @@ use near_primitives::account::{AccessKey, Account};
 use near_primitives::apply::ApplyChunkReason;
 use near_primitives::checked_feature;
 use near_primitives::congestion_info::{
-    CongestionControl, ExtendedCongestionInfo, RejectTransactionReason, ShardAcceptsTransactions,
+    BlockCongestionInfo, CongestionControl, ExtendedCongestionInfo, RejectTransactionReason,
+    ShardAcceptsTransactions,
 };
-use near_primitives::errors::{InvalidTxError, RuntimeError, StorageError};
+use near_primitives::errors::{InvalidTxError, RuntimeError, StorageError, TxExecutionError};
 use near_primitives::hash::{hash, CryptoHash};
 use near_primitives::receipt::{DelayedReceiptIndices, Receipt};
 use near_primitives::runtime::migration_data::{MigrationData, MigrationFlags};
@@ -29,16 +31,19 @@ use near_primitives::shard_layout::{
     account_id_to_shard_id, account_id_to_shard_uid, ShardLayout, ShardUId,
 };
 use near_primitives::state_part::PartId;
-use near_primitives::transaction::SignedTransaction;
+use near_primitives::transaction::{ExecutionOutcomeWithId, SignedTransaction};
 use near_primitives::trie_key::TrieKey;
 use near_primitives::types::{
     AccountId, Balance, BlockHeight, EpochHeight, EpochId, EpochInfoProvider, Gas, MerkleHash,
-    ShardId, StateChangeCause, StateChangesForResharding, StateRoot, StateRootNode,
+    ShardId, StateChangeCause, StateChanges, StateChangesExt, StateChangesForResharding, StateRoot,
+    StateRootNode,
 };
 use near_primitives::version::{ProtocolFeature, ProtocolVersion};
 use near_primitives::views::{
-    AccessKeyInfoView, CallResult, ContractCodeView, QueryRequest, QueryResponse,
-    QueryResponseKind, ViewStateResult,
+    AccessKeyInfoView, AccountBalanceChangeView, CallGasProfileView, CallResult, ContractCodeView,
+    ExecutionOutcomeWithIdView, FinalExecutionStatus, QueryRequest, QueryResponse,
+    QueryResponseKind, StateProofKey, TransactionSimulationView, ViewStateProofResult,
+    ViewStateResult,
 };
 use near_store::config::StateSnapshotType;
 use near_store::flat::FlatStorageManager;
@@ -1055,7 +1060,7 @@ impl RuntimeAdapter for NightshadeRuntime {
                     block_hash: *block_hash,
                 })
             }
-            QueryRequest::CallFunction { account_id, method_name, args } => {
+            QueryRequest::CallFunction { account_id, method_name, args, include_gas_profile } => {
                 let mut logs = vec![];
                 let (epoch_height, current_protocol_version) = {
                     let epoch_manager = self.epoch_manager.read();
@@ -1069,7 +1074,7 @@ impl RuntimeAdapter for NightshadeRuntime {
                     (epoch_info.epoch_height(), epoch_info.protocol_version())
                 };
 
-                let call_function_result = self
+                let (call_function_result, gas_profile) = self
                     .call_function(
                         &shard_uid,
                         *state_root,
@@ -1097,6 +1102,7 @@ impl RuntimeAdapter for NightshadeRuntime {
                     kind: QueryResponseKind::CallResult(CallResult {
                         result: call_function_result,
                         logs,
+                        gas_profile: include_gas_profile.then_some(gas_profile),
                     }),
                     block_height,
                     block_hash: *block_hash,
@@ -1124,6 +1130,21 @@ impl RuntimeAdapter for NightshadeRuntime {
                     block_hash: *block_hash,
                 })
             }
+            QueryRequest::ViewStateProof { keys } => {
+                let view_state_proof_result =
+                    self.view_state_proof(&shard_uid, *state_root, keys).map_err(|err| {
+                        crate::near_chain_primitives::error::QueryError::from_view_state_error(
+                            err,
+                            block_height,
+                            *block_hash,
+                        )
+                    })?;
+                Ok(QueryResponse {
+                    kind: QueryResponseKind::ViewStateProof(view_state_proof_result),
+                    block_height,
+                    block_hash: *block_hash,
+                })
+            }
             QueryRequest::ViewAccessKeyList { account_id } => {
                 let access_key_list =
                     self.view_access_keys(&shard_uid, *state_root, account_id).map_err(|err| {
@@ -1166,6 +1187,105 @@ impl RuntimeAdapter for NightshadeRuntime {
         }
     }
 
+    fn simulate_transaction(
+        &self,
+        block_header: &BlockHeader,
+        state_roots: &HashMap<ShardId, StateRoot>,
+        transaction: &SignedTransaction,
+        verify_signature: bool,
+        max_receipts: usize,
+    ) -> Result<TransactionSimulationView, Error> {
+        let block_hash = *block_header.hash();
+        let epoch_id = *block_header.epoch_id();
+        let epoch_info = self.epoch_manager.get_epoch_info(&epoch_id)?;
+        let current_protocol_version = epoch_info.protocol_version();
+        let mut tries = HashMap::new();
+        for (&shard_id, state_root) in state_roots {
+            let shard_uid = self.get_shard_uid_from_epoch_id(shard_id, &epoch_id)?;
+            tries.insert(shard_id, self.tries.get_view_trie_for_shard(shard_uid, *state_root));
+        }
+        let apply_state = ApplyState {
+            apply_reason: None,
+            block_height: block_header.height(),
+            prev_block_hash: *block_header.prev_hash(),
+            block_hash,
+            shard_id: 0,
+            epoch_id,
+            epoch_height: epoch_info.epoch_height(),
+            gas_price: block_header.next_gas_price(),
+            block_timestamp: block_header.raw_timestamp(),
+            gas_limit: None,
+            random_seed: *block_header.random_value(),
+            current_protocol_version,
+            config: self.runtime_config_store.get_config(current_protocol_version).clone(),
+            cache: Some(self.compiled_contract_cache.handle()),
+            is_new_chunk: true,
+            migration_data: Arc::clone(&self.migration_data),
+            migration_flags: MigrationFlags::default(),
+            congestion_info: BlockCongestionInfo::default(),
+        };
+
+        let mut view = TransactionSimulationView {
+            block_hash,
+            block_height: block_header.height(),
+            status: FinalExecutionStatus::NotStarted,
+            transaction_outcome: None,
+            receipts_outcome: vec![],
+            pending_receipts: vec![],
+            state_changes: vec![],
+            balance_changes: vec![],
+        };
+        let result = match self.runtime.simulate_transaction(
+            apply_state,
+            tries,
+            transaction,
+            verify_signature,
+            max_receipts,
+            // Simulations are served to RPC users just like view calls.
+            self.trie_viewer.max_gas_burnt_view(),
+            self.epoch_manager.as_ref(),
+        ) {
+            Ok(result) => result,
+            Err(RuntimeError::InvalidTxError(err)) => {
+                view.status = FinalExecutionStatus::Failure(TxExecutionError::InvalidTxError(err));
+                return Ok(view);
+            }
+            Err(RuntimeError::StorageError(err)) => return Err(Error::StorageError(err)),
+            Err(RuntimeError::ValidatorError(err)) => return Err(err.into()),
+            Err(err) => return Err(Error::Other(err.to_string())),
+        };
+
+        let outcome_view = |outcome_with_id: ExecutionOutcomeWithId| ExecutionOutcomeWithIdView {
+            proof: vec![],
+            block_hash,
+            id: outcome_with_id.id,
+            outcome: outcome_with_id.outcome.into(),
+        };
+        let transaction_outcome = outcome_view(result.transaction_outcome);
+        view.receipts_outcome = result.receipt_outcomes.into_iter().map(outcome_view).collect();
+        let mut outcomes = vec![transaction_outcome.clone()];
+        outcomes.extend(view.receipts_outcome.iter().cloned());
+        view.status = Chain::get_execution_status(&outcomes, &transaction.get_hash());
+        view.transaction_outcome = Some(transaction_outcome);
+        view.pending_receipts = result.pending_receipts.into_iter().map(Into::into).collect();
+        view.state_changes = StateChanges::from_changes(result.state_changes.into_iter().map(Ok))?
+            .into_iter()
+            .map(Into::into)
+            .collect();
+        view.balance_changes = result
+            .balance_changes
+            .into_iter()
+            .map(|change| AccountBalanceChangeView {
+                account_id: change.account_id,
+                amount_before: change.amount_before,
+                amount_after: change.amount_after,
+                locked_before: change.locked_before,
+                locked_after: change.locked_after,
+            })
+            .collect();
+        Ok(view)
+    }
+
     // Wrapper to get the metrics.
     fn obtain_state_part(
         &self,
@@ -1324,6 +1444,11 @@ impl RuntimeAdapter for NightshadeRuntime {
         Ok(runtime_config.as_ref().clone())
     }
 
+    #[cfg(feature = "sandbox")]
+    fn execution_traces(&self) -> Option<&node_runtime::execution_trace::ExecutionTraces> {
+        Some(self.runtime.execution_traces())
+    }
+
     fn get_protocol_config(&self, epoch_id: &EpochId) -> Result<ProtocolConfig, Error> {
         let protocol_version = self.epoch_manager.get_epoch_protocol_version(epoch_id)?;
         let mut genesis_config = self.genesis_config.clone();
@@ -1472,7 +1597,8 @@ impl node_runtime::adapter::ViewRuntimeAdapter for NightshadeRuntime {
         logs: &mut Vec<String>,
         epoch_info_provider: &dyn EpochInfoProvider,
         current_protocol_version: ProtocolVersion,
-    ) -> Result<Vec<u8>, node_runtime::state_viewer::errors::CallFunctionError> {
+    ) -> Result<(Vec<u8>, CallGasProfileView), node_runtime::state_viewer::errors::CallFunctionError>
+    {
         let state_update = self.tries.new_trie_update_view(*shard_uid, state_root);
         let view_state = ViewApplyState {
             shard_id: shard_uid.shard_id(),
@@ -1485,7 +1611,7 @@ impl node_runtime::adapter::ViewRuntimeAdapter for NightshadeRuntime {
             current_protocol_version,
             cache: Some(self.compiled_contract_cache.handle()),
         };
-        self.trie_viewer.call_function(
+        self.trie_viewer.call_function_with_gas_profile(
             state_update,
             view_state,
             contract_id,
@@ -1529,4 +1655,14 @@ impl node_runtime::adapter::ViewRuntimeAdapter for NightshadeRuntime {
         let state_update = self.tries.new_trie_update_view(*shard_uid, state_root);
         self.trie_viewer.view_state(&state_update, account_id, prefix, include_proof)
     }
+
+    fn view_state_proof(
+        &self,
+        shard_uid: &ShardUId,
+        state_root: MerkleHash,
+        keys: &[StateProofKey],
+    ) -> Result<ViewStateProofResult, node_runtime::state_viewer::errors::ViewStateError> {
+        let state_update = self.tries.new_trie_update_view(*shard_uid, state_root);
+        self.trie_viewer.view_state_proof(&state_update, keys)
+    }
 }
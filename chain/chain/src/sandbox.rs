@@ -0,0 +1,69 @@
+use near_async::time::Duration;
+use near_primitives::block::Tip;
+use near_primitives::types::{BlockHeight, BlockHeightDelta};
+use std::collections::BTreeMap;
+
+/// Maximum number of snapshots kept at once. Taking a snapshot beyond it drops the oldest one.
+const MAX_SANDBOX_SNAPSHOTS: usize = 100;
+
+/// Chain heads remembered by the `sandbox_snapshot` RPC method, so that the chain can be
+/// reverted to them later.
+#[derive(Default)]
+pub(crate) struct SandboxSnapshots {
+    /// Id of the next snapshot. Ids are never reused, even for snapshots dropped by a revert.
+    next_id: u64,
+    snapshots: BTreeMap<u64, SandboxSnapshot>,
+}
+
+#[derive(Clone)]
+pub struct SandboxSnapshot {
+    pub head: Tip,
+    pub final_head: Tip,
+    /// Height of the block preceding the final block, memtrie roots from this height on are
+    /// needed to revert to the snapshot.
+    pub final_prev_height: BlockHeight,
+    /// Offset of the block time set with `sandbox_set_next_block_timestamp` so far.
+    pub time_offset: Duration,
+    /// Number of heights skipped by fast-forwards so far.
+    pub accrued_fastforward_delta: BlockHeightDelta,
+}
+
+impl SandboxSnapshots {
+    pub fn insert(&mut self, snapshot: SandboxSnapshot) -> u64 {
+        if self.snapshots.len() >= MAX_SANDBOX_SNAPSHOTS {
+            self.snapshots.pop_first();
+        }
+        let id = self.next_id;
+        self.next_id += 1;
+        self.snapshots.insert(id, snapshot);
+        id
+    }
+
+    pub fn get(&self, id: u64) -> Option<&SandboxSnapshot> {
+        self.snapshots.get(&id)
+    }
+
+    /// Drops the snapshot with the given id. Returns whether it existed.
+    pub fn remove(&mut self, id: u64) -> bool {
+        self.snapshots.remove(&id).is_some()
+    }
+
+    /// Drops all snapshots taken after the one with the given id.
+    pub fn drop_after(&mut self, id: u64) {
+        self.snapshots.split_off(&(id + 1));
+    }
+
+    /// Drops snapshots whose final block is below the given height.
+    pub fn drop_expired(&mut self, min_final_height: BlockHeight) {
+        self.snapshots.retain(|_, snapshot| snapshot.final_head.height >= min_final_height);
+    }
+
+    /// Returns the snapshot with the lowest final block among the ones whose final block is at
+    /// or above the given height.
+    pub fn oldest_live(&self, min_final_height: BlockHeight) -> Option<&SandboxSnapshot> {
+        self.snapshots
+            .values()
+            .filter(|snapshot| snapshot.final_head.height >= min_final_height)
+            .min_by_key(|snapshot| snapshot.final_head.height)
+    }
+}
@@ -46,7 +46,8 @@ use near_primitives::types::{
 use near_primitives::version::{ProtocolFeature, ProtocolVersion, PROTOCOL_VERSION};
 use near_primitives::views::{
     AccessKeyInfoView, AccessKeyList, CallResult, ContractCodeView, EpochValidatorInfo,
-    QueryRequest, QueryResponse, QueryResponseKind, ViewStateResult,
+    QueryRequest, QueryResponse, QueryResponseKind, TransactionSimulationView,
+    ViewStateProofResult, ViewStateResult,
 };
 use near_primitives::{checked_feature, shard_layout};
 use near_store::test_utils::TestTriesBuilder;
@@ -1373,10 +1374,20 @@ impl RuntimeAdapter for KeyValueRuntime {
                 block_height,
                 block_hash: *block_hash,
             }),
+            QueryRequest::ViewStateProof { .. } => Ok(QueryResponse {
+                kind: QueryResponseKind::ViewStateProof(ViewStateProofResult {
+                    state_root: *state_root,
+                    values: vec![],
+                    proof: vec![],
+                }),
+                block_height,
+                block_hash: *block_hash,
+            }),
             QueryRequest::CallFunction { .. } => Ok(QueryResponse {
                 kind: QueryResponseKind::CallResult(CallResult {
                     result: Default::default(),
                     logs: Default::default(),
+                    gas_profile: None,
                 }),
                 block_height,
                 block_hash: *block_hash,
@@ -1384,6 +1395,17 @@ impl RuntimeAdapter for KeyValueRuntime {
         }
     }
 
+    fn simulate_transaction(
+        &self,
+        _block_header: &BlockHeader,
+        _state_roots: &HashMap<ShardId, StateRoot>,
+        _transaction: &SignedTransaction,
+        _verify_signature: bool,
+        _max_receipts: usize,
+    ) -> Result<TransactionSimulationView, Error> {
+        Err(Error::Other("simulate_transaction should not be used in KeyValueRuntime".into()))
+    }
+
     fn obtain_state_part(
         &self,
         _shard_id: ShardId,
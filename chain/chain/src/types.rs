@@ -33,7 +33,7 @@ use near_primitives::version::{
     ProtocolVersion, MIN_GAS_PRICE_NEP_92, MIN_GAS_PRICE_NEP_92_FIX, MIN_PROTOCOL_VERSION_NEP_92,
     MIN_PROTOCOL_VERSION_NEP_92_FIX,
 };
-use near_primitives::views::{QueryRequest, QueryResponse};
+use near_primitives::views::{QueryRequest, QueryResponse, TransactionSimulationView};
 use near_store::flat::FlatStorageManager;
 use near_store::{PartialStorage, ShardTries, Store, Trie, WrappedTrieChanges};
 use num_rational::Rational32;
@@ -472,6 +472,21 @@ pub trait RuntimeAdapter: Send + Sync {
         request: &QueryRequest,
     ) -> Result<QueryResponse, near_chain_primitives::error::QueryError>;
 
+    /// Executes the transaction and all the receipts it generates on top of
+    /// the state after the block with the given header, without persisting
+    /// any changes.  `state_roots` are the post-state roots of the shards of
+    /// the block.  Stops after executing `max_receipts` receipts or once the
+    /// burnt gas reaches the `max_gas_burnt_view` limit.  Receipts sent to
+    /// shards missing from `state_roots` are returned unexecuted.
+    fn simulate_transaction(
+        &self,
+        block_header: &BlockHeader,
+        state_roots: &HashMap<ShardId, StateRoot>,
+        transaction: &SignedTransaction,
+        verify_signature: bool,
+        max_receipts: usize,
+    ) -> Result<TransactionSimulationView, Error>;
+
     /// Get part of the state corresponding to the given state root.
     /// `prev_hash` is a block whose post state root is `state_root`.
     /// Returns error when storage is inconsistent.
@@ -528,6 +543,12 @@ pub trait RuntimeAdapter: Send + Sync {
 
     fn get_runtime_config(&self, protocol_version: ProtocolVersion)
         -> Result<RuntimeConfig, Error>;
+
+    /// Returns the store of contract execution traces, if the runtime records them.
+    #[cfg(feature = "sandbox")]
+    fn execution_traces(&self) -> Option<&node_runtime::execution_trace::ExecutionTraces> {
+        None
+    }
 }
 
 /// The last known / checked height and time when we have processed it.
@@ -1,16 +1,22 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
 
 use actix::Message;
 use itertools::Itertools;
 
+use near_chain_configs::TransactionPoolConfig;
+use near_crypto::PublicKey;
+use near_parameters::RuntimeConfig;
+use near_pool::ordering::ordering_policy;
 use near_pool::types::TransactionGroupIterator;
 use near_pool::{InsertTransactionResult, PoolIteratorWrapper, TransactionPool};
 use near_primitives::shard_layout::{account_id_to_shard_uid, ShardLayout, ShardUId};
+use near_primitives::views::{ShardTxPoolView, SignerPendingNoncesView, TxPoolView};
 use near_primitives::{
     epoch_manager::RngSeed,
     sharding::{EncodedShardChunk, PartialEncodedChunk, ShardChunk, ShardChunkHeader},
     transaction::SignedTransaction,
-    types::{AccountId, ShardId},
+    types::{AccountId, Nonce, ShardId},
 };
 
 #[derive(Message, Debug)]
@@ -43,11 +49,22 @@ pub struct ShardedTransactionPool {
     /// If set, new transactions that bring the size of the pool over this limit will be rejected.
     /// The size is tracked and enforced separately for each shard.
     pool_size_limit: Option<u64>,
+
+    /// Ordering policy and per-account limits of the pool of each shard.
+    pool_config: TransactionPoolConfig,
+
+    /// Fees used to order transactions by the gas they burn.
+    runtime_config: Arc<RuntimeConfig>,
 }
 
 impl ShardedTransactionPool {
-    pub fn new(rng_seed: RngSeed, pool_size_limit: Option<u64>) -> Self {
-        Self { tx_pools: HashMap::new(), rng_seed, pool_size_limit }
+    pub fn new(
+        rng_seed: RngSeed,
+        pool_size_limit: Option<u64>,
+        pool_config: TransactionPoolConfig,
+        runtime_config: Arc<RuntimeConfig>,
+    ) -> Self {
+        Self { tx_pools: HashMap::new(), rng_seed, pool_size_limit, pool_config, runtime_config }
     }
 
     pub fn get_pool_iterator(&mut self, shard_uid: ShardUId) -> Option<PoolIteratorWrapper<'_>> {
@@ -82,9 +99,11 @@ impl ShardedTransactionPool {
 
     fn pool_for_shard(&mut self, shard_uid: ShardUId) -> &mut TransactionPool {
         self.tx_pools.entry(shard_uid).or_insert_with(|| {
-            TransactionPool::new(
+            TransactionPool::new_with_config(
                 Self::random_seed(&self.rng_seed, shard_uid.shard_id()),
                 self.pool_size_limit,
+                &self.pool_config,
+                ordering_policy(self.pool_config.ordering, self.runtime_config.clone()),
                 &shard_uid.to_string(),
             )
         })
@@ -98,6 +117,54 @@ impl ShardedTransactionPool {
             .join("; ")
     }
 
+    /// Returns the contents of the non-empty pools. Signers are limited to `account_id` and
+    /// the transactions signed by or sent to it are listed, if it is given.
+    pub fn view(&self, account_id: Option<&AccountId>) -> TxPoolView {
+        let mut shards = self
+            .tx_pools
+            .iter()
+            .filter(|(_, pool)| pool.len() > 0)
+            .map(|(shard_uid, pool)| {
+                let mut nonces: BTreeMap<(&AccountId, &PublicKey), Vec<Nonce>> = BTreeMap::new();
+                let mut account_transactions = vec![];
+                for tx in pool.transactions() {
+                    let signer_id = tx.transaction.signer_id();
+                    if account_id.map_or(true, |account_id| account_id == signer_id) {
+                        nonces
+                            .entry((signer_id, tx.transaction.public_key()))
+                            .or_default()
+                            .push(tx.transaction.nonce());
+                    }
+                    if account_id.is_some_and(|account_id| {
+                        account_id == signer_id || account_id == tx.transaction.receiver_id()
+                    }) {
+                        account_transactions.push(tx.get_hash());
+                    }
+                }
+                let signers = nonces
+                    .into_iter()
+                    .map(|((signer_id, public_key), mut nonces)| {
+                        nonces.sort();
+                        SignerPendingNoncesView {
+                            signer_id: signer_id.clone(),
+                            public_key: public_key.clone(),
+                            nonces,
+                        }
+                    })
+                    .collect();
+                ShardTxPoolView {
+                    shard_uid: *shard_uid,
+                    transactions_count: pool.len() as u64,
+                    transactions_size: pool.transaction_size(),
+                    signers,
+                    account_transactions,
+                }
+            })
+            .collect_vec();
+        shards.sort_by_key(|shard| shard.shard_uid);
+        TxPoolView { shards }
+    }
+
     /// Reintroduces transactions back during the chain reorg. Returns the number of transactions
     /// that were added or are already present in the pool.
     pub fn reintroduce_transactions(
@@ -110,7 +177,8 @@ impl ShardedTransactionPool {
         for tx in transactions {
             reintroduced_count += match pool.insert_transaction(tx.clone()) {
                 InsertTransactionResult::Success | InsertTransactionResult::Duplicate => 1,
-                InsertTransactionResult::NoSpaceLeft => 0,
+                InsertTransactionResult::NoSpaceLeft
+                | InsertTransactionResult::AccountLimitExceeded => 0,
             }
         }
         reintroduced_count
@@ -155,6 +223,7 @@ mod tests {
     use crate::client::ShardedTransactionPool;
     use near_crypto::{InMemorySigner, KeyType};
     use near_o11y::testonly::init_test_logger;
+    use near_parameters::RuntimeConfig;
     use near_pool::types::TransactionGroupIterator;
     use near_primitives::{
         epoch_manager::RngSeed,
@@ -165,7 +234,7 @@ mod tests {
     };
     use near_store::ShardUId;
     use rand::{rngs::StdRng, seq::SliceRandom, SeedableRng};
-    use std::{collections::HashMap, str::FromStr};
+    use std::{collections::HashMap, str::FromStr, sync::Arc};
 
     const TEST_SEED: RngSeed = [3; 32];
 
@@ -188,13 +257,70 @@ mod tests {
         assert_ne!(seed1000, seed1000000);
     }
 
+    #[test]
+    fn test_transaction_pool_view() {
+        let mut pool = ShardedTransactionPool::new(
+            TEST_SEED,
+            None,
+            Default::default(),
+            Arc::new(RuntimeConfig::test()),
+        );
+        let shard_uid = ShardUId { shard_id: 0, version: 0 };
+        let alice = AccountId::from_str("alice").unwrap();
+        let bob = AccountId::from_str("bob").unwrap();
+        let mut hashes = vec![];
+        for (nonce, signer_id, receiver_id) in
+            [(3, &alice, &bob), (1, &alice, &bob), (7, &bob, &alice), (2, &bob, &bob)]
+        {
+            let signer = InMemorySigner::from_seed(signer_id.clone(), KeyType::ED25519, "seed");
+            let tx = SignedTransaction::send_money(
+                nonce,
+                signer_id.clone(),
+                receiver_id.clone(),
+                &signer.into(),
+                1,
+                CryptoHash::default(),
+            );
+            hashes.push(tx.get_hash());
+            pool.insert_transaction(shard_uid, tx);
+        }
+
+        let view = pool.view(None);
+        assert_eq!(view.shards.len(), 1);
+        let shard = &view.shards[0];
+        assert_eq!(shard.shard_uid, shard_uid);
+        assert_eq!(shard.transactions_count, 4);
+        let nonces = shard
+            .signers
+            .iter()
+            .map(|signer| (signer.signer_id.as_str(), signer.nonces.clone()))
+            .collect::<Vec<_>>();
+        assert_eq!(nonces, vec![("alice", vec![1, 3]), ("bob", vec![2, 7])]);
+        assert!(shard.account_transactions.is_empty());
+
+        let view = pool.view(Some(&alice));
+        let shard = &view.shards[0];
+        assert_eq!(shard.signers.len(), 1);
+        assert_eq!(shard.signers[0].signer_id, alice);
+        let mut account_transactions = shard.account_transactions.clone();
+        account_transactions.sort();
+        let mut expected = hashes[..3].to_vec();
+        expected.sort();
+        assert_eq!(account_transactions, expected);
+    }
+
     #[test]
     fn test_transaction_pool_resharding() {
         init_test_logger();
         let old_shard_layout = ShardLayout::get_simple_nightshade_layout();
         let new_shard_layout = ShardLayout::get_simple_nightshade_layout_v2();
 
-        let mut pool = ShardedTransactionPool::new(TEST_SEED, None);
+        let mut pool = ShardedTransactionPool::new(
+            TEST_SEED,
+            None,
+            Default::default(),
+            Arc::new(RuntimeConfig::test()),
+        );
 
         let mut shard_id_to_accounts = HashMap::new();
         shard_id_to_accounts.insert(0, vec!["aaa", "abcd", "a-a-a-a-a"]);
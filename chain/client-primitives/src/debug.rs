@@ -2,7 +2,8 @@
 //! without backwards compatibility of JSON encoding.
 use crate::types::StatusError;
 use near_primitives::congestion_info::CongestionInfo;
-use near_primitives::types::EpochId;
+use near_primitives::shard_layout::ShardUId;
+use near_primitives::types::{EpochId, StoreKey};
 use near_primitives::views::{
     CatchupStatusView, ChainProcessingInfo, EpochValidatorInfo, RequestedStatePartsView,
     SyncStatusView,
@@ -174,6 +175,29 @@ pub struct ValidatorStatus {
     pub banned_chunk_producers: Vec<(EpochId, Vec<AccountId>)>,
 }
 
+/// Progress of the flat storage scrubber on a single shard.
+#[derive(serde::Serialize, serde::Deserialize, Debug)]
+pub struct FlatStorageScrubberShardView {
+    pub shard_uid: ShardUId,
+    pub completed_passes: u64,
+    pub keys_checked_in_pass: u64,
+    pub keys_in_last_pass: u64,
+    // Key the next batch starts from, None at the start of a pass.
+    pub cursor: Option<StoreKey>,
+    // Height of the flat storage head the last batch was checked at.
+    pub flat_head_height: BlockHeight,
+    pub total_mismatches: u64,
+    // Most recently found mismatches, oldest first.
+    pub recent_mismatches: Vec<FlatStorageMismatchView>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug)]
+pub struct FlatStorageMismatchView {
+    pub kind: String,
+    pub key: StoreKey,
+    pub flat_head_height: BlockHeight,
+}
+
 // Different debug requests that can be sent by HTML pages, via GET.
 #[derive(Debug)]
 pub enum DebugStatus {
@@ -193,6 +217,8 @@ pub enum DebugStatus {
     ChainProcessingStatus,
     // The state parts already requested.
     RequestedStateParts,
+    // Progress and findings of the flat storage scrubber.
+    FlatStorageScrubber,
 }
 
 impl actix::Message for DebugStatus {
@@ -214,4 +240,6 @@ pub enum DebugStatusResponse {
     ChainProcessingStatus(ChainProcessingInfo),
     // The state parts already requested.
     RequestedStateParts(Vec<RequestedStatePartsView>),
+    // Progress of the flat storage scrubber for every shard it has checked.
+    FlatStorageScrubber(Vec<FlatStorageScrubberShardView>),
 }
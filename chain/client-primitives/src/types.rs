@@ -4,16 +4,18 @@ use near_primitives::hash::CryptoHash;
 use near_primitives::merkle::{MerklePath, PartialMerkleTree};
 use near_primitives::network::PeerId;
 use near_primitives::sharding::ChunkHash;
+use near_primitives::transaction::SignedTransaction;
 use near_primitives::types::{
-    AccountId, BlockHeight, BlockReference, EpochId, EpochReference, MaybeBlockId, ShardId,
-    TransactionOrReceiptId,
+    AccountId, BlockHeight, BlockId, BlockReference, EpochId, EpochReference, MaybeBlockId,
+    ShardId, TransactionOrReceiptId,
 };
 use near_primitives::views::validator_stake_view::ValidatorStakeView;
 use near_primitives::views::{
     BlockView, ChunkView, DownloadStatusView, EpochValidatorInfo, ExecutionOutcomeWithIdView,
     GasPriceView, LightClientBlockLiteView, LightClientBlockView, MaintenanceWindowsView,
     QueryRequest, QueryResponse, ReceiptView, ShardSyncDownloadView, SplitStorageInfoView,
-    StateChangesKindsView, StateChangesRequestView, StateChangesView, SyncStatusView, TxStatusView,
+    StateChangesKindsView, StateChangesRequestView, StateChangesView, StateDiffView,
+    SyncStatusView, TransactionSimulationView, TxPoolView, TxStatusView,
 };
 pub use near_primitives::views::{StatusResponse, StatusSyncInfo};
 use std::collections::HashMap;
@@ -547,6 +549,14 @@ pub enum QueryError {
         block_height: near_primitives::types::BlockHeight,
         block_hash: near_primitives::hash::CryptoHash,
     },
+    #[error("State proof is larger than {limit} bytes, request fewer keys")]
+    TooLargeStateProof {
+        limit: u64,
+        block_height: near_primitives::types::BlockHeight,
+        block_hash: near_primitives::hash::CryptoHash,
+    },
+    #[error("Invalid keys for a state proof: {error_message}")]
+    InvalidStateProofKeys { error_message: String },
     #[error("The node reached its limits. Try again later. More details: {error_message}")]
     InternalError { error_message: String },
     #[error(
@@ -858,6 +868,8 @@ impl Message for GetStateChangesWithCauseInBlock {
 pub struct GetStateChangesWithCauseInBlockForTrackedShards {
     pub block_hash: CryptoHash,
     pub epoch_id: EpochId,
+    /// If set, only the changes of these accounts are read from the store.
+    pub account_ids: Option<Vec<AccountId>>,
 }
 
 impl Message for GetStateChangesWithCauseInBlockForTrackedShards {
@@ -1100,6 +1112,130 @@ impl From<near_chain_primitives::Error> for GetClientConfigError {
     }
 }
 
+#[derive(Debug)]
+pub struct GetTxPool {
+    /// If set, limits the signers to this account and lists its pending transactions.
+    pub account_id: Option<AccountId>,
+}
+
+impl Message for GetTxPool {
+    type Result = Result<TxPoolView, GetTxPoolError>;
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum GetTxPoolError {
+    // NOTE: Currently, the underlying errors are too broad, and while we tried to handle
+    // expected cases, we cannot statically guarantee that no other errors will be returned
+    // in the future.
+    // TODO #3851: Remove this variant once we can exhaustively match all the underlying errors
+    #[error("It is a bug if you receive this error type, please, report this incident: https://github.com/near/nearcore/issues/new/choose. Details: {0}")]
+    Unreachable(String),
+}
+
+/// Computes the difference between the states of a shard after two blocks.
+#[derive(Debug)]
+pub struct GetStateDiff {
+    pub from_block_id: BlockId,
+    pub to_block_id: BlockId,
+    /// Shard to compare.  Required unless `account_id` is set.
+    pub shard_id: Option<ShardId>,
+    /// If set, only changes of this account's keys are returned and the shard
+    /// is the one the account belongs to.
+    pub account_id: Option<AccountId>,
+    /// If set, only changes of keys not smaller than this key are returned.
+    pub start_key: Option<Vec<u8>>,
+    /// Maximum number of changes to return.
+    pub limit: usize,
+    /// Maximum total size of the keys and values of the returned changes.  The
+    /// first change is returned even if it is larger.
+    pub max_bytes: usize,
+}
+
+impl Message for GetStateDiff {
+    type Result = Result<StateDiffView, GetStateDiffError>;
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum GetStateDiffError {
+    #[error("Block not found: {error_message}")]
+    UnknownBlock { error_message: String },
+    #[error("Invalid request: {error_message}")]
+    InvalidRequest { error_message: String },
+    #[error("State is not available: {error_message}")]
+    UnavailableState { error_message: String },
+    #[error("IO Error: {0}")]
+    IOError(String),
+    // NOTE: Currently, the underlying errors are too broad, and while we tried to handle
+    // expected cases, we cannot statically guarantee that no other errors will be returned
+    // in the future.
+    // TODO #3851: Remove this variant once we can exhaustively match all the underlying errors
+    #[error("It is a bug if you receive this error type, please, report this incident: https://github.com/near/nearcore/issues/new/choose. Details: {0}")]
+    Unreachable(String),
+}
+
+impl From<near_chain_primitives::Error> for GetStateDiffError {
+    fn from(error: near_chain_primitives::Error) -> Self {
+        match error {
+            near_chain_primitives::Error::DBNotFoundErr(error_message) => {
+                Self::UnknownBlock { error_message }
+            }
+            near_chain_primitives::Error::IOErr(error) => Self::IOError(error.to_string()),
+            _ => Self::Unreachable(error.to_string()),
+        }
+    }
+}
+
+/// Executes a transaction and all the receipts it generates on top of the
+/// state after the given block without persisting any changes.
+#[derive(Debug)]
+pub struct SimulateTransaction {
+    pub block_reference: BlockReference,
+    pub transaction: SignedTransaction,
+    /// Unset to simulate transactions which are not signed yet.
+    pub verify_signature: bool,
+    /// Maximum number of receipts to execute.
+    pub max_receipts: usize,
+}
+
+impl Message for SimulateTransaction {
+    type Result = Result<TransactionSimulationView, SimulateTransactionError>;
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum SimulateTransactionError {
+    #[error("There are no fully synchronized blocks yet")]
+    NoSyncedBlocks,
+    #[error("Block not found: {error_message}")]
+    UnknownBlock { error_message: String },
+    #[error("State is not available: {error_message}")]
+    UnavailableState { error_message: String },
+    #[error("IO Error: {0}")]
+    IOError(String),
+    // NOTE: Currently, the underlying errors are too broad, and while we tried to handle
+    // expected cases, we cannot statically guarantee that no other errors will be returned
+    // in the future.
+    // TODO #3851: Remove this variant once we can exhaustively match all the underlying errors
+    #[error("It is a bug if you receive this error type, please, report this incident: https://github.com/near/nearcore/issues/new/choose. Details: {0}")]
+    Unreachable(String),
+}
+
+impl From<near_chain_primitives::Error> for SimulateTransactionError {
+    fn from(error: near_chain_primitives::Error) -> Self {
+        match error {
+            near_chain_primitives::Error::DBNotFoundErr(error_message) => {
+                Self::UnknownBlock { error_message }
+            }
+            // The transaction touched a shard which is not tracked or the
+            // state was garbage collected.
+            near_chain_primitives::Error::StorageError(error) => {
+                Self::UnavailableState { error_message: error.to_string() }
+            }
+            near_chain_primitives::Error::IOErr(error) => Self::IOError(error.to_string()),
+            _ => Self::Unreachable(error.to_string()),
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct GetSplitStorageInfo {}
 
@@ -1141,6 +1277,17 @@ pub enum SandboxMessage {
     SandboxPatchStateStatus,
     SandboxFastForward(near_primitives::types::BlockHeightDelta),
     SandboxFastForwardStatus,
+    SandboxSetExecutionTracing(bool),
+    SandboxExecutionTrace(CryptoHash),
+    SandboxSnapshot,
+    SandboxRevert(u64),
+    SandboxDropSnapshot(u64),
+    SandboxSetBlockProductionPaused(bool),
+    SandboxProduceBlocks(near_primitives::types::NumBlocks),
+    SandboxProduceBlocksStatus,
+    SandboxSetNextBlockTimestamp(Utc),
+    SandboxAdvanceEpoch,
+    SandboxAdvanceEpochStatus,
 }
 
 #[cfg(feature = "sandbox")]
@@ -1149,6 +1296,24 @@ pub enum SandboxResponse {
     SandboxPatchStateFinished(bool),
     SandboxFastForwardFinished(bool),
     SandboxFastForwardFailed(String),
+    SandboxExecutionTrace(Option<Vec<near_primitives::views::FunctionCallTraceView>>),
+    SandboxSnapshotTaken {
+        snapshot_id: u64,
+        block_hash: CryptoHash,
+        block_height: near_primitives::types::BlockHeight,
+    },
+    SandboxSnapshotFailed(String),
+    SandboxRevertFinished {
+        block_hash: CryptoHash,
+        block_height: near_primitives::types::BlockHeight,
+    },
+    SandboxRevertFailed(String),
+    SandboxSnapshotDropped(bool),
+    SandboxProduceBlocksFinished(bool),
+    SandboxProduceBlocksFailed(String),
+    SandboxSetNextBlockTimestampFailed(String),
+    SandboxAdvanceEpochFinished(bool),
+    SandboxAdvanceEpochFailed(String),
     SandboxNoResponse,
 }
 #[cfg(feature = "sandbox")]
@@ -133,6 +133,12 @@ pub struct Client {
     /// Fast Forward accrued delta height used to calculate fast forwarded timestamps for each block.
     #[cfg(feature = "sandbox")]
     pub(crate) accrued_fastforward_delta: near_primitives::types::BlockHeightDelta,
+    /// Shift of the sandbox time caused by explicitly set block timestamps.
+    #[cfg(feature = "sandbox")]
+    sandbox_time_offset: Duration,
+    /// Timestamp of the next produced block, set with `sandbox_set_next_block_timestamp`.
+    #[cfg(feature = "sandbox")]
+    sandbox_next_block_timestamp: Option<near_async::time::Utc>,
 
     pub clock: Clock,
     pub config: ClientConfig,
@@ -283,8 +289,15 @@ impl Client {
             chain.chain_store(),
             chain_config.background_migration_threads,
         )?;
-        let sharded_tx_pool =
-            ShardedTransactionPool::new(rng_seed, config.transaction_pool_size_limit);
+        // Fees only affect the order of pooled transactions, so the ones at startup are good
+        // enough even after a protocol upgrade changes them.
+        let protocol_version = epoch_manager.get_epoch_protocol_version(&chain.head()?.epoch_id)?;
+        let sharded_tx_pool = ShardedTransactionPool::new(
+            rng_seed,
+            config.transaction_pool_size_limit,
+            config.transaction_pool.clone(),
+            Arc::new(runtime_adapter.get_runtime_config(protocol_version)?),
+        );
         let sync_status = SyncStatus::AwaitingPeers;
         let genesis_block = chain.genesis_block();
         let epoch_sync = EpochSync::new(
@@ -381,6 +394,10 @@ impl Client {
             produce_invalid_tx_in_chunks: false,
             #[cfg(feature = "sandbox")]
             accrued_fastforward_delta: 0,
+            #[cfg(feature = "sandbox")]
+            sandbox_time_offset: Duration::ZERO,
+            #[cfg(feature = "sandbox")]
+            sandbox_next_block_timestamp: None,
             clock: clock.clone(),
             config,
             sync_status,
@@ -717,11 +734,6 @@ impl Client {
             prev_next_bp_hash
         };
 
-        #[cfg(feature = "sandbox")]
-        let sandbox_delta_time = Some(self.sandbox_delta_time());
-        #[cfg(not(feature = "sandbox"))]
-        let sandbox_delta_time = None;
-
         // Get block extra from previous block.
         let block_merkle_tree = self.chain.chain_store().get_block_merkle_tree(&prev_hash)?;
         let mut block_merkle_tree = PartialMerkleTree::clone(&block_merkle_tree);
@@ -792,6 +804,11 @@ impl Client {
         let next_epoch_protocol_version =
             self.epoch_manager.get_epoch_protocol_version(&next_epoch_id)?;
 
+        #[cfg(feature = "sandbox")]
+        let sandbox_time = Some(self.sandbox_block_time());
+        #[cfg(not(feature = "sandbox"))]
+        let sandbox_time = None;
+
         let block = Block::produce(
             this_epoch_protocol_version,
             next_epoch_protocol_version,
@@ -814,7 +831,7 @@ impl Client {
             next_bp_hash,
             block_merkle_root,
             self.clock.clone(),
-            sandbox_delta_time,
+            sandbox_time,
         );
 
         // Update latest known even before returning block out, to prevent race conditions.
@@ -1544,6 +1561,7 @@ impl Client {
     }
 
     /// Gets the advanced timestamp delta in nanoseconds for sandbox once it has been fast-forwarded
+    /// or a block timestamp has been set explicitly
     #[cfg(feature = "sandbox")]
     pub fn sandbox_delta_time(&self) -> Duration {
         let avg_block_prod_time = (self.config.min_block_production_delay.whole_nanoseconds()
@@ -1559,7 +1577,68 @@ impl Client {
                 )
             });
 
-        Duration::nanoseconds(ns)
+        Duration::nanoseconds(ns) + self.sandbox_time_offset
+    }
+
+    /// Sets the timestamp of the next produced block. Blocks produced after it
+    /// continue from that timestamp.
+    #[cfg(feature = "sandbox")]
+    pub fn sandbox_set_next_block_timestamp(
+        &mut self,
+        timestamp: near_async::time::Utc,
+    ) -> Result<(), Error> {
+        let head = self.chain.head()?;
+        let head_timestamp = self.chain.get_block_header(&head.last_block_hash)?.timestamp();
+        if timestamp <= head_timestamp {
+            return Err(Error::Other(format!(
+                "Next block timestamp {} must be after the timestamp {} of the head block",
+                timestamp, head_timestamp
+            )));
+        }
+        self.sandbox_next_block_timestamp = Some(timestamp);
+        Ok(())
+    }
+
+    /// Returns the time of the block being produced: the timestamp set with
+    /// `sandbox_set_next_block_timestamp` if there is one, or the current time
+    /// shifted by fast-forwards and previously set timestamps otherwise.
+    #[cfg(feature = "sandbox")]
+    fn sandbox_block_time(&mut self) -> near_async::time::Utc {
+        let now = self.clock.now_utc() + self.sandbox_delta_time();
+        let Some(timestamp) = self.sandbox_next_block_timestamp.take() else {
+            return now;
+        };
+        self.sandbox_time_offset += timestamp - now;
+        timestamp
+    }
+
+    /// Takes a sandbox snapshot of the chain head and of the block time shift
+    /// accumulated by fast-forwards and set timestamps.
+    #[cfg(feature = "sandbox")]
+    pub fn sandbox_snapshot(&mut self) -> Result<(u64, Tip), Error> {
+        self.chain.sandbox_snapshot(self.sandbox_time_offset, self.accrued_fastforward_delta)
+    }
+
+    /// Reverts the chain to the given sandbox snapshot and returns the new head.
+    ///
+    /// The block time shift is restored to its value at the snapshot and a
+    /// pending next block timestamp is discarded. The next block is produced
+    /// above all heights seen so far, as is done after a fast-forward, so that
+    /// heights of discarded blocks are not reused.
+    #[cfg(feature = "sandbox")]
+    pub fn sandbox_revert(&mut self, snapshot_id: u64) -> Result<Tip, Error> {
+        let snapshot = self.chain.sandbox_revert(snapshot_id)?;
+        self.sandbox_time_offset = snapshot.time_offset;
+        self.accrued_fastforward_delta = snapshot.accrued_fastforward_delta;
+        self.sandbox_next_block_timestamp = None;
+        let head = snapshot.head;
+        let latest_known = self.chain.chain_store().get_latest_known()?;
+        self.sandbox_update_tip(latest_known.height)?;
+        self.shards_manager_adapter.send(ShardsManagerRequestFromClient::UpdateChainHeads {
+            head: head.clone(),
+            header_head: self.chain.header_head()?,
+        });
+        Ok(head)
     }
 
     pub fn send_approval(
@@ -2394,6 +2473,13 @@ impl Client {
                                 trace!(target: "client", ?shard_uid, tx_hash = ?tx.get_hash(), "Transaction pool is full, trying to forward the transaction.");
                             }
                         }
+                        InsertTransactionResult::AccountLimitExceeded => {
+                            if is_forwarded {
+                                trace!(target: "client", ?shard_uid, tx_hash = ?tx.get_hash(), "Too many transactions of the signer or the receiver in the pool, dropping the transaction.");
+                            } else {
+                                trace!(target: "client", ?shard_uid, tx_hash = ?tx.get_hash(), "Too many transactions of the signer or the receiver in the pool, trying to forward the transaction.");
+                            }
+                        }
                     }
                 }
 
@@ -47,8 +47,8 @@ use near_chunks::adapter::ShardsManagerRequestFromClient;
 use near_chunks::client::ShardsManagerResponse;
 use near_chunks::logic::get_shards_cares_about_this_or_next_epoch;
 use near_client_primitives::types::{
-    Error, GetClientConfig, GetClientConfigError, GetNetworkInfo, NetworkInfoResponse,
-    StateSyncStatus, Status, StatusError, StatusSyncInfo, SyncStatus,
+    Error, GetClientConfig, GetClientConfigError, GetNetworkInfo, GetTxPool, GetTxPoolError,
+    NetworkInfoResponse, StateSyncStatus, Status, StatusError, StatusSyncInfo, SyncStatus,
 };
 use near_epoch_manager::shard_tracker::ShardTracker;
 use near_epoch_manager::{EpochManagerAdapter, RngSeed};
@@ -71,7 +71,7 @@ use near_primitives::unwrap_or_return;
 use near_primitives::utils::MaybeValidated;
 use near_primitives::validator_signer::ValidatorSigner;
 use near_primitives::version::PROTOCOL_VERSION;
-use near_primitives::views::{DetailedDebugStatus, ValidatorInfo};
+use near_primitives::views::{DetailedDebugStatus, TxPoolView, ValidatorInfo};
 #[cfg(feature = "test_features")]
 use near_store::DBCol;
 use near_store::ShardUId;
@@ -261,6 +261,15 @@ pub struct ClientActorInner {
 
     #[cfg(feature = "sandbox")]
     fastforward_delta: near_primitives::types::BlockHeightDelta,
+    /// Whether automatic block production is paused with `sandbox_set_block_production`.
+    #[cfg(feature = "sandbox")]
+    block_production_paused: bool,
+    /// Number of blocks still to be produced for the `sandbox_produce_blocks` request.
+    #[cfg(feature = "sandbox")]
+    blocks_to_produce: near_primitives::types::NumBlocks,
+    /// Epoch the `sandbox_advance_epoch` request waits to leave.
+    #[cfg(feature = "sandbox")]
+    advance_from_epoch: Option<EpochId>,
 
     /// Synchronization measure to allow graceful shutdown.
     /// Informs the system when a ClientActor gets dropped.
@@ -349,6 +358,12 @@ impl ClientActorInner {
             sync_started: false,
             #[cfg(feature = "sandbox")]
             fastforward_delta: 0,
+            #[cfg(feature = "sandbox")]
+            block_production_paused: false,
+            #[cfg(feature = "sandbox")]
+            blocks_to_produce: 0,
+            #[cfg(feature = "sandbox")]
+            advance_from_epoch: None,
             shutdown_signal,
             config_updater,
             sync_jobs_sender,
@@ -661,6 +676,124 @@ impl Handler<near_client_primitives::types::SandboxMessage> for ClientActorInner
                     self.fastforward_delta == 0,
                 )
             }
+            near_client_primitives::types::SandboxMessage::SandboxSetExecutionTracing(enabled) => {
+                if let Some(traces) = self.client.runtime_adapter.execution_traces() {
+                    traces.set_enabled(enabled);
+                }
+                near_client_primitives::types::SandboxResponse::SandboxNoResponse
+            }
+            near_client_primitives::types::SandboxMessage::SandboxExecutionTrace(receipt_id) => {
+                near_client_primitives::types::SandboxResponse::SandboxExecutionTrace(
+                    self.client
+                        .runtime_adapter
+                        .execution_traces()
+                        .and_then(|traces| traces.get(&receipt_id)),
+                )
+            }
+            near_client_primitives::types::SandboxMessage::SandboxSnapshot => {
+                match self.client.sandbox_snapshot() {
+                    Ok((snapshot_id, head)) => {
+                        near_client_primitives::types::SandboxResponse::SandboxSnapshotTaken {
+                            snapshot_id,
+                            block_hash: head.last_block_hash,
+                            block_height: head.height,
+                        }
+                    }
+                    Err(err) => {
+                        near_client_primitives::types::SandboxResponse::SandboxSnapshotFailed(
+                            err.to_string(),
+                        )
+                    }
+                }
+            }
+            near_client_primitives::types::SandboxMessage::SandboxRevert(snapshot_id) => {
+                if self.fastforward_delta > 0 {
+                    return near_client_primitives::types::SandboxResponse::SandboxRevertFailed(
+                        "Cannot revert while a fast_forward request is going on.".to_string(),
+                    );
+                }
+                match self.client.sandbox_revert(snapshot_id) {
+                    Ok(head) => {
+                        near_client_primitives::types::SandboxResponse::SandboxRevertFinished {
+                            block_hash: head.last_block_hash,
+                            block_height: head.height,
+                        }
+                    }
+                    Err(err) => {
+                        near_client_primitives::types::SandboxResponse::SandboxRevertFailed(
+                            err.to_string(),
+                        )
+                    }
+                }
+            }
+            near_client_primitives::types::SandboxMessage::SandboxDropSnapshot(snapshot_id) => {
+                match self.client.chain.sandbox_drop_snapshot(snapshot_id) {
+                    Ok(dropped) => {
+                        near_client_primitives::types::SandboxResponse::SandboxSnapshotDropped(
+                            dropped,
+                        )
+                    }
+                    Err(err) => near_client_primitives::types::SandboxResponse::SandboxSnapshotFailed(
+                        err.to_string(),
+                    ),
+                }
+            }
+            near_client_primitives::types::SandboxMessage::SandboxSetBlockProductionPaused(
+                paused,
+            ) => {
+                self.block_production_paused = paused;
+                near_client_primitives::types::SandboxResponse::SandboxNoResponse
+            }
+            near_client_primitives::types::SandboxMessage::SandboxProduceBlocks(num_blocks) => {
+                if self.blocks_to_produce > 0 {
+                    return near_client_primitives::types::SandboxResponse::SandboxProduceBlocksFailed(
+                        "Consecutive produce_blocks requests cannot be made while a current one is going on.".to_string());
+                }
+
+                self.blocks_to_produce = num_blocks;
+                near_client_primitives::types::SandboxResponse::SandboxNoResponse
+            }
+            near_client_primitives::types::SandboxMessage::SandboxProduceBlocksStatus => {
+                near_client_primitives::types::SandboxResponse::SandboxProduceBlocksFinished(
+                    self.blocks_to_produce == 0,
+                )
+            }
+            near_client_primitives::types::SandboxMessage::SandboxSetNextBlockTimestamp(
+                timestamp,
+            ) => match self.client.sandbox_set_next_block_timestamp(timestamp) {
+                Ok(()) => near_client_primitives::types::SandboxResponse::SandboxNoResponse,
+                Err(err) => {
+                    near_client_primitives::types::SandboxResponse::SandboxSetNextBlockTimestampFailed(
+                        err.to_string(),
+                    )
+                }
+            },
+            near_client_primitives::types::SandboxMessage::SandboxAdvanceEpoch => {
+                if self.advance_from_epoch.is_some() || self.fastforward_delta > 0 {
+                    return near_client_primitives::types::SandboxResponse::SandboxAdvanceEpochFailed(
+                        "advance_epoch requests cannot be made while a fast_forward or advance_epoch request is going on.".to_string());
+                }
+                match self.sandbox_start_advance_epoch() {
+                    Ok(()) => near_client_primitives::types::SandboxResponse::SandboxNoResponse,
+                    Err(err) => {
+                        near_client_primitives::types::SandboxResponse::SandboxAdvanceEpochFailed(
+                            err.to_string(),
+                        )
+                    }
+                }
+            }
+            near_client_primitives::types::SandboxMessage::SandboxAdvanceEpochStatus => {
+                match self.sandbox_check_epoch_advanced() {
+                    Ok(()) => near_client_primitives::types::SandboxResponse::SandboxAdvanceEpochFinished(
+                        self.advance_from_epoch.is_none(),
+                    ),
+                    Err(err) => {
+                        near_client_primitives::types::SandboxResponse::SandboxAdvanceEpochFailed(
+                            err.to_string(),
+                        )
+                    }
+                }
+            }
         }
     }
 }
@@ -1012,9 +1145,48 @@ impl ClientActorInner {
         Ok(Some(new_latest_known))
     }
 
+    /// Starts the sandbox advance epoch request. Heights up to the end of the current epoch are
+    /// fast forwarded and blocks are then produced until the head is in the next epoch, even if
+    /// block production is paused.
+    #[cfg(feature = "sandbox")]
+    fn sandbox_start_advance_epoch(&mut self) -> Result<(), Error> {
+        let head = self.client.chain.head()?;
+        let epoch_start_height =
+            self.client.epoch_manager.get_epoch_start_height(&head.last_block_hash)?;
+        let epoch_end_height = epoch_start_height + self.client.config.epoch_length;
+        self.fastforward_delta = epoch_end_height.saturating_sub(head.height);
+        self.advance_from_epoch = Some(head.epoch_id);
+        Ok(())
+    }
+
+    /// Finishes the sandbox advance epoch request once the head is in the next epoch.
+    #[cfg(feature = "sandbox")]
+    fn sandbox_check_epoch_advanced(&mut self) -> Result<(), Error> {
+        let Some(epoch_id) = &self.advance_from_epoch else {
+            return Ok(());
+        };
+        if &self.client.chain.head()?.epoch_id != epoch_id {
+            self.advance_from_epoch = None;
+            // Don't fast forward any further into the new epoch.
+            self.fastforward_delta = 0;
+        }
+        Ok(())
+    }
+
+    /// Whether block production is paused with `sandbox_set_block_production` and no sandbox
+    /// request currently needs blocks to be produced.
+    #[cfg(feature = "sandbox")]
+    fn sandbox_block_production_paused(&self) -> bool {
+        self.block_production_paused
+            && self.blocks_to_produce == 0
+            && self.fastforward_delta == 0
+            && self.advance_from_epoch.is_none()
+    }
+
     fn pre_block_production(&mut self) -> Result<(), Error> {
         #[cfg(feature = "sandbox")]
         {
+            self.sandbox_check_epoch_advanced()?;
             let latest_known = self.client.chain.mut_chain_store().get_latest_known()?;
             if let Some(new_latest_known) =
                 self.sandbox_process_fast_forward(latest_known.height)?
@@ -1035,6 +1207,10 @@ impl ClientActorInner {
             // stepping between epoch boundaries.
             self.fastforward_delta -= 1;
         }
+        #[cfg(feature = "sandbox")]
+        if self.blocks_to_produce > 0 {
+            self.blocks_to_produce -= 1;
+        }
     }
 
     /// Retrieves latest height, and checks if must produce next block.
@@ -1104,6 +1280,11 @@ impl ClientActorInner {
         for height in
             latest_known.height + 1..=self.client.doomslug.get_largest_height_crossing_threshold()
         {
+            #[cfg(feature = "sandbox")]
+            if self.sandbox_block_production_paused() {
+                break;
+            }
+
             let next_block_producer_account =
                 self.client.epoch_manager.get_block_producer(&epoch_id, height)?;
 
@@ -2148,6 +2329,14 @@ impl Handler<GetClientConfig> for ClientActorInner {
     }
 }
 
+impl Handler<GetTxPool> for ClientActorInner {
+    fn handle(&mut self, msg: GetTxPool) -> Result<TxPoolView, GetTxPoolError> {
+        tracing::debug!(target: "client", ?msg);
+
+        Ok(self.client.sharded_tx_pool.view(msg.account_id.as_ref()))
+    }
+}
+
 impl Handler<SyncMessage> for ClientActorInner {
     fn handle(&mut self, msg: SyncMessage) {
         tracing::debug!(target: "client", ?msg);
@@ -8,7 +8,8 @@ use near_chain::crypto_hash_timer::CryptoHashTimer;
 use near_chain::{near_chain_primitives, Block, Chain, ChainStoreAccess};
 use near_client_primitives::debug::{
     ApprovalAtHeightStatus, BlockProduction, ChunkCollection, DebugBlockStatusData, DebugStatus,
-    DebugStatusResponse, MissedHeightInfo, ProductionAtHeight, ValidatorStatus,
+    DebugStatusResponse, FlatStorageMismatchView, FlatStorageScrubberShardView, MissedHeightInfo,
+    ProductionAtHeight, ValidatorStatus,
 };
 use near_client_primitives::types::Error;
 use near_client_primitives::{
@@ -178,11 +179,42 @@ impl Handler<DebugStatus> for ClientActorInner {
             DebugStatus::ChainProcessingStatus => Ok(DebugStatusResponse::ChainProcessingStatus(
                 self.client.chain.get_chain_processing_info(),
             )),
+            DebugStatus::FlatStorageScrubber => {
+                Ok(DebugStatusResponse::FlatStorageScrubber(self.get_flat_storage_scrubber_view()?))
+            }
         }
     }
 }
 
 impl ClientActorInner {
+    fn get_flat_storage_scrubber_view(
+        &self,
+    ) -> Result<Vec<FlatStorageScrubberShardView>, near_chain_primitives::Error> {
+        let store = self.client.chain.chain_store().store();
+        let statuses = near_store::flat::get_flat_storage_scrubber_statuses(store)?;
+        Ok(statuses
+            .into_iter()
+            .map(|(shard_uid, status)| FlatStorageScrubberShardView {
+                shard_uid,
+                completed_passes: status.completed_passes,
+                keys_checked_in_pass: status.keys_checked_in_pass,
+                keys_in_last_pass: status.keys_in_last_pass,
+                cursor: status.cursor.map(Into::into),
+                flat_head_height: status.flat_head_height,
+                total_mismatches: status.total_mismatches,
+                recent_mismatches: status
+                    .recent_mismatches
+                    .into_iter()
+                    .map(|mismatch| FlatStorageMismatchView {
+                        kind: <&str>::from(mismatch.kind).to_string(),
+                        key: mismatch.key.into(),
+                        flat_head_height: mismatch.flat_head_height,
+                    })
+                    .collect(),
+            })
+            .collect())
+    }
+
     // Gets a list of block producers and chunk-only producers for a given epoch.
     fn get_producers_for_epoch(
         &self,
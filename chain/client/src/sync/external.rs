@@ -58,6 +58,12 @@ pub enum ExternalConnection {
         reqwest_client: Arc<reqwest::Client>,
         bucket: String,
     },
+    /// Read-only mirror served by a static HTTP server, e.g. nginx or a CDN,
+    /// with the same layout as `Filesystem`.
+    Http {
+        reqwest_client: Arc<reqwest::Client>,
+        base_url: String,
+    },
 }
 
 const GCS_ENCODE_SET: &percent_encoding::AsciiSet =
@@ -110,9 +116,49 @@ impl ExternalConnection {
                     }
                 }
             }
+            ExternalConnection::Http { reqwest_client, base_url } => {
+                let url = format!("{}/{}", base_url.trim_end_matches('/'), location);
+                let bytes = download_http(reqwest_client, &url, false).await?;
+                tracing::debug!(target: "sync", %shard_id, location, num_bytes = bytes.len(), "HTTP request finished");
+                Ok(bytes)
+            }
         }
     }
 
+    /// Like [`Self::get_file`], but fails unless the data passes `is_valid`, e.g. a state part
+    /// matches the state root.  Files of an HTTP mirror are checked as soon as they arrive and
+    /// invalid ones are downloaded again from scratch, bypassing caches, so that a bad mirror
+    /// fails after a few attempts.
+    pub async fn get_verified_file(
+        &self,
+        shard_id: ShardId,
+        location: &str,
+        file_type: &StateFileType,
+        is_valid: impl Fn(&[u8]) -> bool,
+    ) -> Result<Vec<u8>, anyhow::Error> {
+        let ExternalConnection::Http { reqwest_client, base_url } = self else {
+            let data = self.get_file(shard_id, location, file_type).await?;
+            anyhow::ensure!(is_valid(&data), "Invalid {} {location}", file_type.to_string());
+            return Ok(data);
+        };
+        let _timer = metrics::STATE_SYNC_EXTERNAL_PARTS_REQUEST_DELAY
+            .with_label_values(&[&shard_id.to_string(), &file_type.to_string()])
+            .start_timer();
+        let url = format!("{}/{}", base_url.trim_end_matches('/'), location);
+        for attempt in 1..=HTTP_DOWNLOAD_ATTEMPTS {
+            let bytes = download_http(reqwest_client, &url, attempt > 1).await?;
+            if is_valid(&bytes) {
+                tracing::debug!(target: "sync", %shard_id, location, num_bytes = bytes.len(), "HTTP request finished");
+                return Ok(bytes);
+            }
+            tracing::warn!(target: "sync", %shard_id, url, attempt, num_bytes = bytes.len(), "Downloaded invalid {}", file_type.to_string());
+        }
+        anyhow::bail!(
+            "Downloaded invalid {} {url} {HTTP_DOWNLOAD_ATTEMPTS} times",
+            file_type.to_string()
+        )
+    }
+
     /// Uploads the given state part or header to external storage.
     /// Wrapper for adding is_ok to the metric labels.
     pub async fn put_file(
@@ -168,6 +214,9 @@ impl ExternalConnection {
                 tracing::debug!(target: "state_sync_dump", shard_id, part_length = data.len(), ?location, ?file_type, "Wrote a state part to GCS");
                 Ok(())
             }
+            ExternalConnection::Http { .. } => {
+                anyhow::bail!("HTTP external storage is read-only, can't write {location}")
+            }
         }
     }
 
@@ -238,6 +287,9 @@ impl ExternalConnection {
                     .flatten()
                     .collect())
             }
+            ExternalConnection::Http { .. } => {
+                anyhow::bail!("HTTP external storage doesn't support listing {directory_path}")
+            }
         }
     }
 
@@ -272,6 +324,112 @@ impl ExternalConnection {
     }
 }
 
+/// Number of attempts to download a file over HTTP before giving up.
+const HTTP_DOWNLOAD_ATTEMPTS: u32 = 3;
+/// Delay before the first retry of an HTTP download, grows linearly with the attempts.
+const HTTP_RETRY_DELAY: Duration = Duration::from_millis(500);
+
+enum HttpDownloadError {
+    /// The request may succeed if repeated, e.g. the connection was interrupted.
+    Retryable(anyhow::Error),
+    /// Repeating the request makes no sense, e.g. the file doesn't exist.
+    Fatal(anyhow::Error),
+}
+
+/// Downloads the file at `url`. Interrupted downloads are retried, resuming from
+/// the received bytes if the server supports range requests. The length of the
+/// result is checked against the length announced by the server. With
+/// `bypass_cache`, caches on the way are asked to fetch the file from the origin.
+async fn download_http(
+    client: &reqwest::Client,
+    url: &str,
+    bypass_cache: bool,
+) -> Result<Vec<u8>, anyhow::Error> {
+    let mut data = vec![];
+    let mut attempt = 0;
+    loop {
+        attempt += 1;
+        match download_http_attempt(client, url, bypass_cache, &mut data).await {
+            Ok(()) => return Ok(data),
+            Err(HttpDownloadError::Fatal(err)) => return Err(err),
+            Err(HttpDownloadError::Retryable(err)) if attempt >= HTTP_DOWNLOAD_ATTEMPTS => {
+                return Err(err)
+            }
+            Err(HttpDownloadError::Retryable(err)) => {
+                tracing::debug!(target: "sync", url, attempt, received = data.len(), ?err, "Retrying HTTP download");
+                tokio::time::sleep(HTTP_RETRY_DELAY * attempt).await;
+            }
+        }
+    }
+}
+
+async fn download_http_attempt(
+    client: &reqwest::Client,
+    url: &str,
+    bypass_cache: bool,
+    data: &mut Vec<u8>,
+) -> Result<(), HttpDownloadError> {
+    use HttpDownloadError::{Fatal, Retryable};
+
+    let mut request = client.get(url);
+    if bypass_cache {
+        request = request.header(reqwest::header::CACHE_CONTROL, "no-cache");
+    }
+    if !data.is_empty() {
+        request = request.header(reqwest::header::RANGE, format!("bytes={}-", data.len()));
+    }
+    let mut response = request.send().await.map_err(|err| Retryable(err.into()))?;
+    let status = response.status();
+    let expected_length = if status == reqwest::StatusCode::PARTIAL_CONTENT && !data.is_empty() {
+        let content_range = response
+            .headers()
+            .get(reqwest::header::CONTENT_RANGE)
+            .and_then(|value| value.to_str().ok())
+            .and_then(parse_content_range);
+        match content_range {
+            Some((start, total)) if start == data.len() as u64 => Some(total),
+            // The server didn't continue from where we stopped, start over.
+            _ => {
+                data.clear();
+                return Err(Retryable(anyhow::anyhow!(
+                    "Unexpected Content-Range {:?}",
+                    response.headers().get(reqwest::header::CONTENT_RANGE)
+                )));
+            }
+        }
+    } else if status.is_success() {
+        // The server ignored the range or it wasn't requested.
+        data.clear();
+        response.content_length()
+    } else if status.is_client_error()
+        && status != reqwest::StatusCode::REQUEST_TIMEOUT
+        && status != reqwest::StatusCode::TOO_MANY_REQUESTS
+    {
+        return Err(Fatal(anyhow::anyhow!("Bad response status code: {status}")));
+    } else {
+        return Err(Retryable(anyhow::anyhow!("Bad response status code: {status}")));
+    };
+
+    while let Some(chunk) = response.chunk().await.map_err(|err| Retryable(err.into()))? {
+        data.extend_from_slice(&chunk);
+    }
+    match expected_length {
+        Some(expected_length) if expected_length != data.len() as u64 => {
+            let received = data.len();
+            data.clear();
+            Err(Retryable(anyhow::anyhow!("Received {received} bytes, expected {expected_length}")))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Parses `bytes {start}-{end}/{total}` into `(start, total)`.
+fn parse_content_range(value: &str) -> Option<(u64, u64)> {
+    let (range, total) = value.strip_prefix("bytes ")?.split_once('/')?;
+    let (start, _end) = range.split_once('-')?;
+    Some((start.parse().ok()?, total.parse().ok()?))
+}
+
 /// Construct the state file location on the external storage.
 pub fn external_storage_location(
     chain_id: &str,
@@ -351,13 +509,45 @@ pub fn get_part_id_from_filename(s: &str) -> Option<u64> {
     None
 }
 
+/// Location of an S3 or S3-compatible bucket, see `ExternalStorageLocation::S3`.
+#[derive(Debug, Clone, Default)]
+pub struct S3BucketLocation {
+    pub bucket: String,
+    pub region: String,
+    /// Endpoint of an S3-compatible storage. The AWS endpoint of `region` if not set.
+    pub endpoint: Option<String>,
+    /// Whether the bucket name goes to the path instead of the host name.
+    pub path_style: bool,
+}
+
+impl S3BucketLocation {
+    /// Location of a bucket at the AWS endpoint of `region`.
+    pub fn aws(bucket: &str, region: &str) -> Self {
+        Self { bucket: bucket.to_string(), region: region.to_string(), ..Default::default() }
+    }
+
+    fn region(&self) -> Result<s3::Region, anyhow::Error> {
+        Ok(match &self.endpoint {
+            Some(endpoint) => {
+                s3::Region::Custom { region: self.region.clone(), endpoint: endpoint.clone() }
+            }
+            None => self.region.parse::<s3::Region>()?,
+        })
+    }
+}
+
+/// Creates a bucket for fetching state parts. Requests are anonymous unless
+/// `credentials_file` is given.
 pub fn create_bucket_readonly(
-    bucket: &str,
-    region: &str,
+    location: &S3BucketLocation,
     timeout: Duration,
+    credentials_file: Option<PathBuf>,
 ) -> Result<s3::Bucket, anyhow::Error> {
-    let creds = s3::creds::Credentials::anonymous()?;
-    create_bucket(bucket, region, timeout, creds)
+    let creds = match credentials_file {
+        Some(credentials_file) => read_credentials_file(credentials_file)?,
+        None => s3::creds::Credentials::anonymous()?,
+    };
+    create_bucket(location, timeout, creds)
 }
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
@@ -366,38 +556,47 @@ struct S3CredentialsConfig {
     secret_key: String,
 }
 
+/// Creates a bucket for dumping state parts. The credentials are taken from
+/// the environment unless `credentials_file` is given.
 pub fn create_bucket_readwrite(
-    bucket: &str,
-    region: &str,
+    location: &S3BucketLocation,
     timeout: Duration,
     credentials_file: Option<PathBuf>,
 ) -> Result<s3::Bucket, anyhow::Error> {
     let creds = match credentials_file {
-        Some(credentials_file) => {
-            let mut file = std::fs::File::open(credentials_file)?;
-            let mut json_config_str = String::new();
-            file.read_to_string(&mut json_config_str)?;
-            let credentials_config: S3CredentialsConfig = serde_json::from_str(&json_config_str)?;
-            s3::creds::Credentials::new(
-                Some(&credentials_config.access_key),
-                Some(&credentials_config.secret_key),
-                None,
-                None,
-                None,
-            )
-        }
-        None => s3::creds::Credentials::default(),
-    }?;
-    create_bucket(bucket, region, timeout, creds)
+        Some(credentials_file) => read_credentials_file(credentials_file)?,
+        None => s3::creds::Credentials::default()?,
+    };
+    create_bucket(location, timeout, creds)
+}
+
+fn read_credentials_file(
+    credentials_file: PathBuf,
+) -> Result<s3::creds::Credentials, anyhow::Error> {
+    let mut file = std::fs::File::open(credentials_file)?;
+    let mut json_config_str = String::new();
+    file.read_to_string(&mut json_config_str)?;
+    let credentials_config: S3CredentialsConfig = serde_json::from_str(&json_config_str)?;
+    Ok(s3::creds::Credentials::new(
+        Some(&credentials_config.access_key),
+        Some(&credentials_config.secret_key),
+        None,
+        None,
+        None,
+    )?)
 }
 
 fn create_bucket(
-    bucket: &str,
-    region: &str,
+    location: &S3BucketLocation,
     timeout: Duration,
     creds: s3::creds::Credentials,
 ) -> Result<s3::Bucket, anyhow::Error> {
-    let mut bucket = s3::Bucket::new(bucket, region.parse::<s3::Region>()?, creds)?;
+    let region = location.region()?;
+    let mut bucket = if location.path_style {
+        s3::Bucket::new_with_path_style(&location.bucket, region, creds)?
+    } else {
+        s3::Bucket::new(&location.bucket, region, creds)?
+    };
     // Ensure requests finish in finite amount of time.
     bucket.set_request_timeout(Some(timeout));
     Ok(bucket)
@@ -406,11 +605,17 @@ fn create_bucket(
 #[cfg(test)]
 mod test {
     use crate::sync::external::{
-        get_num_parts_from_filename, get_part_id_from_filename, is_part_filename,
-        ExternalConnection, StateFileType,
+        create_bucket_readonly, create_bucket_readwrite, get_num_parts_from_filename,
+        get_part_id_from_filename, is_part_filename, parse_content_range, ExternalConnection,
+        S3BucketLocation, StateFileType, HTTP_DOWNLOAD_ATTEMPTS,
     };
     use near_o11y::testonly::init_test_logger;
     use rand::distributions::{Alphanumeric, DistString};
+    use std::collections::BTreeMap;
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::{TcpListener, TcpStream};
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
 
     fn random_string(rand_len: usize) -> String {
         Alphanumeric.sample_string(&mut rand::thread_rng(), rand_len)
@@ -487,4 +692,354 @@ mod test {
             rt.block_on(async { connection.get_file(0, &full_filename, &file_type).await });
         assert!(download_data.is_err(), "{:?}", download_data);
     }
+
+    /// Request received by the local stand-in servers, which serve one request per connection.
+    struct LocalRequest {
+        method: String,
+        target: String,
+        /// Headers with lowercase names.
+        headers: BTreeMap<String, String>,
+        body: Vec<u8>,
+    }
+
+    fn read_request(stream: &TcpStream) -> LocalRequest {
+        let mut reader = BufReader::new(stream);
+        let mut request_line = String::new();
+        reader.read_line(&mut request_line).unwrap();
+        let mut parts = request_line.split_whitespace();
+        let method = parts.next().unwrap_or_default().to_string();
+        let target = parts.next().unwrap_or_default().to_string();
+        let mut headers = BTreeMap::new();
+        loop {
+            let mut line = String::new();
+            reader.read_line(&mut line).unwrap();
+            let line = line.trim_end();
+            if line.is_empty() {
+                break;
+            }
+            let (name, value) = line.split_once(':').unwrap();
+            headers.insert(name.to_ascii_lowercase(), value.trim().to_string());
+        }
+        let content_length =
+            headers.get("content-length").map_or(0, |length| length.parse().unwrap());
+        let mut body = vec![0; content_length];
+        reader.read_exact(&mut body).unwrap();
+        LocalRequest { method, target, headers, body }
+    }
+
+    fn write_response(
+        mut stream: &TcpStream,
+        status: &str,
+        headers: &[(&str, String)],
+        content_length: usize,
+        body: &[u8],
+    ) {
+        write!(stream, "HTTP/1.1 {}\r\nContent-Length: {}\r\n", status, content_length).unwrap();
+        for (name, value) in headers {
+            write!(stream, "{}: {}\r\n", name, value).unwrap();
+        }
+        write!(stream, "Connection: close\r\n\r\n").unwrap();
+        // Errors are expected if the client gives up on a truncated response.
+        let _ = stream.write_all(body);
+    }
+
+    /// Stand-in for a self-hosted S3-compatible storage. Keeps objects in memory and serves
+    /// path-style `GetObject`, `PutObject` and `ListObjectsV2` requests of a single bucket.
+    struct LocalS3Server {
+        endpoint: String,
+        objects: Arc<Mutex<BTreeMap<String, Vec<u8>>>>,
+        /// `Authorization` header of every request, if any.
+        authorizations: Arc<Mutex<Vec<Option<String>>>>,
+    }
+
+    impl LocalS3Server {
+        fn start(bucket: &str) -> Self {
+            let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+            let endpoint = format!("http://{}", listener.local_addr().unwrap());
+            let objects = Arc::new(Mutex::new(BTreeMap::new()));
+            let authorizations = Arc::new(Mutex::new(vec![]));
+            let bucket_path = format!("/{}/", bucket);
+            let (server_objects, server_authorizations) = (objects.clone(), authorizations.clone());
+            std::thread::spawn(move || {
+                for stream in listener.incoming() {
+                    let Ok(stream) = stream else { break };
+                    Self::handle(stream, &bucket_path, &server_objects, &server_authorizations);
+                }
+            });
+            Self { endpoint, objects, authorizations }
+        }
+
+        fn handle(
+            stream: TcpStream,
+            bucket_path: &str,
+            objects: &Mutex<BTreeMap<String, Vec<u8>>>,
+            authorizations: &Mutex<Vec<Option<String>>>,
+        ) {
+            let request = read_request(&stream);
+            authorizations.lock().unwrap().push(request.headers.get("authorization").cloned());
+
+            let (path, query) = request.target.split_once('?').unwrap_or((&request.target, ""));
+            let query: BTreeMap<String, String> = query
+                .split('&')
+                .filter_map(|pair| pair.split_once('='))
+                .map(|(name, value)| (percent_decode(name), percent_decode(value)))
+                .collect();
+            let (status, response) = match path.strip_prefix(bucket_path) {
+                // Only path-style requests reach the bucket.
+                None => ("404 Not Found", b"<Error><Code>NoSuchBucket</Code></Error>".to_vec()),
+                Some(key) => {
+                    let key = percent_decode(key);
+                    let mut objects = objects.lock().unwrap();
+                    match request.method.as_str() {
+                        "PUT" => {
+                            objects.insert(key, request.body);
+                            ("200 OK", vec![])
+                        }
+                        "GET" if query.get("list-type").map(String::as_str) == Some("2") => {
+                            ("200 OK", list_objects_response(&objects, &query).into_bytes())
+                        }
+                        "GET" => match objects.get(&key) {
+                            Some(data) => ("200 OK", data.clone()),
+                            None => {
+                                ("404 Not Found", b"<Error><Code>NoSuchKey</Code></Error>".to_vec())
+                            }
+                        },
+                        _ => ("405 Method Not Allowed", vec![]),
+                    }
+                }
+            };
+            let headers = [("ETag", "\"etag\"".to_string())];
+            write_response(&stream, status, &headers, response.len(), &response);
+        }
+    }
+
+    /// Stand-in for a static HTTP server or CDN serving files with support of range requests.
+    /// The first `interrupted_responses` responses are cut in the middle of the body.
+    struct LocalHttpServer {
+        base_url: String,
+        /// `Range` header of every request, if any.
+        ranges: Arc<Mutex<Vec<Option<String>>>>,
+        /// `Cache-Control` header of every request, if any.
+        cache_controls: Arc<Mutex<Vec<Option<String>>>>,
+    }
+
+    impl LocalHttpServer {
+        fn start(files: BTreeMap<String, Vec<u8>>, interrupted_responses: usize) -> Self {
+            let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+            let base_url = format!("http://{}/state-parts", listener.local_addr().unwrap());
+            let ranges = Arc::new(Mutex::new(vec![]));
+            let server_ranges = ranges.clone();
+            let cache_controls = Arc::new(Mutex::new(vec![]));
+            let server_cache_controls = cache_controls.clone();
+            std::thread::spawn(move || {
+                for (idx, stream) in listener.incoming().enumerate() {
+                    let Ok(stream) = stream else { break };
+                    let request = read_request(&stream);
+                    let range = request.headers.get("range").cloned();
+                    server_ranges.lock().unwrap().push(range.clone());
+                    server_cache_controls
+                        .lock()
+                        .unwrap()
+                        .push(request.headers.get("cache-control").cloned());
+                    let path = request.target.strip_prefix("/state-parts/").unwrap_or_default();
+                    let Some(data) = files.get(&percent_decode(path)) else {
+                        write_response(&stream, "404 Not Found", &[], 0, &[]);
+                        continue;
+                    };
+                    let start = range
+                        .and_then(|range| {
+                            range.strip_prefix("bytes=")?.strip_suffix('-')?.parse().ok()
+                        })
+                        .unwrap_or(0);
+                    let (status, headers) = if start > 0 {
+                        let content_range =
+                            format!("bytes {}-{}/{}", start, data.len() - 1, data.len());
+                        ("206 Partial Content", vec![("Content-Range", content_range)])
+                    } else {
+                        ("200 OK", vec![])
+                    };
+                    let body = &data[start..];
+                    let sent =
+                        if idx < interrupted_responses { &body[..body.len() / 2] } else { body };
+                    write_response(&stream, status, &headers, body.len(), sent);
+                }
+            });
+            Self { base_url, ranges, cache_controls }
+        }
+    }
+
+    fn percent_decode(s: &str) -> String {
+        percent_encoding::percent_decode_str(s).decode_utf8().unwrap().to_string()
+    }
+
+    fn list_objects_response(
+        objects: &BTreeMap<String, Vec<u8>>,
+        query: &BTreeMap<String, String>,
+    ) -> String {
+        let prefix = query.get("prefix").cloned().unwrap_or_default();
+        let contents = objects
+            .iter()
+            .filter(|(key, _)| {
+                key.strip_prefix(&prefix).is_some_and(|name| !name.is_empty() && !name.contains('/'))
+            })
+            .map(|(key, data)| {
+                format!(
+                    "<Contents><Key>{}</Key><LastModified>2024-01-01T00:00:00.000Z</LastModified><ETag>\"etag\"</ETag><Size>{}</Size><StorageClass>STANDARD</StorageClass></Contents>",
+                    key,
+                    data.len()
+                )
+            })
+            .collect::<String>();
+        format!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?><ListBucketResult><Name>state-parts</Name><Prefix>{}</Prefix><MaxKeys>1000</MaxKeys><Delimiter>/</Delimiter><IsTruncated>false</IsTruncated>{}</ListBucketResult>",
+            prefix, contents
+        )
+    }
+
+    #[test]
+    fn test_s3_compatible_endpoint() {
+        init_test_logger();
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let server = LocalS3Server::start("state-parts");
+        let location = S3BucketLocation {
+            bucket: "state-parts".to_string(),
+            region: "local".to_string(),
+            endpoint: Some(server.endpoint.clone()),
+            path_style: true,
+        };
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let credentials_file = tmp_dir.path().join("credentials.json");
+        std::fs::write(
+            &credentials_file,
+            r#"{"access_key": "local-access-key", "secret_key": "local-secret-key"}"#,
+        )
+        .unwrap();
+
+        let writer = ExternalConnection::S3 {
+            bucket: Arc::new(
+                create_bucket_readwrite(&location, Duration::from_secs(5), Some(credentials_file))
+                    .unwrap(),
+            ),
+        };
+        let reader = ExternalConnection::S3 {
+            bucket: Arc::new(
+                create_bucket_readonly(&location, Duration::from_secs(5), None).unwrap(),
+            ),
+        };
+
+        let dir = "chain_id=test/epoch_height=1/epoch_id=test/shard_id=0";
+        let file_type = StateFileType::StatePart { part_id: 0, num_parts: 1 };
+        let full_filename = format!("{}/{}", dir, file_type.filename());
+        let data = random_string(1000).into_bytes();
+        rt.block_on(async {
+            writer.put_file(file_type.clone(), &data, 0, &full_filename).await.unwrap();
+        });
+        assert_eq!(server.objects.lock().unwrap().get(&full_filename), Some(&data));
+        // Dumping requests are signed with the credentials from the file.
+        let authorizations = server.authorizations.lock().unwrap().clone();
+        assert_eq!(authorizations.len(), 1);
+        assert!(authorizations[0]
+            .as_ref()
+            .is_some_and(|auth| auth.contains("Credential=local-access-key/")));
+
+        let files = rt.block_on(async { writer.list_objects(0, dir).await.unwrap() });
+        assert_eq!(files, vec![file_type.filename()]);
+
+        let downloaded =
+            rt.block_on(async { reader.get_file(0, &full_filename, &file_type).await });
+        assert_eq!(downloaded.unwrap(), data);
+        // Fetching is anonymous.
+        assert_eq!(server.authorizations.lock().unwrap().last().unwrap(), &None);
+
+        let missing = format!("{}/{}", dir, random_string(8));
+        let downloaded = rt.block_on(async { reader.get_file(0, &missing, &file_type).await });
+        assert!(downloaded.is_err(), "{:?}", downloaded);
+    }
+
+    #[test]
+    fn test_http_download() {
+        init_test_logger();
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let dir = "chain_id=test/epoch_height=1/epoch_id=test/shard_id=0";
+        let file_type = StateFileType::StatePart { part_id: 0, num_parts: 1 };
+        let full_filename = format!("{}/{}", dir, file_type.filename());
+        let data = random_string(100_000).into_bytes();
+        let files = BTreeMap::from([(full_filename.clone(), data.clone())]);
+
+        // The first response is interrupted, the download is resumed from where it stopped.
+        let server = LocalHttpServer::start(files.clone(), 1);
+        let connection = ExternalConnection::Http {
+            reqwest_client: Arc::new(reqwest::Client::default()),
+            base_url: server.base_url.clone(),
+        };
+        let downloaded =
+            rt.block_on(async { connection.get_file(0, &full_filename, &file_type).await });
+        assert_eq!(downloaded.unwrap(), data);
+        let ranges = server.ranges.lock().unwrap().clone();
+        assert_eq!(ranges.len(), 2);
+        assert_eq!(ranges[0], None);
+        assert!(ranges[1].is_some(), "{:?}", ranges);
+
+        // Gives up after all the attempts are interrupted.
+        let server = LocalHttpServer::start(files, HTTP_DOWNLOAD_ATTEMPTS as usize);
+        let connection = ExternalConnection::Http {
+            reqwest_client: Arc::new(reqwest::Client::default()),
+            base_url: server.base_url.clone(),
+        };
+        let downloaded =
+            rt.block_on(async { connection.get_file(0, &full_filename, &file_type).await });
+        assert!(downloaded.is_err(), "{:?}", downloaded);
+        assert_eq!(server.ranges.lock().unwrap().len(), HTTP_DOWNLOAD_ATTEMPTS as usize);
+
+        // Missing files are not retried.
+        let missing = format!("{}/{}", dir, random_string(8));
+        let ranges_before = server.ranges.lock().unwrap().len();
+        let downloaded = rt.block_on(async { connection.get_file(0, &missing, &file_type).await });
+        assert!(downloaded.is_err(), "{:?}", downloaded);
+        assert_eq!(server.ranges.lock().unwrap().len(), ranges_before + 1);
+
+        // Writing is not supported.
+        let written =
+            rt.block_on(async { connection.put_file(file_type.clone(), &data, 0, &missing).await });
+        assert!(written.is_err());
+    }
+
+    #[test]
+    fn test_http_download_verified() {
+        init_test_logger();
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let dir = "chain_id=test/epoch_height=1/epoch_id=test/shard_id=0";
+        let file_type = StateFileType::StatePart { part_id: 0, num_parts: 1 };
+        let full_filename = format!("{}/{}", dir, file_type.filename());
+        let data = random_string(1000).into_bytes();
+        let files = BTreeMap::from([(full_filename.clone(), data.clone())]);
+        let server = LocalHttpServer::start(files, 0);
+        let connection = ExternalConnection::Http {
+            reqwest_client: Arc::new(reqwest::Client::default()),
+            base_url: server.base_url.clone(),
+        };
+
+        let downloaded = rt.block_on(async {
+            connection.get_verified_file(0, &full_filename, &file_type, |d| d == data).await
+        });
+        assert_eq!(downloaded.unwrap(), data);
+        assert_eq!(server.cache_controls.lock().unwrap().clone(), vec![None]);
+
+        // An invalid file is downloaded again bypassing caches and rejected in the end.
+        let downloaded = rt.block_on(async {
+            connection.get_verified_file(0, &full_filename, &file_type, |d| d != data).await
+        });
+        assert!(downloaded.is_err(), "{:?}", downloaded);
+        let cache_controls = server.cache_controls.lock().unwrap().clone();
+        assert_eq!(cache_controls.len(), 1 + HTTP_DOWNLOAD_ATTEMPTS as usize);
+        assert_eq!(cache_controls[1], None);
+        assert!(cache_controls[2..].iter().all(|c| c.as_deref() == Some("no-cache")));
+    }
+
+    #[test]
+    fn test_parse_content_range() {
+        assert_eq!(parse_content_range("bytes 100-999/1000"), Some((100, 1000)));
+        assert_eq!(parse_content_range("bytes */1000"), None);
+        assert_eq!(parse_content_range("100-999/1000"), None);
+    }
 }
@@ -22,7 +22,7 @@
 
 use crate::metrics;
 use crate::sync::external::{
-    create_bucket_readonly, external_storage_location, ExternalConnection,
+    create_bucket_readonly, external_storage_location, ExternalConnection, S3BucketLocation,
 };
 use borsh::BorshDeserialize;
 use futures::{future, FutureExt};
@@ -182,11 +182,23 @@ impl StateSync {
                 num_concurrent_requests_during_catchup,
             }) => {
                 let external = match location {
-                    ExternalStorageLocation::S3 { bucket, region, .. } => {
+                    ExternalStorageLocation::S3 {
+                        bucket,
+                        region,
+                        endpoint,
+                        path_style,
+                        credentials_file,
+                    } => {
+                        let location = S3BucketLocation {
+                            bucket: bucket.clone(),
+                            region: region.clone(),
+                            endpoint: endpoint.clone(),
+                            path_style: *path_style,
+                        };
                         let bucket = create_bucket_readonly(
-                            &bucket,
-                            &region,
+                            &location,
                             timeout.max(Duration::ZERO).unsigned_abs(),
+                            credentials_file.clone(),
                         );
                         if let Err(err) = bucket {
                             panic!("Failed to create an S3 bucket: {}", err);
@@ -201,6 +213,15 @@ impl StateSync {
                         reqwest_client: Arc::new(reqwest::Client::default()),
                         bucket: bucket.clone(),
                     },
+                    ExternalStorageLocation::Http { base_url } => ExternalConnection::Http {
+                        reqwest_client: Arc::new(
+                            reqwest::Client::builder()
+                                .timeout(timeout.max(Duration::ZERO).unsigned_abs())
+                                .build()
+                                .expect("Failed to create an HTTP client"),
+                        ),
+                        base_url: base_url.clone(),
+                    },
                 };
                 let num_permits = if catchup {
                     *num_concurrent_requests_during_catchup
@@ -656,6 +677,7 @@ impl StateSync {
                     epoch_height,
                     &chain_id.clone(),
                     external.clone(),
+                    chain.runtime_adapter.clone(),
                     state_parts_future_spawner,
                     self.state_parts_mpsc_tx.clone(),
                 );
@@ -1190,9 +1212,18 @@ async fn download_header_from_external_storage(
     sync_hash: CryptoHash,
     location: String,
     external: ExternalConnection,
+    runtime_adapter: Arc<dyn RuntimeAdapter>,
 ) -> Result<StateSyncFileDownloadResult, std::string::String> {
+    // The header is checked against the state root it declares, the chunk is checked by the
+    // chain once the header is set.
+    let is_valid = |data: &[u8]| {
+        ShardStateSyncResponseHeader::try_from_slice(data).is_ok_and(|header| {
+            runtime_adapter
+                .validate_state_root_node(header.state_root_node(), &header.chunk_prev_state_root())
+        })
+    };
     external
-    .get_file(shard_id, &location, &StateFileType::StateHeader)
+    .get_verified_file(shard_id, &location, &StateFileType::StateHeader, is_valid)
     .await
     .map_err(|err| err.to_string())
     .and_then(|data| {
@@ -1216,6 +1247,7 @@ fn request_header_from_external_storage(
     epoch_height: EpochHeight,
     chain_id: &str,
     external: ExternalConnection,
+    runtime_adapter: Arc<dyn RuntimeAdapter>,
     state_parts_future_spawner: &dyn FutureSpawner,
     state_parts_mpsc_tx: Sender<StateSyncGetFileResult>,
 ) {
@@ -1236,7 +1268,7 @@ fn request_header_from_external_storage(
     state_parts_future_spawner.spawn(
         "download_header_from_external_storage",
         async move {
-            let result = download_header_from_external_storage(shard_id, sync_hash, location, external).await;
+            let result = download_header_from_external_storage(shard_id, sync_hash, location, external, runtime_adapter).await;
             match state_parts_mpsc_tx.send(StateSyncGetFileResult {
                 sync_hash,
                 shard_id,
@@ -1262,25 +1294,22 @@ async fn download_and_store_part_from_external_storage(
     external: ExternalConnection,
     runtime_adapter: Arc<dyn RuntimeAdapter>,
 ) -> Result<StateSyncFileDownloadResult, String> {
+    let is_valid = |data: &[u8]| runtime_adapter.validate_state_part(&state_root, part_id, data);
     external
-    .get_file(shard_id, &location, file_type)
+    .get_verified_file(shard_id, &location, file_type, is_valid)
     .await
-    .map_err(|err| err.to_string())
+    .map_err(|err| format!("{err}. state_root={state_root:?}, part_id={part_id:?}, shard_id={shard_id}"))
     .and_then(|data|  {
         info!(target: "sync", ?shard_id, ?part_id, "downloaded state part");
-        if runtime_adapter.validate_state_part(&state_root, part_id, &data) {
-            let mut store_update = runtime_adapter.store().store_update();
-            borsh::to_vec(&StatePartKey(sync_hash, shard_id, part_id.idx))
-            .and_then(|key| {
-                store_update.set(DBCol::StateParts, &key, &data);
-                store_update.commit()
-            })
-            .map_err(|err| format!("Failed to store a state part. err={err:?}, state_root={state_root:?}, part_id={part_id:?}, shard_id={shard_id:?}"))
-            .map(|_| data.len() as u64)
-            .map(|part_length| StateSyncFileDownloadResult::StatePart { part_length })
-        } else {
-            Err(format!("validate_state_part failed. state_root={state_root:?}, part_id={part_id:?}, shard_id={shard_id}"))
-        }
+        let mut store_update = runtime_adapter.store().store_update();
+        borsh::to_vec(&StatePartKey(sync_hash, shard_id, part_id.idx))
+        .and_then(|key| {
+            store_update.set(DBCol::StateParts, &key, &data);
+            store_update.commit()
+        })
+        .map_err(|err| format!("Failed to store a state part. err={err:?}, state_root={state_root:?}, part_id={part_id:?}, shard_id={shard_id:?}"))
+        .map(|_| data.len() as u64)
+        .map(|part_length| StateSyncFileDownloadResult::StatePart { part_length })
     })
 }
 /// Starts an asynchronous network request to external storage to fetch the given state part.
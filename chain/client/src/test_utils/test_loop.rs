@@ -94,6 +94,7 @@ where
                 account_id: account_id.clone(),
                 method_name: method.to_string(),
                 args: args.to_vec().into(),
+                include_gas_profile: false,
             },
         );
         if let QueryResponseKind::CallResult(call_result) = response.kind {
@@ -22,7 +22,8 @@ use near_client_primitives::types::{
     GetMaintenanceWindowsError, GetNextLightClientBlockError, GetProtocolConfig,
     GetProtocolConfigError, GetReceipt, GetReceiptError, GetSplitStorageInfo,
     GetSplitStorageInfoError, GetStateChangesError, GetStateChangesWithCauseInBlock,
-    GetStateChangesWithCauseInBlockForTrackedShards, GetValidatorInfoError, Query, QueryError,
+    GetStateChangesWithCauseInBlockForTrackedShards, GetStateDiff, GetStateDiffError,
+    GetValidatorInfoError, Query, QueryError, SimulateTransaction, SimulateTransactionError,
     TxStatus, TxStatusError,
 };
 use near_epoch_manager::shard_tracker::ShardTracker;
@@ -38,6 +39,7 @@ use near_network::types::{
 use near_performance_metrics_macros::perf;
 use near_primitives::block::{Block, BlockHeader};
 use near_primitives::epoch_manager::epoch_info::EpochInfo;
+use near_primitives::errors::StorageError;
 use near_primitives::hash::CryptoHash;
 use near_primitives::merkle::{merklize, PartialMerkleTree};
 use near_primitives::network::AnnounceAccount;
@@ -49,7 +51,7 @@ use near_primitives::state_sync::{
 use near_primitives::transaction::SignedTransaction;
 use near_primitives::types::{
     AccountId, BlockHeight, BlockId, BlockReference, EpochReference, Finality, MaybeBlockId,
-    ShardId, SyncCheckpoint, TransactionOrReceiptId, ValidatorInfoIdentifier,
+    ShardId, StateChangesRequest, SyncCheckpoint, TransactionOrReceiptId, ValidatorInfoIdentifier,
 };
 use near_primitives::validator_signer::ValidatorSigner;
 use near_primitives::views::validator_stake_view::ValidatorStakeView;
@@ -58,10 +60,13 @@ use near_primitives::views::{
     FinalExecutionOutcomeView, FinalExecutionOutcomeViewEnum, FinalExecutionStatus, GasPriceView,
     LightClientBlockView, MaintenanceWindowsView, QueryRequest, QueryResponse, ReceiptView,
     SignedTransactionView, SplitStorageInfoView, StateChangesKindsView, StateChangesView,
-    TxExecutionStatus, TxStatusView,
+    StateDiffItemView, StateDiffView, TransactionSimulationView, TxExecutionStatus, TxStatusView,
+    MAX_STATE_PROOF_KEYS,
 };
 use near_store::flat::{FlatStorageReadyStatus, FlatStorageStatus};
-use near_store::{DBCol, COLD_HEAD_KEY, FINAL_HEAD_KEY, HEAD_KEY};
+use near_store::{
+    DBCol, TrieDiffLimits, COLD_HEAD_KEY, COLD_TAIL_KEY, FINAL_HEAD_KEY, HEAD_KEY, TAIL_KEY,
+};
 use std::cmp::Ordering;
 use std::collections::{BTreeSet, HashMap, HashSet, VecDeque};
 use std::hash::Hash;
@@ -336,11 +341,48 @@ impl ViewClientActorInner {
             QueryRequest::ViewAccessKeyList { account_id, .. } => account_id,
             QueryRequest::CallFunction { account_id, .. } => account_id,
             QueryRequest::ViewCode { account_id, .. } => account_id,
+            QueryRequest::ViewStateProof { keys } if keys.len() > MAX_STATE_PROOF_KEYS => {
+                return Err(QueryError::InvalidStateProofKeys {
+                    error_message: format!(
+                        "{} keys requested, at most {} are allowed",
+                        keys.len(),
+                        MAX_STATE_PROOF_KEYS
+                    ),
+                })
+            }
+            QueryRequest::ViewStateProof { keys } => match keys.first() {
+                Some(key) => key.account_id(),
+                None => {
+                    return Err(QueryError::InvalidStateProofKeys {
+                        error_message: "no keys requested".to_string(),
+                    })
+                }
+            },
         };
         let shard_id = self
             .epoch_manager
             .account_id_to_shard_id(account_id, header.epoch_id())
             .map_err(|err| QueryError::InternalError { error_message: err.to_string() })?;
+        if let QueryRequest::ViewStateProof { keys } = &msg.request {
+            // The proof is built against the state root of a single shard.
+            for key in keys {
+                let key_shard_id = self
+                    .epoch_manager
+                    .account_id_to_shard_id(key.account_id(), header.epoch_id())
+                    .map_err(|err| QueryError::InternalError { error_message: err.to_string() })?;
+                if key_shard_id != shard_id {
+                    return Err(QueryError::InvalidStateProofKeys {
+                        error_message: format!(
+                            "account {} belongs to shard {}, while {} belongs to shard {}",
+                            key.account_id(),
+                            key_shard_id,
+                            account_id,
+                            shard_id
+                        ),
+                    });
+                }
+            }
+        }
         let shard_uid = self
             .epoch_manager
             .shard_id_to_uid(shard_id, header.epoch_id())
@@ -424,6 +466,11 @@ impl ViewClientActorInner {
                     block_height,
                     block_hash,
                 },
+                near_chain::near_chain_primitives::error::QueryError::TooLargeStateProof {
+                    limit,
+                    block_height,
+                    block_hash,
+                } => QueryError::TooLargeStateProof { limit, block_height, block_hash },
             }),
         }
     }
@@ -924,8 +971,28 @@ impl Handler<GetStateChangesWithCauseInBlockForTrackedShards> for ViewClientActo
         let _timer = metrics::VIEW_CLIENT_MESSAGE_TIME
             .with_label_values(&["GetStateChangesWithCauseInBlockForTrackedShards"])
             .start_timer();
-        let state_changes_with_cause_in_block =
-            self.chain.chain_store().get_state_changes_with_cause_in_block(&msg.block_hash)?;
+        let state_changes_with_cause_in_block = match msg.account_ids {
+            Some(account_ids) => {
+                // Looks up the keys of the accounts instead of reading all the changes of the
+                // block, which matters on blocks touching lots of accounts.
+                let requests = [
+                    StateChangesRequest::AccountChanges { account_ids: account_ids.clone() },
+                    StateChangesRequest::AllAccessKeyChanges { account_ids: account_ids.clone() },
+                    StateChangesRequest::ContractCodeChanges { account_ids: account_ids.clone() },
+                    StateChangesRequest::DataChanges { account_ids, key_prefix: vec![].into() },
+                ];
+                let mut state_changes = vec![];
+                for request in &requests {
+                    state_changes.extend(
+                        self.chain.chain_store().get_state_changes(&msg.block_hash, request)?,
+                    );
+                }
+                state_changes
+            }
+            None => {
+                self.chain.chain_store().get_state_changes_with_cause_in_block(&msg.block_hash)?
+            }
+        };
 
         let mut state_changes_with_cause_split_by_shard_id: HashMap<ShardId, StateChangesView> =
             HashMap::new();
@@ -1503,14 +1570,181 @@ impl Handler<GetSplitStorageInfo> for ViewClientActorInner {
         let head = store.get_ser::<Tip>(DBCol::BlockMisc, HEAD_KEY)?;
         let final_head = store.get_ser::<Tip>(DBCol::BlockMisc, FINAL_HEAD_KEY)?;
         let cold_head = store.get_ser::<Tip>(DBCol::BlockMisc, COLD_HEAD_KEY)?;
+        let cold_tail = store.get_ser::<BlockHeight>(DBCol::BlockMisc, COLD_TAIL_KEY)?;
+        let hot_tail = store.get_ser::<BlockHeight>(DBCol::BlockMisc, TAIL_KEY)?;
 
         let hot_db_kind = store.get_db_kind()?.map(|kind| kind.to_string());
 
+        // Without TAIL (archival nodes) or COLD_TAIL (unpruned cold storage)
+        // everything since genesis is available.  With cold storage, the
+        // blocks between cold tail and hot tail are in cold storage.
+        let genesis_height = self.chain.genesis().height();
+        let hot_tail_height = hot_tail.unwrap_or(genesis_height);
+        let earliest_available_height = match cold_head {
+            Some(_) => cold_tail.unwrap_or(genesis_height).min(hot_tail_height),
+            None => hot_tail_height,
+        };
+
         Ok(SplitStorageInfoView {
             head_height: head.map(|tip| tip.height),
             final_head_height: final_head.map(|tip| tip.height),
             cold_head_height: cold_head.map(|tip| tip.height),
+            cold_tail_height: cold_tail,
+            earliest_available_height: Some(earliest_available_height),
             hot_db_kind,
         })
     }
 }
+
+impl Handler<GetStateDiff> for ViewClientActorInner {
+    #[perf]
+    fn handle(&mut self, msg: GetStateDiff) -> Result<StateDiffView, GetStateDiffError> {
+        tracing::debug!(target: "client", ?msg);
+        let _timer =
+            metrics::VIEW_CLIENT_MESSAGE_TIME.with_label_values(&["GetStateDiff"]).start_timer();
+
+        let from_header = self.maybe_block_id_to_block_header(Some(msg.from_block_id))?;
+        let to_header = self.maybe_block_id_to_block_header(Some(msg.to_block_id))?;
+        let shard_id = match (&msg.account_id, msg.shard_id) {
+            (Some(account_id), shard_id) => {
+                let account_shard_id = self
+                    .epoch_manager
+                    .account_id_to_shard_id(account_id, to_header.epoch_id())
+                    .into_chain_error()?;
+                if shard_id.is_some_and(|shard_id| shard_id != account_shard_id) {
+                    return Err(GetStateDiffError::InvalidRequest {
+                        error_message: format!(
+                            "account {account_id} belongs to shard {account_shard_id}"
+                        ),
+                    });
+                }
+                account_shard_id
+            }
+            (None, Some(shard_id)) => shard_id,
+            (None, None) => {
+                return Err(GetStateDiffError::InvalidRequest {
+                    error_message: "either shard_id or account_id must be set".to_string(),
+                })
+            }
+        };
+        let shard_uid = self
+            .epoch_manager
+            .shard_id_to_uid(shard_id, to_header.epoch_id())
+            .into_chain_error()?;
+        let from_shard_uid = self
+            .epoch_manager
+            .shard_id_to_uid(shard_id, from_header.epoch_id())
+            .into_chain_error()?;
+        if from_shard_uid != shard_uid {
+            return Err(GetStateDiffError::InvalidRequest {
+                error_message: format!(
+                    "shard {shard_id} is {from_shard_uid} at the first block but {shard_uid} at the second one"
+                ),
+            });
+        }
+
+        let get_state_root = |header: &BlockHeader| match self
+            .chain
+            .get_chunk_extra(header.hash(), &shard_uid)
+        {
+            Ok(chunk_extra) => Ok(*chunk_extra.state_root()),
+            Err(near_chain::Error::DBNotFoundErr(_)) => Err(GetStateDiffError::UnavailableState {
+                error_message: format!(
+                    "shard {shard_uid} at block {} is not tracked or was garbage collected",
+                    header.hash()
+                ),
+            }),
+            Err(err) => Err(err.into()),
+        };
+        let from_state_root = get_state_root(&from_header)?;
+        let to_state_root = get_state_root(&to_header)?;
+
+        let trie = self.runtime.get_tries().get_view_trie_for_shard(shard_uid, from_state_root);
+        let start_key = msg.start_key.as_deref().unwrap_or_default();
+        let limits =
+            TrieDiffLimits { max_changes: Some(msg.limit), max_bytes: Some(msg.max_bytes) };
+        let diff = match &msg.account_id {
+            Some(account_id) => {
+                trie.diff_for_account(&to_state_root, account_id, start_key, limits)
+            }
+            None => trie.diff(&to_state_root, &[], start_key, limits),
+        }
+        .map_err(|err| match err {
+            StorageError::MissingTrieValue(..) => {
+                GetStateDiffError::UnavailableState { error_message: err.to_string() }
+            }
+            _ => GetStateDiffError::Unreachable(err.to_string()),
+        })?;
+
+        Ok(StateDiffView {
+            shard_id,
+            from_block_hash: *from_header.hash(),
+            from_state_root,
+            to_block_hash: *to_header.hash(),
+            to_state_root,
+            changes: diff
+                .changes
+                .into_iter()
+                .map(|change| StateDiffItemView {
+                    key: change.key.into(),
+                    old_value: change.old_value.map(Into::into),
+                    new_value: change.new_value.map(Into::into),
+                })
+                .collect(),
+            next_key: diff.next_key.map(Into::into),
+        })
+    }
+}
+
+impl Handler<SimulateTransaction> for ViewClientActorInner {
+    #[perf]
+    fn handle(
+        &mut self,
+        msg: SimulateTransaction,
+    ) -> Result<TransactionSimulationView, SimulateTransactionError> {
+        tracing::debug!(target: "client", ?msg);
+        let _timer = metrics::VIEW_CLIENT_MESSAGE_TIME
+            .with_label_values(&["SimulateTransaction"])
+            .start_timer();
+
+        let header = self
+            .get_block_header_by_reference(&msg.block_reference)?
+            .ok_or(SimulateTransactionError::NoSyncedBlocks)?;
+        // Receipts sent to shards which aren't tracked are returned in
+        // `pending_receipts`, only the shard of the signer is required.
+        let mut state_roots = HashMap::new();
+        for shard_id in self.epoch_manager.shard_ids(header.epoch_id()).into_chain_error()? {
+            let shard_uid = self
+                .epoch_manager
+                .shard_id_to_uid(shard_id, header.epoch_id())
+                .into_chain_error()?;
+            match self.chain.get_chunk_extra(header.hash(), &shard_uid) {
+                Ok(chunk_extra) => {
+                    state_roots.insert(shard_id, *chunk_extra.state_root());
+                }
+                Err(near_chain::Error::DBNotFoundErr(_)) => {}
+                Err(err) => return Err(err.into()),
+            }
+        }
+        let signer_shard_id = self
+            .epoch_manager
+            .account_id_to_shard_id(msg.transaction.transaction.signer_id(), header.epoch_id())
+            .into_chain_error()?;
+        if !state_roots.contains_key(&signer_shard_id) {
+            return Err(SimulateTransactionError::UnavailableState {
+                error_message: format!(
+                    "shard {} of the signer at block {} is not tracked or the block was garbage collected",
+                    signer_shard_id,
+                    header.hash()
+                ),
+            });
+        }
+        Ok(self.runtime.simulate_transaction(
+            &header,
+            &state_roots,
+            &msg.transaction,
+            msg.verify_signature,
+            msg.max_receipts,
+        )?)
+    }
+}
@@ -0,0 +1,133 @@
+use near_primitives::types::{AccountId, ShardId};
+use near_primitives::views::{self, ActionView, ReceiptEnumView};
+
+/// Kinds of actions `IndexerFilter::action_kinds` can select.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ActionKind {
+    CreateAccount,
+    DeployContract,
+    FunctionCall,
+    Transfer,
+    #[cfg(feature = "protocol_feature_nonrefundable_transfer_nep491")]
+    NonrefundableStorageTransfer,
+    Stake,
+    AddKey,
+    DeleteKey,
+    DeleteAccount,
+    Delegate,
+}
+
+impl From<&ActionView> for ActionKind {
+    fn from(action: &ActionView) -> Self {
+        match action {
+            ActionView::CreateAccount => ActionKind::CreateAccount,
+            ActionView::DeployContract { .. } => ActionKind::DeployContract,
+            ActionView::FunctionCall { .. } => ActionKind::FunctionCall,
+            ActionView::Transfer { .. } => ActionKind::Transfer,
+            #[cfg(feature = "protocol_feature_nonrefundable_transfer_nep491")]
+            ActionView::NonrefundableStorageTransfer { .. } => {
+                ActionKind::NonrefundableStorageTransfer
+            }
+            ActionView::Stake { .. } => ActionKind::Stake,
+            ActionView::AddKey { .. } => ActionKind::AddKey,
+            ActionView::DeleteKey { .. } => ActionKind::DeleteKey,
+            ActionView::DeleteAccount { .. } => ActionKind::DeleteAccount,
+            ActionView::Delegate { .. } => ActionKind::Delegate,
+        }
+    }
+}
+
+/// Restricts what ends up in the `StreamerMessage`s. Everything not selected
+/// is dropped while fetching, so it's neither requested from the node nor
+/// kept in memory. An empty list doesn't restrict anything.
+#[derive(Debug, Clone, Default)]
+pub struct IndexerFilter {
+    /// Accounts to index: transactions signed by or sent to them, receipts
+    /// executed by them and changes of their state.
+    pub account_ids: Vec<AccountId>,
+    /// Same as `account_ids`, but selects every account whose id ends with
+    /// one of the given suffixes, e.g. `.poolv1.near`.
+    pub account_suffixes: Vec<String>,
+    /// Transactions and receipts to index by the kinds of their actions.
+    /// Selects the ones which contain at least one action of the given kinds.
+    pub action_kinds: Vec<ActionKind>,
+    /// Shards to index, other shards are left out of `StreamerMessage::shards`.
+    pub shard_ids: Vec<ShardId>,
+}
+
+impl IndexerFilter {
+    pub fn is_empty(&self) -> bool {
+        self.account_ids.is_empty()
+            && self.account_suffixes.is_empty()
+            && self.action_kinds.is_empty()
+            && self.shard_ids.is_empty()
+    }
+
+    pub fn matches_shard(&self, shard_id: ShardId) -> bool {
+        self.shard_ids.is_empty() || self.shard_ids.contains(&shard_id)
+    }
+
+    pub fn matches_account(&self, account_id: &AccountId) -> bool {
+        if self.account_ids.is_empty() && self.account_suffixes.is_empty() {
+            return true;
+        }
+        self.account_ids.contains(account_id)
+            || self.account_suffixes.iter().any(|suffix| account_id.as_str().ends_with(suffix))
+    }
+
+    pub fn matches_actions(&self, actions: &[ActionView]) -> bool {
+        self.action_kinds.is_empty()
+            || actions.iter().any(|action| self.action_kinds.contains(&ActionKind::from(action)))
+    }
+
+    pub fn matches_transaction(&self, transaction: &views::SignedTransactionView) -> bool {
+        (self.matches_account(&transaction.signer_id)
+            || self.matches_account(&transaction.receiver_id))
+            && self.matches_actions(&transaction.actions)
+    }
+
+    /// Data receipts carry no actions, so they are only selected when
+    /// `action_kinds` is empty.
+    pub fn matches_receipt(&self, receipt: &views::ReceiptView) -> bool {
+        if !self.matches_account(&receipt.receiver_id) {
+            return false;
+        }
+        match &receipt.receipt {
+            ReceiptEnumView::Action { actions, .. } => self.matches_actions(actions),
+            ReceiptEnumView::Data { .. } => self.action_kinds.is_empty(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ActionKind, IndexerFilter};
+    use near_primitives::views::ActionView;
+
+    #[test]
+    fn test_empty_filter_matches_everything() {
+        let filter = IndexerFilter::default();
+        assert!(filter.is_empty());
+        assert!(filter.matches_shard(3));
+        assert!(filter.matches_account(&"alice.near".parse().unwrap()));
+        assert!(filter.matches_actions(&[ActionView::CreateAccount]));
+    }
+
+    #[test]
+    fn test_filter_accounts_and_actions() {
+        let filter = IndexerFilter {
+            account_ids: vec!["alice.near".parse().unwrap()],
+            account_suffixes: vec![".poolv1.near".to_string()],
+            action_kinds: vec![ActionKind::Transfer],
+            shard_ids: vec![1],
+        };
+        assert!(filter.matches_account(&"alice.near".parse().unwrap()));
+        assert!(filter.matches_account(&"staking.poolv1.near".parse().unwrap()));
+        assert!(!filter.matches_account(&"bob.near".parse().unwrap()));
+        assert!(!filter.matches_shard(0));
+        assert!(filter.matches_shard(1));
+        assert!(filter
+            .matches_actions(&[ActionView::CreateAccount, ActionView::Transfer { deposit: 1 }]));
+        assert!(!filter.matches_actions(&[ActionView::CreateAccount]));
+    }
+}
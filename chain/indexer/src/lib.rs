@@ -5,7 +5,7 @@ use tokio::sync::mpsc;
 
 use near_chain_configs::GenesisValidationMode;
 pub use near_primitives;
-use near_primitives::types::Gas;
+use near_primitives::types::{BlockHeight, Gas};
 pub use nearcore::{get_default_home, init_configs, NearConfig};
 
 pub use near_indexer_primitives::{
@@ -14,8 +14,14 @@ pub use near_indexer_primitives::{
     StreamerMessage,
 };
 
-pub use streamer::build_streamer_message;
+pub use filter::{ActionKind, IndexerFilter};
+pub use sinks::{
+    BorshLogReader, BorshLogRecord, BorshLogSink, Checkpoint, JsonLinesSink, Sink, SqliteSink,
+};
+pub use streamer::{build_filtered_streamer_message, build_streamer_message};
 
+mod filter;
+mod sinks;
 mod streamer;
 
 pub const INDEXER: &str = "indexer";
@@ -57,7 +63,8 @@ pub struct InitConfigArgs {
 pub enum SyncModeEnum {
     /// Real-time syncing, always taking the latest finalized block to stream
     LatestSynced,
-    /// Starts syncing from the block NEAR Indexer was interrupted last time
+    /// Starts syncing from the block NEAR Indexer was interrupted last time.
+    /// When a [`Sink`] is used, resumes right after the last block it acknowledged.
     FromInterruption,
     /// Specific block height to start syncing from
     BlockHeight(u64),
@@ -83,6 +90,24 @@ pub struct IndexerConfig {
     pub await_for_node_synced: AwaitForNodeSyncedEnum,
     /// Tells whether to validate the genesis file before starting
     pub validate_genesis: bool,
+    /// Selects the parts of the blocks to be streamed, everything is streamed by default
+    pub filter: IndexerFilter,
+}
+
+/// Configuration of the offline replay started with [`replay`]
+#[derive(Debug, Clone)]
+pub struct ReplayConfig {
+    /// Path to `home_dir` of an archival node, whose storage is opened in read-only mode
+    pub home_dir: std::path::PathBuf,
+    /// Height of the first block to stream
+    pub start_block_height: BlockHeight,
+    /// Height of the last block to stream (inclusive)
+    pub end_block_height: BlockHeight,
+    /// Number of blocks whose chunks, outcomes and state changes are fetched at once, ahead of
+    /// the one whose `StreamerMessage` is being assembled
+    pub concurrency: usize,
+    /// Selects the parts of the blocks to be streamed, everything is streamed by default
+    pub filter: IndexerFilter,
 }
 
 /// This is the core component, which handles `nearcore` and internal `streamer`.
@@ -126,6 +151,10 @@ impl Indexer {
 
     /// Boots up `near_indexer::streamer`, so it monitors the new blocks with chunks, transactions, receipts, and execution outcomes inside. The returned stream handler should be drained and handled on the user side.
     pub fn streamer(&self) -> mpsc::Receiver<StreamerMessage> {
+        self.start_streamer(None)
+    }
+
+    fn start_streamer(&self, checkpoint: Option<Checkpoint>) -> mpsc::Receiver<StreamerMessage> {
         let (sender, receiver) = mpsc::channel(100);
         actix::spawn(streamer::start(
             self.view_client.clone(),
@@ -133,11 +162,25 @@ impl Indexer {
             self.indexer_config.clone(),
             self.near_config.config.store.clone(),
             self.near_config.config.archive,
+            checkpoint,
             sender,
         ));
         receiver
     }
 
+    /// Boots up `near_indexer::streamer` and returns a future writing every message into `sink`.
+    /// The indexer [`Checkpoint`] is advanced after each flush of the sink, so restarting with
+    /// `SyncModeEnum::FromInterruption` resumes right after the last acknowledged block.
+    /// The future resolves once the streamer stops or the sink fails.
+    pub fn stream_into_sink<S: Sink + 'static>(
+        &self,
+        sink: S,
+    ) -> anyhow::Result<impl std::future::Future<Output = anyhow::Result<()>>> {
+        let checkpoint = Checkpoint::new(&self.indexer_config.home_dir);
+        sinks::restore_checkpoint(&sink, &checkpoint)?;
+        Ok(sinks::run_sink(self.start_streamer(Some(checkpoint.clone())), sink, checkpoint))
+    }
+
     /// Expose neard config
     pub fn near_config(&self) -> &nearcore::NearConfig {
         &self.near_config
@@ -151,6 +194,32 @@ impl Indexer {
     }
 }
 
+/// Streams `StreamerMessage`s for a range of blocks read directly from the storage of an
+/// archival node (legacy or split hot+cold), without starting the node itself.
+///
+/// The storage is opened in read-only mode, so the node may even keep running meanwhile. The
+/// messages are delivered in the order of heights and the stream ends after
+/// `end_block_height`. Has to be called from within an actix `System`.
+pub fn replay(replay_config: ReplayConfig) -> anyhow::Result<mpsc::Receiver<StreamerMessage>> {
+    anyhow::ensure!(
+        replay_config.start_block_height <= replay_config.end_block_height,
+        "start block height #{} is above end block height #{}",
+        replay_config.start_block_height,
+        replay_config.end_block_height
+    );
+    let mut near_config =
+        nearcore::config::load_config(&replay_config.home_dir, GenesisValidationMode::UnsafeFast)
+            .context("Error loading config")?;
+    // The parts of every block fetched ahead are requested from the view client in parallel.
+    near_config.client_config.view_client_threads =
+        near_config.client_config.view_client_threads.max(replay_config.concurrency);
+    let view_client = nearcore::start_read_only_view_client(&replay_config.home_dir, &near_config)?;
+
+    let (sender, receiver) = mpsc::channel(100);
+    actix::spawn(streamer::replay(view_client, replay_config, sender));
+    Ok(receiver)
+}
+
 /// Function that initializes configs for the node which
 /// accepts `InitConfigWrapper` and calls original `init_configs` from `neard`
 pub fn indexer_init_configs(
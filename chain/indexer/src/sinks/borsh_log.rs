@@ -0,0 +1,211 @@
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+
+use anyhow::Context;
+use borsh::{BorshDeserialize, BorshSerialize};
+
+use near_indexer_primitives::StreamerMessage;
+use near_primitives::hash::CryptoHash;
+use near_primitives::types::BlockHeight;
+
+use super::Sink;
+use crate::INDEXER;
+
+/// A single entry of the log written by [`BorshLogSink`].
+///
+/// The views making up a `StreamerMessage` are only serde-serializable, so the
+/// message itself is kept as JSON while the block it belongs to is available
+/// without decoding it.
+#[derive(Debug, Clone, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+pub struct BorshLogRecord {
+    pub block_height: BlockHeight,
+    pub block_hash: CryptoHash,
+    pub message: Vec<u8>,
+}
+
+impl BorshLogRecord {
+    pub fn new(message: &StreamerMessage) -> serde_json::Result<Self> {
+        Ok(Self {
+            block_height: message.block.header.height,
+            block_hash: message.block.header.hash,
+            message: serde_json::to_vec(message)?,
+        })
+    }
+
+    pub fn streamer_message(&self) -> serde_json::Result<StreamerMessage> {
+        serde_json::from_slice(&self.message)
+    }
+}
+
+/// Appends messages to a single file as borsh encoded [`BorshLogRecord`]s,
+/// each one prefixed with its length as a little-endian `u32`.
+///
+/// On open, a record left incomplete by a crash is cut off and the height of
+/// the last complete record is reported as acknowledged.
+pub struct BorshLogSink {
+    writer: BufWriter<File>,
+    last_block_height: Option<BlockHeight>,
+}
+
+impl BorshLogSink {
+    pub fn open(path: &Path) -> anyhow::Result<Self> {
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .read(true)
+            .write(true)
+            .open(path)
+            .with_context(|| format!("Unable to open {}", path.display()))?;
+
+        let mut reader = BorshLogReader::new(BufReader::new(&mut file));
+        let mut last_block_height = None;
+        for record in reader.by_ref() {
+            last_block_height = Some(record?.block_height);
+        }
+        let valid_len = reader.offset;
+        drop(reader);
+        if valid_len < file.metadata()?.len() {
+            tracing::warn!(
+                target: INDEXER,
+                "Truncating incomplete record at the end of {}",
+                path.display()
+            );
+            file.set_len(valid_len)?;
+        }
+        file.seek(SeekFrom::Start(valid_len))?;
+
+        Ok(Self { writer: BufWriter::new(file), last_block_height })
+    }
+
+    fn append(&mut self, record: &BorshLogRecord) -> anyhow::Result<()> {
+        let bytes = borsh::to_vec(record)?;
+        let len = u32::try_from(bytes.len()).context("record does not fit into the log")?;
+        self.writer.write_all(&len.to_le_bytes())?;
+        self.writer.write_all(&bytes)?;
+        Ok(())
+    }
+}
+
+impl Sink for BorshLogSink {
+    fn write(&mut self, message: &StreamerMessage) -> anyhow::Result<()> {
+        self.append(&BorshLogRecord::new(message)?)?;
+        self.last_block_height = Some(message.block.header.height);
+        Ok(())
+    }
+
+    fn flush(&mut self) -> anyhow::Result<()> {
+        self.writer.flush()?;
+        self.writer.get_ref().sync_data()?;
+        Ok(())
+    }
+
+    fn last_acknowledged_block_height(&self) -> anyhow::Result<Option<BlockHeight>> {
+        Ok(self.last_block_height)
+    }
+}
+
+/// Iterates over the records of a log written by [`BorshLogSink`]. Stops at
+/// the end of the input or at the first incomplete record.
+pub struct BorshLogReader<R> {
+    reader: R,
+    offset: u64,
+}
+
+impl<R: Read> BorshLogReader<R> {
+    pub fn new(reader: R) -> Self {
+        Self { reader, offset: 0 }
+    }
+
+    fn read_record(&mut self) -> std::io::Result<Option<BorshLogRecord>> {
+        let mut len = [0u8; 4];
+        if !read_exact_or_eof(&mut self.reader, &mut len)? {
+            return Ok(None);
+        }
+        let mut bytes = vec![0u8; u32::from_le_bytes(len) as usize];
+        if !read_exact_or_eof(&mut self.reader, &mut bytes)? {
+            return Ok(None);
+        }
+        let record = BorshLogRecord::try_from_slice(&bytes)?;
+        self.offset += (len.len() + bytes.len()) as u64;
+        Ok(Some(record))
+    }
+}
+
+impl BorshLogReader<BufReader<File>> {
+    pub fn open(path: &Path) -> std::io::Result<Self> {
+        Ok(Self::new(BufReader::new(File::open(path)?)))
+    }
+}
+
+impl<R: Read> Iterator for BorshLogReader<R> {
+    type Item = std::io::Result<BorshLogRecord>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read_record().transpose()
+    }
+}
+
+/// Like `read_exact`, but returns `false` instead of failing when the input
+/// ends before `buf` is filled.
+fn read_exact_or_eof(reader: &mut impl Read, buf: &mut [u8]) -> std::io::Result<bool> {
+    match reader.read_exact(buf) {
+        Ok(()) => Ok(true),
+        Err(err) if err.kind() == std::io::ErrorKind::UnexpectedEof => Ok(false),
+        Err(err) => Err(err),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BorshLogReader, BorshLogRecord, BorshLogSink};
+    use crate::sinks::Sink;
+    use near_primitives::hash::CryptoHash;
+
+    fn record(block_height: u64) -> BorshLogRecord {
+        BorshLogRecord {
+            block_height,
+            block_hash: CryptoHash::hash_bytes(&block_height.to_le_bytes()),
+            message: format!("{{\"height\":{}}}", block_height).into_bytes(),
+        }
+    }
+
+    #[test]
+    fn test_borsh_log_roundtrip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("log");
+        let mut sink = BorshLogSink::open(&path).unwrap();
+        for height in 1..=3 {
+            sink.append(&record(height)).unwrap();
+        }
+        sink.flush().unwrap();
+
+        let records =
+            BorshLogReader::open(&path).unwrap().collect::<std::io::Result<Vec<_>>>().unwrap();
+        assert_eq!(records, vec![record(1), record(2), record(3)]);
+    }
+
+    #[test]
+    fn test_borsh_log_truncates_incomplete_record() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("log");
+        let mut sink = BorshLogSink::open(&path).unwrap();
+        sink.append(&record(7)).unwrap();
+        sink.flush().unwrap();
+        drop(sink);
+
+        let complete_len = std::fs::metadata(&path).unwrap().len();
+        let mut contents = std::fs::read(&path).unwrap();
+        contents.extend_from_slice(&[200, 0, 0, 0, 1, 2, 3]);
+        std::fs::write(&path, contents).unwrap();
+
+        let mut sink = BorshLogSink::open(&path).unwrap();
+        assert_eq!(sink.last_acknowledged_block_height().unwrap(), Some(7));
+        assert_eq!(std::fs::metadata(&path).unwrap().len(), complete_len);
+        sink.append(&record(8)).unwrap();
+        sink.flush().unwrap();
+
+        let records =
+            BorshLogReader::open(&path).unwrap().collect::<std::io::Result<Vec<_>>>().unwrap();
+        assert_eq!(records, vec![record(7), record(8)]);
+    }
+}
@@ -0,0 +1,63 @@
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+
+use near_primitives::types::BlockHeight;
+
+/// Height of the last block acknowledged by a [`super::Sink`], persisted in
+/// `home_dir` so that `SyncModeEnum::FromInterruption` can resume after it.
+#[derive(Debug, Clone)]
+pub struct Checkpoint {
+    path: PathBuf,
+}
+
+impl Checkpoint {
+    const FILE_NAME: &'static str = "indexer_checkpoint";
+
+    pub fn new(home_dir: &Path) -> Self {
+        Self { path: home_dir.join(Self::FILE_NAME) }
+    }
+
+    /// Returns the last acknowledged block height, if any block was ever acknowledged.
+    pub fn load(&self) -> anyhow::Result<Option<BlockHeight>> {
+        match std::fs::read_to_string(&self.path) {
+            Ok(contents) => {
+                let height = contents.trim().parse::<BlockHeight>().with_context(|| {
+                    format!("Malformed indexer checkpoint in {}", self.path.display())
+                })?;
+                Ok(Some(height))
+            }
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err).with_context(|| format!("Unable to read {}", self.path.display())),
+        }
+    }
+
+    /// Atomically replaces the stored checkpoint with `block_height`.
+    pub fn save(&self, block_height: BlockHeight) -> anyhow::Result<()> {
+        let tmp_path = self.path.with_extension("tmp");
+        let mut file = std::fs::File::create(&tmp_path)
+            .with_context(|| format!("Unable to create {}", tmp_path.display()))?;
+        file.write_all(block_height.to_string().as_bytes())?;
+        file.sync_all()?;
+        std::fs::rename(&tmp_path, &self.path)
+            .with_context(|| format!("Unable to write {}", self.path.display()))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Checkpoint;
+
+    #[test]
+    fn test_checkpoint_roundtrip() {
+        let dir = tempfile::tempdir().unwrap();
+        let checkpoint = Checkpoint::new(dir.path());
+        assert_eq!(checkpoint.load().unwrap(), None);
+        checkpoint.save(42).unwrap();
+        assert_eq!(checkpoint.load().unwrap(), Some(42));
+        checkpoint.save(43).unwrap();
+        assert_eq!(Checkpoint::new(dir.path()).load().unwrap(), Some(43));
+    }
+}
@@ -0,0 +1,121 @@
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::PathBuf;
+
+use anyhow::Context;
+
+use near_indexer_primitives::StreamerMessage;
+
+use super::Sink;
+
+/// Writes every `StreamerMessage` as a single JSON line.
+///
+/// Files are named after the height of the first block they contain
+/// (`000000012345.ndjson`) and a new file is started once the current one
+/// grows past `max_file_size` bytes, so a file is never split mid-block.
+pub struct JsonLinesSink {
+    directory: PathBuf,
+    max_file_size: u64,
+    current: Option<CurrentFile>,
+}
+
+struct CurrentFile {
+    writer: BufWriter<File>,
+    size: u64,
+}
+
+impl JsonLinesSink {
+    pub fn new(directory: impl Into<PathBuf>, max_file_size: u64) -> anyhow::Result<Self> {
+        let directory = directory.into();
+        std::fs::create_dir_all(&directory)
+            .with_context(|| format!("Unable to create {}", directory.display()))?;
+        Ok(Self { directory, max_file_size, current: None })
+    }
+
+    fn rotate(&mut self, first_block_height: u64) -> anyhow::Result<()> {
+        if let Some(mut current) = self.current.take() {
+            current.writer.flush()?;
+            current.writer.get_ref().sync_data()?;
+        }
+        let path = self.directory.join(format!("{:012}.ndjson", first_block_height));
+        // The same block may be streamed again after a restart, append to the
+        // existing file rather than truncating already acknowledged data.
+        let file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .with_context(|| format!("Unable to open {}", path.display()))?;
+        let size = file.metadata()?.len();
+        self.current = Some(CurrentFile { writer: BufWriter::new(file), size });
+        Ok(())
+    }
+}
+
+impl Sink for JsonLinesSink {
+    fn write(&mut self, message: &StreamerMessage) -> anyhow::Result<()> {
+        let needs_rotation = match &self.current {
+            Some(current) => current.size >= self.max_file_size,
+            None => true,
+        };
+        if needs_rotation {
+            self.rotate(message.block.header.height)?;
+        }
+
+        let mut line = serde_json::to_vec(message)?;
+        line.push(b'\n');
+        let current = self.current.as_mut().expect("file is opened above");
+        current.writer.write_all(&line)?;
+        current.size += line.len() as u64;
+        Ok(())
+    }
+
+    fn flush(&mut self) -> anyhow::Result<()> {
+        if let Some(current) = self.current.as_mut() {
+            current.writer.flush()?;
+            current.writer.get_ref().sync_data()?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use near_indexer_primitives::StreamerMessage;
+
+    use super::JsonLinesSink;
+    use crate::sinks::tests::message;
+    use crate::sinks::Sink;
+
+    fn heights_in(path: &std::path::Path) -> Vec<u64> {
+        std::fs::read_to_string(path)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str::<StreamerMessage>(line).unwrap().block.header.height)
+            .collect()
+    }
+
+    #[test]
+    fn test_json_lines_sink_rotates_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let line_len = serde_json::to_vec(&message(1)).unwrap().len() as u64 + 1;
+        // The second message pushes a file past the limit, so every file holds two messages.
+        let mut sink = JsonLinesSink::new(dir.path(), line_len + 1).unwrap();
+        for height in 1..=5 {
+            sink.write(&message(height)).unwrap();
+        }
+        sink.flush().unwrap();
+
+        let mut files: Vec<_> = std::fs::read_dir(dir.path())
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .collect();
+        files.sort();
+        assert_eq!(
+            files,
+            vec!["000000000001.ndjson", "000000000003.ndjson", "000000000005.ndjson"]
+        );
+        assert_eq!(heights_in(&dir.path().join("000000000001.ndjson")), vec![1, 2]);
+        assert_eq!(heights_in(&dir.path().join("000000000003.ndjson")), vec![3, 4]);
+        assert_eq!(heights_in(&dir.path().join("000000000005.ndjson")), vec![5]);
+    }
+}
@@ -0,0 +1,199 @@
+//! Pluggable destinations for the messages produced by the streamer.
+//!
+//! Instead of draining the `mpsc::Receiver<StreamerMessage>` returned by
+//! `Indexer::streamer` by hand, an indexer can hand a [`Sink`] over to
+//! `Indexer::stream_into_sink`. The messages are then written to the sink in
+//! block height order and, once the sink confirms they are durably stored,
+//! the indexer [`Checkpoint`] is advanced so that
+//! `SyncModeEnum::FromInterruption` resumes right after the last acknowledged
+//! block.
+
+use tokio::sync::mpsc;
+
+use near_indexer_primitives::StreamerMessage;
+use near_primitives::types::BlockHeight;
+
+use crate::INDEXER;
+
+pub use self::borsh_log::{BorshLogReader, BorshLogRecord, BorshLogSink};
+pub use self::checkpoint::Checkpoint;
+pub use self::json_lines::JsonLinesSink;
+pub use self::sqlite::SqliteSink;
+
+mod borsh_log;
+mod checkpoint;
+mod json_lines;
+mod sqlite;
+
+/// Maximum number of messages written to a sink between two flushes.
+/// Messages are flushed earlier whenever the streamer has nothing more queued.
+const MAX_UNACKNOWLEDGED_MESSAGES: usize = 100;
+
+/// Destination for `StreamerMessage`s.
+///
+/// `write` is called for every message in block height order. The messages
+/// written so far are considered acknowledged only after `flush` returns
+/// successfully, so implementations may buffer in `write` but must make the
+/// data durable in `flush`.
+pub trait Sink: Send {
+    /// Stores a single message.
+    fn write(&mut self, message: &StreamerMessage) -> anyhow::Result<()>;
+
+    /// Makes every message written so far durable.
+    fn flush(&mut self) -> anyhow::Result<()>;
+
+    /// Height of the last block stored by the sink, for sinks which keep track
+    /// of it atomically together with the data. When present it takes
+    /// precedence over the checkpoint file on startup.
+    fn last_acknowledged_block_height(&self) -> anyhow::Result<Option<BlockHeight>> {
+        Ok(None)
+    }
+}
+
+impl<S: Sink + ?Sized> Sink for Box<S> {
+    fn write(&mut self, message: &StreamerMessage) -> anyhow::Result<()> {
+        (**self).write(message)
+    }
+
+    fn flush(&mut self) -> anyhow::Result<()> {
+        (**self).flush()
+    }
+
+    fn last_acknowledged_block_height(&self) -> anyhow::Result<Option<BlockHeight>> {
+        (**self).last_acknowledged_block_height()
+    }
+}
+
+/// Moves `checkpoint` to the last block stored by `sink`, for sinks keeping track of it.
+///
+/// Sinks committing the height together with the data know better than the checkpoint file,
+/// which may lag behind if the indexer was interrupted between a flush and saving it.
+pub(crate) fn restore_checkpoint<S: Sink>(sink: &S, checkpoint: &Checkpoint) -> anyhow::Result<()> {
+    if let Some(height) = sink.last_acknowledged_block_height()? {
+        checkpoint.save(height)?;
+    }
+    Ok(())
+}
+
+/// Drains `stream` into `sink`, advancing `checkpoint` after every flush.
+/// Returns once the streamer stops or as soon as the sink fails.
+pub(crate) async fn run_sink<S: Sink>(
+    mut stream: mpsc::Receiver<StreamerMessage>,
+    mut sink: S,
+    checkpoint: Checkpoint,
+) -> anyhow::Result<()> {
+    while let Some(message) = stream.recv().await {
+        let mut last_block_height = message.block.header.height;
+        sink.write(&message)?;
+
+        let mut unacknowledged = 1;
+        while unacknowledged < MAX_UNACKNOWLEDGED_MESSAGES {
+            let Ok(message) = stream.try_recv() else {
+                break;
+            };
+            last_block_height = message.block.header.height;
+            sink.write(&message)?;
+            unacknowledged += 1;
+        }
+
+        sink.flush()?;
+        checkpoint.save(last_block_height)?;
+        tracing::debug!(
+            target: INDEXER,
+            "Acknowledged {} streamer messages up to block #{}",
+            unacknowledged,
+            last_block_height
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use tokio::sync::mpsc;
+
+    use near_indexer_primitives::{IndexerShard, StreamerMessage};
+    use near_primitives::block::Block;
+    use near_primitives::hash::CryptoHash;
+    use near_primitives::types::BlockHeight;
+    use near_primitives::version::PROTOCOL_VERSION;
+    use near_primitives::views::{
+        BlockView, StateChangeCauseView, StateChangeValueView, StateChangeWithCauseView,
+    };
+
+    use super::{restore_checkpoint, run_sink, Checkpoint, Sink, SqliteSink};
+
+    /// A message for a block without chunks, with a single state change in shard 0.
+    pub(crate) fn message(height: BlockHeight) -> StreamerMessage {
+        let block = Block::genesis(
+            PROTOCOL_VERSION,
+            vec![],
+            near_time::Utc::UNIX_EPOCH,
+            height,
+            1000,
+            1000,
+            CryptoHash::default(),
+        );
+        let state_change = StateChangeWithCauseView {
+            cause: StateChangeCauseView::InitialState,
+            value: StateChangeValueView::AccountDeletion {
+                account_id: format!("account{}.near", height).parse().unwrap(),
+            },
+        };
+        StreamerMessage {
+            block: BlockView::from_author_block("test.near".parse().unwrap(), block),
+            shards: vec![IndexerShard {
+                shard_id: 0,
+                chunk: None,
+                receipt_execution_outcomes: vec![],
+                state_changes: vec![state_change],
+            }],
+        }
+    }
+
+    /// Sends messages for `heights` into `run_sink` and waits until all of them are stored.
+    fn stream(
+        heights: impl Iterator<Item = BlockHeight>,
+        sink: SqliteSink,
+        checkpoint: &Checkpoint,
+    ) {
+        let (sender, receiver) = mpsc::channel(100);
+        for height in heights {
+            sender.try_send(message(height)).unwrap();
+        }
+        drop(sender);
+        futures::executor::block_on(run_sink(receiver, sink, checkpoint.clone())).unwrap();
+    }
+
+    /// Restarting after the sink committed blocks which didn't make it into the checkpoint file
+    /// resumes after the last committed block, so no block is skipped or stored twice.
+    #[test]
+    fn test_run_sink_resumes_after_last_committed_block() {
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("indexer.sqlite");
+        let checkpoint = Checkpoint::new(dir.path());
+
+        stream(1..=3, SqliteSink::open(&db_path).unwrap(), &checkpoint);
+        assert_eq!(checkpoint.load().unwrap(), Some(3));
+
+        // The indexer is interrupted after committing block 4 but before saving the checkpoint.
+        let mut sink = SqliteSink::open(&db_path).unwrap();
+        sink.write(&message(4)).unwrap();
+        sink.flush().unwrap();
+        drop(sink);
+        assert_eq!(checkpoint.load().unwrap(), Some(3));
+
+        let sink = SqliteSink::open(&db_path).unwrap();
+        restore_checkpoint(&sink, &checkpoint).unwrap();
+        let resume_height = checkpoint.load().unwrap().unwrap() + 1;
+        assert_eq!(resume_height, 5);
+        stream(resume_height..=6, sink, &checkpoint);
+        assert_eq!(checkpoint.load().unwrap(), Some(6));
+
+        let conn = rusqlite::Connection::open(&db_path).unwrap();
+        let mut statement = conn.prepare("SELECT height FROM blocks ORDER BY height").unwrap();
+        let heights: Vec<BlockHeight> =
+            statement.query_map([], |row| row.get(0)).unwrap().collect::<Result<_, _>>().unwrap();
+        assert_eq!(heights, vec![1, 2, 3, 4, 5, 6]);
+    }
+}
@@ -0,0 +1,186 @@
+use std::path::Path;
+
+use anyhow::Context;
+use rusqlite::{params, Connection, OptionalExtension};
+
+use near_indexer_primitives::{IndexerShard, StreamerMessage};
+use near_primitives::types::BlockHeight;
+
+use super::Sink;
+
+/// Stores messages in an SQLite database with a table per entity: `blocks`,
+/// `chunks`, `transactions`, `receipts` and `state_changes`. See `sqlite.sql`
+/// for the schema. Identifiers, accounts and amounts get their own columns,
+/// while nested values (actions, receipt bodies, execution statuses and the
+/// state changes themselves) are stored as JSON text.
+///
+/// Everything written between two flushes is committed in a single
+/// transaction together with the `checkpoint` table, so the database never
+/// contains a partially stored block and restarting resumes exactly after
+/// the last committed one.
+pub struct SqliteSink {
+    conn: Connection,
+    in_transaction: bool,
+}
+
+impl SqliteSink {
+    /// Opens an existing database or creates it.
+    pub fn open(path: &Path) -> anyhow::Result<Self> {
+        let conn =
+            Connection::open(path).with_context(|| format!("Unable to open {}", path.display()))?;
+        conn.execute_batch(include_str!("sqlite.sql"))?;
+        Ok(Self { conn, in_transaction: false })
+    }
+
+    fn insert_shard(&self, block_height: BlockHeight, shard: &IndexerShard) -> anyhow::Result<()> {
+        let shard_id = shard.shard_id;
+        if let Some(chunk) = &shard.chunk {
+            self.conn.execute(
+                "INSERT OR REPLACE INTO chunks (chunk_hash, block_height, shard_id, author, gas_used, gas_limit)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                params![
+                    chunk.header.chunk_hash.to_string(),
+                    block_height,
+                    shard_id,
+                    chunk.author.as_str(),
+                    chunk.header.gas_used,
+                    chunk.header.gas_limit,
+                ],
+            )?;
+            for tx in &chunk.transactions {
+                let outcome = &tx.outcome.execution_outcome.outcome;
+                self.conn.execute(
+                    "INSERT OR REPLACE INTO transactions (hash, block_height, shard_id, signer_id, receiver_id, nonce, actions, status, gas_burnt)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+                    params![
+                        tx.transaction.hash.to_string(),
+                        block_height,
+                        shard_id,
+                        tx.transaction.signer_id.as_str(),
+                        tx.transaction.receiver_id.as_str(),
+                        tx.transaction.nonce,
+                        serde_json::to_string(&tx.transaction.actions)?,
+                        serde_json::to_string(&outcome.status)?,
+                        outcome.gas_burnt,
+                    ],
+                )?;
+            }
+        }
+        for receipt_outcome in &shard.receipt_execution_outcomes {
+            let receipt = &receipt_outcome.receipt;
+            let outcome = &receipt_outcome.execution_outcome.outcome;
+            self.conn.execute(
+                "INSERT OR REPLACE INTO receipts (receipt_id, block_height, shard_id, predecessor_id, receiver_id, receipt, status, gas_burnt)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+                params![
+                    receipt.receipt_id.to_string(),
+                    block_height,
+                    shard_id,
+                    receipt.predecessor_id.as_str(),
+                    receipt.receiver_id.as_str(),
+                    serde_json::to_string(&receipt.receipt)?,
+                    serde_json::to_string(&outcome.status)?,
+                    outcome.gas_burnt,
+                ],
+            )?;
+        }
+        for (idx, change) in shard.state_changes.iter().enumerate() {
+            self.conn.execute(
+                "INSERT OR REPLACE INTO state_changes (block_height, shard_id, idx, account_id, cause, value)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                params![
+                    block_height,
+                    shard_id,
+                    idx,
+                    change.value.affected_account_id().as_str(),
+                    serde_json::to_string(&change.cause)?,
+                    serde_json::to_string(&change.value)?,
+                ],
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl Sink for SqliteSink {
+    fn write(&mut self, message: &StreamerMessage) -> anyhow::Result<()> {
+        if !self.in_transaction {
+            self.conn.execute_batch("BEGIN")?;
+            self.in_transaction = true;
+        }
+
+        let header = &message.block.header;
+        self.conn.execute(
+            "INSERT OR REPLACE INTO blocks (height, hash, prev_hash, epoch_id, author, timestamp_nanosec, gas_price)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![
+                header.height,
+                header.hash.to_string(),
+                header.prev_hash.to_string(),
+                header.epoch_id.to_string(),
+                message.block.author.as_str(),
+                header.timestamp_nanosec,
+                header.gas_price.to_string(),
+            ],
+        )?;
+        for shard in &message.shards {
+            self.insert_shard(header.height, shard)?;
+        }
+        self.conn.execute(
+            "INSERT OR REPLACE INTO checkpoint (id, block_height) VALUES (0, ?1)",
+            params![header.height],
+        )?;
+        Ok(())
+    }
+
+    fn flush(&mut self) -> anyhow::Result<()> {
+        if self.in_transaction {
+            self.conn.execute_batch("COMMIT")?;
+            self.in_transaction = false;
+        }
+        Ok(())
+    }
+
+    fn last_acknowledged_block_height(&self) -> anyhow::Result<Option<BlockHeight>> {
+        let height = self
+            .conn
+            .query_row("SELECT block_height FROM checkpoint WHERE id = 0", [], |row| row.get(0))
+            .optional()?;
+        Ok(height)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SqliteSink;
+    use crate::sinks::tests::message;
+    use crate::sinks::Sink;
+
+    #[test]
+    fn test_sqlite_sink_commits_on_flush() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("indexer.sqlite");
+        let mut sink = SqliteSink::open(&path).unwrap();
+        assert_eq!(sink.last_acknowledged_block_height().unwrap(), None);
+        sink.write(&message(1)).unwrap();
+        sink.write(&message(2)).unwrap();
+        sink.flush().unwrap();
+        assert_eq!(sink.last_acknowledged_block_height().unwrap(), Some(2));
+        // Never flushed, so it's rolled back when the connection is closed.
+        sink.write(&message(3)).unwrap();
+        drop(sink);
+
+        let sink = SqliteSink::open(&path).unwrap();
+        assert_eq!(sink.last_acknowledged_block_height().unwrap(), Some(2));
+        let blocks: u64 =
+            sink.conn.query_row("SELECT COUNT(*) FROM blocks", [], |row| row.get(0)).unwrap();
+        assert_eq!(blocks, 2);
+        let account_id: String = sink
+            .conn
+            .query_row("SELECT account_id FROM state_changes WHERE block_height = 2", [], |row| {
+                row.get(0)
+            })
+            .unwrap();
+        assert_eq!(account_id, "account2.near");
+    }
+}
@@ -0,0 +1,54 @@
+CREATE TABLE IF NOT EXISTS blocks (
+    height INTEGER PRIMARY KEY,                     -- block height
+    hash TEXT NOT NULL UNIQUE,                      -- base58 block hash
+    prev_hash TEXT NOT NULL,                        -- base58 hash of the previous block
+    epoch_id TEXT NOT NULL,                         -- base58 epoch id
+    author TEXT NOT NULL,                           -- block producer account id
+    timestamp_nanosec INTEGER NOT NULL,             -- block timestamp in nanoseconds
+    gas_price TEXT NOT NULL                         -- gas price in yoctoNEAR, as a decimal string
+);
+CREATE TABLE IF NOT EXISTS chunks (
+    chunk_hash TEXT PRIMARY KEY,                    -- base58 chunk hash
+    block_height INTEGER NOT NULL REFERENCES blocks(height),
+    shard_id INTEGER NOT NULL,
+    author TEXT NOT NULL,                           -- chunk producer account id
+    gas_used INTEGER NOT NULL,
+    gas_limit INTEGER NOT NULL
+);
+CREATE TABLE IF NOT EXISTS transactions (
+    hash TEXT PRIMARY KEY,                          -- base58 transaction hash
+    block_height INTEGER NOT NULL REFERENCES blocks(height),
+    shard_id INTEGER NOT NULL,
+    signer_id TEXT NOT NULL,
+    receiver_id TEXT NOT NULL,
+    nonce INTEGER NOT NULL,
+    actions TEXT NOT NULL,                          -- JSON encoded list of actions
+    status TEXT NOT NULL,                           -- JSON encoded execution status
+    gas_burnt INTEGER NOT NULL
+);
+CREATE TABLE IF NOT EXISTS receipts (
+    receipt_id TEXT PRIMARY KEY,                    -- base58 receipt id
+    block_height INTEGER NOT NULL REFERENCES blocks(height),
+    shard_id INTEGER NOT NULL,
+    predecessor_id TEXT NOT NULL,
+    receiver_id TEXT NOT NULL,
+    receipt TEXT NOT NULL,                          -- JSON encoded receipt body
+    status TEXT NOT NULL,                           -- JSON encoded execution status
+    gas_burnt INTEGER NOT NULL
+);
+CREATE TABLE IF NOT EXISTS state_changes (
+    block_height INTEGER NOT NULL REFERENCES blocks(height),
+    shard_id INTEGER NOT NULL,
+    idx INTEGER NOT NULL,                           -- position of the change within the shard
+    account_id TEXT NOT NULL,                       -- account affected by the change
+    cause TEXT NOT NULL,                            -- JSON encoded cause of the change
+    value TEXT NOT NULL,                            -- JSON encoded change
+    PRIMARY KEY (block_height, shard_id, idx)
+);
+CREATE INDEX IF NOT EXISTS transactions_signer_id ON transactions (signer_id);
+CREATE INDEX IF NOT EXISTS receipts_receiver_id ON receipts (receiver_id);
+CREATE INDEX IF NOT EXISTS state_changes_account_id ON state_changes (account_id);
+CREATE TABLE IF NOT EXISTS checkpoint (
+    id INTEGER PRIMARY KEY CHECK (id = 0),
+    block_height INTEGER NOT NULL                   -- last block committed together with its data
+);
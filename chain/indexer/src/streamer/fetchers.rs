@@ -1,6 +1,6 @@
 //! Streamer watches the network and collects all the blocks and related chunks
 //! into one struct and pushes in to the given queue
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 use actix::Addr;
 use futures::stream::StreamExt;
@@ -13,6 +13,7 @@ use near_primitives::{types, views};
 
 use super::errors::FailedToFetchData;
 use super::INDEXER;
+use crate::IndexerFilter;
 
 pub(crate) async fn fetch_status(
     client: &Addr<near_client::ClientActor>,
@@ -56,6 +57,25 @@ pub(crate) async fn fetch_block_by_height(
         .map_err(|err| FailedToFetchData::String(err.to_string()))
 }
 
+/// Fetches specific block by its height, returns `None` if there is no block at that height
+pub(crate) async fn fetch_block_by_height_if_exists(
+    client: &Addr<near_client::ViewClientActor>,
+    height: u64,
+) -> Result<Option<views::BlockView>, FailedToFetchData> {
+    tracing::debug!(target: INDEXER, "Fetching block by height: {}", height);
+    match client
+        .send(
+            near_client::GetBlock(near_primitives::types::BlockId::Height(height).into())
+                .with_span_context(),
+        )
+        .await?
+    {
+        Ok(block) => Ok(Some(block)),
+        Err(near_client::GetBlockError::UnknownBlock { .. }) => Ok(None),
+        Err(err) => Err(FailedToFetchData::String(err.to_string())),
+    }
+}
+
 /// Fetches specific block by it's hash
 pub(crate) async fn fetch_block(
     client: &Addr<near_client::ViewClientActor>,
@@ -71,26 +91,53 @@ pub(crate) async fn fetch_block(
         .map_err(|err| FailedToFetchData::String(err.to_string()))
 }
 
+/// Fetches state changes of the given block grouped by shard, leaving out
+/// the shards and accounts not selected by `filter`.
+///
+/// When the accounts are selected only by `account_ids`, just their changes
+/// are read by the node. Account suffixes can't be looked up by key, so in
+/// that case all the changes are fetched and filtered here.
 pub(crate) async fn fetch_state_changes(
     client: &Addr<near_client::ViewClientActor>,
     block_hash: CryptoHash,
     epoch_id: near_primitives::types::EpochId,
+    filter: &IndexerFilter,
 ) -> Result<HashMap<near_primitives::types::ShardId, views::StateChangesView>, FailedToFetchData> {
     tracing::debug!(target: INDEXER, "Fetching state changes for block: {}, epoch_id: {:?}", block_hash, epoch_id);
-    client
+    let account_ids = (!filter.account_ids.is_empty() && filter.account_suffixes.is_empty())
+        .then(|| filter.account_ids.clone());
+    let mut state_changes = client
         .send(
-            near_client::GetStateChangesWithCauseInBlockForTrackedShards { block_hash, epoch_id }
-                .with_span_context(),
+            near_client::GetStateChangesWithCauseInBlockForTrackedShards {
+                block_hash,
+                epoch_id,
+                account_ids,
+            }
+            .with_span_context(),
         )
         .await?
-        .map_err(|err| FailedToFetchData::String(err.to_string()))
+        .map_err(|err| FailedToFetchData::String(err.to_string()))?;
+    if !filter.is_empty() {
+        state_changes.retain(|shard_id, _| filter.matches_shard(*shard_id));
+        for changes in state_changes.values_mut() {
+            changes.retain(|change| filter.matches_account(change.value.affected_account_id()));
+        }
+    }
+    Ok(state_changes)
 }
 
 /// Fetch all ExecutionOutcomeWithId for current block
 /// Returns a HashMap where the key is shard id IndexerExecutionOutcomeWithOptionalReceipt
+///
+/// `transactions` holds the hashes of the transactions included in the block. Their outcomes
+/// are always returned, since the local receipts are built from them even if the transactions
+/// themselves are not selected by `filter`. Outcomes of receipts executed by accounts or on
+/// shards which aren't selected are left out and their receipts are not fetched at all.
 pub(crate) async fn fetch_outcomes(
     client: &Addr<near_client::ViewClientActor>,
     block_hash: CryptoHash,
+    filter: &IndexerFilter,
+    transactions: &HashSet<CryptoHash>,
 ) -> Result<
     HashMap<near_primitives::types::ShardId, Vec<IndexerExecutionOutcomeWithOptionalReceipt>>,
     FailedToFetchData,
@@ -106,9 +153,22 @@ pub(crate) async fn fetch_outcomes(
         Vec<IndexerExecutionOutcomeWithOptionalReceipt>,
     > = HashMap::new();
     for (shard_id, shard_outcomes) in outcomes {
+        if !filter.matches_shard(shard_id) {
+            continue;
+        }
         tracing::debug!(target: INDEXER, "Fetching outcomes with receipts for shard: {}", shard_id);
         let mut outcomes_with_receipts: Vec<IndexerExecutionOutcomeWithOptionalReceipt> = vec![];
         for outcome in shard_outcomes {
+            if transactions.contains(&outcome.id) {
+                outcomes_with_receipts.push(IndexerExecutionOutcomeWithOptionalReceipt {
+                    execution_outcome: outcome,
+                    receipt: None,
+                });
+                continue;
+            }
+            if !filter.matches_account(&outcome.outcome.executor_id) {
+                continue;
+            }
             let receipt = match fetch_receipt_by_id(&client, outcome.id).await {
                 Ok(res) => res,
                 Err(e) => {
@@ -158,15 +218,18 @@ async fn fetch_single_chunk(
 
 /// Fetches all chunks belonging to given block.
 /// Includes transactions and receipts in custom struct (to provide more info).
+/// Chunks of the shards not selected by `filter` are not fetched.
 pub(crate) async fn fetch_block_chunks(
     client: &Addr<near_client::ViewClientActor>,
     block: &views::BlockView,
+    filter: &IndexerFilter,
 ) -> Result<Vec<views::ChunkView>, FailedToFetchData> {
     tracing::debug!(target: INDEXER, "Fetching chunks for block #{}", block.header.height);
     let mut futures: futures::stream::FuturesUnordered<_> = block
         .chunks
         .iter()
         .filter(|chunk| chunk.height_included == block.header.height)
+        .filter(|chunk| filter.matches_shard(chunk.shard_id))
         .map(|chunk| fetch_single_chunk(&client, chunk.chunk_hash))
         .collect();
     let mut chunks = Vec::<views::ChunkView>::with_capacity(futures.len());
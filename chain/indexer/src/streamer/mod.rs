@@ -1,8 +1,9 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::sync::{Arc, RwLock};
 use std::time::Duration;
 
 use actix::Addr;
+use futures::StreamExt;
 use lazy_static::lazy_static;
 use rocksdb::DB;
 use tokio::sync::mpsc;
@@ -16,17 +17,19 @@ use near_indexer_primitives::{
 };
 use near_parameters::RuntimeConfig;
 use near_primitives::hash::CryptoHash;
+use near_primitives::types::{AccountId, ShardId};
 use near_primitives::views;
 
 use self::errors::FailedToFetchData;
 use self::fetchers::{
-    fetch_block, fetch_block_by_height, fetch_block_chunks, fetch_latest_block, fetch_outcomes,
-    fetch_state_changes, fetch_status,
+    fetch_block, fetch_block_by_height, fetch_block_by_height_if_exists, fetch_block_chunks,
+    fetch_latest_block, fetch_outcomes, fetch_state_changes, fetch_status,
 };
 use self::utils::convert_transactions_sir_into_local_receipts;
+use crate::sinks::Checkpoint;
 use crate::streamer::fetchers::fetch_protocol_config;
 use crate::INDEXER;
-use crate::{AwaitForNodeSyncedEnum, IndexerConfig};
+use crate::{AwaitForNodeSyncedEnum, IndexerConfig, IndexerFilter, ReplayConfig};
 
 mod errors;
 mod fetchers;
@@ -76,25 +79,83 @@ fn test_problematic_blocks_hash() {
 pub async fn build_streamer_message(
     client: &Addr<near_client::ViewClientActor>,
     block: views::BlockView,
+) -> Result<StreamerMessage, FailedToFetchData> {
+    build_filtered_streamer_message(client, block, &IndexerFilter::default()).await
+}
+
+/// Same as [`build_streamer_message`], but only fetches and includes the parts of the block
+/// selected by `filter`.
+pub async fn build_filtered_streamer_message(
+    client: &Addr<near_client::ViewClientActor>,
+    block: views::BlockView,
+    filter: &IndexerFilter,
 ) -> Result<StreamerMessage, FailedToFetchData> {
     let _timer = metrics::BUILD_STREAMER_MESSAGE_TIME.start_timer();
-    let chunks = fetch_block_chunks(&client, &block).await?;
+    let parts = fetch_streamer_message_parts(client, block, filter).await?;
+    assemble_streamer_message(client, parts, filter).await
+}
+
+/// Parts of the `StreamerMessage` of a block which don't depend on other blocks, so that they
+/// can be fetched for several blocks at once.
+struct StreamerMessageParts {
+    block: views::BlockView,
+    chain_id: String,
+    runtime_config: Arc<RuntimeConfig>,
+    chunks: Vec<ChunkParts>,
+    shards_outcomes: HashMap<ShardId, Vec<IndexerExecutionOutcomeWithOptionalReceipt>>,
+    indexer_shards: Vec<IndexerShard>,
+}
+
+struct ChunkParts {
+    author: AccountId,
+    header: views::ChunkHeaderView,
+    transactions: Vec<IndexerTransactionWithOutcome>,
+    local_receipts: Vec<views::ReceiptView>,
+    /// Local receipts executed in a later block, to be passed on through
+    /// `DELAYED_LOCAL_RECEIPTS_CACHE`.
+    delayed_local_receipts: Vec<views::ReceiptView>,
+    non_local_receipts: Vec<views::ReceiptView>,
+    /// Outcomes of the receipts executed in the chunk.  Receipts of the outcomes of the delayed
+    /// local receipts of previous blocks are missing.
+    receipt_outcomes: Vec<IndexerExecutionOutcomeWithOptionalReceipt>,
+}
+
+/// Fetches the chunks, outcomes and state changes of a block and builds the local receipts of
+/// its transactions.  Doesn't access `DELAYED_LOCAL_RECEIPTS_CACHE`.
+async fn fetch_streamer_message_parts(
+    client: &Addr<near_client::ViewClientActor>,
+    block: views::BlockView,
+    filter: &IndexerFilter,
+) -> Result<StreamerMessageParts, FailedToFetchData> {
+    let mut chunks = fetch_block_chunks(&client, &block, filter).await?;
+    // Transactions are filtered only after the local receipts are built from them, since the
+    // outcomes of the local receipts are selected on their own.
+    let transaction_hashes: HashSet<CryptoHash> = chunks
+        .iter()
+        .flat_map(|chunk| chunk.transactions.iter().map(|transaction| transaction.hash))
+        .collect();
+    for chunk in &mut chunks {
+        chunk.receipts.retain(|receipt| filter.matches_receipt(receipt));
+    }
 
     let protocol_config_view = fetch_protocol_config(&client, block.header.hash).await?;
     let num_shards = protocol_config_view.num_block_producer_seats_per_shard.len()
         as near_primitives::types::NumShards;
 
     let runtime_config_store = near_parameters::RuntimeConfigStore::new(None);
-    let runtime_config = runtime_config_store.get_config(protocol_config_view.protocol_version);
+    let runtime_config =
+        runtime_config_store.get_config(protocol_config_view.protocol_version).clone();
 
-    let mut shards_outcomes = fetch_outcomes(&client, block.header.hash).await?;
+    let mut shards_outcomes =
+        fetch_outcomes(&client, block.header.hash, filter, &transaction_hashes).await?;
     let mut state_changes = fetch_state_changes(
         &client,
         block.header.hash,
         near_primitives::types::EpochId(block.header.epoch_id),
+        filter,
     )
     .await?;
-    let mut indexer_shards = (0..num_shards)
+    let indexer_shards = (0..num_shards)
         .map(|shard_id| IndexerShard {
             shard_id,
             chunk: None,
@@ -103,11 +164,9 @@ pub async fn build_streamer_message(
         })
         .collect::<Vec<_>>();
 
+    let mut chunks_parts = Vec::with_capacity(chunks.len());
     for chunk in chunks {
-        let views::ChunkView { transactions, author, header, receipts: chunk_non_local_receipts } =
-            chunk;
-
-        let shard_id = header.shard_id as usize;
+        let views::ChunkView { transactions, author, header, receipts: non_local_receipts } = chunk;
 
         let mut outcomes = shards_outcomes
             .remove(&header.shard_id)
@@ -116,7 +175,7 @@ pub async fn build_streamer_message(
         // Take execution outcomes for receipts from the vec and keep only the ones for transactions
         let mut receipt_outcomes = outcomes.split_off(transactions.len());
 
-        let indexer_transactions = transactions
+        let mut indexer_transactions = transactions
             .into_iter()
             .zip(outcomes.into_iter())
             .map(|(transaction, outcome)| {
@@ -128,7 +187,7 @@ pub async fn build_streamer_message(
             })
             .collect::<Vec<IndexerTransactionWithOutcome>>();
 
-        let chunk_local_receipts = convert_transactions_sir_into_local_receipts(
+        let mut local_receipts = convert_transactions_sir_into_local_receipts(
             &client,
             &runtime_config,
             indexer_transactions
@@ -140,28 +199,87 @@ pub async fn build_streamer_message(
         )
         .await?;
 
+        indexer_transactions.retain(|tx| filter.matches_transaction(&tx.transaction));
+
         // Add local receipts to corresponding outcomes
-        for receipt in &chunk_local_receipts {
+        let mut delayed_local_receipts = vec![];
+        for receipt in &local_receipts {
             if let Some(outcome) = receipt_outcomes
                 .iter_mut()
                 .find(|outcome| outcome.execution_outcome.id == receipt.receipt_id)
             {
                 debug_assert!(outcome.receipt.is_none());
                 outcome.receipt = Some(receipt.clone());
-            } else {
-                if let Ok(mut cache) = DELAYED_LOCAL_RECEIPTS_CACHE.write() {
-                    cache.insert(receipt.receipt_id, receipt.clone());
-                } else {
-                    tracing::warn!(
-                        target: INDEXER,
-                        "Unable to insert receipt {} into DELAYED_LOCAL_RECEIPTS_CACHE",
-                        receipt.receipt_id,
-                    );
-                }
+            } else if filter.matches_account(&receipt.receiver_id) {
+                // Outcomes of the receipts executed by the accounts which aren't selected are
+                // never fetched, so their receipts would stay in the cache forever.
+                delayed_local_receipts.push(receipt.clone());
             }
         }
+        local_receipts.retain(|receipt| filter.matches_receipt(receipt));
+
+        chunks_parts.push(ChunkParts {
+            author,
+            header,
+            transactions: indexer_transactions,
+            local_receipts,
+            delayed_local_receipts,
+            non_local_receipts,
+            receipt_outcomes,
+        });
+    }
+
+    Ok(StreamerMessageParts {
+        block,
+        chain_id: protocol_config_view.chain_id,
+        runtime_config,
+        chunks: chunks_parts,
+        shards_outcomes,
+        indexer_shards,
+    })
+}
+
+/// Completes the `StreamerMessage` of a block with the receipts of the outcomes of delayed local
+/// receipts.  The receipts are taken from `DELAYED_LOCAL_RECEIPTS_CACHE`, so the messages have to
+/// be assembled in the order of blocks.
+async fn assemble_streamer_message(
+    client: &Addr<near_client::ViewClientActor>,
+    parts: StreamerMessageParts,
+    filter: &IndexerFilter,
+) -> Result<StreamerMessage, FailedToFetchData> {
+    let StreamerMessageParts {
+        block,
+        chain_id,
+        runtime_config,
+        chunks,
+        shards_outcomes,
+        mut indexer_shards,
+    } = parts;
+
+    for chunk in chunks {
+        let ChunkParts {
+            author,
+            header,
+            transactions,
+            local_receipts: mut chunk_receipts,
+            delayed_local_receipts,
+            non_local_receipts: chunk_non_local_receipts,
+            receipt_outcomes,
+        } = chunk;
 
-        let mut chunk_receipts = chunk_local_receipts;
+        let shard_id = header.shard_id as usize;
+
+        for receipt in delayed_local_receipts {
+            if let Ok(mut cache) = DELAYED_LOCAL_RECEIPTS_CACHE.write() {
+                cache.insert(receipt.receipt_id, receipt);
+            } else {
+                tracing::warn!(
+                    target: INDEXER,
+                    "Unable to insert receipt {} into DELAYED_LOCAL_RECEIPTS_CACHE",
+                    receipt.receipt_id,
+                );
+            }
+        }
 
         let mut receipt_execution_outcomes: Vec<IndexerExecutionOutcomeWithReceipt> = vec![];
         for outcome in receipt_outcomes {
@@ -209,6 +327,9 @@ pub async fn build_streamer_message(
                     .await?
                 }
             };
+            if !filter.matches_receipt(&receipt) {
+                continue;
+            }
             receipt_execution_outcomes
                 .push(IndexerExecutionOutcomeWithReceipt { execution_outcome, receipt });
         }
@@ -222,7 +343,7 @@ pub async fn build_streamer_message(
         // ExecutionOutcomes appear.
         // ref: https://github.com/near/nearcore/pull/4248
         if PROBLEMATIC_BLOCKS.contains(&block.header.hash)
-            && &protocol_config_view.chain_id == near_primitives::chains::MAINNET
+            && chain_id == near_primitives::chains::MAINNET
         {
             let mut restored_receipts: Vec<views::ReceiptView> = vec![];
             let receipt_ids_included: std::collections::HashSet<CryptoHash> =
@@ -240,12 +361,8 @@ pub async fn build_streamer_message(
 
         indexer_shards[shard_id].receipt_execution_outcomes = receipt_execution_outcomes;
         // Put the chunk into corresponding indexer shard
-        indexer_shards[shard_id].chunk = Some(IndexerChunkView {
-            author,
-            header,
-            transactions: indexer_transactions,
-            receipts: chunk_receipts,
-        });
+        indexer_shards[shard_id].chunk =
+            Some(IndexerChunkView { author, header, transactions, receipts: chunk_receipts });
     }
 
     // Ideally we expect `shards_outcomes` to be empty by this time, but if something went wrong with
@@ -260,6 +377,8 @@ pub async fn build_streamer_message(
         )
     }
 
+    indexer_shards.retain(|shard| filter.matches_shard(shard.shard_id));
+
     Ok(StreamerMessage { block, shards: indexer_shards })
 }
 
@@ -321,10 +440,12 @@ async fn find_local_receipt_by_id_in_block(
     block: views::BlockView,
     receipt_id: near_primitives::hash::CryptoHash,
 ) -> Result<Option<views::ReceiptView>, FailedToFetchData> {
-    let chunks = fetch_block_chunks(&client, &block).await?;
+    let filter = IndexerFilter::default();
+    let chunks = fetch_block_chunks(&client, &block, &filter).await?;
 
     let protocol_config_view = fetch_protocol_config(&client, block.header.hash).await?;
-    let mut shards_outcomes = fetch_outcomes(&client, block.header.hash).await?;
+    let mut shards_outcomes =
+        fetch_outcomes(&client, block.header.hash, &filter, &HashSet::new()).await?;
 
     for chunk in chunks {
         let views::ChunkView { header, transactions, .. } = chunk;
@@ -370,6 +491,7 @@ pub(crate) async fn start(
     indexer_config: IndexerConfig,
     store_config: near_store::StoreConfig,
     archive: bool,
+    checkpoint: Option<Checkpoint>,
     blocks_sink: mpsc::Sender<StreamerMessage>,
 ) {
     info!(target: INDEXER, "Starting Streamer...");
@@ -406,22 +528,34 @@ pub(crate) async fn start(
         };
 
         let latest_block_height = block.header.height;
-        let start_syncing_block_height = if let Some(last_synced_block_height) =
-            last_synced_block_height
-        {
-            last_synced_block_height + 1
-        } else {
-            match indexer_config.sync_mode {
-                crate::SyncModeEnum::FromInterruption => {
-                    match db.get(b"last_synced_block_height").unwrap() {
-                        Some(value) => String::from_utf8(value).unwrap().parse::<u64>().unwrap(),
-                        None => latest_block_height,
+        let start_syncing_block_height =
+            if let Some(last_synced_block_height) = last_synced_block_height {
+                last_synced_block_height + 1
+            } else {
+                match indexer_config.sync_mode {
+                    crate::SyncModeEnum::FromInterruption => {
+                        // The checkpoint is only advanced by a sink, without one the
+                        // last synced block stored by the streamer itself is used.
+                        let acknowledged_block_height = match &checkpoint {
+                            Some(checkpoint) => checkpoint.load().unwrap_or_else(|err| {
+                                panic!("Unable to read indexer checkpoint: {:?}", err)
+                            }),
+                            None => None,
+                        };
+                        match acknowledged_block_height {
+                            Some(acknowledged_block_height) => acknowledged_block_height + 1,
+                            None => match db.get(b"last_synced_block_height").unwrap() {
+                                Some(value) => {
+                                    String::from_utf8(value).unwrap().parse::<u64>().unwrap()
+                                }
+                                None => latest_block_height,
+                            },
+                        }
                     }
+                    crate::SyncModeEnum::LatestSynced => latest_block_height,
+                    crate::SyncModeEnum::BlockHeight(height) => height,
                 }
-                crate::SyncModeEnum::LatestSynced => latest_block_height,
-                crate::SyncModeEnum::BlockHeight(height) => height,
-            }
-        };
+            };
 
         debug!(
             target: INDEXER,
@@ -434,7 +568,9 @@ pub(crate) async fn start(
         for block_height in start_syncing_block_height..=latest_block_height {
             metrics::CURRENT_BLOCK_HEIGHT.set(block_height as i64);
             if let Ok(block) = fetch_block_by_height(&view_client, block_height).await {
-                let response = build_streamer_message(&view_client, block).await;
+                let response =
+                    build_filtered_streamer_message(&view_client, block, &indexer_config.filter)
+                        .await;
 
                 match response {
                     Ok(streamer_message) => {
@@ -463,3 +599,234 @@ pub(crate) async fn start(
         }
     }
 }
+
+/// Streams the blocks of `[start_block_height, end_block_height]` in order and stops.
+///
+/// The blocks and their chunks, outcomes and state changes are fetched for up to `concurrency`
+/// blocks at once, while the messages are assembled one at a time in the order of heights, so
+/// that delayed local receipts pass through `DELAYED_LOCAL_RECEIPTS_CACHE` from the block
+/// producing them to the block executing them.  Heights without a block are skipped.
+pub(crate) async fn replay(
+    view_client: Addr<near_client::ViewClientActor>,
+    replay_config: ReplayConfig,
+    blocks_sink: mpsc::Sender<StreamerMessage>,
+) {
+    info!(
+        target: INDEXER,
+        "Replaying blocks #{}..=#{}...",
+        replay_config.start_block_height,
+        replay_config.end_block_height
+    );
+    metrics::START_BLOCK_HEIGHT.set(replay_config.start_block_height as i64);
+    metrics::LATEST_BLOCK_HEIGHT.set(replay_config.end_block_height as i64);
+
+    let view_client = &view_client;
+    let filter = &replay_config.filter;
+    let mut blocks =
+        futures::stream::iter(replay_config.start_block_height..=replay_config.end_block_height)
+            .map(|block_height| async move {
+                let parts = match fetch_block_by_height_if_exists(view_client, block_height).await {
+                    Ok(Some(block)) => {
+                        fetch_streamer_message_parts(view_client, block, filter).await.map(Some)
+                    }
+                    Ok(None) => Ok(None),
+                    Err(err) => Err(err),
+                };
+                (block_height, parts)
+            })
+            .buffered(replay_config.concurrency.max(1));
+
+    while let Some((block_height, parts)) = blocks.next().await {
+        metrics::CURRENT_BLOCK_HEIGHT.set(block_height as i64);
+        let message = match parts {
+            Ok(Some(parts)) => assemble_streamer_message(view_client, parts, filter).await,
+            Ok(None) => {
+                debug!(target: INDEXER, "No block at height #{}", block_height);
+                continue;
+            }
+            Err(err) => Err(err),
+        };
+        match message {
+            Ok(streamer_message) => {
+                if blocks_sink.send(streamer_message).await.is_err() {
+                    error!(
+                        target: INDEXER,
+                        "Unable to send StreamerMessage to listener, listener doesn't listen. terminating..."
+                    );
+                    return;
+                }
+                metrics::NUM_STREAMER_MESSAGES_SENT.inc();
+            }
+            Err(err) => {
+                error!(
+                    target: INDEXER,
+                    "Unable to fetch or build StreamerMessage for block #{}, terminating: {:?}",
+                    block_height,
+                    err
+                );
+                return;
+            }
+        }
+    }
+    info!(target: INDEXER, "Replay of blocks up to #{} finished", replay_config.end_block_height);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ActionKind;
+    use near_actix_test_utils::run_actix;
+    use near_chain_configs::Genesis;
+    use near_crypto::{InMemorySigner, KeyType};
+    use near_network::client::ProcessTxRequest;
+    use near_network::tcp;
+    use near_o11y::WithSpanContextExt;
+    use near_primitives::transaction::SignedTransaction;
+
+    /// Replays a range reaching past the head of a running node with split storage, whose hot
+    /// and cold databases are opened in read-only mode next to it. The blocks of the chain are
+    /// streamed in the order of heights and the heights without a block are skipped.
+    #[test]
+    fn test_replay_skips_missing_heights() {
+        let genesis = Genesis::test(vec!["test0".parse().unwrap()], 1);
+        let genesis_height = genesis.config.genesis_height;
+        let mut near_config =
+            nearcore::load_test_config("test0", tcp::ListenerAddr::reserve_for_test(), genesis);
+        near_config.config.archive = true;
+        near_config.client_config.archive = true;
+        near_config.config.save_trie_changes = Some(true);
+        near_config.client_config.save_trie_changes = true;
+        near_config.config.cold_store = Some(near_store::StoreConfig {
+            path: Some("cold-data".into()),
+            ..near_config.config.store.clone()
+        });
+        let dir = tempfile::tempdir().unwrap();
+        near_config.save_to_dir(dir.path());
+
+        run_actix(async move {
+            let nearcore::NearNode { view_client, .. } =
+                nearcore::start_with_config(dir.path(), near_config).unwrap();
+            let final_block = loop {
+                if let Ok(block) = fetch_latest_block(&view_client).await {
+                    if block.header.height >= genesis_height + 5 {
+                        break block;
+                    }
+                }
+                time::sleep(Duration::from_millis(100)).await;
+            };
+            let mut expected_heights = vec![final_block.header.height];
+            let mut block = final_block.clone();
+            while block.header.height > genesis_height {
+                block = fetch_block(&view_client, block.header.prev_hash).await.unwrap();
+                expected_heights.push(block.header.height);
+            }
+            expected_heights.reverse();
+
+            let replay_config = ReplayConfig {
+                home_dir: dir.path().to_path_buf(),
+                start_block_height: genesis_height,
+                end_block_height: final_block.header.height + 1000,
+                concurrency: 4,
+                filter: IndexerFilter::default(),
+            };
+            let mut receiver = crate::replay(replay_config).unwrap();
+            let mut heights = vec![];
+            while let Some(message) = receiver.recv().await {
+                heights.push(message.block.header.height);
+            }
+
+            assert!(heights.windows(2).all(|pair| pair[0] < pair[1]), "{:?}", heights);
+            let replayed_final_heights: Vec<_> = heights
+                .iter()
+                .copied()
+                .take_while(|height| *height <= final_block.header.height)
+                .collect();
+            assert_eq!(replayed_final_heights, expected_heights);
+            assert!(heights.len() < 1000, "heights past the head must be skipped");
+            actix::System::current().stop();
+        });
+    }
+
+    /// A self-transfer produces a local receipt, which is built from the transaction. Filtering
+    /// the transaction out by its actions must drop the outcome of the receipt too instead of
+    /// looking the receipt up in the previous blocks.
+    #[test]
+    fn test_filter_drops_local_receipt_of_filtered_transaction() {
+        let genesis = Genesis::test(vec!["test0".parse().unwrap()], 1);
+        let genesis_height = genesis.config.genesis_height;
+        let near_config =
+            nearcore::load_test_config("test0", tcp::ListenerAddr::reserve_for_test(), genesis);
+        let dir = tempfile::tempdir().unwrap();
+
+        run_actix(async move {
+            let nearcore::NearNode { client, view_client, .. } =
+                nearcore::start_with_config(dir.path(), near_config).unwrap();
+            let genesis_block = fetch_block_by_height(&view_client, genesis_height).await.unwrap();
+            let signer =
+                InMemorySigner::from_seed("test0".parse().unwrap(), KeyType::ED25519, "test0");
+            let transaction = SignedTransaction::send_money(
+                1,
+                "test0".parse().unwrap(),
+                "test0".parse().unwrap(),
+                &signer.into(),
+                1,
+                genesis_block.header.hash,
+            );
+            let transaction_hash = transaction.get_hash();
+            client
+                .send(
+                    ProcessTxRequest { transaction, is_forwarded: false, check_only: false }
+                        .with_span_context(),
+                )
+                .await
+                .unwrap();
+
+            let filter = IndexerFilter {
+                action_kinds: vec![ActionKind::FunctionCall],
+                ..Default::default()
+            };
+            let mut local_receipt_id = None;
+            let mut local_receipt_executed = false;
+            let mut block_height = genesis_height + 1;
+            while !local_receipt_executed {
+                let Some(block) =
+                    fetch_block_by_height_if_exists(&view_client, block_height).await.unwrap()
+                else {
+                    time::sleep(Duration::from_millis(100)).await;
+                    continue;
+                };
+                block_height += 1;
+                let message = build_streamer_message(&view_client, block.clone()).await.unwrap();
+                for shard in &message.shards {
+                    let transactions = shard.chunk.iter().flat_map(|chunk| &chunk.transactions);
+                    for transaction in transactions {
+                        if transaction.transaction.hash == transaction_hash {
+                            local_receipt_id = transaction
+                                .outcome
+                                .execution_outcome
+                                .outcome
+                                .receipt_ids
+                                .first()
+                                .copied();
+                        }
+                    }
+                    local_receipt_executed |= shard
+                        .receipt_execution_outcomes
+                        .iter()
+                        .any(|outcome| Some(outcome.receipt.receipt_id) == local_receipt_id);
+                }
+
+                let message =
+                    build_filtered_streamer_message(&view_client, block, &filter).await.unwrap();
+                for shard in message.shards {
+                    assert!(shard.receipt_execution_outcomes.is_empty());
+                    if let Some(chunk) = shard.chunk {
+                        assert!(chunk.transactions.is_empty());
+                        assert!(chunk.receipts.is_empty());
+                    }
+                }
+            }
+            actix::System::current().stop();
+        });
+    }
+}
@@ -12,7 +12,11 @@ pub mod network_info;
 pub mod query;
 pub mod receipts;
 pub mod sandbox;
+pub mod simulation;
 pub mod split_storage;
+pub mod state_diff;
 pub mod status;
+pub mod subscriptions;
 pub mod transactions;
+pub mod tx_pool;
 pub mod validator;
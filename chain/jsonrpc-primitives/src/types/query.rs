@@ -60,6 +60,14 @@ pub enum RpcQueryError {
         block_height: near_primitives::types::BlockHeight,
         block_hash: near_primitives::hash::CryptoHash,
     },
+    #[error("State proof is larger than {limit} bytes, request fewer keys")]
+    TooLargeStateProof {
+        limit: u64,
+        block_height: near_primitives::types::BlockHeight,
+        block_hash: near_primitives::hash::CryptoHash,
+    },
+    #[error("Invalid keys for a state proof: {error_message}")]
+    InvalidStateProofKeys { error_message: String },
     #[error("The node reached its limits. Try again later. More details: {error_message}")]
     InternalError { error_message: String },
 }
@@ -77,6 +85,8 @@ pub struct RpcQueryResponse {
 pub enum QueryResponseKind {
     ViewAccount(near_primitives::views::AccountView),
     ViewCode(near_primitives::views::ContractCodeView),
+    // Goes before `ViewState`, which would otherwise match proofs of absent keys.
+    ViewStateProof(near_primitives::views::ViewStateProofResult),
     ViewState(near_primitives::views::ViewStateResult),
     CallResult(near_primitives::views::CallResult),
     AccessKey(near_primitives::views::AccessKeyView),
@@ -1,5 +1,8 @@
+use near_primitives::hash::CryptoHash;
+use near_primitives::serialize::dec_format;
 use near_primitives::state_record::StateRecord;
-use near_primitives::types::BlockHeightDelta;
+use near_primitives::types::{BlockHeight, BlockHeightDelta, NumBlocks};
+use near_primitives::views::FunctionCallTraceView;
 
 #[derive(serde::Deserialize, serde::Serialize, Debug)]
 pub struct RpcSandboxPatchStateRequest {
@@ -60,3 +63,309 @@ impl From<RpcSandboxFastForwardError> for crate::errors::RpcError {
         Self::new_internal_or_handler_error(Some(error_data.clone()), error_data)
     }
 }
+
+/// Enables or disables recording of the host function calls, accessed storage
+/// keys and trap call stacks of every executed function call.  Traces of the
+/// last 1000 receipts, up to 64 MiB in total, are kept in memory.
+#[derive(serde::Deserialize, serde::Serialize)]
+pub struct RpcSandboxSetExecutionTracingRequest {
+    pub enabled: bool,
+}
+
+#[derive(serde::Deserialize, serde::Serialize)]
+pub struct RpcSandboxSetExecutionTracingResponse {}
+
+#[derive(thiserror::Error, Debug, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "name", content = "info", rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum RpcSandboxSetExecutionTracingError {
+    #[error("The node reached its limits. Try again later. More details: {error_message}")]
+    InternalError { error_message: String },
+}
+
+impl From<RpcSandboxSetExecutionTracingError> for crate::errors::RpcError {
+    fn from(error: RpcSandboxSetExecutionTracingError) -> Self {
+        let error_data = match serde_json::to_value(error) {
+            Ok(value) => value,
+            Err(err) => {
+                return Self::new_internal_error(
+                    None,
+                    format!("Failed to serialize RpcSandboxSetExecutionTracingError: {:?}", err),
+                )
+            }
+        };
+        Self::new_internal_or_handler_error(Some(error_data.clone()), error_data)
+    }
+}
+
+/// Returns the traces of the function calls of a receipt.
+#[derive(serde::Deserialize, serde::Serialize)]
+pub struct RpcSandboxExecutionTraceRequest {
+    pub receipt_id: CryptoHash,
+}
+
+#[derive(serde::Deserialize, serde::Serialize)]
+pub struct RpcSandboxExecutionTraceResponse {
+    /// Traces of the function calls executed by the receipt, in action order.
+    pub traces: Vec<FunctionCallTraceView>,
+}
+
+#[derive(thiserror::Error, Debug, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "name", content = "info", rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum RpcSandboxExecutionTraceError {
+    #[error("No execution trace is available for receipt {receipt_id}")]
+    UnknownReceipt { receipt_id: CryptoHash },
+    #[error("The node reached its limits. Try again later. More details: {error_message}")]
+    InternalError { error_message: String },
+}
+
+impl From<RpcSandboxExecutionTraceError> for crate::errors::RpcError {
+    fn from(error: RpcSandboxExecutionTraceError) -> Self {
+        let error_data = match serde_json::to_value(error) {
+            Ok(value) => value,
+            Err(err) => {
+                return Self::new_internal_error(
+                    None,
+                    format!("Failed to serialize RpcSandboxExecutionTraceError: {:?}", err),
+                )
+            }
+        };
+        Self::new_internal_or_handler_error(Some(error_data.clone()), error_data)
+    }
+}
+
+/// Remembers the current head.  At most 100 snapshots are kept and snapshots
+/// expire about an epoch before garbage collection reaches their final block.
+#[derive(serde::Deserialize, serde::Serialize)]
+pub struct RpcSandboxSnapshotRequest {}
+
+#[derive(serde::Deserialize, serde::Serialize)]
+pub struct RpcSandboxSnapshotResponse {
+    /// Id to pass to `sandbox_revert` to return to this snapshot.
+    pub snapshot_id: u64,
+    pub block_hash: CryptoHash,
+    pub block_height: BlockHeight,
+}
+
+#[derive(thiserror::Error, Debug, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "name", content = "info", rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum RpcSandboxSnapshotError {
+    #[error("Failed to take a snapshot: {error_message}")]
+    SnapshotFailed { error_message: String },
+    #[error("The node reached its limits. Try again later. More details: {error_message}")]
+    InternalError { error_message: String },
+}
+
+impl From<RpcSandboxSnapshotError> for crate::errors::RpcError {
+    fn from(error: RpcSandboxSnapshotError) -> Self {
+        let error_data = match serde_json::to_value(error) {
+            Ok(value) => value,
+            Err(err) => {
+                return Self::new_internal_error(
+                    None,
+                    format!("Failed to serialize RpcSandboxSnapshotError: {:?}", err),
+                )
+            }
+        };
+        Self::new_internal_or_handler_error(Some(error_data.clone()), error_data)
+    }
+}
+
+/// Resets the chain head, the state and the block time shift to a snapshot and
+/// drops the snapshots taken after it.  Transactions of the reverted blocks are
+/// discarded.
+#[derive(serde::Deserialize, serde::Serialize)]
+pub struct RpcSandboxRevertRequest {
+    pub snapshot_id: u64,
+}
+
+#[derive(serde::Deserialize, serde::Serialize)]
+pub struct RpcSandboxRevertResponse {
+    /// Head of the chain after the revert.
+    pub block_hash: CryptoHash,
+    pub block_height: BlockHeight,
+}
+
+#[derive(thiserror::Error, Debug, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "name", content = "info", rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum RpcSandboxRevertError {
+    #[error("Failed to revert to the snapshot: {error_message}")]
+    RevertFailed { error_message: String },
+    #[error("The node reached its limits. Try again later. More details: {error_message}")]
+    InternalError { error_message: String },
+}
+
+impl From<RpcSandboxRevertError> for crate::errors::RpcError {
+    fn from(error: RpcSandboxRevertError) -> Self {
+        let error_data = match serde_json::to_value(error) {
+            Ok(value) => value,
+            Err(err) => {
+                return Self::new_internal_error(
+                    None,
+                    format!("Failed to serialize RpcSandboxRevertError: {:?}", err),
+                )
+            }
+        };
+        Self::new_internal_or_handler_error(Some(error_data.clone()), error_data)
+    }
+}
+
+#[derive(serde::Deserialize, serde::Serialize)]
+pub struct RpcSandboxDropSnapshotRequest {
+    pub snapshot_id: u64,
+}
+
+#[derive(serde::Deserialize, serde::Serialize)]
+pub struct RpcSandboxDropSnapshotResponse {}
+
+#[derive(thiserror::Error, Debug, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "name", content = "info", rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum RpcSandboxDropSnapshotError {
+    #[error("Snapshot {snapshot_id} doesn't exist or has expired")]
+    UnknownSnapshot { snapshot_id: u64 },
+    #[error("The node reached its limits. Try again later. More details: {error_message}")]
+    InternalError { error_message: String },
+}
+
+impl From<RpcSandboxDropSnapshotError> for crate::errors::RpcError {
+    fn from(error: RpcSandboxDropSnapshotError) -> Self {
+        let error_data = match serde_json::to_value(error) {
+            Ok(value) => value,
+            Err(err) => {
+                return Self::new_internal_error(
+                    None,
+                    format!("Failed to serialize RpcSandboxDropSnapshotError: {:?}", err),
+                )
+            }
+        };
+        Self::new_internal_or_handler_error(Some(error_data.clone()), error_data)
+    }
+}
+
+#[derive(serde::Deserialize, serde::Serialize)]
+pub struct RpcSandboxSetBlockProductionRequest {
+    /// Whether automatic block production is paused. Blocks requested with
+    /// `sandbox_produce_blocks`, `sandbox_fast_forward` or `sandbox_advance_epoch` are still
+    /// produced while it is paused.
+    pub paused: bool,
+}
+
+#[derive(serde::Deserialize, serde::Serialize)]
+pub struct RpcSandboxSetBlockProductionResponse {}
+
+#[derive(thiserror::Error, Debug, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "name", content = "info", rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum RpcSandboxSetBlockProductionError {
+    #[error("The node reached its limits. Try again later. More details: {error_message}")]
+    InternalError { error_message: String },
+}
+
+impl From<RpcSandboxSetBlockProductionError> for crate::errors::RpcError {
+    fn from(error: RpcSandboxSetBlockProductionError) -> Self {
+        let error_data = match serde_json::to_value(error) {
+            Ok(value) => value,
+            Err(err) => {
+                return Self::new_internal_error(
+                    None,
+                    format!("Failed to serialize RpcSandboxSetBlockProductionError: {:?}", err),
+                )
+            }
+        };
+        Self::new_internal_or_handler_error(Some(error_data.clone()), error_data)
+    }
+}
+
+/// Produces `num_blocks` blocks, also while block production is paused, and
+/// returns once they are produced.
+#[derive(serde::Deserialize, serde::Serialize)]
+pub struct RpcSandboxProduceBlocksRequest {
+    pub num_blocks: NumBlocks,
+}
+
+#[derive(serde::Deserialize, serde::Serialize)]
+pub struct RpcSandboxProduceBlocksResponse {}
+
+#[derive(thiserror::Error, Debug, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "name", content = "info", rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum RpcSandboxProduceBlocksError {
+    #[error("The node reached its limits. Try again later. More details: {error_message}")]
+    InternalError { error_message: String },
+}
+
+impl From<RpcSandboxProduceBlocksError> for crate::errors::RpcError {
+    fn from(error: RpcSandboxProduceBlocksError) -> Self {
+        let error_data = match serde_json::to_value(error) {
+            Ok(value) => value,
+            Err(err) => {
+                return Self::new_internal_error(
+                    None,
+                    format!("Failed to serialize RpcSandboxProduceBlocksError: {:?}", err),
+                )
+            }
+        };
+        Self::new_internal_or_handler_error(Some(error_data.clone()), error_data)
+    }
+}
+
+/// Sets the timestamp of the next block.  Later blocks continue from it.
+#[derive(serde::Deserialize, serde::Serialize)]
+pub struct RpcSandboxSetNextBlockTimestampRequest {
+    #[serde(with = "dec_format")]
+    pub timestamp_nanosec: u64,
+}
+
+#[derive(serde::Deserialize, serde::Serialize)]
+pub struct RpcSandboxSetNextBlockTimestampResponse {}
+
+#[derive(thiserror::Error, Debug, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "name", content = "info", rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum RpcSandboxSetNextBlockTimestampError {
+    #[error("Invalid next block timestamp: {error_message}")]
+    InvalidTimestamp { error_message: String },
+    #[error("The node reached its limits. Try again later. More details: {error_message}")]
+    InternalError { error_message: String },
+}
+
+impl From<RpcSandboxSetNextBlockTimestampError> for crate::errors::RpcError {
+    fn from(error: RpcSandboxSetNextBlockTimestampError) -> Self {
+        let error_data = match serde_json::to_value(error) {
+            Ok(value) => value,
+            Err(err) => {
+                return Self::new_internal_error(
+                    None,
+                    format!("Failed to serialize RpcSandboxSetNextBlockTimestampError: {:?}", err),
+                )
+            }
+        };
+        Self::new_internal_or_handler_error(Some(error_data.clone()), error_data)
+    }
+}
+
+/// Fast forwards to the end of the current epoch and returns once the head is
+/// in the next epoch.
+#[derive(serde::Deserialize, serde::Serialize)]
+pub struct RpcSandboxAdvanceEpochRequest {}
+
+#[derive(serde::Deserialize, serde::Serialize)]
+pub struct RpcSandboxAdvanceEpochResponse {}
+
+#[derive(thiserror::Error, Debug, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "name", content = "info", rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum RpcSandboxAdvanceEpochError {
+    #[error("The node reached its limits. Try again later. More details: {error_message}")]
+    InternalError { error_message: String },
+}
+
+impl From<RpcSandboxAdvanceEpochError> for crate::errors::RpcError {
+    fn from(error: RpcSandboxAdvanceEpochError) -> Self {
+        let error_data = match serde_json::to_value(error) {
+            Ok(value) => value,
+            Err(err) => {
+                return Self::new_internal_error(
+                    None,
+                    format!("Failed to serialize RpcSandboxAdvanceEpochError: {:?}", err),
+                )
+            }
+        };
+        Self::new_internal_or_handler_error(Some(error_data.clone()), error_data)
+    }
+}
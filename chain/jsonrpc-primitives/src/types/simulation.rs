@@ -0,0 +1,94 @@
+use near_crypto::Signature;
+use near_primitives::transaction::{SignedTransaction, Transaction};
+use near_primitives::types::BlockReference;
+use near_primitives::views::TransactionSimulationView;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Maximum number of receipts executed by a single request.
+pub const MAX_SIMULATED_RECEIPTS: usize = 1000;
+
+/// Request of `EXPERIMENTAL_simulate_tx`.
+///
+/// Executes the transaction and all the receipts it generates, refunds and
+/// cross-shard calls included, on top of the state after the given block
+/// without persisting anything.  At most [`MAX_SIMULATED_RECEIPTS`] receipts
+/// are executed and none once `max_gas_burnt_view` gas was burnt; the
+/// remaining receipts and those sent to shards the node doesn't track are
+/// returned as pending.  Only served if `rpc.enable_simulate_tx` is set.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct RpcSimulateTransactionRequest {
+    /// Block on top of which the transaction is executed.
+    #[serde(flatten)]
+    pub block_reference: BlockReference,
+    #[serde(flatten)]
+    pub transaction: SimulatedTransaction,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub enum SimulatedTransaction {
+    #[serde(rename = "signed_tx_base64")]
+    Signed(SignedTransaction),
+    /// Borsh serialized `Transaction` encoded in base64.  Its signature is not
+    /// checked.
+    #[serde(rename = "tx_base64")]
+    Unsigned(String),
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct RpcSimulateTransactionResponse {
+    #[serde(flatten)]
+    pub result: TransactionSimulationView,
+}
+
+#[derive(thiserror::Error, Debug, Serialize, Deserialize)]
+#[serde(tag = "name", content = "info", rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum RpcSimulateTransactionError {
+    #[error("There are no fully synchronized blocks on the node yet")]
+    NoSyncedBlocks,
+    #[error("Block not found: {error_message}")]
+    UnknownBlock {
+        #[serde(skip_serializing)]
+        error_message: String,
+    },
+    #[error("Invalid request: {error_message}")]
+    InvalidRequest { error_message: String },
+    #[error("State is not available: {error_message}")]
+    UnavailableState { error_message: String },
+    #[error("The node reached its limits. Try again later. More details: {error_message}")]
+    InternalError { error_message: String },
+}
+
+impl From<RpcSimulateTransactionError> for crate::errors::RpcError {
+    fn from(error: RpcSimulateTransactionError) -> Self {
+        let error_data = Some(Value::String(error.to_string()));
+        let error_data_value = match serde_json::to_value(error) {
+            Ok(value) => value,
+            Err(err) => {
+                return Self::new_internal_error(
+                    None,
+                    format!("Failed to serialize RpcSimulateTransactionError: {:?}", err),
+                )
+            }
+        };
+        Self::new_internal_or_handler_error(error_data, error_data_value)
+    }
+}
+
+impl SimulatedTransaction {
+    /// Returns the transaction to execute and whether its signature should be
+    /// verified.  Unsigned transactions get an empty signature.
+    pub fn into_signed_transaction(self) -> Result<(SignedTransaction, bool), String> {
+        match self {
+            Self::Signed(signed_transaction) => Ok((signed_transaction, true)),
+            Self::Unsigned(tx_base64) => {
+                let bytes = near_primitives::serialize::from_base64(&tx_base64)
+                    .map_err(|err| format!("Failed to decode transaction: {}", err))?;
+                let transaction = near_primitives::borsh::from_slice::<Transaction>(&bytes)
+                    .map_err(|err| format!("Failed to decode transaction: {}", err))?;
+                let signature = Signature::empty(transaction.public_key().key_type());
+                Ok((SignedTransaction::new(signature, transaction), false))
+            }
+        }
+    }
+}
@@ -0,0 +1,76 @@
+use near_primitives::types::{AccountId, BlockId, ShardId, StoreKey};
+use near_primitives::views::StateDiffView;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Maximum number of changes returned by a single request.
+pub const MAX_STATE_DIFF_CHANGES: usize = 1000;
+
+/// Maximum total size of the keys and values returned by a single request,
+/// unless a single change is larger.
+pub const MAX_STATE_DIFF_BYTES: usize = 4 * 1024 * 1024;
+
+/// Request of `EXPERIMENTAL_state_diff`, returning the keys whose values
+/// differ between the states after two blocks together with their old and new
+/// values.  Changes are returned in key order and, if there are more than fit
+/// into `limit` changes or `MAX_STATE_DIFF_BYTES` bytes, `next_key` of the
+/// response is set and the next page is requested by passing it as
+/// `start_key`.  Only served if `rpc.enable_debug_rpc` is set.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct RpcStateDiffRequest {
+    /// Block after which the old state is taken.
+    pub from_block_id: BlockId,
+    /// Block after which the new state is taken.
+    pub to_block_id: BlockId,
+    /// Shard to compare.  Required unless `account_id` is set.
+    #[serde(default)]
+    pub shard_id: Option<ShardId>,
+    /// If set, only changes of this account's keys are returned.
+    #[serde(default)]
+    pub account_id: Option<AccountId>,
+    /// If set, only changes of keys not smaller than this key are returned.
+    #[serde(default)]
+    pub start_key: Option<StoreKey>,
+    /// Maximum number of changes to return, at most `MAX_STATE_DIFF_CHANGES`
+    /// which is also the default.
+    #[serde(default)]
+    pub limit: Option<usize>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct RpcStateDiffResponse {
+    #[serde(flatten)]
+    pub result: StateDiffView,
+}
+
+#[derive(thiserror::Error, Debug, Serialize, Deserialize)]
+#[serde(tag = "name", content = "info", rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum RpcStateDiffError {
+    #[error("Block not found: {error_message}")]
+    UnknownBlock {
+        #[serde(skip_serializing)]
+        error_message: String,
+    },
+    #[error("Invalid request: {error_message}")]
+    InvalidRequest { error_message: String },
+    #[error("State is not available: {error_message}")]
+    UnavailableState { error_message: String },
+    #[error("The node reached its limits. Try again later. More details: {error_message}")]
+    InternalError { error_message: String },
+}
+
+impl From<RpcStateDiffError> for crate::errors::RpcError {
+    fn from(error: RpcStateDiffError) -> Self {
+        let error_data = Some(Value::String(error.to_string()));
+        let error_data_value = match serde_json::to_value(error) {
+            Ok(value) => value,
+            Err(err) => {
+                return Self::new_internal_error(
+                    None,
+                    format!("Failed to serialize RpcStateDiffError: {:?}", err),
+                )
+            }
+        };
+        Self::new_internal_or_handler_error(error_data, error_data_value)
+    }
+}
@@ -1,12 +1,13 @@
 #[cfg(feature = "debug_types")]
 use near_client_primitives::debug::{
-    DebugBlockStatusData, EpochInfoView, TrackedShardsView, ValidatorStatus,
+    DebugBlockStatusData, EpochInfoView, FlatStorageScrubberShardView, TrackedShardsView,
+    ValidatorStatus,
 };
 #[cfg(feature = "debug_types")]
 use near_primitives::views::{
     CatchupStatusView, ChainProcessingInfo, NetworkGraphView, NetworkRoutesView, PeerStoreView,
     RecentOutboundConnectionsView, RequestedStatePartsView, SnapshotHostsView,
-    SplitStorageInfoView, SyncStatusView,
+    SplitStorageInfoView, SyncStatusView, TxPoolView,
 };
 
 #[derive(Debug, serde::Serialize, serde::Deserialize)]
@@ -36,6 +37,9 @@ pub enum DebugStatusResponse {
     Routes(NetworkRoutesView),
     SnapshotHosts(SnapshotHostsView),
     SplitStoreStatus(SplitStorageInfoView),
+    TxPool(TxPoolView),
+    // Progress of the flat storage scrubber for every shard it has checked.
+    FlatStorageScrubber(Vec<FlatStorageScrubberShardView>),
 }
 
 #[cfg(feature = "debug_types")]
@@ -0,0 +1,141 @@
+use near_primitives::hash::CryptoHash;
+use near_primitives::types::{AccountId, BlockHeight, Finality, ShardId};
+use near_primitives::views::{
+    BlockView, ChunkView, ExecutionOutcomeWithIdView, StateChangeWithCauseView,
+};
+use serde_json::Value;
+
+/// Name of the JSON RPC method used for notifications pushed to subscribers.
+pub const SUBSCRIPTION_NOTIFICATION_METHOD: &str = "subscription";
+
+/// Identifier of a subscription, unique within a single WebSocket connection.
+pub type SubscriptionId = u64;
+
+/// Stream of events a client can subscribe to over the WebSocket endpoint.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum SubscriptionKind {
+    /// Every new block reaching the given finality.
+    Blocks {
+        #[serde(default)]
+        finality: Finality,
+    },
+    /// Every new chunk of the given shard included in a block reaching the given finality.
+    Chunks {
+        #[serde(default)]
+        finality: Finality,
+        shard_id: ShardId,
+    },
+    /// Execution outcomes of transactions and receipts whose receiver is `receiver_id`.
+    ExecutionOutcomes {
+        #[serde(default)]
+        finality: Finality,
+        receiver_id: AccountId,
+    },
+    /// State changes of accounts whose id starts with `account_id_prefix`.
+    StateChanges {
+        #[serde(default)]
+        finality: Finality,
+        account_id_prefix: String,
+    },
+}
+
+impl SubscriptionKind {
+    pub fn finality(&self) -> &Finality {
+        match self {
+            SubscriptionKind::Blocks { finality }
+            | SubscriptionKind::Chunks { finality, .. }
+            | SubscriptionKind::ExecutionOutcomes { finality, .. }
+            | SubscriptionKind::StateChanges { finality, .. } => finality,
+        }
+    }
+}
+
+/// Parameters of the `EXPERIMENTAL_subscribe` method, only served over the
+/// `/ws` WebSocket endpoint, which is enabled by
+/// `rpc.subscriptions_config.enabled`.  Events are pushed as `subscription`
+/// notifications until `EXPERIMENTAL_unsubscribe` is called or the connection
+/// is closed.  Blocks are delivered in height order without gaps.
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct RpcSubscribeRequest {
+    #[serde(flatten)]
+    pub subscription: SubscriptionKind,
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct RpcSubscribeResponse {
+    pub subscription_id: SubscriptionId,
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct RpcUnsubscribeRequest {
+    pub subscription_id: SubscriptionId,
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct RpcUnsubscribeResponse {}
+
+/// Payload of a single notification delivered to a subscriber.
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum SubscriptionEvent {
+    Block {
+        #[serde(flatten)]
+        block_view: BlockView,
+    },
+    Chunk {
+        block_hash: CryptoHash,
+        block_height: BlockHeight,
+        #[serde(flatten)]
+        chunk_view: ChunkView,
+    },
+    ExecutionOutcome {
+        block_height: BlockHeight,
+        shard_id: ShardId,
+        #[serde(flatten)]
+        outcome: ExecutionOutcomeWithIdView,
+    },
+    StateChanges {
+        block_hash: CryptoHash,
+        block_height: BlockHeight,
+        changes: Vec<StateChangeWithCauseView>,
+    },
+}
+
+/// Parameters of the `subscription` notification.
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct RpcSubscriptionNotification {
+    pub subscription_id: SubscriptionId,
+    pub result: SubscriptionEvent,
+}
+
+#[derive(thiserror::Error, Debug, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "name", content = "info", rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum RpcSubscriptionError {
+    #[error("Subscriptions are only available over the WebSocket endpoint")]
+    NotSupported,
+    #[error("Subscription {subscription_id} does not exist")]
+    UnknownSubscription { subscription_id: SubscriptionId },
+    #[error("Too many subscriptions on this connection, the limit is {limit}")]
+    TooManySubscriptions { limit: usize },
+    #[error("The node reached its limits. Try again later. More details: {error_message}")]
+    InternalError { error_message: String },
+}
+
+impl From<RpcSubscriptionError> for crate::errors::RpcError {
+    fn from(error: RpcSubscriptionError) -> Self {
+        let error_data = Some(Value::String(error.to_string()));
+
+        let error_data_value = match serde_json::to_value(error) {
+            Ok(value) => value,
+            Err(err) => {
+                return Self::new_internal_error(
+                    None,
+                    format!("Failed to serialize RpcSubscriptionError: {:?}", err),
+                )
+            }
+        };
+
+        Self::new_internal_or_handler_error(error_data, error_data_value)
+    }
+}
@@ -0,0 +1,61 @@
+use near_primitives::types::AccountId;
+use near_primitives::views::TxPoolView;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::types::status::RpcStatusError;
+
+/// Request of `EXPERIMENTAL_tx_pool`, returning the size of the pool of each
+/// shard and the pending nonces of each signer access key.  Only served if
+/// `rpc.enable_debug_rpc` is set.
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct RpcTxPoolRequest {
+    /// If set, the hashes of the pending transactions signed by or sent to this
+    /// account are returned too.
+    #[serde(default)]
+    pub account_id: Option<AccountId>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct RpcTxPoolResponse {
+    #[serde(flatten)]
+    pub result: TxPoolView,
+}
+
+#[derive(thiserror::Error, Debug, Serialize, Deserialize)]
+#[serde(tag = "name", content = "info", rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum RpcTxPoolError {
+    #[error("The node reached its limits. Try again later. More details: {error_message}")]
+    InternalError { error_message: String },
+}
+
+impl From<RpcTxPoolError> for crate::errors::RpcError {
+    fn from(error: RpcTxPoolError) -> Self {
+        let error_data = match &error {
+            RpcTxPoolError::InternalError { .. } => Some(Value::String(error.to_string())),
+        };
+
+        let error_data_value = match serde_json::to_value(error) {
+            Ok(value) => value,
+            Err(err) => {
+                return Self::new_internal_error(
+                    None,
+                    format!("Failed to serialize RpcTxPoolError: {:?}", err),
+                )
+            }
+        };
+
+        Self::new_internal_or_handler_error(error_data, error_data_value)
+    }
+}
+
+impl RpcTxPoolError {
+    // See `RpcSplitStorageInfoError::into_rpc_status_error`.
+    pub fn into_rpc_status_error(self) -> RpcStatusError {
+        match self {
+            RpcTxPoolError::InternalError { error_message } => {
+                RpcStatusError::InternalError { error_message }
+            }
+        }
+    }
+}
@@ -267,6 +267,31 @@ impl JsonRpcClient {
         call_method(&self.client, &self.server_addr, "EXPERIMENTAL_split_storage_info", request)
     }
 
+    #[allow(non_snake_case)]
+    pub fn EXPERIMENTAL_tx_pool(
+        &self,
+        request: near_jsonrpc_primitives::types::tx_pool::RpcTxPoolRequest,
+    ) -> RpcRequest<near_jsonrpc_primitives::types::tx_pool::RpcTxPoolResponse> {
+        call_method(&self.client, &self.server_addr, "EXPERIMENTAL_tx_pool", request)
+    }
+
+    #[allow(non_snake_case)]
+    pub fn EXPERIMENTAL_state_diff(
+        &self,
+        request: near_jsonrpc_primitives::types::state_diff::RpcStateDiffRequest,
+    ) -> RpcRequest<near_jsonrpc_primitives::types::state_diff::RpcStateDiffResponse> {
+        call_method(&self.client, &self.server_addr, "EXPERIMENTAL_state_diff", request)
+    }
+
+    #[allow(non_snake_case)]
+    pub fn EXPERIMENTAL_simulate_tx(
+        &self,
+        request: near_jsonrpc_primitives::types::simulation::RpcSimulateTransactionRequest,
+    ) -> RpcRequest<near_jsonrpc_primitives::types::simulation::RpcSimulateTransactionResponse>
+    {
+        call_method(&self.client, &self.server_addr, "EXPERIMENTAL_simulate_tx", request)
+    }
+
     pub fn validators(
         &self,
         epoch_id_or_block_id: Option<EpochReference>,
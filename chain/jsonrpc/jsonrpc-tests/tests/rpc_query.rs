@@ -325,6 +325,7 @@ fn test_query_call_function() {
                     account_id: "test".parse().unwrap(),
                     method_name: "method".to_string(),
                     args: vec![].into(),
+                    include_gas_profile: false,
                 },
             })
             .await
@@ -583,3 +584,57 @@ fn test_get_chunk_with_object_in_params() {
         assert_eq!(chunk.header.chunk_hash, same_chunk.header.chunk_hash);
     });
 }
+
+/// Send a batch of requests and check that responses come back in order.
+#[test]
+fn test_batch_request() {
+    test_with_client!(test_utils::NodeType::NonValidator, client, async move {
+        let batch = json!([
+            {"jsonrpc": "2.0", "id": 1, "method": "block", "params": {"block_id": 0}},
+            {"jsonrpc": "2.0", "id": 2, "method": "no_such_method", "params": []},
+            {"jsonrpc": "2.0", "id": 3, "method": "status", "params": []},
+        ]);
+        let mut response = client
+            .client
+            .post(&client.server_addr)
+            .insert_header(("Content-Type", "application/json"))
+            .send_json(&batch)
+            .await
+            .unwrap();
+        let response: serde_json::Value = response.json().await.unwrap();
+        let responses = response.as_array().unwrap();
+        assert_eq!(responses.len(), 3);
+        assert_eq!(responses[0]["id"], json!(1));
+        assert_eq!(responses[0]["result"]["header"]["height"], json!(0));
+        assert_eq!(responses[1]["id"], json!(2));
+        assert_eq!(responses[1]["error"]["name"], json!("REQUEST_VALIDATION_ERROR"));
+        assert_eq!(responses[2]["id"], json!(3));
+        assert_eq!(responses[2]["result"]["chain_id"], json!("unittest"));
+    });
+}
+
+/// Empty batches and batches over the limit are rejected as a whole.
+#[test]
+fn test_batch_request_limits() {
+    test_with_client!(test_utils::NodeType::NonValidator, client, async move {
+        let request = json!({"jsonrpc": "2.0", "id": "dontcare", "method": "status"});
+        let too_large = serde_json::Value::Array(vec![
+            request;
+            near_jsonrpc::RpcLimitsConfig::default()
+                .batch_max_size
+                + 1
+        ]);
+        for batch in [json!([]), too_large] {
+            let mut response = client
+                .client
+                .post(&client.server_addr)
+                .insert_header(("Content-Type", "application/json"))
+                .send_json(&batch)
+                .await
+                .unwrap();
+            let response: serde_json::Value = response.json().await.unwrap();
+            assert!(response.is_object(), "expected a single error, got {:?}", response);
+            assert_ne!(response["error"], json!(null));
+        }
+    });
+}
@@ -0,0 +1,92 @@
+use actix::System;
+use futures::{SinkExt, StreamExt};
+use serde_json::json;
+
+use near_actix_test_utils::run_actix;
+use near_jsonrpc::client::new_client;
+use near_jsonrpc::{RpcConfig, RpcSubscriptionsConfig};
+use near_jsonrpc_primitives::message::{from_slice, Message};
+use near_jsonrpc_primitives::types::subscriptions::{
+    RpcSubscribeResponse, RpcSubscriptionNotification, SubscriptionEvent,
+    SUBSCRIPTION_NOTIFICATION_METHOD,
+};
+use near_o11y::testonly::init_test_logger;
+use near_time::Clock;
+
+use near_jsonrpc_tests::{self as test_utils, test_with_client};
+
+/// Subscriptions are only served over the WebSocket endpoint.
+#[test]
+fn test_subscribe_over_http_is_rejected() {
+    test_with_client!(test_utils::NodeType::NonValidator, client, async move {
+        let result = test_utils::call_method::<serde_json::Value>(
+            &client.client,
+            &client.server_addr,
+            "EXPERIMENTAL_subscribe",
+            json!({ "kind": "blocks", "finality": "optimistic" }),
+        )
+        .await;
+        assert!(result.is_err());
+    });
+}
+
+/// Subscribe to optimistic blocks over WebSocket and wait for the first notification.
+#[test]
+fn test_subscribe_blocks_over_websocket() {
+    init_test_logger();
+
+    run_actix(async {
+        let (_view_client_addr, addr) = test_utils::start_all_with_rpc_config(
+            Clock::real(),
+            test_utils::NodeType::Validator,
+            100,
+            false,
+            |addr| RpcConfig {
+                subscriptions_config: RpcSubscriptionsConfig {
+                    enabled: true,
+                    ..Default::default()
+                },
+                ..RpcConfig::new(addr)
+            },
+        );
+
+        actix::spawn(async move {
+            let (_response, mut connection) =
+                awc::Client::new().ws(format!("ws://{}/ws", addr)).connect().await.unwrap();
+            let request = json!({
+                "jsonrpc": "2.0",
+                "method": "EXPERIMENTAL_subscribe",
+                "id": "dontcare",
+                "params": { "kind": "blocks", "finality": "optimistic" },
+            });
+            connection.send(awc::ws::Message::Text(request.to_string().into())).await.unwrap();
+
+            let mut subscription_id = None;
+            while let Some(frame) = connection.next().await {
+                let awc::ws::Frame::Text(bytes) = frame.unwrap() else {
+                    continue;
+                };
+                match from_slice(&bytes).unwrap() {
+                    Message::Response(response) => {
+                        let response: RpcSubscribeResponse =
+                            serde_json::from_value(response.result.unwrap()).unwrap();
+                        subscription_id = Some(response.subscription_id);
+                    }
+                    Message::Notification(notification) => {
+                        assert_eq!(notification.method, SUBSCRIPTION_NOTIFICATION_METHOD);
+                        let notification: RpcSubscriptionNotification =
+                            serde_json::from_value(notification.params).unwrap();
+                        assert_eq!(Some(notification.subscription_id), subscription_id);
+                        let SubscriptionEvent::Block { block_view } = notification.result else {
+                            panic!("expected a block notification");
+                        };
+                        assert_eq!(block_view.author, "test1");
+                        break;
+                    }
+                    message => panic!("unexpected message {:?}", message),
+                }
+            }
+            System::current().stop();
+        });
+    });
+}
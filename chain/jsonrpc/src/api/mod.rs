@@ -17,9 +17,13 @@ mod network_info;
 mod query;
 mod receipts;
 mod sandbox;
+mod simulation;
 mod split_storage;
+mod state_diff;
 mod status;
+mod subscriptions;
 mod transactions;
+mod tx_pool;
 mod validator;
 
 pub(crate) trait RpcRequest: Sized {
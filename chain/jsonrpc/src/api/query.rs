@@ -78,6 +78,7 @@ fn parse_path_data(path: String, data: String) -> Result<RpcQueryRequest, RpcPar
                 account_id,
                 method_name: method_name.to_string(),
                 args: parse_data()?.into(),
+                include_gas_profile: false,
             },
             None => return Err(RpcParseError("Method name is missing".to_string())),
         },
@@ -130,6 +131,12 @@ impl RpcFrom<QueryError> for RpcQueryError {
             QueryError::TooLargeContractState { contract_account_id, block_height, block_hash } => {
                 Self::TooLargeContractState { contract_account_id, block_height, block_hash }
             }
+            QueryError::TooLargeStateProof { limit, block_height, block_hash } => {
+                Self::TooLargeStateProof { limit, block_height, block_hash }
+            }
+            QueryError::InvalidStateProofKeys { error_message } => {
+                Self::InvalidStateProofKeys { error_message }
+            }
         }
     }
 }
@@ -158,6 +165,9 @@ impl RpcFrom<near_primitives::views::QueryResponseKind>
             near_primitives::views::QueryResponseKind::ViewState(view_state_result) => {
                 Self::ViewState(view_state_result)
             }
+            near_primitives::views::QueryResponseKind::ViewStateProof(view_state_proof_result) => {
+                Self::ViewStateProof(view_state_proof_result)
+            }
             near_primitives::views::QueryResponseKind::CallResult(call_result) => {
                 Self::CallResult(call_result)
             }
@@ -3,8 +3,14 @@ use serde_json::Value;
 
 use near_jsonrpc_primitives::errors::RpcParseError;
 use near_jsonrpc_primitives::types::sandbox::{
+    RpcSandboxAdvanceEpochError, RpcSandboxAdvanceEpochRequest, RpcSandboxDropSnapshotError,
+    RpcSandboxDropSnapshotRequest, RpcSandboxExecutionTraceError, RpcSandboxExecutionTraceRequest,
     RpcSandboxFastForwardError, RpcSandboxFastForwardRequest, RpcSandboxPatchStateError,
-    RpcSandboxPatchStateRequest,
+    RpcSandboxPatchStateRequest, RpcSandboxProduceBlocksError, RpcSandboxProduceBlocksRequest,
+    RpcSandboxRevertError, RpcSandboxRevertRequest, RpcSandboxSetBlockProductionError,
+    RpcSandboxSetBlockProductionRequest, RpcSandboxSetExecutionTracingError,
+    RpcSandboxSetExecutionTracingRequest, RpcSandboxSetNextBlockTimestampError,
+    RpcSandboxSetNextBlockTimestampRequest, RpcSandboxSnapshotError, RpcSandboxSnapshotRequest,
 };
 
 use super::{Params, RpcFrom, RpcRequest};
@@ -21,6 +27,60 @@ impl RpcRequest for RpcSandboxFastForwardRequest {
     }
 }
 
+impl RpcRequest for RpcSandboxSetExecutionTracingRequest {
+    fn parse(value: Value) -> Result<Self, RpcParseError> {
+        Params::parse(value)
+    }
+}
+
+impl RpcRequest for RpcSandboxExecutionTraceRequest {
+    fn parse(value: Value) -> Result<Self, RpcParseError> {
+        Params::parse(value)
+    }
+}
+
+impl RpcRequest for RpcSandboxSnapshotRequest {
+    fn parse(value: Value) -> Result<Self, RpcParseError> {
+        Params::parse(value)
+    }
+}
+
+impl RpcRequest for RpcSandboxRevertRequest {
+    fn parse(value: Value) -> Result<Self, RpcParseError> {
+        Params::parse(value)
+    }
+}
+
+impl RpcRequest for RpcSandboxDropSnapshotRequest {
+    fn parse(value: Value) -> Result<Self, RpcParseError> {
+        Params::parse(value)
+    }
+}
+
+impl RpcRequest for RpcSandboxSetBlockProductionRequest {
+    fn parse(value: Value) -> Result<Self, RpcParseError> {
+        Params::parse(value)
+    }
+}
+
+impl RpcRequest for RpcSandboxProduceBlocksRequest {
+    fn parse(value: Value) -> Result<Self, RpcParseError> {
+        Params::parse(value)
+    }
+}
+
+impl RpcRequest for RpcSandboxSetNextBlockTimestampRequest {
+    fn parse(value: Value) -> Result<Self, RpcParseError> {
+        Params::parse(value)
+    }
+}
+
+impl RpcRequest for RpcSandboxAdvanceEpochRequest {
+    fn parse(value: Value) -> Result<Self, RpcParseError> {
+        Params::parse(value)
+    }
+}
+
 impl RpcFrom<AsyncSendError> for RpcSandboxPatchStateError {
     fn rpc_from(error: AsyncSendError) -> Self {
         Self::InternalError { error_message: error.to_string() }
@@ -32,3 +92,57 @@ impl RpcFrom<AsyncSendError> for RpcSandboxFastForwardError {
         Self::InternalError { error_message: error.to_string() }
     }
 }
+
+impl RpcFrom<AsyncSendError> for RpcSandboxSetExecutionTracingError {
+    fn rpc_from(error: AsyncSendError) -> Self {
+        Self::InternalError { error_message: error.to_string() }
+    }
+}
+
+impl RpcFrom<AsyncSendError> for RpcSandboxExecutionTraceError {
+    fn rpc_from(error: AsyncSendError) -> Self {
+        Self::InternalError { error_message: error.to_string() }
+    }
+}
+
+impl RpcFrom<AsyncSendError> for RpcSandboxSnapshotError {
+    fn rpc_from(error: AsyncSendError) -> Self {
+        Self::InternalError { error_message: error.to_string() }
+    }
+}
+
+impl RpcFrom<AsyncSendError> for RpcSandboxRevertError {
+    fn rpc_from(error: AsyncSendError) -> Self {
+        Self::InternalError { error_message: error.to_string() }
+    }
+}
+
+impl RpcFrom<AsyncSendError> for RpcSandboxDropSnapshotError {
+    fn rpc_from(error: AsyncSendError) -> Self {
+        Self::InternalError { error_message: error.to_string() }
+    }
+}
+
+impl RpcFrom<AsyncSendError> for RpcSandboxSetBlockProductionError {
+    fn rpc_from(error: AsyncSendError) -> Self {
+        Self::InternalError { error_message: error.to_string() }
+    }
+}
+
+impl RpcFrom<AsyncSendError> for RpcSandboxProduceBlocksError {
+    fn rpc_from(error: AsyncSendError) -> Self {
+        Self::InternalError { error_message: error.to_string() }
+    }
+}
+
+impl RpcFrom<AsyncSendError> for RpcSandboxSetNextBlockTimestampError {
+    fn rpc_from(error: AsyncSendError) -> Self {
+        Self::InternalError { error_message: error.to_string() }
+    }
+}
+
+impl RpcFrom<AsyncSendError> for RpcSandboxAdvanceEpochError {
+    fn rpc_from(error: AsyncSendError) -> Self {
+        Self::InternalError { error_message: error.to_string() }
+    }
+}
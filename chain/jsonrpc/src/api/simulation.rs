@@ -0,0 +1,45 @@
+use near_async::messaging::AsyncSendError;
+use near_client_primitives::types::SimulateTransactionError;
+use near_jsonrpc_primitives::{
+    errors::RpcParseError,
+    types::simulation::{RpcSimulateTransactionError, RpcSimulateTransactionRequest},
+};
+use serde_json::Value;
+
+use super::{Params, RpcFrom, RpcRequest};
+
+impl RpcRequest for RpcSimulateTransactionRequest {
+    fn parse(value: Value) -> Result<Self, RpcParseError> {
+        Params::parse(value)
+    }
+}
+
+impl RpcFrom<AsyncSendError> for RpcSimulateTransactionError {
+    fn rpc_from(error: AsyncSendError) -> Self {
+        Self::InternalError { error_message: error.to_string() }
+    }
+}
+
+impl RpcFrom<SimulateTransactionError> for RpcSimulateTransactionError {
+    fn rpc_from(error: SimulateTransactionError) -> Self {
+        match error {
+            SimulateTransactionError::NoSyncedBlocks => Self::NoSyncedBlocks,
+            SimulateTransactionError::UnknownBlock { error_message } => {
+                Self::UnknownBlock { error_message }
+            }
+            SimulateTransactionError::UnavailableState { error_message } => {
+                Self::UnavailableState { error_message }
+            }
+            SimulateTransactionError::IOError(error_message) => {
+                Self::InternalError { error_message }
+            }
+            SimulateTransactionError::Unreachable(ref error_message) => {
+                tracing::warn!(target: "jsonrpc", "Unreachable error occurred: {}", error_message);
+                crate::metrics::RPC_UNREACHABLE_ERROR_COUNT
+                    .with_label_values(&["RpcSimulateTransactionError"])
+                    .inc();
+                Self::InternalError { error_message: error.to_string() }
+            }
+        }
+    }
+}
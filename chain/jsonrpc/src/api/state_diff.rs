@@ -0,0 +1,45 @@
+use near_async::messaging::AsyncSendError;
+use near_client_primitives::types::GetStateDiffError;
+use near_jsonrpc_primitives::{
+    errors::RpcParseError,
+    types::state_diff::{RpcStateDiffError, RpcStateDiffRequest},
+};
+use serde_json::Value;
+
+use super::{Params, RpcFrom, RpcRequest};
+
+impl RpcRequest for RpcStateDiffRequest {
+    fn parse(value: Value) -> Result<Self, RpcParseError> {
+        Params::parse(value)
+    }
+}
+
+impl RpcFrom<AsyncSendError> for RpcStateDiffError {
+    fn rpc_from(error: AsyncSendError) -> Self {
+        Self::InternalError { error_message: error.to_string() }
+    }
+}
+
+impl RpcFrom<GetStateDiffError> for RpcStateDiffError {
+    fn rpc_from(error: GetStateDiffError) -> Self {
+        match error {
+            GetStateDiffError::UnknownBlock { error_message } => {
+                Self::UnknownBlock { error_message }
+            }
+            GetStateDiffError::InvalidRequest { error_message } => {
+                Self::InvalidRequest { error_message }
+            }
+            GetStateDiffError::UnavailableState { error_message } => {
+                Self::UnavailableState { error_message }
+            }
+            GetStateDiffError::IOError(error_message) => Self::InternalError { error_message },
+            GetStateDiffError::Unreachable(ref error_message) => {
+                tracing::warn!(target: "jsonrpc", "Unreachable error occurred: {}", error_message);
+                crate::metrics::RPC_UNREACHABLE_ERROR_COUNT
+                    .with_label_values(&["RpcStateDiffError"])
+                    .inc();
+                Self::InternalError { error_message: error.to_string() }
+            }
+        }
+    }
+}
@@ -50,6 +50,9 @@ impl RpcFrom<near_client_primitives::debug::DebugStatusResponse>
                     x,
                 )
             }
+            near_client_primitives::debug::DebugStatusResponse::FlatStorageScrubber(x) => {
+                near_jsonrpc_primitives::types::status::DebugStatusResponse::FlatStorageScrubber(x)
+            }
         }
     }
 }
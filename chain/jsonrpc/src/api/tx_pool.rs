@@ -0,0 +1,35 @@
+use near_async::messaging::AsyncSendError;
+use near_client_primitives::types::GetTxPoolError;
+use near_jsonrpc_primitives::{
+    errors::RpcParseError,
+    types::tx_pool::{RpcTxPoolError, RpcTxPoolRequest},
+};
+use serde_json::Value;
+
+use super::{Params, RpcFrom, RpcRequest};
+
+impl RpcRequest for RpcTxPoolRequest {
+    fn parse(value: Value) -> Result<Self, RpcParseError> {
+        Params::parse(value)
+    }
+}
+
+impl RpcFrom<AsyncSendError> for RpcTxPoolError {
+    fn rpc_from(error: AsyncSendError) -> Self {
+        Self::InternalError { error_message: error.to_string() }
+    }
+}
+
+impl RpcFrom<GetTxPoolError> for RpcTxPoolError {
+    fn rpc_from(error: GetTxPoolError) -> Self {
+        match error {
+            GetTxPoolError::Unreachable(ref error_message) => {
+                tracing::warn!(target: "jsonrpc", "Unreachable error occurred: {}", error_message);
+                crate::metrics::RPC_UNREACHABLE_ERROR_COUNT
+                    .with_label_values(&["RpcTxPoolError"])
+                    .inc();
+                Self::InternalError { error_message: error.to_string() }
+            }
+        }
+    }
+}
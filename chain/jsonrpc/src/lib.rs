@@ -15,9 +15,11 @@ use near_async::messaging::{
 use near_chain_configs::GenesisConfig;
 use near_client::{
     DebugStatus, GetBlock, GetBlockProof, GetChunk, GetClientConfig, GetExecutionOutcome,
-    GetGasPrice, GetMaintenanceWindows, GetNetworkInfo, GetNextLightClientBlock, GetProtocolConfig,
-    GetReceipt, GetStateChanges, GetStateChangesInBlock, GetValidatorInfo, GetValidatorOrdered,
-    ProcessTxRequest, ProcessTxResponse, Query, Status, TxStatus,
+    GetExecutionOutcomesForBlock, GetGasPrice, GetMaintenanceWindows, GetNetworkInfo,
+    GetNextLightClientBlock, GetProtocolConfig, GetReceipt, GetStateChanges,
+    GetStateChangesInBlock, GetStateChangesWithCauseInBlock, GetStateDiff, GetTxPool,
+    GetValidatorInfo, GetValidatorOrdered, ProcessTxRequest, ProcessTxResponse, Query,
+    SimulateTransaction, Status, TxStatus,
 };
 use near_client_primitives::types::GetSplitStorageInfo;
 pub use near_jsonrpc_client as client;
@@ -29,9 +31,11 @@ use near_jsonrpc_primitives::types::query::RpcQueryRequest;
 use near_jsonrpc_primitives::types::split_storage::{
     RpcSplitStorageInfoRequest, RpcSplitStorageInfoResponse,
 };
+use near_jsonrpc_primitives::types::subscriptions::RpcSubscriptionError;
 use near_jsonrpc_primitives::types::transactions::{
     RpcSendTransactionRequest, RpcTransactionResponse,
 };
+use near_jsonrpc_primitives::types::tx_pool::{RpcTxPoolRequest, RpcTxPoolResponse};
 use near_network::debug::GetDebugStatus;
 use near_network::tcp;
 use near_o11y::metrics::{prometheus, Encoder, TextEncoder};
@@ -48,6 +52,9 @@ use tracing::{error, info};
 
 mod api;
 mod metrics;
+mod subscriptions;
+
+pub use subscriptions::RpcSubscriptionsConfig;
 
 #[derive(serde::Serialize, serde::Deserialize, Clone, Copy, Debug)]
 pub struct RpcPollingConfig {
@@ -64,15 +71,24 @@ impl Default for RpcPollingConfig {
     }
 }
 
+fn default_batch_max_size() -> usize {
+    100
+}
+
 #[derive(serde::Serialize, serde::Deserialize, Clone, Debug)]
 pub struct RpcLimitsConfig {
     /// Maximum byte size of the json payload.
     pub json_payload_max_size: usize,
+    /// Maximum number of requests in a single JSON RPC batch.  Requests of a
+    /// batch are executed concurrently and responses are returned in the
+    /// request order.
+    #[serde(default = "default_batch_max_size")]
+    pub batch_max_size: usize,
 }
 
 impl Default for RpcLimitsConfig {
     fn default() -> Self {
-        Self { json_payload_max_size: 10 * 1024 * 1024 }
+        Self { json_payload_max_size: 10 * 1024 * 1024, batch_max_size: default_batch_max_size() }
     }
 }
 
@@ -93,10 +109,17 @@ pub struct RpcConfig {
     // We disable it by default, as some of those endpoints might be quite CPU heavy.
     #[serde(default = "default_enable_debug_rpc")]
     pub enable_debug_rpc: bool,
+    // If true, enable the `EXPERIMENTAL_simulate_tx` method. Disabled by default, as every call
+    // executes up to `max_gas_burnt_view` gas worth of receipts.
+    #[serde(default)]
+    pub enable_simulate_tx: bool,
     // For node developers only: if specified, the HTML files used to serve the debug pages will
     // be read from this directory, instead of the contents compiled into the binary. This allows
     // for quick iterative development.
     pub experimental_debug_pages_src_path: Option<String>,
+    // Configuration of the `/ws` endpoint serving push-based subscriptions.
+    #[serde(default)]
+    pub subscriptions_config: RpcSubscriptionsConfig,
 }
 
 impl Default for RpcConfig {
@@ -108,7 +131,9 @@ impl Default for RpcConfig {
             polling_config: Default::default(),
             limits_config: Default::default(),
             enable_debug_rpc: false,
+            enable_simulate_tx: false,
             experimental_debug_pages_src_path: None,
+            subscriptions_config: Default::default(),
         }
     }
 }
@@ -225,6 +250,7 @@ pub struct ClientSenderForRpc(
     AsyncSender<DebugStatus, ActixResult<DebugStatus>>,
     AsyncSender<GetClientConfig, ActixResult<GetClientConfig>>,
     AsyncSender<GetNetworkInfo, ActixResult<GetNetworkInfo>>,
+    AsyncSender<GetTxPool, ActixResult<GetTxPool>>,
     AsyncSender<ProcessTxRequest, ActixResult<ProcessTxRequest>>,
     AsyncSender<Status, ActixResult<Status>>,
     Sender<ProcessTxRequest>,
@@ -247,6 +273,7 @@ pub struct ViewClientSenderForRpc(
     AsyncSender<GetBlockProof, ActixResult<GetBlockProof>>,
     AsyncSender<GetChunk, ActixResult<GetChunk>>,
     AsyncSender<GetExecutionOutcome, ActixResult<GetExecutionOutcome>>,
+    AsyncSender<GetExecutionOutcomesForBlock, ActixResult<GetExecutionOutcomesForBlock>>,
     AsyncSender<GetGasPrice, ActixResult<GetGasPrice>>,
     AsyncSender<GetMaintenanceWindows, ActixResult<GetMaintenanceWindows>>,
     AsyncSender<GetNextLightClientBlock, ActixResult<GetNextLightClientBlock>>,
@@ -255,9 +282,12 @@ pub struct ViewClientSenderForRpc(
     AsyncSender<GetSplitStorageInfo, ActixResult<GetSplitStorageInfo>>,
     AsyncSender<GetStateChanges, ActixResult<GetStateChanges>>,
     AsyncSender<GetStateChangesInBlock, ActixResult<GetStateChangesInBlock>>,
+    AsyncSender<GetStateChangesWithCauseInBlock, ActixResult<GetStateChangesWithCauseInBlock>>,
+    AsyncSender<GetStateDiff, ActixResult<GetStateDiff>>,
     AsyncSender<GetValidatorInfo, ActixResult<GetValidatorInfo>>,
     AsyncSender<GetValidatorOrdered, ActixResult<GetValidatorOrdered>>,
     AsyncSender<Query, ActixResult<Query>>,
+    AsyncSender<SimulateTransaction, ActixResult<SimulateTransaction>>,
     AsyncSender<TxStatus, ActixResult<TxStatus>>,
     #[cfg(feature = "test_features")] Sender<near_client::NetworkAdversarialMessage>,
 );
@@ -281,10 +311,13 @@ struct JsonRpcHandler {
     #[cfg(feature = "test_features")]
     gc_sender: GCSenderForRpc,
     polling_config: RpcPollingConfig,
+    batch_max_size: usize,
     genesis_config: GenesisConfig,
     enable_debug_rpc: bool,
+    enable_simulate_tx: bool,
     debug_pages_src_path: Option<PathBuf>,
     entity_debug_handler: Arc<dyn EntityDebugHandler>,
+    subscription_hub: Arc<subscriptions::SubscriptionHub>,
 }
 
 impl JsonRpcHandler {
@@ -294,12 +327,47 @@ impl JsonRpcHandler {
             Message::Request(request) => {
                 Ok(Message::response(id, self.process_request(request).await))
             }
+            Message::Batch(messages) => Ok(self.process_batch(messages).await),
             _ => Ok(Message::error(RpcError::parse_error(
                 "JSON RPC Request format was expected".to_owned(),
             ))),
         }
     }
 
+    /// Processes all requests of a batch concurrently.
+    ///
+    /// Responses are returned in the same order as the requests.  Elements
+    /// which are not requests (including nested batches) are answered with a
+    /// parse error without affecting the rest of the batch.
+    async fn process_batch(&self, messages: Vec<Message>) -> Message {
+        if messages.is_empty() {
+            return Message::error(RpcError::parse_error(
+                "JSON RPC batch must contain at least one request".to_owned(),
+            ));
+        }
+        if messages.len() > self.batch_max_size {
+            return Message::error(RpcError::parse_error(format!(
+                "JSON RPC batch contains {} requests, the limit is {}",
+                messages.len(),
+                self.batch_max_size
+            )));
+        }
+        metrics::RPC_BATCH_SIZE.observe(messages.len() as f64);
+        let responses = futures::future::join_all(messages.into_iter().map(|message| async move {
+            match message {
+                Message::Request(request) => {
+                    let id = request.id.clone();
+                    Message::response(id, self.process_request(request).await)
+                }
+                _ => Message::error(RpcError::parse_error(
+                    "JSON RPC Request format was expected".to_owned(),
+                )),
+            }
+        }))
+        .await;
+        Message::Batch(responses)
+    }
+
     // `process_request` increments affected metrics but the request processing is done by
     // `process_request_internal`.
     async fn process_request(&self, request: Request) -> Result<Value, RpcError> {
@@ -354,6 +422,7 @@ impl JsonRpcHandler {
                             "query_view_state"
                         }
                     }
+                    QueryRequest::ViewStateProof { .. } => "query_view_state_proof",
                     QueryRequest::ViewAccessKey { .. } => "query_view_access_key",
                     QueryRequest::ViewAccessKeyList { .. } => "query_view_access_key_list",
                     QueryRequest::CallFunction { .. } => "query_call_function",
@@ -447,6 +516,23 @@ impl JsonRpcHandler {
             "EXPERIMENTAL_split_storage_info" => {
                 process_method_call(request, |params| self.split_storage_info(params)).await
             }
+            // Exposes pending transactions of all accounts, so it is only available together
+            // with the debug pages.
+            "EXPERIMENTAL_tx_pool" if self.enable_debug_rpc => {
+                process_method_call(request, |params| self.tx_pool(params)).await
+            }
+            // Walks whole tries of a shard, so like the other debug methods it
+            // is only available together with the debug pages.
+            "EXPERIMENTAL_state_diff" if self.enable_debug_rpc => {
+                process_method_call(request, |params| self.state_diff(params)).await
+            }
+            "EXPERIMENTAL_simulate_tx" if self.enable_simulate_tx => {
+                process_method_call(request, |params| self.simulate_tx(params)).await
+            }
+            // Subscriptions are handled by the WebSocket session, see `subscriptions` module.
+            "EXPERIMENTAL_subscribe" | "EXPERIMENTAL_unsubscribe" => {
+                Err(RpcSubscriptionError::NotSupported.into())
+            }
             #[cfg(feature = "sandbox")]
             "sandbox_patch_state" => {
                 process_method_call(request, |params| self.sandbox_patch_state(params)).await
@@ -455,6 +541,45 @@ impl JsonRpcHandler {
             "sandbox_fast_forward" => {
                 process_method_call(request, |params| self.sandbox_fast_forward(params)).await
             }
+            #[cfg(feature = "sandbox")]
+            "sandbox_set_execution_tracing" => {
+                process_method_call(request, |params| self.sandbox_set_execution_tracing(params))
+                    .await
+            }
+            #[cfg(feature = "sandbox")]
+            "sandbox_execution_trace" => {
+                process_method_call(request, |params| self.sandbox_execution_trace(params)).await
+            }
+            #[cfg(feature = "sandbox")]
+            "sandbox_snapshot" => {
+                process_method_call(request, |params| self.sandbox_snapshot(params)).await
+            }
+            #[cfg(feature = "sandbox")]
+            "sandbox_revert" => {
+                process_method_call(request, |params| self.sandbox_revert(params)).await
+            }
+            #[cfg(feature = "sandbox")]
+            "sandbox_drop_snapshot" => {
+                process_method_call(request, |params| self.sandbox_drop_snapshot(params)).await
+            }
+            #[cfg(feature = "sandbox")]
+            "sandbox_set_block_production" => {
+                process_method_call(request, |params| self.sandbox_set_block_production(params))
+                    .await
+            }
+            #[cfg(feature = "sandbox")]
+            "sandbox_produce_blocks" => {
+                process_method_call(request, |params| self.sandbox_produce_blocks(params)).await
+            }
+            #[cfg(feature = "sandbox")]
+            "sandbox_set_next_block_timestamp" => {
+                process_method_call(request, |params| self.sandbox_set_next_block_timestamp(params))
+                    .await
+            }
+            #[cfg(feature = "sandbox")]
+            "sandbox_advance_epoch" => {
+                process_method_call(request, |params| self.sandbox_advance_epoch(params)).await
+            }
             _ => return Err(request),
         })
     }
@@ -801,6 +926,9 @@ impl JsonRpcHandler {
                     "/debug/api/requested_state_parts" => {
                         self.client_send(DebugStatus::RequestedStateParts).await?.rpc_into()
                     }
+                    "/debug/api/flat_storage_scrubber" => {
+                        self.client_send(DebugStatus::FlatStorageScrubber).await?.rpc_into()
+                    }
                     "/debug/api/peer_store" => self
                         .peer_manager_send(near_network::debug::GetDebugStatus::PeerStore)
                         .await?
@@ -830,6 +958,15 @@ impl JsonRpcHandler {
                             .map_err(|e| e.into_rpc_status_error())?;
                         near_jsonrpc_primitives::types::status::DebugStatusResponse::SplitStoreStatus(split_storage_info.result)
                     }
+                    "/debug/api/tx_pool" => {
+                        let tx_pool: RpcTxPoolResponse = self
+                            .tx_pool(RpcTxPoolRequest::default())
+                            .await
+                            .map_err(|e| e.into_rpc_status_error())?;
+                        near_jsonrpc_primitives::types::status::DebugStatusResponse::TxPool(
+                            tx_pool.result,
+                        )
+                    }
                     _ => return Ok(None),
                 };
             Ok(Some(near_jsonrpc_primitives::types::status::RpcDebugStatusResponse {
@@ -1184,6 +1321,69 @@ impl JsonRpcHandler {
         let split_storage = self.view_client_send(GetSplitStorageInfo {}).await?;
         Ok(RpcSplitStorageInfoResponse { result: split_storage })
     }
+
+    pub async fn tx_pool(
+        &self,
+        request_data: near_jsonrpc_primitives::types::tx_pool::RpcTxPoolRequest,
+    ) -> Result<
+        near_jsonrpc_primitives::types::tx_pool::RpcTxPoolResponse,
+        near_jsonrpc_primitives::types::tx_pool::RpcTxPoolError,
+    > {
+        let tx_pool = self.client_send(GetTxPool { account_id: request_data.account_id }).await?;
+        Ok(RpcTxPoolResponse { result: tx_pool })
+    }
+
+    pub async fn state_diff(
+        &self,
+        request_data: near_jsonrpc_primitives::types::state_diff::RpcStateDiffRequest,
+    ) -> Result<
+        near_jsonrpc_primitives::types::state_diff::RpcStateDiffResponse,
+        near_jsonrpc_primitives::types::state_diff::RpcStateDiffError,
+    > {
+        use near_jsonrpc_primitives::types::state_diff::{
+            MAX_STATE_DIFF_BYTES, MAX_STATE_DIFF_CHANGES,
+        };
+        let state_diff = self
+            .view_client_send(GetStateDiff {
+                from_block_id: request_data.from_block_id,
+                to_block_id: request_data.to_block_id,
+                shard_id: request_data.shard_id,
+                account_id: request_data.account_id,
+                start_key: request_data.start_key.map(Into::into),
+                limit: request_data
+                    .limit
+                    .map_or(MAX_STATE_DIFF_CHANGES, |limit| limit.clamp(1, MAX_STATE_DIFF_CHANGES)),
+                max_bytes: MAX_STATE_DIFF_BYTES,
+            })
+            .await?;
+        Ok(near_jsonrpc_primitives::types::state_diff::RpcStateDiffResponse { result: state_diff })
+    }
+
+    pub async fn simulate_tx(
+        &self,
+        request_data: near_jsonrpc_primitives::types::simulation::RpcSimulateTransactionRequest,
+    ) -> Result<
+        near_jsonrpc_primitives::types::simulation::RpcSimulateTransactionResponse,
+        near_jsonrpc_primitives::types::simulation::RpcSimulateTransactionError,
+    > {
+        let (transaction, verify_signature) =
+            request_data.transaction.into_signed_transaction().map_err(|error_message| {
+                near_jsonrpc_primitives::types::simulation::RpcSimulateTransactionError::InvalidRequest {
+                    error_message,
+                }
+            })?;
+        let simulation = self
+            .view_client_send(SimulateTransaction {
+                block_reference: request_data.block_reference,
+                transaction,
+                verify_signature,
+                max_receipts: near_jsonrpc_primitives::types::simulation::MAX_SIMULATED_RECEIPTS,
+            })
+            .await?;
+        Ok(near_jsonrpc_primitives::types::simulation::RpcSimulateTransactionResponse {
+            result: simulation,
+        })
+    }
 }
 
 #[cfg(feature = "sandbox")]
@@ -1277,6 +1477,324 @@ impl JsonRpcHandler {
 
         Ok(near_jsonrpc_primitives::types::sandbox::RpcSandboxFastForwardResponse {})
     }
+
+    async fn sandbox_set_execution_tracing(
+        &self,
+        request: near_jsonrpc_primitives::types::sandbox::RpcSandboxSetExecutionTracingRequest,
+    ) -> Result<
+        near_jsonrpc_primitives::types::sandbox::RpcSandboxSetExecutionTracingResponse,
+        near_jsonrpc_primitives::types::sandbox::RpcSandboxSetExecutionTracingError,
+    > {
+        self.client_sender
+            .send_async(near_client_primitives::types::SandboxMessage::SandboxSetExecutionTracing(
+                request.enabled,
+            ))
+            .await
+            .map_err(RpcFrom::rpc_from)?;
+        Ok(near_jsonrpc_primitives::types::sandbox::RpcSandboxSetExecutionTracingResponse {})
+    }
+
+    async fn sandbox_execution_trace(
+        &self,
+        request: near_jsonrpc_primitives::types::sandbox::RpcSandboxExecutionTraceRequest,
+    ) -> Result<
+        near_jsonrpc_primitives::types::sandbox::RpcSandboxExecutionTraceResponse,
+        near_jsonrpc_primitives::types::sandbox::RpcSandboxExecutionTraceError,
+    > {
+        use near_client_primitives::types::SandboxResponse;
+        use near_jsonrpc_primitives::types::sandbox::RpcSandboxExecutionTraceError;
+
+        let response = self
+            .client_sender
+            .send_async(near_client_primitives::types::SandboxMessage::SandboxExecutionTrace(
+                request.receipt_id,
+            ))
+            .await
+            .map_err(RpcFrom::rpc_from)?;
+        match response {
+            SandboxResponse::SandboxExecutionTrace(Some(traces)) => {
+                Ok(near_jsonrpc_primitives::types::sandbox::RpcSandboxExecutionTraceResponse {
+                    traces,
+                })
+            }
+            SandboxResponse::SandboxExecutionTrace(None) => {
+                Err(RpcSandboxExecutionTraceError::UnknownReceipt {
+                    receipt_id: request.receipt_id,
+                })
+            }
+            other => Err(RpcSandboxExecutionTraceError::InternalError {
+                error_message: format!(
+                    "unexpected response to execution trace request: {:?}",
+                    other
+                ),
+            }),
+        }
+    }
+
+    async fn sandbox_snapshot(
+        &self,
+        _request: near_jsonrpc_primitives::types::sandbox::RpcSandboxSnapshotRequest,
+    ) -> Result<
+        near_jsonrpc_primitives::types::sandbox::RpcSandboxSnapshotResponse,
+        near_jsonrpc_primitives::types::sandbox::RpcSandboxSnapshotError,
+    > {
+        use near_client_primitives::types::SandboxResponse;
+        use near_jsonrpc_primitives::types::sandbox::RpcSandboxSnapshotError;
+
+        let response = self
+            .client_sender
+            .send_async(near_client_primitives::types::SandboxMessage::SandboxSnapshot)
+            .await
+            .map_err(RpcFrom::rpc_from)?;
+        match response {
+            SandboxResponse::SandboxSnapshotTaken { snapshot_id, block_hash, block_height } => {
+                Ok(near_jsonrpc_primitives::types::sandbox::RpcSandboxSnapshotResponse {
+                    snapshot_id,
+                    block_hash,
+                    block_height,
+                })
+            }
+            SandboxResponse::SandboxSnapshotFailed(error_message) => {
+                Err(RpcSandboxSnapshotError::SnapshotFailed { error_message })
+            }
+            other => Err(RpcSandboxSnapshotError::InternalError {
+                error_message: format!("unexpected response to snapshot request: {:?}", other),
+            }),
+        }
+    }
+
+    async fn sandbox_revert(
+        &self,
+        request: near_jsonrpc_primitives::types::sandbox::RpcSandboxRevertRequest,
+    ) -> Result<
+        near_jsonrpc_primitives::types::sandbox::RpcSandboxRevertResponse,
+        near_jsonrpc_primitives::types::sandbox::RpcSandboxRevertError,
+    > {
+        use near_client_primitives::types::SandboxResponse;
+        use near_jsonrpc_primitives::types::sandbox::RpcSandboxRevertError;
+
+        let response = self
+            .client_sender
+            .send_async(near_client_primitives::types::SandboxMessage::SandboxRevert(
+                request.snapshot_id,
+            ))
+            .await
+            .map_err(RpcFrom::rpc_from)?;
+        match response {
+            SandboxResponse::SandboxRevertFinished { block_hash, block_height } => {
+                Ok(near_jsonrpc_primitives::types::sandbox::RpcSandboxRevertResponse {
+                    block_hash,
+                    block_height,
+                })
+            }
+            SandboxResponse::SandboxRevertFailed(error_message) => {
+                Err(RpcSandboxRevertError::RevertFailed { error_message })
+            }
+            other => Err(RpcSandboxRevertError::InternalError {
+                error_message: format!("unexpected response to revert request: {:?}", other),
+            }),
+        }
+    }
+
+    async fn sandbox_drop_snapshot(
+        &self,
+        request: near_jsonrpc_primitives::types::sandbox::RpcSandboxDropSnapshotRequest,
+    ) -> Result<
+        near_jsonrpc_primitives::types::sandbox::RpcSandboxDropSnapshotResponse,
+        near_jsonrpc_primitives::types::sandbox::RpcSandboxDropSnapshotError,
+    > {
+        use near_client_primitives::types::SandboxResponse;
+        use near_jsonrpc_primitives::types::sandbox::RpcSandboxDropSnapshotError;
+
+        let response = self
+            .client_sender
+            .send_async(near_client_primitives::types::SandboxMessage::SandboxDropSnapshot(
+                request.snapshot_id,
+            ))
+            .await
+            .map_err(RpcFrom::rpc_from)?;
+        match response {
+            SandboxResponse::SandboxSnapshotDropped(true) => {
+                Ok(near_jsonrpc_primitives::types::sandbox::RpcSandboxDropSnapshotResponse {})
+            }
+            SandboxResponse::SandboxSnapshotDropped(false) => {
+                Err(RpcSandboxDropSnapshotError::UnknownSnapshot {
+                    snapshot_id: request.snapshot_id,
+                })
+            }
+            SandboxResponse::SandboxSnapshotFailed(error_message) => {
+                Err(RpcSandboxDropSnapshotError::InternalError { error_message })
+            }
+            other => Err(RpcSandboxDropSnapshotError::InternalError {
+                error_message: format!("unexpected response to drop snapshot request: {:?}", other),
+            }),
+        }
+    }
+
+    async fn sandbox_set_block_production(
+        &self,
+        request: near_jsonrpc_primitives::types::sandbox::RpcSandboxSetBlockProductionRequest,
+    ) -> Result<
+        near_jsonrpc_primitives::types::sandbox::RpcSandboxSetBlockProductionResponse,
+        near_jsonrpc_primitives::types::sandbox::RpcSandboxSetBlockProductionError,
+    > {
+        self.client_sender
+            .send_async(
+                near_client_primitives::types::SandboxMessage::SandboxSetBlockProductionPaused(
+                    request.paused,
+                ),
+            )
+            .await
+            .map_err(RpcFrom::rpc_from)?;
+        Ok(near_jsonrpc_primitives::types::sandbox::RpcSandboxSetBlockProductionResponse {})
+    }
+
+    async fn sandbox_produce_blocks(
+        &self,
+        request: near_jsonrpc_primitives::types::sandbox::RpcSandboxProduceBlocksRequest,
+    ) -> Result<
+        near_jsonrpc_primitives::types::sandbox::RpcSandboxProduceBlocksResponse,
+        near_jsonrpc_primitives::types::sandbox::RpcSandboxProduceBlocksError,
+    > {
+        use near_client_primitives::types::SandboxResponse;
+
+        let response = self
+            .client_sender
+            .send_async(near_client_primitives::types::SandboxMessage::SandboxProduceBlocks(
+                request.num_blocks,
+            ))
+            .await
+            .map_err(RpcFrom::rpc_from)?;
+        if let SandboxResponse::SandboxProduceBlocksFailed(error_message) = response {
+            return Err(
+                near_jsonrpc_primitives::types::sandbox::RpcSandboxProduceBlocksError::InternalError {
+                    error_message,
+                },
+            );
+        }
+
+        // Same limit as for fast forwarding, blocks are produced at the regular pace.
+        timeout(Duration::from_secs(60 * 60), async {
+            loop {
+                let produce_blocks_finished = self
+                    .client_sender
+                    .send_async(
+                        near_client_primitives::types::SandboxMessage::SandboxProduceBlocksStatus,
+                    )
+                    .await;
+                if let Ok(SandboxResponse::SandboxProduceBlocksFinished(true)) =
+                    produce_blocks_finished
+                {
+                    break;
+                }
+                let _ = sleep(self.polling_config.polling_interval).await;
+            }
+        })
+        .await
+        .map_err(|_| {
+            near_jsonrpc_primitives::types::sandbox::RpcSandboxProduceBlocksError::InternalError {
+                error_message: "sandbox failed to produce blocks within reasonable time of an hour"
+                    .to_string(),
+            }
+        })?;
+        Ok(near_jsonrpc_primitives::types::sandbox::RpcSandboxProduceBlocksResponse {})
+    }
+
+    async fn sandbox_set_next_block_timestamp(
+        &self,
+        request: near_jsonrpc_primitives::types::sandbox::RpcSandboxSetNextBlockTimestampRequest,
+    ) -> Result<
+        near_jsonrpc_primitives::types::sandbox::RpcSandboxSetNextBlockTimestampResponse,
+        near_jsonrpc_primitives::types::sandbox::RpcSandboxSetNextBlockTimestampError,
+    > {
+        use near_client_primitives::types::SandboxResponse;
+        use near_jsonrpc_primitives::types::sandbox::RpcSandboxSetNextBlockTimestampError;
+
+        let timestamp =
+            near_async::time::Utc::from_unix_timestamp_nanos(request.timestamp_nanosec as i128)
+                .map_err(|err| RpcSandboxSetNextBlockTimestampError::InvalidTimestamp {
+                    error_message: err.to_string(),
+                })?;
+        let response = self
+            .client_sender
+            .send_async(
+                near_client_primitives::types::SandboxMessage::SandboxSetNextBlockTimestamp(
+                    timestamp,
+                ),
+            )
+            .await
+            .map_err(RpcFrom::rpc_from)?;
+        match response {
+            SandboxResponse::SandboxNoResponse => Ok(
+                near_jsonrpc_primitives::types::sandbox::RpcSandboxSetNextBlockTimestampResponse {},
+            ),
+            SandboxResponse::SandboxSetNextBlockTimestampFailed(error_message) => {
+                Err(RpcSandboxSetNextBlockTimestampError::InvalidTimestamp { error_message })
+            }
+            other => Err(RpcSandboxSetNextBlockTimestampError::InternalError {
+                error_message: format!(
+                    "unexpected response to set next block timestamp request: {:?}",
+                    other
+                ),
+            }),
+        }
+    }
+
+    async fn sandbox_advance_epoch(
+        &self,
+        _request: near_jsonrpc_primitives::types::sandbox::RpcSandboxAdvanceEpochRequest,
+    ) -> Result<
+        near_jsonrpc_primitives::types::sandbox::RpcSandboxAdvanceEpochResponse,
+        near_jsonrpc_primitives::types::sandbox::RpcSandboxAdvanceEpochError,
+    > {
+        use near_client_primitives::types::SandboxResponse;
+
+        let response = self
+            .client_sender
+            .send_async(near_client_primitives::types::SandboxMessage::SandboxAdvanceEpoch)
+            .await
+            .map_err(RpcFrom::rpc_from)?;
+        if let SandboxResponse::SandboxAdvanceEpochFailed(error_message) = response {
+            return Err(
+                near_jsonrpc_primitives::types::sandbox::RpcSandboxAdvanceEpochError::InternalError {
+                    error_message,
+                },
+            );
+        }
+
+        timeout(Duration::from_secs(60 * 60), async {
+            loop {
+                let advance_epoch_finished = self
+                    .client_sender
+                    .send_async(
+                        near_client_primitives::types::SandboxMessage::SandboxAdvanceEpochStatus,
+                    )
+                    .await;
+
+                match advance_epoch_finished {
+                    Ok(SandboxResponse::SandboxAdvanceEpochFinished(true)) => break,
+                    Ok(SandboxResponse::SandboxAdvanceEpochFailed(err)) => return Err(err),
+                    _ => (),
+                }
+
+                let _ = sleep(self.polling_config.polling_interval).await;
+            }
+            Ok(())
+        })
+        .await
+        .map_err(|_| {
+            near_jsonrpc_primitives::types::sandbox::RpcSandboxAdvanceEpochError::InternalError {
+                error_message: "sandbox failed to advance epoch within reasonable time of an hour"
+                    .to_string(),
+            }
+        })?
+        .map_err(|err| {
+            near_jsonrpc_primitives::types::sandbox::RpcSandboxAdvanceEpochError::InternalError {
+                error_message: format!("sandbox failed to advance epoch due to: {:?}", err),
+            }
+        })?;
+        Ok(near_jsonrpc_primitives::types::sandbox::RpcSandboxAdvanceEpochResponse {})
+    }
 }
 
 #[cfg(feature = "test_features")]
@@ -1399,6 +1917,16 @@ async fn debug_handler(
     }
 }
 
+async fn websocket_handler(
+    req: HttpRequest,
+    body: web::Payload,
+    handler: web::Data<JsonRpcHandler>,
+) -> Result<HttpResponse, HttpError> {
+    let (response, session, stream) = actix_ws::handle(&req, body)?;
+    actix_web::rt::spawn(subscriptions::run_session(handler.into_inner(), session, stream));
+    Ok(response)
+}
+
 async fn handle_entity_debug(
     req: web::Json<EntityQuery>,
     handler: web::Data<JsonRpcHandler>,
@@ -1521,6 +2049,8 @@ async fn display_debug_html(
         "validator" => Some(debug_page_string!("validator.html", handler)),
         "validator.css" => Some(debug_page_string!("validator.css", handler)),
         "split_store" => Some(debug_page_string!("split_store.html", handler)),
+        "tx_pool" => Some(debug_page_string!("tx_pool.html", handler)),
+        "flat_storage_scrubber" => Some(debug_page_string!("flat_storage_scrubber.html", handler)),
         "congestion_control" => Some(debug_page_string!("congestion_control.html", handler)),
         "congestion_control.css" => Some(debug_page_string!("congestion_control.css", handler)),
         "congestion_control.js" => Some(debug_page_string!("congestion_control.js", handler)),
@@ -1562,10 +2092,17 @@ pub fn start_http(
         polling_config,
         limits_config,
         enable_debug_rpc,
+        enable_simulate_tx,
         experimental_debug_pages_src_path: debug_pages_src_path,
+        subscriptions_config,
     } = config;
     let prometheus_addr = prometheus_addr.filter(|it| it != &addr.to_string());
     let cors_allowed_origins_clone = cors_allowed_origins.clone();
+    let enable_websocket = subscriptions_config.enabled;
+    let subscription_hub = Arc::new(subscriptions::SubscriptionHub::new(
+        view_client_sender.clone(),
+        subscriptions_config,
+    ));
     info!(target:"network", "Starting http server at {}", addr);
     let mut servers = Vec::new();
     let listener = HttpServer::new(move || {
@@ -1576,10 +2113,13 @@ pub fn start_http(
                 view_client_sender: view_client_sender.clone(),
                 peer_manager_sender: peer_manager_sender.clone(),
                 polling_config,
+                batch_max_size: limits_config.batch_max_size,
                 genesis_config: genesis_config.clone(),
                 enable_debug_rpc,
+                enable_simulate_tx,
                 debug_pages_src_path: debug_pages_src_path.clone().map(Into::into),
                 entity_debug_handler: entity_debug_handler.clone(),
+                subscription_hub: subscription_hub.clone(),
                 #[cfg(feature = "test_features")]
                 gc_sender: gc_sender.clone(),
             }))
@@ -1609,6 +2149,11 @@ pub fn start_http(
             )
             .service(debug_html)
             .service(display_debug_html)
+            .configure(|cfg| {
+                if enable_websocket {
+                    cfg.service(web::resource("/ws").route(web::get().to(websocket_handler)));
+                }
+            })
     });
 
     match listener.listen(addr.std_listener().unwrap()) {
@@ -0,0 +1,519 @@
+//! Push-based subscriptions served over the `/ws` WebSocket endpoint.
+//!
+//! Every WebSocket connection accepts regular JSON RPC requests and batches as
+//! well as `EXPERIMENTAL_subscribe` and `EXPERIMENTAL_unsubscribe`.
+//! Subscriptions are fed from a single poller per finality which asks the view
+//! client for the latest block.  Chunks, execution outcomes and state changes
+//! are fetched at most once per block, by the first subscription needing them,
+//! and shared with all other subscriptions, so the load on the view client
+//! doesn't grow with the number of subscribers.
+use crate::api::RpcRequest;
+use crate::{metrics, JsonRpcHandler, ViewClientSenderForRpc};
+use near_async::messaging::SendAsync;
+use near_client::{
+    GetBlock, GetBlockError, GetChunk, GetExecutionOutcomesForBlock,
+    GetStateChangesWithCauseInBlock,
+};
+use near_jsonrpc_primitives::errors::RpcError;
+use near_jsonrpc_primitives::message::{self, Message, Request};
+use near_jsonrpc_primitives::types::subscriptions::{
+    RpcSubscribeRequest, RpcSubscribeResponse, RpcSubscriptionError, RpcSubscriptionNotification,
+    RpcUnsubscribeRequest, RpcUnsubscribeResponse, SubscriptionEvent, SubscriptionId,
+    SubscriptionKind, SUBSCRIPTION_NOTIFICATION_METHOD,
+};
+use near_primitives::hash::CryptoHash;
+use near_primitives::sharding::ChunkHash;
+use near_primitives::types::{BlockHeight, BlockId, BlockReference, Finality, ShardId};
+use near_primitives::views::{BlockView, ChunkView, ExecutionOutcomeWithIdView, StateChangesView};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::{broadcast, OnceCell};
+
+/// Number of blocks buffered for each subscriber before it starts lagging behind.
+const BLOCK_STREAM_CAPACITY: usize = 64;
+
+/// Maximum number of blocks the poller delivers in a single poll. If the head
+/// moved further, the remaining blocks are delivered by the following polls.
+const MAX_BLOCKS_PER_POLL: usize = 128;
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug)]
+pub struct RpcSubscriptionsConfig {
+    /// If false, the `/ws` endpoint is not served.
+    pub enabled: bool,
+    /// Maximum number of active subscriptions on a single WebSocket connection.
+    pub max_subscriptions_per_connection: usize,
+    /// How often the view client is polled for new blocks.
+    pub block_polling_interval: Duration,
+}
+
+impl Default for RpcSubscriptionsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_subscriptions_per_connection: 64,
+            block_polling_interval: Duration::from_millis(250),
+        }
+    }
+}
+
+/// A new block together with the data derived from it, fetched lazily and
+/// shared between all subscriptions.
+struct BlockData {
+    block: BlockView,
+    /// Chunks in the order of `block.chunks`.
+    chunks: Vec<OnceCell<Option<ChunkView>>>,
+    /// Execution outcomes ordered by shard.
+    execution_outcomes: OnceCell<Option<Vec<(ShardId, ExecutionOutcomeWithIdView)>>>,
+    state_changes: OnceCell<Option<StateChangesView>>,
+}
+
+impl BlockData {
+    fn new(block: BlockView) -> Self {
+        Self {
+            chunks: block.chunks.iter().map(|_| OnceCell::new()).collect(),
+            block,
+            execution_outcomes: OnceCell::new(),
+            state_changes: OnceCell::new(),
+        }
+    }
+}
+
+/// Shares block pollers between all WebSocket connections of the server.
+pub(crate) struct SubscriptionHub {
+    view_client_sender: ViewClientSenderForRpc,
+    config: RpcSubscriptionsConfig,
+    block_streams: Mutex<Vec<(Finality, broadcast::Sender<Arc<BlockData>>)>>,
+}
+
+impl SubscriptionHub {
+    pub(crate) fn new(
+        view_client_sender: ViewClientSenderForRpc,
+        config: RpcSubscriptionsConfig,
+    ) -> Self {
+        Self { view_client_sender, config, block_streams: Mutex::new(Vec::new()) }
+    }
+
+    /// Returns a stream of new blocks at the given finality, starting a poller
+    /// for that finality if there isn't one running yet.
+    fn blocks(self: &Arc<Self>, finality: &Finality) -> broadcast::Receiver<Arc<BlockData>> {
+        let mut streams = self.block_streams.lock().unwrap();
+        if let Some((_, sender)) = streams.iter().find(|(f, _)| f == finality) {
+            return sender.subscribe();
+        }
+        let (sender, receiver) = broadcast::channel(BLOCK_STREAM_CAPACITY);
+        streams.push((finality.clone(), sender.clone()));
+        actix_web::rt::spawn(self.clone().poll_blocks(finality.clone(), sender));
+        receiver
+    }
+
+    /// Stops tracking the stream for `finality` if nobody listens to it anymore.
+    fn release_if_unused(&self, finality: &Finality) -> bool {
+        let mut streams = self.block_streams.lock().unwrap();
+        let unused =
+            streams.iter().any(|(f, sender)| f == finality && sender.receiver_count() == 0);
+        if unused {
+            streams.retain(|(f, _)| f != finality);
+        }
+        unused
+    }
+
+    async fn poll_blocks(
+        self: Arc<Self>,
+        finality: Finality,
+        sender: broadcast::Sender<Arc<BlockData>>,
+    ) {
+        let mut interval = tokio::time::interval(self.config.block_polling_interval);
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+        let mut last_block: Option<(BlockHeight, CryptoHash)> = None;
+        loop {
+            interval.tick().await;
+            if self.release_if_unused(&finality) {
+                tracing::debug!(target: "jsonrpc", ?finality, "stopped polling blocks, no subscribers left");
+                return;
+            }
+            let Some(head) = self.get_block(BlockReference::Finality(finality.clone())).await
+            else {
+                continue;
+            };
+            let new_blocks = match last_block {
+                None => vec![head],
+                Some((_, last_hash)) if last_hash == head.header.hash => continue,
+                Some((last_height, _))
+                    if head.header.height.saturating_sub(last_height)
+                        > MAX_BLOCKS_PER_POLL as BlockHeight =>
+                {
+                    self.blocks_after(last_height, head.header.height).await
+                }
+                Some((last_height, _)) => self.blocks_since(head, last_height).await,
+            };
+            for block in new_blocks {
+                last_block = Some((block.header.height, block.header.hash));
+                // An error only means there are no receivers at the moment.
+                let _ = sender.send(Arc::new(BlockData::new(block)));
+            }
+        }
+    }
+
+    /// Walks back from `head` and returns all blocks above `last_height` in
+    /// increasing height order.
+    async fn blocks_since(&self, head: BlockView, last_height: BlockHeight) -> Vec<BlockView> {
+        let mut blocks = vec![];
+        let mut prev_hash = head.header.prev_hash;
+        blocks.push(head);
+        while blocks.len() < MAX_BLOCKS_PER_POLL {
+            let Some(block) = self.get_block(BlockId::Hash(prev_hash).into()).await else {
+                break;
+            };
+            if block.header.height <= last_height {
+                break;
+            }
+            prev_hash = block.header.prev_hash;
+            blocks.push(block);
+        }
+        blocks.reverse();
+        blocks
+    }
+
+    /// Returns up to `MAX_BLOCKS_PER_POLL` canonical blocks above `last_height`
+    /// and not above `head_height`, in increasing height order. Stops early if
+    /// a block can't be fetched, so that no block is skipped.
+    async fn blocks_after(
+        &self,
+        last_height: BlockHeight,
+        head_height: BlockHeight,
+    ) -> Vec<BlockView> {
+        let mut blocks = vec![];
+        for height in last_height + 1..=head_height {
+            if blocks.len() >= MAX_BLOCKS_PER_POLL {
+                break;
+            }
+            match self.view_client_sender.send_async(GetBlock(BlockId::Height(height).into())).await
+            {
+                Ok(Ok(block)) => blocks.push(block),
+                // Heights without a block are skipped.
+                Ok(Err(GetBlockError::UnknownBlock { .. })) => {}
+                result => {
+                    tracing::debug!(target: "jsonrpc", ?result, height, "failed to fetch block for subscribers");
+                    break;
+                }
+            }
+        }
+        blocks
+    }
+
+    async fn get_block(&self, block_reference: BlockReference) -> Option<BlockView> {
+        match self.view_client_sender.send_async(GetBlock(block_reference)).await {
+            Ok(Ok(block)) => Some(block),
+            Ok(Err(err)) => {
+                tracing::debug!(target: "jsonrpc", ?err, "failed to fetch block for subscribers");
+                None
+            }
+            Err(err) => {
+                tracing::warn!(target: "jsonrpc", ?err, "failed to reach view client");
+                None
+            }
+        }
+    }
+
+    /// Returns the chunk at `index` of the block, fetching it if no subscription
+    /// has needed it yet.
+    async fn chunk<'a>(&self, data: &'a BlockData, index: usize) -> Option<&'a ChunkView> {
+        let block_hash = data.block.header.hash;
+        let header = &data.block.chunks[index];
+        let chunk_hash = ChunkHash(header.chunk_hash);
+        data.chunks[index]
+            .get_or_init(|| async {
+                match self.view_client_sender.send_async(GetChunk::ChunkHash(chunk_hash)).await {
+                    Ok(Ok(chunk_view)) => Some(chunk_view),
+                    result => {
+                        tracing::debug!(target: "jsonrpc", ?result, %block_hash, shard_id = header.shard_id, "failed to fetch chunk for subscribers");
+                        None
+                    }
+                }
+            })
+            .await
+            .as_ref()
+    }
+
+    /// Builds the notifications a subscription of the given kind should receive
+    /// for a new block.
+    async fn events_for_block(
+        &self,
+        kind: &SubscriptionKind,
+        data: &BlockData,
+    ) -> Vec<SubscriptionEvent> {
+        let block = &data.block;
+        let block_hash = block.header.hash;
+        let block_height = block.header.height;
+        match kind {
+            SubscriptionKind::Blocks { .. } => {
+                vec![SubscriptionEvent::Block { block_view: block.clone() }]
+            }
+            SubscriptionKind::Chunks { shard_id, .. } => {
+                let Some(index) = block.chunks.iter().position(|chunk| {
+                    chunk.shard_id == *shard_id && chunk.height_included == block_height
+                }) else {
+                    return vec![];
+                };
+                let chunk_view = self.chunk(data, index).await;
+                let Some(chunk_view) = chunk_view else {
+                    return vec![];
+                };
+                vec![SubscriptionEvent::Chunk {
+                    block_hash,
+                    block_height,
+                    chunk_view: chunk_view.clone(),
+                }]
+            }
+            SubscriptionKind::ExecutionOutcomes { receiver_id, .. } => {
+                let outcomes = data
+                    .execution_outcomes
+                    .get_or_init(|| async {
+                        match self
+                            .view_client_sender
+                            .send_async(GetExecutionOutcomesForBlock { block_hash })
+                            .await
+                        {
+                            Ok(Ok(outcomes)) => {
+                                let mut outcomes: Vec<_> = outcomes.into_iter().collect();
+                                outcomes.sort_by_key(|(shard_id, _)| *shard_id);
+                                Some(
+                                    outcomes
+                                        .into_iter()
+                                        .flat_map(|(shard_id, outcomes)| {
+                                            outcomes.into_iter().map(move |outcome| (shard_id, outcome))
+                                        })
+                                        .collect(),
+                                )
+                            }
+                            result => {
+                                tracing::debug!(target: "jsonrpc", ?result, %block_hash, "failed to fetch execution outcomes for subscribers");
+                                None
+                            }
+                        }
+                    })
+                    .await;
+                let Some(outcomes) = outcomes else {
+                    return vec![];
+                };
+                // A receipt is executed by its receiver, but a transaction is executed
+                // by its signer, so receivers of transactions are taken from the chunks
+                // which included them.
+                let mut transaction_receivers = HashMap::new();
+                for (index, chunk) in block.chunks.iter().enumerate() {
+                    if chunk.height_included != block_height {
+                        continue;
+                    }
+                    if let Some(chunk_view) = self.chunk(data, index).await {
+                        transaction_receivers.extend(
+                            chunk_view.transactions.iter().map(|tx| (tx.hash, &tx.receiver_id)),
+                        );
+                    }
+                }
+                outcomes
+                    .iter()
+                    .filter(|(_, outcome)| {
+                        let receiver = transaction_receivers
+                            .get(&outcome.id)
+                            .copied()
+                            .unwrap_or(&outcome.outcome.executor_id);
+                        receiver == receiver_id
+                    })
+                    .map(|(shard_id, outcome)| SubscriptionEvent::ExecutionOutcome {
+                        block_height,
+                        shard_id: *shard_id,
+                        outcome: outcome.clone(),
+                    })
+                    .collect()
+            }
+            SubscriptionKind::StateChanges { account_id_prefix, .. } => {
+                let changes = data
+                    .state_changes
+                    .get_or_init(|| async {
+                        match self
+                            .view_client_sender
+                            .send_async(GetStateChangesWithCauseInBlock { block_hash })
+                            .await
+                        {
+                            Ok(Ok(changes)) => Some(changes),
+                            result => {
+                                tracing::debug!(target: "jsonrpc", ?result, %block_hash, "failed to fetch state changes for subscribers");
+                                None
+                            }
+                        }
+                    })
+                    .await;
+                let Some(changes) = changes else {
+                    return vec![];
+                };
+                let changes: Vec<_> = changes
+                    .iter()
+                    .filter(|change| {
+                        change.value.affected_account_id().as_str().starts_with(account_id_prefix)
+                    })
+                    .cloned()
+                    .collect();
+                if changes.is_empty() {
+                    return vec![];
+                }
+                vec![SubscriptionEvent::StateChanges { block_hash, block_height, changes }]
+            }
+        }
+    }
+}
+
+fn subscription_kind_label(kind: &SubscriptionKind) -> &'static str {
+    match kind {
+        SubscriptionKind::Blocks { .. } => "blocks",
+        SubscriptionKind::Chunks { .. } => "chunks",
+        SubscriptionKind::ExecutionOutcomes { .. } => "execution_outcomes",
+        SubscriptionKind::StateChanges { .. } => "state_changes",
+    }
+}
+
+/// Forwards events of a single subscription to the WebSocket until the
+/// connection is closed or the subscription is cancelled.
+async fn forward_events(
+    hub: Arc<SubscriptionHub>,
+    subscription_id: SubscriptionId,
+    kind: SubscriptionKind,
+    mut session: actix_ws::Session,
+) {
+    let mut blocks = hub.blocks(kind.finality());
+    loop {
+        let block = match blocks.recv().await {
+            Ok(block) => block,
+            Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                tracing::warn!(target: "jsonrpc", subscription_id, skipped, "subscriber is lagging behind, skipping blocks");
+                continue;
+            }
+            Err(broadcast::error::RecvError::Closed) => return,
+        };
+        for event in hub.events_for_block(&kind, &block).await {
+            let notification = match serde_json::to_value(RpcSubscriptionNotification {
+                subscription_id,
+                result: event,
+            }) {
+                Ok(value) => value,
+                Err(err) => {
+                    tracing::error!(target: "jsonrpc", ?err, "failed to serialize subscription notification");
+                    continue;
+                }
+            };
+            let message: String =
+                Message::notification(SUBSCRIPTION_NOTIFICATION_METHOD.to_string(), notification)
+                    .into();
+            if session.text(message).await.is_err() {
+                return;
+            }
+        }
+    }
+}
+
+/// State of a single WebSocket connection.
+struct Connection {
+    handler: Arc<JsonRpcHandler>,
+    session: actix_ws::Session,
+    subscriptions: HashMap<SubscriptionId, (&'static str, actix_web::rt::task::JoinHandle<()>)>,
+    next_subscription_id: SubscriptionId,
+}
+
+impl Connection {
+    fn subscribe(&mut self, params: Value) -> Result<Value, RpcError> {
+        let RpcSubscribeRequest { subscription } = RpcSubscribeRequest::parse(params)?;
+        let limit = self.handler.subscription_hub.config.max_subscriptions_per_connection;
+        if self.subscriptions.len() >= limit {
+            return Err(RpcSubscriptionError::TooManySubscriptions { limit }.into());
+        }
+        let subscription_id = self.next_subscription_id;
+        self.next_subscription_id += 1;
+        let label = subscription_kind_label(&subscription);
+        let task = actix_web::rt::spawn(forward_events(
+            self.handler.subscription_hub.clone(),
+            subscription_id,
+            subscription,
+            self.session.clone(),
+        ));
+        self.subscriptions.insert(subscription_id, (label, task));
+        metrics::RPC_ACTIVE_SUBSCRIPTIONS.with_label_values(&[label]).inc();
+        crate::serialize_response(RpcSubscribeResponse { subscription_id })
+    }
+
+    fn unsubscribe(&mut self, params: Value) -> Result<Value, RpcError> {
+        let RpcUnsubscribeRequest { subscription_id } = RpcUnsubscribeRequest::parse(params)?;
+        let Some((label, task)) = self.subscriptions.remove(&subscription_id) else {
+            return Err(RpcSubscriptionError::UnknownSubscription { subscription_id }.into());
+        };
+        task.abort();
+        metrics::RPC_ACTIVE_SUBSCRIPTIONS.with_label_values(&[label]).dec();
+        crate::serialize_response(RpcUnsubscribeResponse {})
+    }
+
+    async fn process_request(&mut self, request: Request) -> Message {
+        let id = request.id.clone();
+        let response = match request.method.as_ref() {
+            "EXPERIMENTAL_subscribe" => self.subscribe(request.params),
+            "EXPERIMENTAL_unsubscribe" => self.unsubscribe(request.params),
+            _ => self.handler.process_request(request).await,
+        };
+        Message::response(id, response)
+    }
+
+    async fn process_text(&mut self, text: &str) -> Message {
+        match message::from_str(text) {
+            Ok(Message::Request(request)) => self.process_request(request).await,
+            Ok(Message::Batch(messages)) => self.handler.process_batch(messages).await,
+            Ok(_) => Message::error(RpcError::parse_error(
+                "JSON RPC Request format was expected".to_owned(),
+            )),
+            Err(broken) => broken.reply(),
+        }
+    }
+
+    fn close(self) {
+        for (_, (label, task)) in self.subscriptions {
+            task.abort();
+            metrics::RPC_ACTIVE_SUBSCRIPTIONS.with_label_values(&[label]).dec();
+        }
+    }
+}
+
+/// Serves a single WebSocket connection until the client disconnects.
+pub(crate) async fn run_session(
+    handler: Arc<JsonRpcHandler>,
+    session: actix_ws::Session,
+    mut stream: actix_ws::MessageStream,
+) {
+    metrics::RPC_WEBSOCKET_CONNECTIONS.inc();
+    let mut state =
+        Connection { handler, session, subscriptions: HashMap::new(), next_subscription_id: 0 };
+    while let Some(Ok(msg)) = stream.recv().await {
+        let response = match msg {
+            actix_ws::Message::Text(text) => state.process_text(&text).await,
+            actix_ws::Message::Binary(bytes) => match std::str::from_utf8(&bytes) {
+                Ok(text) => state.process_text(text).await,
+                Err(err) => Message::error(RpcError::parse_error(err.to_string())),
+            },
+            actix_ws::Message::Ping(bytes) => {
+                if state.session.pong(&bytes).await.is_err() {
+                    break;
+                }
+                continue;
+            }
+            actix_ws::Message::Close(_) => break,
+            actix_ws::Message::Pong(_)
+            | actix_ws::Message::Continuation(_)
+            | actix_ws::Message::Nop => continue,
+        };
+        let response: String = response.into();
+        if state.session.text(response).await.is_err() {
+            break;
+        }
+    }
+    let session = state.session.clone();
+    state.close();
+    let _ = session.close(None).await;
+    metrics::RPC_WEBSOCKET_CONNECTIONS.dec();
+}
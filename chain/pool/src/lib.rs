@@ -1,10 +1,13 @@
+use std::cmp::{Ordering, Reverse};
 use std::collections::btree_map::Entry;
-use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::collections::{BTreeMap, BTreeSet, BinaryHeap, HashMap, HashSet, VecDeque};
 
+use crate::ordering::{OrderingPolicy, RoundRobinOrdering};
 use crate::types::{PoolKey, TransactionGroup, TransactionGroupIterator};
 
+use near_chain_configs::TransactionPoolConfig;
 use near_crypto::PublicKey;
-use near_o11y::metrics::prometheus::core::{AtomicI64, GenericGauge};
+use near_o11y::metrics::prometheus::core::{AtomicI64, AtomicU64, GenericCounter, GenericGauge};
 use near_primitives::epoch_manager::RngSeed;
 use near_primitives::hash::{hash, CryptoHash};
 use near_primitives::transaction::SignedTransaction;
@@ -12,6 +15,7 @@ use near_primitives::types::AccountId;
 use std::ops::Bound;
 
 mod metrics;
+pub mod ordering;
 pub mod types;
 
 #[derive(Debug, PartialEq)]
@@ -22,6 +26,18 @@ pub enum InsertTransactionResult {
     Duplicate,
     /// Not enough space to fit the transaction.
     NoSpaceLeft,
+    /// The signer or the receiver of the transaction already has as many transactions in the
+    /// pool as allowed.
+    AccountLimitExceeded,
+}
+
+/// Bookkeeping of a single transaction in the pool.
+struct PooledTransaction {
+    key: PoolKey,
+    signer_id: AccountId,
+    receiver_id: AccountId,
+    priority: u64,
+    size: u64,
 }
 
 /// Transaction pool: keeps track of transactions that were not yet accepted into the block chain.
@@ -30,8 +46,8 @@ pub struct TransactionPool {
     /// NOTE: It's more efficient on average to keep transactions unsorted and with potentially
     /// conflicting nonce than to create a BTreeMap for every transaction.
     transactions: BTreeMap<PoolKey, Vec<SignedTransaction>>,
-    /// Set of all hashes to quickly check if the given transaction is in the pool.
-    unique_transactions: HashSet<CryptoHash>,
+    /// All hashes to quickly check if the given transaction is in the pool.
+    unique_transactions: HashMap<CryptoHash, PooledTransaction>,
     /// A uniquely generated key seed to randomize PoolKey order.
     key_seed: RngSeed,
     /// The key after which the pool iterator starts. Doesn't have to be present in the pool.
@@ -40,9 +56,25 @@ pub struct TransactionPool {
     total_transaction_size_limit: Option<u64>,
     /// Total size of transactions in the pool measured in bytes.
     total_transaction_size: u64,
+    /// Decides the order of the transaction groups handed out by the pool iterator.
+    ordering_policy: Box<dyn OrderingPolicy>,
+    /// The transaction with the highest nonce of each group, lowest priority first. Evicting
+    /// any other transaction would leave a nonce gap blocking the rest of the group.
+    /// Only maintained if the ordering policy is prioritized.
+    eviction_queue: BTreeSet<(u64, PoolKey, CryptoHash)>,
+    /// Priority and hash of the entry of each group in `eviction_queue`.
+    eviction_candidates: HashMap<PoolKey, (u64, CryptoHash)>,
+    /// If set, new transactions of signers with that many transactions in the pool are rejected.
+    max_transactions_per_signer: Option<usize>,
+    /// If set, new transactions to receivers with that many transactions in the pool are rejected.
+    max_transactions_per_receiver: Option<usize>,
+    /// Number of transactions in the pool per signer and per receiver account.
+    signer_transaction_count: HashMap<AccountId, usize>,
+    receiver_transaction_count: HashMap<AccountId, usize>,
     /// Metrics tracked for transaction pool.
     transaction_pool_count_metric: GenericGauge<AtomicI64>,
     transaction_pool_size_metric: GenericGauge<AtomicI64>,
+    transaction_pool_evicted_metric: GenericCounter<AtomicU64>,
 }
 
 impl TransactionPool {
@@ -50,24 +82,53 @@ impl TransactionPool {
         key_seed: RngSeed,
         total_transaction_size_limit: Option<u64>,
         metrics_label: &str,
+    ) -> Self {
+        Self::new_with_config(
+            key_seed,
+            total_transaction_size_limit,
+            &TransactionPoolConfig::default(),
+            Box::new(RoundRobinOrdering),
+            metrics_label,
+        )
+    }
+
+    /// Creates a pool with the per-account limits of `config`. Transactions are ordered by
+    /// `ordering_policy` rather than `config.ordering`, see `ordering::ordering_policy`.
+    pub fn new_with_config(
+        key_seed: RngSeed,
+        total_transaction_size_limit: Option<u64>,
+        config: &TransactionPoolConfig,
+        ordering_policy: Box<dyn OrderingPolicy>,
+        metrics_label: &str,
     ) -> Self {
         let transaction_pool_count_metric =
             metrics::TRANSACTION_POOL_COUNT.with_label_values(&[metrics_label]);
         let transaction_pool_size_metric =
             metrics::TRANSACTION_POOL_SIZE.with_label_values(&[metrics_label]);
+        let transaction_pool_evicted_metric =
+            metrics::TRANSACTION_POOL_EVICTED.with_label_values(&[metrics_label]);
         // A `get()` call initializes a metric even if its value is zero.
         transaction_pool_count_metric.get();
         transaction_pool_size_metric.get();
+        transaction_pool_evicted_metric.get();
 
         Self {
             key_seed,
             transactions: BTreeMap::new(),
-            unique_transactions: HashSet::new(),
+            unique_transactions: HashMap::new(),
             last_used_key: CryptoHash::default(),
             total_transaction_size_limit,
             total_transaction_size: 0,
+            ordering_policy,
+            eviction_queue: BTreeSet::new(),
+            eviction_candidates: HashMap::new(),
+            max_transactions_per_signer: config.max_transactions_per_signer,
+            max_transactions_per_receiver: config.max_transactions_per_receiver,
+            signer_transaction_count: HashMap::new(),
+            receiver_transaction_count: HashMap::new(),
             transaction_pool_count_metric,
             transaction_pool_size_metric,
+            transaction_pool_evicted_metric,
         }
     }
 
@@ -84,37 +145,162 @@ impl TransactionPool {
         &mut self,
         signed_transaction: SignedTransaction,
     ) -> InsertTransactionResult {
-        if !self.unique_transactions.insert(signed_transaction.get_hash()) {
+        let tx_hash = signed_transaction.get_hash();
+        if self.unique_transactions.contains_key(&tx_hash) {
             // The hash of this transaction was already seen, skip it.
             return InsertTransactionResult::Duplicate;
         }
+        let signer_id = signed_transaction.transaction.signer_id();
+        let receiver_id = signed_transaction.transaction.receiver_id();
+        if exceeds_limit(
+            &self.signer_transaction_count,
+            signer_id,
+            self.max_transactions_per_signer,
+        ) || exceeds_limit(
+            &self.receiver_transaction_count,
+            receiver_id,
+            self.max_transactions_per_receiver,
+        ) {
+            return InsertTransactionResult::AccountLimitExceeded;
+        }
+
+        let priority = self.ordering_policy.priority(&signed_transaction);
+        let size = signed_transaction.get_size();
         // We never expect the total size to go over `u64` during real operation as that would
         // be more than 10^9 GiB of RAM consumed for transaction pool, so panicing here is intended
         // to catch a logic error in estimation of transaction size.
         let new_total_transaction_size = self
             .total_transaction_size
-            .checked_add(signed_transaction.get_size())
+            .checked_add(size)
             .expect("Total transaction size is too large");
         if let Some(limit) = self.total_transaction_size_limit {
-            if new_total_transaction_size > limit {
+            if new_total_transaction_size > limit
+                && !self.evict_lower_priority(priority, new_total_transaction_size - limit)
+            {
                 return InsertTransactionResult::NoSpaceLeft;
             }
         }
 
         // At this point transaction is accepted to the pool.
-        self.total_transaction_size = new_total_transaction_size;
-        let signer_id = signed_transaction.transaction.signer_id();
-        let signer_public_key = signed_transaction.transaction.public_key();
-        self.transactions
-            .entry(self.key(signer_id, signer_public_key))
-            .or_insert_with(Vec::new)
-            .push(signed_transaction);
+        self.total_transaction_size += size;
+        let key = self.key(signer_id, signed_transaction.transaction.public_key());
+        *self.signer_transaction_count.entry(signer_id.clone()).or_default() += 1;
+        *self.receiver_transaction_count.entry(receiver_id.clone()).or_default() += 1;
+        self.unique_transactions.insert(
+            tx_hash,
+            PooledTransaction {
+                key,
+                signer_id: signer_id.clone(),
+                receiver_id: receiver_id.clone(),
+                priority,
+                size,
+            },
+        );
+        self.transactions.entry(key).or_insert_with(Vec::new).push(signed_transaction);
+        self.update_eviction_candidate(key);
 
-        self.transaction_pool_count_metric.inc();
+        self.transaction_pool_count_metric.set(self.unique_transactions.len() as i64);
         self.transaction_pool_size_metric.set(self.total_transaction_size as i64);
         InsertTransactionResult::Success
     }
 
+    /// Evicts transactions with priority lower than `priority` to free at least `required_size`
+    /// bytes. Nothing is evicted and `false` is returned if that much can't be freed.
+    ///
+    /// A transaction is only evicted together with all the transactions of its group with a
+    /// higher nonce, so the lowest priority candidate is always the last one of some group.
+    fn evict_lower_priority(&mut self, priority: u64, required_size: u64) -> bool {
+        let mut freed_size = 0;
+        let mut evicted = vec![];
+        // Remaining transactions of the groups evicted from so far, sorted the same way as in
+        // `update_eviction_candidate`.
+        let mut remaining: HashMap<PoolKey, Vec<CryptoHash>> = HashMap::new();
+        // The last remaining transactions of those groups, which are not in `eviction_queue`.
+        let mut exposed = BinaryHeap::new();
+        let mut queue = self.eviction_queue.iter().copied().peekable();
+        while freed_size < required_size {
+            let candidate = match (queue.peek(), exposed.peek()) {
+                (Some(queued), Some(Reverse(next))) if next < queued => exposed.pop(),
+                (Some(_), _) => queue.next().map(Reverse),
+                (None, _) => exposed.pop(),
+            };
+            let Some(Reverse((evicted_priority, key, tx_hash))) = candidate else {
+                break;
+            };
+            if evicted_priority >= priority {
+                break;
+            }
+            freed_size += self.unique_transactions[&tx_hash].size;
+            evicted.push((key, tx_hash));
+
+            let group = remaining.entry(key).or_insert_with(|| {
+                let mut group = self.transactions[&key].iter().collect::<Vec<_>>();
+                group.sort_by_key(|tx| (tx.transaction.nonce(), tx.get_hash()));
+                group.into_iter().map(|tx| tx.get_hash()).collect()
+            });
+            debug_assert_eq!(group.last(), Some(&tx_hash));
+            group.pop();
+            if let Some(next) = group.last() {
+                exposed.push(Reverse((self.unique_transactions[next].priority, key, *next)));
+            }
+        }
+        if freed_size < required_size {
+            return false;
+        }
+
+        for (key, tx_hash) in evicted {
+            let Some(pooled) = self.forget_transaction(&tx_hash) else {
+                continue;
+            };
+            if let Entry::Occupied(mut entry) = self.transactions.entry(key) {
+                entry.get_mut().retain(|tx| tx.get_hash() != tx_hash);
+                if entry.get().is_empty() {
+                    entry.remove_entry();
+                }
+            }
+            // See the comment in `insert_transaction` where we increase the size for reasoning
+            // why panicing here catches a logic error.
+            self.total_transaction_size = self
+                .total_transaction_size
+                .checked_sub(pooled.size)
+                .expect("Total transaction size dropped below zero");
+            self.transaction_pool_evicted_metric.inc();
+        }
+        for key in remaining.into_keys() {
+            self.update_eviction_candidate(key);
+        }
+        true
+    }
+
+    /// Puts the transaction with the highest nonce of the group into `eviction_queue`, in place
+    /// of the previous one. Removes the group from the queue if it's no longer in the pool.
+    fn update_eviction_candidate(&mut self, key: PoolKey) {
+        if !self.ordering_policy.is_prioritized() {
+            return;
+        }
+        if let Some((priority, tx_hash)) = self.eviction_candidates.remove(&key) {
+            self.eviction_queue.remove(&(priority, key, tx_hash));
+        }
+        let Some(last) = self.transactions.get(&key).and_then(|transactions| {
+            transactions.iter().max_by_key(|tx| (tx.transaction.nonce(), tx.get_hash()))
+        }) else {
+            return;
+        };
+        let tx_hash = last.get_hash();
+        let priority = self.unique_transactions[&tx_hash].priority;
+        self.eviction_queue.insert((priority, key, tx_hash));
+        self.eviction_candidates.insert(key, (priority, tx_hash));
+    }
+
+    /// Drops the bookkeeping of the transaction, except for the total size of the pool.
+    /// Returns `None` if the transaction is not in the pool.
+    fn forget_transaction(&mut self, tx_hash: &CryptoHash) -> Option<PooledTransaction> {
+        let pooled = self.unique_transactions.remove(tx_hash)?;
+        decrement_count(&mut self.signer_transaction_count, &pooled.signer_id);
+        decrement_count(&mut self.receiver_transaction_count, &pooled.receiver_id);
+        Some(pooled)
+    }
+
     /// Returns a pool iterator wrapper that implements an iterator-like trait to iterate over
     /// transaction groups in the proper order defined by the protocol.
     /// When the iterator is dropped, all remaining groups are inserted back into the pool.
@@ -130,14 +316,11 @@ impl TransactionPool {
         let mut grouped_transactions = HashMap::new();
         for tx in transactions {
             // If transaction is not present in the pool, skip it.
-            if !self.unique_transactions.remove(&tx.get_hash()) {
+            let Some(pooled) = self.forget_transaction(&tx.get_hash()) else {
                 continue;
-            }
-
-            let signer_id = tx.transaction.signer_id();
-            let signer_public_key = tx.transaction.public_key();
+            };
             grouped_transactions
-                .entry(self.key(signer_id, signer_public_key))
+                .entry(pooled.key)
                 .or_insert_with(HashSet::new)
                 .insert(tx.get_hash());
         }
@@ -159,6 +342,7 @@ impl TransactionPool {
                     entry.remove_entry();
                 }
             }
+            self.update_eviction_candidate(key);
         }
 
         // We can update metrics only once for the whole batch of transactions.
@@ -175,6 +359,59 @@ impl TransactionPool {
     pub fn transaction_size(&self) -> u64 {
         self.total_transaction_size
     }
+
+    /// Returns all transactions in the pool in no particular order.
+    pub fn transactions(&self) -> impl Iterator<Item = &SignedTransaction> {
+        self.transactions.values().flatten()
+    }
+}
+
+fn exceeds_limit(
+    counts: &HashMap<AccountId, usize>,
+    account_id: &AccountId,
+    limit: Option<usize>,
+) -> bool {
+    limit.is_some_and(|limit| counts.get(account_id).copied().unwrap_or(0) >= limit)
+}
+
+fn decrement_count(counts: &mut HashMap<AccountId, usize>, account_id: &AccountId) {
+    if let std::collections::hash_map::Entry::Occupied(mut entry) = counts.entry(account_id.clone())
+    {
+        *entry.get_mut() -= 1;
+        if *entry.get() == 0 {
+            entry.remove_entry();
+        }
+    }
+}
+
+/// A transaction group waiting in the queue of a prioritized pool iterator, ordered by the
+/// priority of its next transaction.
+struct PrioritizedGroup {
+    priority: u64,
+    /// When the group was put into the queue, to visit groups of equal priority round robin.
+    sequence: u64,
+    group: TransactionGroup,
+}
+
+impl PartialEq for PrioritizedGroup {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for PrioritizedGroup {}
+
+impl PartialOrd for PrioritizedGroup {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for PrioritizedGroup {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // Of groups with equal priority, the one waiting the longest goes first.
+        self.priority.cmp(&other.priority).then_with(|| other.sequence.cmp(&self.sequence))
+    }
 }
 
 /// PoolIterator is a structure to pull transactions from the pool.
@@ -186,11 +423,89 @@ pub struct PoolIteratorWrapper<'a> {
 
     /// Queue of transaction groups. Each group there is sorted by nonce.
     sorted_groups: VecDeque<TransactionGroup>,
+
+    /// Used instead of `sorted_groups` if the ordering policy of the pool is prioritized.
+    /// Non-empty groups sorted by nonce, except for the one last returned by the iterator.
+    prioritized_groups: BinaryHeap<PrioritizedGroup>,
+    /// The group last returned by a prioritized iterator.
+    current_group: Option<TransactionGroup>,
+    /// Number of groups put into `prioritized_groups` so far.
+    next_sequence: u64,
 }
 
 impl<'a> PoolIteratorWrapper<'a> {
     pub fn new(pool: &'a mut TransactionPool) -> Self {
-        Self { pool, sorted_groups: Default::default() }
+        Self {
+            pool,
+            sorted_groups: Default::default(),
+            prioritized_groups: Default::default(),
+            current_group: None,
+            next_sequence: 0,
+        }
+    }
+
+    fn take_sorted_group(&mut self, key: PoolKey) -> TransactionGroup {
+        let mut transactions = self.pool.transactions.remove(&key).expect("just checked existence");
+        self.pool.update_eviction_candidate(key);
+        transactions.sort_by_key(|st| std::cmp::Reverse(st.transaction.nonce()));
+        TransactionGroup {
+            key,
+            transactions,
+            removed_transaction_hashes: vec![],
+            removed_transaction_size: 0,
+        }
+    }
+
+    /// Removes the transactions pulled from the group from the pool bookkeeping.
+    fn finalize_group(&mut self, group: &mut TransactionGroup) {
+        for hash in group.removed_transaction_hashes.drain(..) {
+            self.pool.forget_transaction(&hash);
+        }
+        // See the comment in `insert_transaction` where we increase the size for reasoning
+        // why panicing here catches a logic error.
+        self.pool.total_transaction_size = self
+            .pool
+            .total_transaction_size
+            .checked_sub(group.removed_transaction_size)
+            .expect("Total transaction size dropped below zero");
+        group.removed_transaction_size = 0;
+    }
+
+    fn push_prioritized(&mut self, priority: u64, group: TransactionGroup) {
+        let sequence = self.next_sequence;
+        self.next_sequence += 1;
+        self.prioritized_groups.push(PrioritizedGroup { priority, sequence, group });
+    }
+
+    /// Returns the group whose next transaction has the highest priority. The group returned
+    /// previously is put back into the queue with the priority of its new next transaction.
+    fn next_prioritized(&mut self) -> Option<&mut TransactionGroup> {
+        if let Some(mut group) = self.current_group.take() {
+            match group.peek_next() {
+                Some(tx) => {
+                    let priority = self.pool.ordering_policy.priority(tx);
+                    self.push_prioritized(priority, group);
+                }
+                None => {
+                    self.finalize_group(&mut group);
+                    self.pool
+                        .transaction_pool_count_metric
+                        .set(self.pool.unique_transactions.len() as i64);
+                    self.pool.transaction_pool_size_metric.set(self.pool.transaction_size() as i64);
+                }
+            }
+        }
+        // Groups are only taken from the pool on the first call, all of them at once. Ties are
+        // initially broken by the randomized pool keys.
+        while let Some(key) = self.pool.transactions.keys().next().copied() {
+            let group = self.take_sorted_group(key);
+            let tx = group.peek_next().expect("groups in the pool are not empty");
+            let priority = self.pool.ordering_policy.priority(tx);
+            self.push_prioritized(priority, group);
+        }
+        let PrioritizedGroup { group, .. } = self.prioritized_groups.pop()?;
+        self.current_group = Some(group);
+        self.current_group.as_mut()
     }
 }
 
@@ -211,8 +526,14 @@ impl<'a> PoolIteratorWrapper<'a> {
 ///
 /// When the iterator is dropped, `unique_transactions` in the pool is updated for every group.
 /// And all non-empty group from the sorted groups queue are inserted back into the pool.
+///
+/// If the ordering policy of the pool is prioritized, the iterator instead always returns the
+/// group whose next transaction has the highest priority, see `next_prioritized`.
 impl<'a> TransactionGroupIterator for PoolIteratorWrapper<'a> {
     fn next(&mut self) -> Option<&mut TransactionGroup> {
+        if self.pool.ordering_policy.is_prioritized() {
+            return self.next_prioritized();
+        }
         if !self.pool.transactions.is_empty() {
             let key = *self
                 .pool
@@ -228,29 +549,13 @@ impl<'a> TransactionGroupIterator for PoolIteratorWrapper<'a> {
                         .expect("we've just checked that the map is not empty")
                 });
             self.pool.last_used_key = key;
-            let mut transactions =
-                self.pool.transactions.remove(&key).expect("just checked existence");
-            transactions.sort_by_key(|st| std::cmp::Reverse(st.transaction.nonce()));
-            self.sorted_groups.push_back(TransactionGroup {
-                key,
-                transactions,
-                removed_transaction_hashes: vec![],
-                removed_transaction_size: 0,
-            });
+            let group = self.take_sorted_group(key);
+            self.sorted_groups.push_back(group);
             Some(self.sorted_groups.back_mut().expect("just pushed"))
         } else {
-            while let Some(sorted_group) = self.sorted_groups.pop_front() {
+            while let Some(mut sorted_group) = self.sorted_groups.pop_front() {
                 if sorted_group.transactions.is_empty() {
-                    for hash in sorted_group.removed_transaction_hashes {
-                        self.pool.unique_transactions.remove(&hash);
-                    }
-                    // See the comment in `insert_transaction` where we increase the size for reasoning
-                    // why panicing here catches a logic error.
-                    self.pool.total_transaction_size = self
-                        .pool
-                        .total_transaction_size
-                        .checked_sub(sorted_group.removed_transaction_size)
-                        .expect("Total transaction size dropped below zero");
+                    self.finalize_group(&mut sorted_group);
 
                     self.pool
                         .transaction_pool_count_metric
@@ -271,20 +576,16 @@ impl<'a> TransactionGroupIterator for PoolIteratorWrapper<'a> {
 /// removed from the pool's unique_transactions.
 impl<'a> Drop for PoolIteratorWrapper<'a> {
     fn drop(&mut self) {
-        for group in self.sorted_groups.drain(..) {
-            for hash in group.removed_transaction_hashes {
-                self.pool.unique_transactions.remove(&hash);
-            }
-            // See the comment in `insert_transaction` where we increase the size for reasoning
-            // why panicing here catches a logic error.
-            self.pool.total_transaction_size = self
-                .pool
-                .total_transaction_size
-                .checked_sub(group.removed_transaction_size)
-                .expect("Total transaction size dropped below zero");
-
+        let prioritized_groups = std::mem::take(&mut self.prioritized_groups);
+        let groups = std::mem::take(&mut self.sorted_groups)
+            .into_iter()
+            .chain(self.current_group.take())
+            .chain(prioritized_groups.into_iter().map(|prioritized| prioritized.group));
+        for mut group in groups {
+            self.finalize_group(&mut group);
             if !group.transactions.is_empty() {
                 self.pool.transactions.insert(group.key, group.transactions);
+                self.pool.update_eviction_candidate(group.key);
             }
         }
         // We can update metrics only once for the whole batch of transactions.
@@ -337,9 +638,12 @@ mod tests {
     use rand::seq::SliceRandom;
     use rand::thread_rng;
 
-    use near_crypto::{InMemorySigner, KeyType};
+    use near_crypto::{InMemorySigner, KeyType, Signer};
 
+    use crate::ordering::{BurntGasOrdering, PriorityFeeOrdering};
+    use near_parameters::RuntimeConfig;
     use near_primitives::hash::CryptoHash;
+    use near_primitives::transaction::{Action, Transaction, TransactionV1, TransferAction};
     use near_primitives::types::Balance;
 
     const TEST_SEED: RngSeed = [3; 32];
@@ -629,4 +933,224 @@ mod tests {
             }
         }
     }
+
+    /// A function call whose priority under `BurntGasOrdering` grows with `args_len`.
+    fn function_call(signer_id: &str, nonce: u64, args_len: usize) -> SignedTransaction {
+        function_call_with_gas(signer_id, nonce, args_len, 100)
+    }
+
+    fn function_call_with_gas(
+        signer_id: &str,
+        nonce: u64,
+        args_len: usize,
+        gas: u64,
+    ) -> SignedTransaction {
+        let signer_id: AccountId = signer_id.parse().unwrap();
+        let signer: Signer =
+            InMemorySigner::from_seed(signer_id.clone(), KeyType::ED25519, signer_id.as_str())
+                .into();
+        SignedTransaction::call(
+            nonce,
+            signer_id,
+            "bob.near".parse().unwrap(),
+            &signer,
+            0,
+            "method".to_string(),
+            vec![0; args_len],
+            gas,
+            CryptoHash::default(),
+        )
+    }
+
+    fn burnt_gas_pool(total_transaction_size_limit: Option<u64>) -> TransactionPool {
+        TransactionPool::new_with_config(
+            TEST_SEED,
+            total_transaction_size_limit,
+            &TransactionPoolConfig::default(),
+            Box::new(BurntGasOrdering::new(Arc::new(RuntimeConfig::test()))),
+            "",
+        )
+    }
+
+    #[test]
+    fn test_burnt_gas_ignores_prepaid_gas() {
+        let ordering = BurntGasOrdering::new(Arc::new(RuntimeConfig::test()));
+        let low = ordering.priority(&function_call_with_gas("alice.near", 1, 10, 100));
+        let high_gas =
+            ordering.priority(&function_call_with_gas("alice.near", 1, 10, 300_000_000_000_000));
+        let long_args = ordering.priority(&function_call_with_gas("alice.near", 1, 20, 100));
+        assert_eq!(low, high_gas);
+        assert!(low < long_args);
+    }
+
+    #[test]
+    fn test_prioritized_pool_iterator() {
+        let mut pool = burnt_gas_pool(None);
+        let transactions = [
+            function_call("alice.near", 1, 10),
+            function_call("alice.near", 2, 300),
+            function_call("carol.near", 1, 200),
+            function_call("dave.near", 1, 100),
+        ];
+        for tx in transactions {
+            assert_eq!(pool.insert_transaction(tx), InsertTransactionResult::Success);
+        }
+        // Groups are ordered by the priority of their next transaction, so alice's
+        // transaction with nonce 2 has to wait for the one with nonce 1.
+        let args_len = prepare_transactions(&mut pool, 4)
+            .iter()
+            .map(|tx| match &tx.transaction.actions()[0] {
+                Action::FunctionCall(function_call) => function_call.args.len(),
+                action => panic!("unexpected action {action:?}"),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(args_len, vec![200, 100, 10, 300]);
+        assert_eq!(pool.len(), 0);
+        assert_eq!(pool.transaction_size(), 0);
+    }
+
+    #[test]
+    fn test_prioritized_pool_iterator_round_robin_on_ties() {
+        let mut pool = burnt_gas_pool(None);
+        for nonce in 1..=3 {
+            for signer_id in ["alice.near", "carol.near"] {
+                let tx = function_call(signer_id, nonce, 0);
+                assert_eq!(pool.insert_transaction(tx), InsertTransactionResult::Success);
+            }
+        }
+        let transactions = prepare_transactions(&mut pool, 6);
+        let nonces = transactions.iter().map(|tx| tx.transaction.nonce()).collect::<Vec<_>>();
+        assert_eq!(nonces, vec![1, 1, 2, 2, 3, 3]);
+        let signers = transactions.iter().map(|tx| tx.transaction.signer_id()).collect::<Vec<_>>();
+        assert_ne!(signers[0], signers[1]);
+        assert_eq!(signers[..2], signers[2..4]);
+        assert_eq!(signers[..2], signers[4..]);
+    }
+
+    fn transfer_with_priority_fee(
+        signer_id: &str,
+        nonce: u64,
+        priority_fee: u64,
+    ) -> SignedTransaction {
+        let signer_id: AccountId = signer_id.parse().unwrap();
+        let signer: Signer =
+            InMemorySigner::from_seed(signer_id.clone(), KeyType::ED25519, signer_id.as_str())
+                .into();
+        Transaction::V1(TransactionV1 {
+            signer_id,
+            public_key: signer.public_key(),
+            nonce,
+            receiver_id: "bob.near".parse().unwrap(),
+            block_hash: CryptoHash::default(),
+            actions: vec![Action::Transfer(TransferAction { deposit: 1 })],
+            priority_fee,
+        })
+        .sign(&signer)
+    }
+
+    #[test]
+    fn test_priority_fee_pool_iterator() {
+        let mut pool = TransactionPool::new_with_config(
+            TEST_SEED,
+            None,
+            &TransactionPoolConfig::default(),
+            Box::new(PriorityFeeOrdering),
+            "",
+        );
+        let transactions = [
+            generate_transactions("alice.near", "alice.near", 1, 1).pop().unwrap(),
+            transfer_with_priority_fee("carol.near", 1, 10),
+            transfer_with_priority_fee("carol.near", 2, 30),
+            transfer_with_priority_fee("dave.near", 1, 20),
+        ];
+        for tx in transactions {
+            assert_eq!(pool.insert_transaction(tx), InsertTransactionResult::Success);
+        }
+        let order = prepare_transactions(&mut pool, 4)
+            .iter()
+            .map(|tx| (tx.transaction.signer_id().to_string(), tx.transaction.nonce()))
+            .collect::<Vec<_>>();
+        // Groups are ordered by the fee of their next transaction, so carol's highest fee only
+        // counts once her transaction with nonce 1 is taken. Transactions without a fee go last.
+        assert_eq!(
+            order,
+            vec![
+                ("dave.near".to_string(), 1),
+                ("carol.near".to_string(), 1),
+                ("carol.near".to_string(), 2),
+                ("alice.near".to_string(), 1),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_prioritized_pool_evicts_lower_priority() {
+        let low = function_call("alice.near", 1, 10);
+        let high = function_call("carol.near", 1, 1000);
+        let mut pool = burnt_gas_pool(Some(low.get_size().max(high.get_size())));
+        assert_eq!(pool.insert_transaction(low.clone()), InsertTransactionResult::Success);
+        assert_eq!(pool.insert_transaction(high.clone()), InsertTransactionResult::Success);
+        assert_eq!(pool.len(), 1);
+        assert_eq!(pool.transaction_size(), high.get_size());
+        // A transaction with a lower priority doesn't evict anything.
+        assert_eq!(pool.insert_transaction(low), InsertTransactionResult::NoSpaceLeft);
+        let mut pool_iter = pool.pool_iterator();
+        assert_eq!(pool_iter.next().unwrap().next().unwrap().get_hash(), high.get_hash());
+    }
+
+    /// Only the transaction with the highest nonce of a group can be evicted, even if
+    /// transactions with lower nonces have lower priority.
+    #[test]
+    fn test_prioritized_pool_evicts_highest_nonce() {
+        let first = function_call("alice.near", 1, 0);
+        let second = function_call("alice.near", 2, 100);
+        let medium = function_call("carol.near", 1, 50);
+        let high = function_call("carol.near", 1, 120);
+        // Fitting `high` requires evicting exactly the size of `second`.
+        let mut pool = burnt_gas_pool(Some(first.get_size() + high.get_size()));
+        assert_eq!(pool.insert_transaction(first.clone()), InsertTransactionResult::Success);
+        assert_eq!(pool.insert_transaction(second), InsertTransactionResult::Success);
+        assert_eq!(pool.insert_transaction(medium), InsertTransactionResult::NoSpaceLeft);
+        assert_eq!(pool.len(), 2);
+        assert_eq!(pool.insert_transaction(high.clone()), InsertTransactionResult::Success);
+        let mut hashes = pool.transactions().map(|tx| tx.get_hash()).collect::<Vec<_>>();
+        hashes.sort();
+        let mut expected = vec![first.get_hash(), high.get_hash()];
+        expected.sort();
+        assert_eq!(hashes, expected);
+        assert_eq!(pool.transaction_size(), first.get_size() + high.get_size());
+    }
+
+    #[test]
+    fn test_account_limits() {
+        let config = TransactionPoolConfig {
+            max_transactions_per_signer: Some(2),
+            max_transactions_per_receiver: Some(3),
+            ..Default::default()
+        };
+        let mut pool = TransactionPool::new_with_config(
+            TEST_SEED,
+            None,
+            &config,
+            Box::new(RoundRobinOrdering),
+            "",
+        );
+        let alice = generate_transactions("alice.near", "alice.near", 1, 3);
+        let carol = generate_transactions("carol.near", "carol.near", 1, 2);
+        assert_eq!(pool.insert_transaction(alice[0].clone()), InsertTransactionResult::Success);
+        assert_eq!(pool.insert_transaction(alice[1].clone()), InsertTransactionResult::Success);
+        assert_eq!(
+            pool.insert_transaction(alice[2].clone()),
+            InsertTransactionResult::AccountLimitExceeded
+        );
+        assert_eq!(pool.insert_transaction(carol[0].clone()), InsertTransactionResult::Success);
+        // All the transactions are sent to bob.near.
+        assert_eq!(
+            pool.insert_transaction(carol[1].clone()),
+            InsertTransactionResult::AccountLimitExceeded
+        );
+        // Removing a transaction makes room for another one.
+        pool.remove_transactions(&alice[..1]);
+        assert_eq!(pool.insert_transaction(alice[2].clone()), InsertTransactionResult::Success);
+    }
 }
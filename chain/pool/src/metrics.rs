@@ -1,4 +1,4 @@
-use near_o11y::metrics::IntGaugeVec;
+use near_o11y::metrics::{IntCounterVec, IntGaugeVec};
 use once_cell::sync::Lazy;
 
 pub static TRANSACTION_POOL_COUNT: Lazy<IntGaugeVec> = Lazy::new(|| {
@@ -18,3 +18,12 @@ pub static TRANSACTION_POOL_SIZE: Lazy<IntGaugeVec> = Lazy::new(|| {
     )
     .unwrap()
 });
+
+pub static TRANSACTION_POOL_EVICTED: Lazy<IntCounterVec> = Lazy::new(|| {
+    near_o11y::metrics::try_create_int_counter_vec(
+        "near_transaction_pool_evicted_total",
+        "Number of transactions evicted from a given shard pool to make room for transactions with a higher priority",
+        &["shard_id"],
+    )
+    .unwrap()
+});
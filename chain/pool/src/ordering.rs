@@ -0,0 +1,91 @@
+use std::sync::Arc;
+
+use near_chain_configs::TransactionOrdering;
+use near_parameters::{ActionCosts, RuntimeConfig};
+use near_primitives::transaction::SignedTransaction;
+use node_runtime::config::total_send_fees;
+
+/// Decides in which order the pool iterator hands out transaction groups.
+pub trait OrderingPolicy: Send + Sync {
+    /// Priority of the transaction, groups whose next transaction has a higher
+    /// priority are handed out first.
+    fn priority(&self, transaction: &SignedTransaction) -> u64;
+
+    /// Whether the groups are ordered by priority at all. If not, they are
+    /// visited round robin and transactions are never evicted from a full pool.
+    fn is_prioritized(&self) -> bool {
+        true
+    }
+}
+
+/// Visits groups one transaction at a time, in the pseudo-random order of the pool keys.
+pub struct RoundRobinOrdering;
+
+impl OrderingPolicy for RoundRobinOrdering {
+    fn priority(&self, _transaction: &SignedTransaction) -> u64 {
+        0
+    }
+
+    fn is_prioritized(&self) -> bool {
+        false
+    }
+}
+
+/// Prioritizes transactions by the gas burnt to convert them into receipts.
+///
+/// That gas is paid for even if the receipts fail, unlike prepaid gas which is
+/// refunded when unused and so would give priority for free.
+pub struct BurntGasOrdering {
+    runtime_config: Arc<RuntimeConfig>,
+}
+
+impl BurntGasOrdering {
+    pub fn new(runtime_config: Arc<RuntimeConfig>) -> Self {
+        Self { runtime_config }
+    }
+}
+
+impl OrderingPolicy for BurntGasOrdering {
+    fn priority(&self, transaction: &SignedTransaction) -> u64 {
+        let transaction = &transaction.transaction;
+        let sender_is_receiver = transaction.signer_id() == transaction.receiver_id();
+        let receipt_fee = self
+            .runtime_config
+            .fees
+            .fee(ActionCosts::new_action_receipt)
+            .send_fee(sender_is_receiver);
+        // Transactions that big are rejected by the size limits long before the sum overflows.
+        total_send_fees(
+            &self.runtime_config,
+            sender_is_receiver,
+            transaction.actions(),
+            transaction.receiver_id(),
+        )
+        .map_or(0, |send_fees| send_fees.saturating_add(receipt_fee))
+    }
+}
+
+/// Prioritizes transactions by the priority fee set by the signer.
+///
+/// Only `TransactionV1` carries a priority fee, transactions without one get
+/// the lowest priority.
+pub struct PriorityFeeOrdering;
+
+impl OrderingPolicy for PriorityFeeOrdering {
+    fn priority(&self, transaction: &SignedTransaction) -> u64 {
+        transaction.transaction.priority_fee().unwrap_or(0)
+    }
+}
+
+/// Returns the built-in policy implementing the configured ordering.
+/// `runtime_config` provides the fees for `TransactionOrdering::BurntGas`.
+pub fn ordering_policy(
+    ordering: TransactionOrdering,
+    runtime_config: Arc<RuntimeConfig>,
+) -> Box<dyn OrderingPolicy> {
+    match ordering {
+        TransactionOrdering::RoundRobin => Box::new(RoundRobinOrdering),
+        TransactionOrdering::BurntGas => Box::new(BurntGasOrdering::new(runtime_config)),
+        TransactionOrdering::Priority => Box::new(PriorityFeeOrdering),
+    }
+}
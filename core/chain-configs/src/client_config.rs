@@ -98,6 +98,19 @@ pub enum ExternalStorageLocation {
         bucket: String,
         /// Data may only be available in certain locations.
         region: String,
+        /// Endpoint of a self-hosted S3-compatible storage, e.g. `http://localhost:9000`.
+        /// If not set, the AWS endpoint of `region` is used.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        endpoint: Option<String>,
+        /// Addresses the bucket as `{endpoint}/{bucket}` instead of `{bucket}.{endpoint}`.
+        /// Most self-hosted storages only support this style.
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        path_style: bool,
+        /// Location of a json file with `access_key` and `secret_key` of the bucket.
+        /// Without it parts are fetched anonymously, and dumped with the credentials
+        /// from `DumpConfig::credentials_file` or from the environment.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        credentials_file: Option<PathBuf>,
     },
     Filesystem {
         root_dir: PathBuf,
@@ -105,6 +118,15 @@ pub enum ExternalStorageLocation {
     GCS {
         bucket: String,
     },
+    /// Read-only mirror of state parts served by a static HTTP server with the
+    /// same directory layout as `Filesystem`. Can't be used for dumping state.
+    /// Downloaded state parts and headers are checked against the state root
+    /// right away and downloaded again if they don't match.
+    Http {
+        /// URL the `chain_id=...` directories are served under, e.g.
+        /// `https://example.com/state-parts`.
+        base_url: String,
+    },
 }
 
 /// Configures how to dump state to external storage.
@@ -180,6 +202,41 @@ impl ReshardingHandle {
     }
 }
 
+/// Order in which the transaction pool hands out groups of transactions
+/// (sharing the signer account and public key) for inclusion into chunks.
+#[derive(serde::Serialize, serde::Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum TransactionOrdering {
+    /// Groups are visited one transaction at a time in a pseudo-random order.
+    #[default]
+    RoundRobin,
+    /// Groups whose next transaction burns the most gas when converted into
+    /// receipts go first. Prepaid gas doesn't count as unused gas is refunded.
+    BurntGas,
+    /// Groups whose next transaction has the highest priority fee go first.
+    /// Transactions without a priority fee have the lowest priority.
+    Priority,
+}
+
+/// Configuration of the transaction pool, applied to each shard separately.
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+#[serde(default)]
+pub struct TransactionPoolConfig {
+    /// Order in which transactions are taken from the pool. With a priority
+    /// based ordering, a transaction which doesn't fit into a full pool evicts
+    /// transactions of lower priority instead of being rejected.
+    pub ordering: TransactionOrdering,
+    /// If set, new transactions of a signer which already has this many
+    /// transactions in the pool are rejected.
+    pub max_transactions_per_signer: Option<usize>,
+    /// If set, new transactions to a receiver which already has this many
+    /// transactions in the pool are rejected. Disabled by default: a single
+    /// signer can fill the limit of a popular receiver and lock out all other
+    /// signers sending to it, so it should only be combined with a low
+    /// `max_transactions_per_signer`.
+    pub max_transactions_per_receiver: Option<usize>,
+}
+
 /// Configuration for resharding.
 #[derive(serde::Serialize, serde::Deserialize, Clone, Copy, Debug, PartialEq)]
 #[serde(default)]
@@ -453,6 +510,8 @@ pub struct ClientConfig {
     /// Limit of the size of per-shard transaction pool measured in bytes. If not set, the size
     /// will be unbounded.
     pub transaction_pool_size_limit: Option<u64>,
+    /// Ordering and per-account limits of the transaction pool.
+    pub transaction_pool: TransactionPoolConfig,
     // Allows more detailed logging, for example a list of orphaned blocks.
     pub enable_multiline_logging: bool,
     // Configuration for resharding.
@@ -559,6 +618,7 @@ impl ClientConfig {
             state_sync_enabled,
             state_sync: StateSyncConfig::default(),
             transaction_pool_size_limit: None,
+            transaction_pool: TransactionPoolConfig::default(),
             enable_multiline_logging: false,
             resharding_config: MutableConfigValue::new(
                 ReshardingConfig::default(),
@@ -297,7 +297,7 @@ impl Block {
         next_bp_hash: CryptoHash,
         block_merkle_root: CryptoHash,
         clock: near_time::Clock,
-        sandbox_delta_time: Option<near_time::Duration>,
+        sandbox_time: Option<near_time::Utc>,
     ) -> Self {
         use crate::hash::hash;
         // Collect aggregate of validators and gas usage/limits from chunks.
@@ -328,11 +328,10 @@ impl Block {
         );
 
         let new_total_supply = prev.total_supply() + minted_amount.unwrap_or(0) - balance_burnt;
-        let now = clock.now_utc().unix_timestamp_nanos() as u64;
-        #[cfg(feature = "sandbox")]
-        let now = now + sandbox_delta_time.unwrap().whole_nanoseconds() as u64;
+        // Sandbox nodes can move their time forward, so they pass the block time explicitly.
+        let now = sandbox_time.unwrap_or_else(|| clock.now_utc()).unix_timestamp_nanos() as u64;
         #[cfg(not(feature = "sandbox"))]
-        debug_assert!(sandbox_delta_time.is_none());
+        debug_assert!(sandbox_time.is_none());
         let time = if now <= prev.raw_timestamp() { prev.raw_timestamp() + 1 } else { now };
 
         let (vrf_value, vrf_proof) = signer.compute_vrf_with_proof(prev.random_value().as_ref());
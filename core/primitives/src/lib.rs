@@ -30,6 +30,7 @@ pub mod sharding;
 pub mod signable_message;
 pub mod state;
 pub mod state_part;
+pub mod state_proof;
 pub mod state_record;
 pub mod state_sync;
 pub mod stateless_validation;
@@ -0,0 +1,209 @@
+//! Verification of Merkle proofs of state trie values.
+//!
+//! A proof is the set of serialized trie nodes visited while looking keys up,
+//! as returned by the `view_state_proof` query. It lets clients which only
+//! know a state root, e.g. light clients and bridges, check values of
+//! arbitrary trie keys without trusting the node which served them.
+use crate::hash::CryptoHash;
+use crate::state::ValueRef;
+use crate::types::StateRoot;
+use borsh::BorshDeserialize;
+use std::collections::HashMap;
+use std::io;
+use std::sync::Arc;
+
+#[derive(thiserror::Error, Debug)]
+pub enum StateProofError {
+    #[error("Trie node {0} required to look the key up is missing from the proof")]
+    MissingNode(CryptoHash),
+}
+
+/// Trie node as it's stored in the `State` column. Mirrors `RawTrieNodeWithSize`
+/// of near-store, which this crate can't depend on.
+#[derive(BorshDeserialize)]
+struct ProofNodeWithSize {
+    node: ProofNode,
+    _memory_usage: u64,
+}
+
+#[derive(BorshDeserialize)]
+enum ProofNode {
+    Leaf(Vec<u8>, ValueRef),
+    BranchNoValue(ProofChildren),
+    BranchWithValue(ValueRef, ProofChildren),
+    Extension(Vec<u8>, CryptoHash),
+}
+
+/// Children of a branch node, serialized as a bitmap of the present children
+/// followed by their hashes.
+struct ProofChildren([Option<CryptoHash>; 16]);
+
+impl BorshDeserialize for ProofChildren {
+    fn deserialize_reader<R: io::Read>(rd: &mut R) -> io::Result<Self> {
+        let mut bitmap = u16::deserialize_reader(rd)?;
+        let mut children = [None; 16];
+        while bitmap != 0 {
+            let idx = bitmap.trailing_zeros() as usize;
+            bitmap &= bitmap - 1;
+            children[idx] = Some(CryptoHash::deserialize_reader(rd)?);
+        }
+        Ok(Self(children))
+    }
+}
+
+/// Nibbles of a key in the hex-prefix encoding used by leaf and extension nodes.
+fn decode_nibbles(encoded: &[u8]) -> Vec<u8> {
+    let Some((&first, rest)) = encoded.split_first() else { return vec![] };
+    let mut nibbles = Vec::with_capacity(rest.len() * 2 + 1);
+    if first & 0x10 != 0 {
+        nibbles.push(first & 0x0f);
+    }
+    for byte in rest {
+        nibbles.extend([byte >> 4, byte & 0x0f]);
+    }
+    nibbles
+}
+
+/// Trie nodes of a proof indexed by their hashes.
+pub struct StateProof {
+    nodes: HashMap<CryptoHash, ProofNode>,
+}
+
+impl StateProof {
+    /// Decodes the proof. Items which aren't trie nodes, such as values
+    /// recorded along with the nodes, are ignored.
+    pub fn new(proof: &[Arc<[u8]>]) -> Self {
+        let nodes = proof
+            .iter()
+            .filter_map(|bytes| {
+                let node = ProofNodeWithSize::try_from_slice(bytes).ok()?;
+                Some((CryptoHash::hash_bytes(bytes), node.node))
+            })
+            .collect();
+        Self { nodes }
+    }
+
+    /// Looks `key` up in the trie with the given root. Returns the reference
+    /// to the value or `None` if the proof shows that the key is absent.
+    pub fn lookup(
+        &self,
+        state_root: &StateRoot,
+        key: &[u8],
+    ) -> Result<Option<&ValueRef>, StateProofError> {
+        if *state_root == StateRoot::default() {
+            return Ok(None);
+        }
+        let key_nibbles: Vec<u8> = key.iter().flat_map(|byte| [byte >> 4, byte & 0x0f]).collect();
+        let mut key = key_nibbles.as_slice();
+        let mut hash = state_root;
+        loop {
+            let node = self.nodes.get(hash).ok_or(StateProofError::MissingNode(*hash))?;
+            match node {
+                ProofNode::Leaf(node_key, value) => {
+                    return Ok((key == decode_nibbles(node_key).as_slice()).then_some(value));
+                }
+                ProofNode::Extension(node_key, child) => {
+                    let Some(rest) = key.strip_prefix(decode_nibbles(node_key).as_slice()) else {
+                        return Ok(None);
+                    };
+                    key = rest;
+                    hash = child;
+                }
+                ProofNode::BranchNoValue(children) | ProofNode::BranchWithValue(_, children) => {
+                    let Some((&nibble, rest)) = key.split_first() else {
+                        return Ok(match node {
+                            ProofNode::BranchWithValue(value, _) => Some(value),
+                            _ => None,
+                        });
+                    };
+                    let Some(child) = &children.0[usize::from(nibble)] else {
+                        return Ok(None);
+                    };
+                    key = rest;
+                    hash = child;
+                }
+            }
+        }
+    }
+
+    /// Checks that in the trie with the given root the value of `key` is
+    /// `value`, where `None` stands for the absence of the key.
+    pub fn verify(
+        &self,
+        state_root: &StateRoot,
+        key: &[u8],
+        value: Option<&[u8]>,
+    ) -> Result<bool, StateProofError> {
+        Ok(match (self.lookup(state_root, key)?, value) {
+            (Some(value_ref), Some(value)) => value_ref == value,
+            (None, None) => true,
+            _ => false,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{decode_nibbles, StateProof, StateProofError};
+    use crate::hash::CryptoHash;
+    use crate::state::ValueRef;
+    use borsh::BorshSerialize;
+    use std::sync::Arc;
+
+    fn leaf(encoded_key: &[u8], value: &[u8]) -> Arc<[u8]> {
+        let mut node = vec![0];
+        encoded_key.to_vec().serialize(&mut node).unwrap();
+        ValueRef::new(value).serialize(&mut node).unwrap();
+        0u64.serialize(&mut node).unwrap();
+        node.into()
+    }
+
+    fn branch(children: &[(u8, &Arc<[u8]>)]) -> Arc<[u8]> {
+        let mut node = vec![1];
+        let bitmap = children.iter().fold(0u16, |bitmap, (idx, _)| bitmap | 1 << idx);
+        bitmap.serialize(&mut node).unwrap();
+        for (_, child) in children {
+            CryptoHash::hash_bytes(child).serialize(&mut node).unwrap();
+        }
+        0u64.serialize(&mut node).unwrap();
+        node.into()
+    }
+
+    #[test]
+    fn test_decode_nibbles() {
+        assert_eq!(decode_nibbles(&[0x00, 0x12]), vec![1, 2]);
+        assert_eq!(decode_nibbles(&[0x31, 0x23]), vec![1, 2, 3]);
+        assert_eq!(decode_nibbles(&[0x20]), Vec::<u8>::new());
+    }
+
+    #[test]
+    fn test_verify_proof() {
+        // Keys [0x12] and [0x34] under a branch on the first nibble.
+        let first = leaf(&[0x32], b"first");
+        let second = leaf(&[0x34], b"second");
+        let root_node = branch(&[(1, &first), (3, &second)]);
+        let root = CryptoHash::hash_bytes(&root_node);
+
+        let proof = StateProof::new(&[root_node.clone(), first.clone(), second]);
+        assert!(proof.verify(&root, &[0x12], Some(&b"first"[..])).unwrap());
+        assert!(proof.verify(&root, &[0x34], Some(&b"second"[..])).unwrap());
+        assert!(!proof.verify(&root, &[0x12], Some(&b"second"[..])).unwrap());
+        assert!(!proof.verify(&root, &[0x12], None).unwrap());
+        // Absent keys diverging at the leaf and at the branch.
+        assert!(proof.verify(&root, &[0x13], None).unwrap());
+        assert!(proof.verify(&root, &[0x56], None).unwrap());
+        assert!(!proof.verify(&root, &[0x56], Some(&b"first"[..])).unwrap());
+
+        // Incomplete proofs and proofs of other roots don't prove anything.
+        let proof = StateProof::new(&[root_node, first]);
+        assert!(proof.verify(&root, &[0x12], Some(&b"first"[..])).unwrap());
+        assert!(matches!(
+            proof.verify(&root, &[0x34], Some(&b"second"[..])),
+            Err(StateProofError::MissingNode(_))
+        ));
+        assert!(matches!(
+            proof.verify(&CryptoHash::hash_bytes(b"root"), &[0x12], Some(&b"first"[..])),
+            Err(StateProofError::MissingNode(_))
+        ));
+    }
+}
@@ -18,12 +18,15 @@ use crate::errors::TxExecutionError;
 use crate::hash::{hash, CryptoHash};
 use crate::merkle::{combine_hash, MerklePath};
 use crate::network::PeerId;
+use crate::profile_data_v3::ProfileDataV3;
 use crate::receipt::{ActionReceipt, DataReceipt, DataReceiver, Receipt, ReceiptEnum, ReceiptV1};
 use crate::serialize::dec_format;
+use crate::shard_layout::ShardUId;
 use crate::sharding::{
     ChunkHash, ShardChunk, ShardChunkHeader, ShardChunkHeaderInner, ShardChunkHeaderInnerV2,
     ShardChunkHeaderInnerV3, ShardChunkHeaderV3,
 };
+use crate::state_proof::{StateProof, StateProofError};
 #[cfg(feature = "protocol_feature_nonrefundable_transfer_nep491")]
 use crate::transaction::NonrefundableStorageTransferAction;
 use crate::transaction::{
@@ -32,6 +35,7 @@ use crate::transaction::{
     ExecutionStatus, FunctionCallAction, PartialExecutionOutcome, PartialExecutionStatus,
     SignedTransaction, StakeAction, TransferAction,
 };
+use crate::trie_key::TrieKey;
 use crate::types::{
     AccountId, AccountWithPublicKey, Balance, BlockHeight, EpochHeight, EpochId, FunctionArgs, Gas,
     Nonce, NumBlocks, ShardId, StateChangeCause, StateChangeKind, StateChangeValue,
@@ -44,7 +48,7 @@ use near_crypto::{PublicKey, Signature};
 use near_fmt::{AbbrBytes, Slice};
 use near_parameters::config::CongestionControlConfig;
 use near_parameters::view::CongestionControlConfigView;
-use near_parameters::{ActionCosts, ExtCosts};
+use near_parameters::{ActionCosts, ExtCosts, ExtCostsConfig};
 use near_primitives_core::version::PROTOCOL_VERSION;
 use near_time::Utc;
 use serde_with::base64::Base64;
@@ -217,10 +221,122 @@ pub struct ViewStateResult {
     pub proof: Vec<Arc<[u8]>>,
 }
 
+/// Maximum number of keys of a single `QueryRequest::ViewStateProof`.
+pub const MAX_STATE_PROOF_KEYS: usize = 100;
+
+/// Trie key whose value and Merkle proof can be requested with `QueryRequest::ViewStateProof`.
+#[derive(serde::Serialize, serde::Deserialize, Debug, PartialEq, Eq, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum StateProofKey {
+    /// The account record, which includes the hash of the deployed contract code.
+    Account {
+        account_id: AccountId,
+    },
+    AccessKey {
+        account_id: AccountId,
+        public_key: PublicKey,
+    },
+    ContractData {
+        account_id: AccountId,
+        #[serde(rename = "key_base64")]
+        key: StoreKey,
+    },
+}
+
+impl StateProofKey {
+    pub fn account_id(&self) -> &AccountId {
+        match self {
+            Self::Account { account_id }
+            | Self::AccessKey { account_id, .. }
+            | Self::ContractData { account_id, .. } => account_id,
+        }
+    }
+
+    pub fn to_trie_key(&self) -> TrieKey {
+        match self.clone() {
+            Self::Account { account_id } => TrieKey::Account { account_id },
+            Self::AccessKey { account_id, public_key } => {
+                TrieKey::AccessKey { account_id, public_key }
+            }
+            Self::ContractData { account_id, key } => {
+                TrieKey::ContractData { account_id, key: key.into() }
+            }
+        }
+    }
+}
+
+/// Raw value of a trie key, `None` if the key is absent from the state.
+#[derive(serde::Serialize, serde::Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct StateProofItem {
+    pub key: StateProofKey,
+    pub value: Option<StoreValue>,
+}
+
+/// Values of the requested trie keys with a single proof covering all of them.
+#[serde_as]
+#[derive(serde::Serialize, serde::Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct ViewStateProofResult {
+    /// State root the proof is built against. It's the `prev_state_root` of
+    /// the next chunk of the shard after the queried block.
+    pub state_root: StateRoot,
+    pub values: Vec<StateProofItem>,
+    #[serde_as(as = "Vec<Base64>")]
+    pub proof: Vec<Arc<[u8]>>,
+}
+
+impl ViewStateProofResult {
+    /// Checks that the proof confirms all the values for the given trusted
+    /// state root, including absence of the keys without a value.
+    pub fn verify(&self, state_root: &StateRoot) -> Result<bool, StateProofError> {
+        let proof = StateProof::new(&self.proof);
+        for item in &self.values {
+            let value = item.value.as_ref().map(|value| value.as_slice());
+            if !proof.verify(state_root, &item.key.to_trie_key().to_vec(), value)? {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+}
+
 #[derive(serde::Serialize, serde::Deserialize, Debug, PartialEq, Eq, Clone, Default)]
 pub struct CallResult {
     pub result: Vec<u8>,
     pub logs: Vec<String>,
+    /// Set if requested with `include_gas_profile`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gas_profile: Option<CallGasProfileView>,
+}
+
+/// Gas used by a view function call.
+#[derive(serde::Serialize, serde::Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct CallGasProfileView {
+    pub gas_burnt: Gas,
+    /// Gas used by WASM instructions, each host function cost and action cost,
+    /// in the same format as `gas_profile` of execution outcomes.
+    pub costs: Vec<CostGasUsed>,
+    /// Numbers of storage operations performed by the call.
+    pub storage_reads: u64,
+    pub storage_writes: u64,
+    pub storage_removes: u64,
+    pub storage_has_keys: u64,
+}
+
+impl CallGasProfileView {
+    pub fn new(gas_burnt: Gas, profile: ProfileDataV3, ext_costs_config: &ExtCostsConfig) -> Self {
+        // Every operation is charged its base cost once.
+        let count = |cost: ExtCosts| {
+            profile.get_ext_cost(cost).checked_div(ext_costs_config.gas_cost(cost)).unwrap_or(0)
+        };
+        let storage_reads = count(ExtCosts::storage_read_base);
+        let storage_writes = count(ExtCosts::storage_write_base);
+        let storage_removes = count(ExtCosts::storage_remove_base);
+        let storage_has_keys = count(ExtCosts::storage_has_key_base);
+        let costs = ExecutionMetadataView::from(ExecutionMetadata::V3(Box::new(profile)))
+            .gas_profile
+            .unwrap_or_default();
+        Self { gas_burnt, costs, storage_reads, storage_writes, storage_removes, storage_has_keys }
+    }
 }
 
 #[derive(serde::Serialize, serde::Deserialize, Debug, PartialEq, Eq, Clone)]
@@ -281,6 +397,7 @@ pub enum QueryResponseKind {
     ViewAccount(AccountView),
     ViewCode(ContractCodeView),
     ViewState(ViewStateResult),
+    ViewStateProof(ViewStateProofResult),
     CallResult(CallResult),
     AccessKey(AccessKeyView),
     AccessKeyList(AccessKeyList),
@@ -302,6 +419,15 @@ pub enum QueryRequest {
         #[serde(default, skip_serializing_if = "is_false")]
         include_proof: bool,
     },
+    /// Values of arbitrary trie keys with a combined Merkle proof. All the keys
+    /// must belong to accounts of the same shard and at most
+    /// `MAX_STATE_PROOF_KEYS` keys can be requested at once.  Absent keys have
+    /// `null` values.  Fails with `TOO_LARGE_STATE_PROOF` if the proof grows
+    /// past `trie_viewer_state_size_limit` bytes.  The proof can be checked
+    /// with `ViewStateProofResult::verify`.
+    ViewStateProof {
+        keys: Vec<StateProofKey>,
+    },
     ViewAccessKey {
         account_id: AccountId,
         public_key: PublicKey,
@@ -314,6 +440,11 @@ pub enum QueryRequest {
         method_name: String,
         #[serde(rename = "args_base64")]
         args: FunctionArgs,
+        /// Whether to return the gas used by the call broken down by WASM
+        /// instructions and host function costs, together with the numbers of
+        /// storage operations, in `gas_profile` of the result.
+        #[serde(default, skip_serializing_if = "is_false")]
+        include_gas_profile: bool,
     },
 }
 
@@ -1156,7 +1287,7 @@ impl BlockView {
     }
 }
 
-#[derive(serde::Serialize, serde::Deserialize, Debug)]
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
 pub struct ChunkView {
     pub author: AccountId,
     pub header: ChunkHeaderView,
@@ -2379,6 +2510,21 @@ pub enum StateChangeValueView {
     },
 }
 
+impl StateChangeValueView {
+    pub fn affected_account_id(&self) -> &AccountId {
+        match &self {
+            StateChangeValueView::AccountUpdate { account_id, .. }
+            | StateChangeValueView::AccountDeletion { account_id }
+            | StateChangeValueView::AccessKeyUpdate { account_id, .. }
+            | StateChangeValueView::AccessKeyDeletion { account_id, .. }
+            | StateChangeValueView::DataUpdate { account_id, .. }
+            | StateChangeValueView::DataDeletion { account_id, .. }
+            | StateChangeValueView::ContractCodeUpdate { account_id, .. }
+            | StateChangeValueView::ContractCodeDeletion { account_id } => account_id,
+        }
+    }
+}
+
 impl From<StateChangeValue> for StateChangeValueView {
     fn from(state_change: StateChangeValue) -> Self {
         match state_change {
@@ -2435,10 +2581,149 @@ pub struct SplitStorageInfoView {
     pub head_height: Option<BlockHeight>,
     pub final_head_height: Option<BlockHeight>,
     pub cold_head_height: Option<BlockHeight>,
+    /// Lowest height kept in cold storage if it's pruned.
+    #[serde(default)]
+    pub cold_tail_height: Option<BlockHeight>,
+    /// Lowest height whose data is available on the node, in hot or cold
+    /// storage.
+    #[serde(default)]
+    pub earliest_available_height: Option<BlockHeight>,
 
     pub hot_db_kind: Option<String>,
 }
 
+/// Contents of the transaction pools of a node, one entry per non-empty shard pool.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct TxPoolView {
+    pub shards: Vec<ShardTxPoolView>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ShardTxPoolView {
+    pub shard_uid: ShardUId,
+    /// Number of transactions in the pool.
+    pub transactions_count: u64,
+    /// Total size of the transactions in the pool in bytes.
+    pub transactions_size: u64,
+    /// Nonces of the transactions in the pool for each signer access key. If
+    /// the pool was requested for an account, limited to that signer.
+    pub signers: Vec<SignerPendingNoncesView>,
+    /// Hashes of the transactions in the pool signed by or sent to the requested account.
+    pub account_transactions: Vec<CryptoHash>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct SignerPendingNoncesView {
+    pub signer_id: AccountId,
+    pub public_key: PublicKey,
+    /// Sorted in increasing order.
+    pub nonces: Vec<Nonce>,
+}
+
+/// Difference between the states of a shard after two blocks.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct StateDiffView {
+    pub shard_id: ShardId,
+    pub from_block_hash: CryptoHash,
+    pub from_state_root: StateRoot,
+    pub to_block_hash: CryptoHash,
+    pub to_state_root: StateRoot,
+    /// Changed keys in increasing order.
+    pub changes: Vec<StateDiffItemView>,
+    /// Key of the first change not returned, set if there are more changes.
+    /// Passing it as `start_key` continues the diff from there.
+    pub next_key: Option<StoreKey>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct StateDiffItemView {
+    pub key: StoreKey,
+    /// Value at the first block, `None` if the key was added.
+    pub old_value: Option<StoreValue>,
+    /// Value at the second block, `None` if the key was removed.
+    pub new_value: Option<StoreValue>,
+}
+
+/// Result of executing a transaction and all the receipts it generates on top
+/// of the state after a block, without persisting any of the changes.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
+pub struct TransactionSimulationView {
+    /// Block whose post-state the transaction was executed on.
+    pub block_hash: CryptoHash,
+    pub block_height: BlockHeight,
+    /// Status of the transaction as it would be reported by `tx` once all the
+    /// executed receipts are included in the chain.  `Started` if the result
+    /// depends on pending receipts.
+    pub status: FinalExecutionStatus,
+    /// `None` if the transaction is invalid, in which case `status` holds the
+    /// error.
+    pub transaction_outcome: Option<ExecutionOutcomeWithIdView>,
+    /// The execution outcomes of receipts, in execution order.  The outcomes
+    /// have no proofs.
+    pub receipts_outcome: Vec<ExecutionOutcomeWithIdView>,
+    /// Receipts which were not executed because the limit of executed
+    /// receipts was reached.
+    pub pending_receipts: Vec<ReceiptView>,
+    pub state_changes: StateChangesView,
+    /// Balances of the accounts modified by the transaction.
+    pub balance_changes: Vec<AccountBalanceChangeView>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct AccountBalanceChangeView {
+    pub account_id: AccountId,
+    /// `None` if the account did not exist before the transaction.
+    #[serde(with = "dec_format")]
+    pub amount_before: Option<Balance>,
+    /// `None` if the account was deleted.
+    #[serde(with = "dec_format")]
+    pub amount_after: Option<Balance>,
+    #[serde(with = "dec_format")]
+    pub locked_before: Option<Balance>,
+    #[serde(with = "dec_format")]
+    pub locked_after: Option<Balance>,
+}
+
+/// Execution trace of a single function call recorded by a sandbox node.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct FunctionCallTraceView {
+    pub method_name: String,
+    /// Wasm frames active when the call trapped, innermost first.
+    pub call_stack: Vec<WasmFrameView>,
+    pub host_calls: Vec<HostFunctionCallView>,
+    pub storage_accesses: Vec<StorageAccessView>,
+    /// Whether some host calls or storage accesses were not recorded due to the trace size limit.
+    pub truncated: bool,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct WasmFrameView {
+    pub func_index: u32,
+    /// Function name from the contract's `name` section, if present.
+    pub name: Option<String>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct HostFunctionCallView {
+    pub name: String,
+    pub args: Vec<u64>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum StorageAccessKindView {
+    Read,
+    Write,
+    Remove,
+    HasKey,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct StorageAccessView {
+    pub kind: StorageAccessKindView,
+    pub key: StoreKey,
+}
+
 #[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
 pub struct CongestionInfoView {
     #[serde(with = "dec_format")]
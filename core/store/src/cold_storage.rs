@@ -1,5 +1,5 @@
 use crate::columns::DBKeyType;
-use crate::db::{ColdDB, COLD_HEAD_KEY, HEAD_KEY};
+use crate::db::{refcount, ColdDB, DBOp, COLD_HEAD_KEY, COLD_TAIL_KEY, HEAD_KEY};
 use crate::{metrics, DBCol, DBTransaction, Database, Store, TrieChanges};
 
 use borsh::BorshDeserialize;
@@ -43,6 +43,69 @@ pub trait ColdMigrationStore {
     ) -> io::Result<T>;
 }
 
+/// Which data cold storage keeps.  By default, it keeps all cold columns for
+/// the whole history of the chain.
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+#[serde(default)]
+pub struct ColdStoreRetentionPolicy {
+    /// Number of most recent epochs (in blocks, as a multiple of the epoch
+    /// length) whose data is kept in cold storage.  Data of older blocks is
+    /// removed by the pruner.  `None` keeps the whole history.
+    ///
+    /// Retention doesn't apply to the `State` column: trie nodes are shared
+    /// between blocks, so nodes of the pruned blocks are kept and the state
+    /// keeps growing with the whole history.  Leave it out of `columns` to
+    /// not keep it at all.
+    pub num_epochs: Option<u64>,
+    /// Cold columns which are copied to cold storage.  Data of other columns
+    /// is not copied and, if already present, removed by the pruner.  `None`
+    /// keeps all cold columns.
+    pub columns: Option<Vec<DBCol>>,
+}
+
+impl ColdStoreRetentionPolicy {
+    /// Returns whether the column is copied to cold storage.
+    pub fn keeps_column(&self, col: DBCol) -> bool {
+        col.is_cold() && self.columns.as_ref().map_or(true, |columns| columns.contains(&col))
+    }
+
+    /// Returns whether the pruner has anything to do.
+    pub fn is_pruning(&self) -> bool {
+        self.num_epochs.is_some() || self.columns.is_some()
+    }
+
+    /// Checks that the policy can be applied.  The pruner finds the keys to
+    /// remove from the block and chunks and the outcome ids at given height,
+    /// so these must be kept if the history is limited.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.num_epochs == Some(0) {
+            return Err("num_epochs must be positive".to_string());
+        }
+        for col in self.columns.iter().flatten() {
+            if !col.is_cold() {
+                return Err(format!("{col} is not a cold column"));
+            }
+        }
+        if self.num_epochs.is_some() {
+            for col in [DBCol::Block, DBCol::Chunks] {
+                if !self.keeps_column(col) {
+                    return Err(format!("{col} must be kept when num_epochs is set"));
+                }
+            }
+            if self.keeps_column(DBCol::TransactionResultForBlock)
+                && !self.keeps_column(DBCol::OutcomeIds)
+            {
+                return Err(format!(
+                    "{} must be kept together with {} when num_epochs is set",
+                    DBCol::OutcomeIds,
+                    DBCol::TransactionResultForBlock
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
 /// The BatchTransaction can be used to write multiple set operations to the cold db in batches.
 /// [`write`] is called every time `transaction_size` overgrows `threshold_transaction_size`.
 /// [`write`] should also be called manually before dropping BatchTransaction to write any leftovers.
@@ -74,12 +137,15 @@ struct BatchTransaction {
 /// 1. add it to `DBCol::is_cold` list
 /// 2. define `DBCol::key_type` for it (if it isn't already defined)
 /// 3. add new clause in `get_keys_from_store` for new key types used for this column (if there are any)
+///
+/// Only the columns kept by `retention` are copied.
 pub fn update_cold_db(
     cold_db: &ColdDB,
     hot_store: &Store,
     shard_layout: &ShardLayout,
     height: &BlockHeight,
     num_threads: usize,
+    retention: &ColdStoreRetentionPolicy,
 ) -> io::Result<bool> {
     let _span = tracing::debug_span!(target: "cold_store", "update cold db", height = height);
     let _timer = metrics::COLD_COPY_DURATION.start_timer();
@@ -94,7 +160,8 @@ pub fn update_cold_db(
 
     let key_type_to_keys =
         get_keys_from_store(&hot_store, shard_layout, &height_key, block_hash_key)?;
-    let cold_columns = DBCol::iter().filter(|col| col.is_cold()).collect::<Vec<DBCol>>();
+    let cold_columns =
+        DBCol::iter().filter(|col| retention.keeps_column(*col)).collect::<Vec<DBCol>>();
 
     // Create new thread pool with `num_threads`.
     rayon::ThreadPoolBuilder::new()
@@ -173,6 +240,28 @@ fn copy_state_from_store(
     let instant = std::time::Instant::now();
 
     let mut transaction = DBTransaction::new();
+    add_state_from_store(&mut transaction, shard_layout, block_hash_key, hot_store)?;
+
+    let read_duration = instant.elapsed();
+
+    let instant = std::time::Instant::now();
+    cold_db.write(transaction)?;
+    let write_duration = instant.elapsed();
+
+    tracing::trace!(target: "cold_store", ?read_duration, ?write_duration, "finished");
+
+    Ok(())
+}
+
+/// Adds the State nodes inserted at given block to `transaction`, see
+/// `copy_state_from_store`.  Returns the number of bytes added.
+fn add_state_from_store(
+    transaction: &mut DBTransaction,
+    shard_layout: &ShardLayout,
+    block_hash_key: &[u8],
+    hot_store: &Store,
+) -> io::Result<usize> {
+    let mut size = 0;
     for shard_uid in shard_layout.shard_uids() {
         debug_assert_eq!(
             DBCol::TrieChanges.key_type(),
@@ -190,19 +279,10 @@ fn copy_state_from_store(
             let value = op.payload().to_vec();
 
             tracing::trace!(target: "cold_store", pretty_key=?near_fmt::StorageKey(&key), "copying state node to colddb");
-            rc_aware_set(&mut transaction, DBCol::State, key, value);
+            size += rc_aware_set(transaction, DBCol::State, key, value);
         }
     }
-
-    let read_duration = instant.elapsed();
-
-    let instant = std::time::Instant::now();
-    cold_db.write(transaction)?;
-    let write_duration = instant.elapsed();
-
-    tracing::trace!(target: "cold_store", ?read_duration, ?write_duration, "finished");
-
-    Ok(())
+    Ok(size)
 }
 
 /// Gets values for given keys in a column from provided hot_store.
@@ -223,8 +303,31 @@ fn copy_from_store(
     let instant = std::time::Instant::now();
 
     let mut transaction = DBTransaction::new();
-    let mut good_keys = 0;
     let total_keys = keys.len();
+    let (good_keys, _) = add_from_store(&mut transaction, hot_store, col, keys)?;
+
+    let read_duration = instant.elapsed();
+
+    let instant = std::time::Instant::now();
+    cold_db.write(transaction)?;
+    let write_duration = instant.elapsed();
+
+    tracing::trace!(target: "cold_store", ?col, ?good_keys, ?total_keys, ?read_duration, ?write_duration, "finished");
+
+    return Ok(());
+}
+
+/// Adds values of given keys in a column of `hot_store` to `transaction`,
+/// see `copy_from_store`.  Returns the number of keys found and the number
+/// of bytes added.
+fn add_from_store(
+    transaction: &mut DBTransaction,
+    hot_store: &Store,
+    col: DBCol,
+    keys: Vec<StoreKey>,
+) -> io::Result<(usize, usize)> {
+    let mut good_keys = 0;
+    let mut size = 0;
     for key in keys {
         // TODO: Look into using RocksDB’s multi_key function.  It
         // might speed things up.  Currently our Database abstraction
@@ -240,19 +343,76 @@ fn copy_from_store(
             // re-adding the reference count.
 
             good_keys += 1;
-            rc_aware_set(&mut transaction, col, key, value);
+            size += rc_aware_set(transaction, col, key, value);
         }
     }
+    Ok((good_keys, size))
+}
 
-    let read_duration = instant.elapsed();
-
-    let instant = std::time::Instant::now();
-    cold_db.write(transaction)?;
-    let write_duration = instant.elapsed();
-
-    tracing::trace!(target: "cold_store", ?col, ?good_keys, ?total_keys, ?read_duration, ?write_duration, "finished");
+/// Adds the data of the block at `height` which `update_cold_db` would copy
+/// to cold storage to `transaction` instead of writing it right away.  This
+/// lets the caller batch updates of many heights in a single write.
+///
+/// Returns the number of bytes added or `None` if there is no block at
+/// `height` in `hot_store`.
+pub fn add_cold_db_update(
+    transaction: &mut DBTransaction,
+    hot_store: &Store,
+    shard_layout: &ShardLayout,
+    height: BlockHeight,
+    retention: &ColdStoreRetentionPolicy,
+) -> io::Result<Option<usize>> {
+    let height_key = height.to_le_bytes();
+    let Some(block_hash_key) = hot_store.get_for_cold(DBCol::BlockHeight, &height_key)? else {
+        return Ok(None);
+    };
+    let key_type_to_keys =
+        get_keys_from_store(hot_store, shard_layout, &height_key, &block_hash_key)?;
+    let mut size = 0;
+    for col in DBCol::iter().filter(|col| retention.keeps_column(*col)) {
+        size += if col == DBCol::State {
+            add_state_from_store(transaction, shard_layout, &block_hash_key, hot_store)?
+        } else {
+            let keys = combine_keys(&key_type_to_keys, &col.key_type());
+            add_from_store(transaction, hot_store, col, keys)?.1
+        };
+    }
+    Ok(Some(size))
+}
 
-    return Ok(());
+/// Checks that cold storage has the data of the block at `height` which
+/// `update_cold_db` copies there, with the same values as in `hot_store`.
+///
+/// Returns the number of checked keys or `None` if there is no block at
+/// `height` in `hot_store`.
+pub fn check_cold_db_update(
+    cold_store: &Store,
+    hot_store: &Store,
+    shard_layout: &ShardLayout,
+    height: BlockHeight,
+    retention: &ColdStoreRetentionPolicy,
+) -> io::Result<Option<usize>> {
+    let mut transaction = DBTransaction::new();
+    if add_cold_db_update(&mut transaction, hot_store, shard_layout, height, retention)?.is_none() {
+        return Ok(None);
+    }
+    for op in &transaction.ops {
+        let (col, key, expected) = match op {
+            DBOp::Set { col, key, value } => (*col, key, value.as_slice()),
+            DBOp::UpdateRefcount { col, key, value } => {
+                (*col, key, refcount::decode_value_with_rc(value).0.unwrap_or_default())
+            }
+            _ => unreachable!("cold storage update only sets values"),
+        };
+        let actual = cold_store.get(col, key)?;
+        if actual.as_deref() != Some(expected) {
+            return Err(io::Error::other(format!(
+                "{col} {:?} of height {height} differs between hot and cold storage",
+                near_fmt::StorageKey(key)
+            )));
+        }
+    }
+    Ok(Some(transaction.ops.len()))
 }
 
 /// This function sets the cold head to the Tip that reflect provided height in two places:
@@ -303,21 +463,108 @@ pub fn update_cold_head(
     return Ok(());
 }
 
+/// Sets the cold tail to provided height, in COLD_TAIL_KEY of the BlockMisc
+/// column of both cold and hot storage.
+/// This function should be used after data of all the blocks below `height`
+/// had been pruned from cold storage.
+pub fn update_cold_tail(
+    cold_db: &ColdDB,
+    hot_store: &Store,
+    height: BlockHeight,
+) -> io::Result<()> {
+    tracing::debug!(target: "cold_store", "update TAIL of cold db to {}", height);
+
+    let mut transaction = DBTransaction::new();
+    transaction.set(DBCol::BlockMisc, COLD_TAIL_KEY.to_vec(), borsh::to_vec(&height)?);
+    cold_db.write(transaction)?;
+
+    let mut transaction = DBTransaction::new();
+    transaction.set(DBCol::BlockMisc, COLD_TAIL_KEY.to_vec(), borsh::to_vec(&height)?);
+    hot_store.storage.write(transaction)?;
+
+    crate::metrics::COLD_TAIL_HEIGHT.set(height as i64);
+    Ok(())
+}
+
+/// Removes data of the block at `height` from cold storage.  The keys to
+/// remove are found the same way as when copying the block, reading the
+/// block and its chunks from cold storage itself.  The `State` column is left
+/// intact since its nodes may be referenced by later blocks.
+///
+/// Returns false if the block isn't in cold storage, either because there's
+/// no block at that height or because it's already been pruned.
+pub fn prune_cold_db(
+    cold_db: &ColdDB,
+    cold_store: &Store,
+    shard_layout: &ShardLayout,
+    height: BlockHeight,
+    block_hash: &CryptoHash,
+) -> io::Result<bool> {
+    let _span = tracing::debug_span!(target: "cold_store", "prune cold db", height);
+    if !cold_store.exists(DBCol::Block, block_hash.as_bytes())? {
+        return Ok(false);
+    }
+
+    // Chunks which aren't new in this block have been pruned together with
+    // the block they were included in.
+    let block: Block = cold_store.get_ser_or_err_for_cold(DBCol::Block, block_hash.as_bytes())?;
+    let chunks = block
+        .chunks()
+        .iter()
+        .filter(|chunk_header| chunk_header.height_included() == height)
+        .map(|chunk_header| {
+            cold_store.get_ser_or_err_for_cold(DBCol::Chunks, chunk_header.chunk_hash().as_bytes())
+        })
+        .collect::<io::Result<Vec<ShardChunk>>>()?;
+    let height_key = height.to_le_bytes();
+    let key_type_to_keys = get_keys_from_block(
+        cold_store,
+        shard_layout,
+        &height_key,
+        block_hash.as_bytes(),
+        &block,
+        &chunks,
+    )?;
+    let mut transaction = DBTransaction::new();
+    for col in DBCol::iter().filter(|col| col.is_cold() && *col != DBCol::State) {
+        for key in combine_keys(&key_type_to_keys, col.key_type()) {
+            transaction.delete(col, key);
+        }
+    }
+    cold_db.write_pruning(transaction)?;
+    Ok(true)
+}
+
+/// Removes all data of the columns which are not kept by `retention` from
+/// cold storage.
+pub fn prune_cold_columns(
+    cold_db: &ColdDB,
+    retention: &ColdStoreRetentionPolicy,
+) -> io::Result<()> {
+    let mut transaction = DBTransaction::new();
+    for col in DBCol::iter().filter(|col| col.is_cold() && !retention.keeps_column(*col)) {
+        tracing::info!(target: "cold_store", ?col, "Removing column not kept by the retention policy");
+        transaction.delete_all(col);
+    }
+    cold_db.write_pruning(transaction)
+}
+
 pub enum CopyAllDataToColdStatus {
     EverythingCopied,
     Interrupted,
 }
 
-/// Copies all contents of all cold columns from `hot_store` to `cold_db`.
+/// Copies all contents of all cold columns kept by `retention` from `hot_store` to `cold_db`.
 /// Does it column by column, and because columns can be huge, writes in batches of ~`batch_size`.
 pub fn copy_all_data_to_cold(
     cold_db: std::sync::Arc<ColdDB>,
     hot_store: &Store,
     batch_size: usize,
     keep_going: &std::sync::Arc<std::sync::atomic::AtomicBool>,
+    retention: &ColdStoreRetentionPolicy,
 ) -> io::Result<CopyAllDataToColdStatus> {
     for col in DBCol::iter() {
-        if col.is_cold() {
+        if retention.keeps_column(col) {
             tracing::info!(target: "cold_store", ?col, "Started column migration");
             let mut transaction = BatchTransaction::new(cold_db.clone(), batch_size);
             for result in hot_store.iter(col) {
@@ -380,8 +627,6 @@ fn get_keys_from_store(
     height_key: &[u8],
     block_hash_key: &[u8],
 ) -> io::Result<HashMap<DBKeyType, Vec<StoreKey>>> {
-    let mut key_type_to_keys = HashMap::new();
-
     let block: Block = store.get_ser_or_err_for_cold(DBCol::Block, &block_hash_key)?;
     let chunks = block
         .chunks()
@@ -390,6 +635,20 @@ fn get_keys_from_store(
             store.get_ser_or_err_for_cold(DBCol::Chunks, chunk_header.chunk_hash().as_bytes())
         })
         .collect::<io::Result<Vec<ShardChunk>>>()?;
+    get_keys_from_block(store, shard_layout, height_key, block_hash_key, &block, &chunks)
+}
+
+/// Same as `get_keys_from_store` but with the block and chunks at provided
+/// height already read.
+fn get_keys_from_block(
+    store: &Store,
+    shard_layout: &ShardLayout,
+    height_key: &[u8],
+    block_hash_key: &[u8],
+    block: &Block,
+    chunks: &[ShardChunk],
+) -> io::Result<HashMap<DBKeyType, Vec<StoreKey>>> {
+    let mut key_type_to_keys = HashMap::new();
 
     for key_type in DBKeyType::iter() {
         if key_type == DBKeyType::TrieNodeOrValueHash {
@@ -13,6 +13,14 @@ pub struct StoreConfig {
     /// database.
     pub path: Option<std::path::PathBuf>,
 
+    /// Database engine storing the data.  Changing it for an existing database
+    /// requires copying the data with `neard database migrate-backend`.
+    pub backend: StoreBackend,
+
+    /// Size of the page cache of the redb backend.  Ignored by RocksDB, which
+    /// is configured with the per column cache sizes.
+    pub redb_cache_size: bytesize::ByteSize,
+
     /// Collect internal storage layer statistics.
     /// Minor performance impact is expected.
     pub enable_statistics: bool,
@@ -44,6 +52,14 @@ pub struct StoreConfig {
     /// the performance of the storage
     pub block_size: bytesize::ByteSize,
 
+    /// Overrides of RocksDB options for individual columns, e.g. stronger
+    /// compression for `Transactions` on archival nodes or larger blocks for
+    /// `State`.  Options which are not set keep their defaults.  Changing
+    /// compression only affects newly written files; existing ones can be
+    /// rewritten with `neard database recompress`.
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    pub column_options: HashMap<DBCol, ColumnOptions>,
+
     /// Trie cache configuration per shard for normal (non-view) caches.
     pub trie_cache: TrieCacheConfig,
     /// Trie cache configuration per shard for view caches.
@@ -69,6 +85,11 @@ pub struct StoreConfig {
     pub load_mem_tries_for_shards: Vec<ShardUId>,
     /// If true, load mem trie for each shard being tracked; this has priority over `load_mem_tries_for_shards`.
     pub load_mem_tries_for_tracked_shards: bool,
+    /// Directory where in-memory tries are saved on shutdown and loaded from
+    /// on startup, which is much faster than loading them from flat storage.
+    /// A relative path is resolved against the home directory. `None`, the
+    /// default, disables the snapshots.
+    pub mem_trie_snapshot_path: Option<std::path::PathBuf>,
 
     /// Path where to create RocksDB checkpoints during database migrations or
     /// `false` to disable that feature.
@@ -97,6 +118,9 @@ pub struct StoreConfig {
     /// State Snapshot configuration
     pub state_snapshot_config: StateSnapshotConfig,
 
+    /// Configuration of the background check of flat storage against the trie.
+    pub flat_storage_scrubber: FlatStorageScrubberConfig,
+
     // TODO (#9989): To be phased out in favor of state_snapshot_config
     pub state_snapshot_enabled: bool,
 }
@@ -108,6 +132,75 @@ pub struct StateSnapshotConfig {
     pub state_snapshot_type: StateSnapshotType,
 }
 
+/// Config of the background thread which walks flat storage of every shard in
+/// small batches and compares its entries against the trie (and the memtrie
+/// if one is loaded), reporting mismatches via metrics and the debug page.
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct FlatStorageScrubberConfig {
+    /// Whether the scrubber runs at all.  Disabled by default.
+    pub enabled: bool,
+    /// Number of flat storage entries compared in a single batch.
+    pub batch_size: usize,
+    /// Upper bound on the number of bytes the scrubber reads from the database
+    /// per second.  The scrubber sleeps between batches to stay under it.
+    pub max_bytes_per_second: bytesize::ByteSize,
+    /// Time to wait after finishing a pass over a shard before starting the
+    /// next one.
+    #[serde(with = "near_time::serde_duration_as_std")]
+    pub pass_interval: near_time::Duration,
+}
+
+impl Default for FlatStorageScrubberConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            batch_size: 1000,
+            max_bytes_per_second: bytesize::ByteSize::mib(1),
+            pass_interval: near_time::Duration::hours(1),
+        }
+    }
+}
+
+/// RocksDB options of a single column.  `None` means the default is used.
+#[derive(Clone, Debug, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct ColumnOptions {
+    /// Compression of all levels except the bottommost one.  By default the
+    /// first two levels are not compressed and the rest use LZ4.
+    pub compression: Option<CompressionType>,
+    /// Compression of the bottommost level, which holds most of the data.
+    /// Zstd by default.
+    pub bottommost_compression: Option<CompressionType>,
+    /// Maximum size of a zstd dictionary trained on samples of each file and
+    /// used to compress all of its blocks, which helps with small similar
+    /// values like trie nodes or transactions.  Zero disables dictionaries.
+    /// By default only the bottommost level uses a 32 KiB dictionary.
+    pub zstd_dictionary_size: Option<bytesize::ByteSize>,
+    /// Bits per key of the bloom filter, 10 by default.  Zero disables the
+    /// filter, which saves memory for columns read mostly by iteration.
+    pub bloom_filter_bits_per_key: Option<f64>,
+    /// Size of data blocks, `StoreConfig::block_size` by default.
+    pub block_size: Option<bytesize::ByteSize>,
+    /// Size of the column's block cache, replacing the defaults from
+    /// `StoreConfig::col_cache_size`.
+    pub cache_size: Option<bytesize::ByteSize>,
+    /// Whether index and filter blocks are kept in the block cache, where they
+    /// compete with data blocks, rather than always held in memory.  True for
+    /// all columns but `FlatState` by default.
+    pub cache_index_and_filter_blocks: Option<bool>,
+}
+
+/// Compression algorithm of RocksDB files.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CompressionType {
+    None,
+    Snappy,
+    Lz4,
+    Zstd,
+}
+
 #[derive(Clone, Debug, Default, serde::Serialize, serde::Deserialize)]
 pub enum StateSnapshotType {
     /// Consider this as the default "disabled" option. We need to have snapshotting enabled for resharding
@@ -119,6 +212,33 @@ pub enum StateSnapshotType {
     EveryEpoch,
 }
 
+/// On-disk database engine.
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    Default,
+    PartialEq,
+    Eq,
+    serde::Serialize,
+    serde::Deserialize,
+    strum::Display,
+    strum::EnumString,
+)]
+#[strum(ascii_case_insensitive)]
+pub enum StoreBackend {
+    /// RocksDB, a log-structured merge-tree.  Best for write heavy workloads.
+    #[default]
+    RocksDb,
+    /// redb, a copy-on-write B-tree.  Reads don't have to go through multiple
+    /// levels of files, which may suit read heavy RPC nodes better.
+    ///
+    /// Experimental.  The database can't be opened by another process while
+    /// the node is running and checkpoints, including the state snapshots
+    /// taken every epoch, copy the data instead of hard linking files.
+    Redb,
+}
+
 #[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
 #[serde(untagged)]
 pub enum MigrationSnapshot {
@@ -175,8 +295,16 @@ impl StoreConfig {
         Self { max_open_files: 512, ..Self::default() }
     }
 
+    /// Returns option overrides for given column.
+    pub fn col_options(&self, col: DBCol) -> ColumnOptions {
+        self.column_options.get(&col).cloned().unwrap_or_default()
+    }
+
     /// Returns cache size for given column.
-    pub const fn col_cache_size(&self, col: DBCol) -> bytesize::ByteSize {
+    pub fn col_cache_size(&self, col: DBCol) -> bytesize::ByteSize {
+        if let Some(cache_size) = self.column_options.get(&col).and_then(|opts| opts.cache_size) {
+            return cache_size;
+        }
         match col {
             DBCol::State => self.col_state_cache_size,
             DBCol::FlatState => self.col_flat_state_cache_size,
@@ -189,6 +317,8 @@ impl Default for StoreConfig {
     fn default() -> Self {
         Self {
             path: None,
+            backend: StoreBackend::RocksDb,
+            redb_cache_size: bytesize::ByteSize::gib(1),
             enable_statistics: false,
             enable_statistics_export: true,
 
@@ -218,6 +348,8 @@ impl Default for StoreConfig {
             // we use it since then.
             block_size: bytesize::ByteSize::kib(16),
 
+            column_options: HashMap::new(),
+
             trie_cache: TrieCacheConfig {
                 default_max_bytes: bytesize::ByteSize::mb(500),
                 // TODO(resharding) The cache size needs to adjusted for every resharding.
@@ -273,11 +405,14 @@ impl Default for StoreConfig {
             // requires more RAM and takes several minutes on startup.
             load_mem_tries_for_shards: Default::default(),
             load_mem_tries_for_tracked_shards: false,
+            mem_trie_snapshot_path: None,
 
             migration_snapshot: Default::default(),
 
             state_snapshot_config: Default::default(),
 
+            flat_storage_scrubber: Default::default(),
+
             // TODO: To be phased out in favor of state_snapshot_config
             state_snapshot_enabled: false,
         }
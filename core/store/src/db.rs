@@ -6,6 +6,7 @@ pub(crate) mod rocksdb;
 
 mod colddb;
 mod mixeddb;
+mod redb;
 mod splitdb;
 
 pub mod refcount;
@@ -16,6 +17,8 @@ mod database_tests;
 
 pub use self::colddb::ColdDB;
 pub use self::mixeddb::{MixedDB, ReadOrder};
+pub use self::redb::RedbDB;
+pub use self::rocksdb::backup::{BackupFile, BackupFiles, SharedBackupFile};
 pub use self::rocksdb::RocksDB;
 pub use self::splitdb::SplitDB;
 
@@ -36,6 +39,8 @@ pub const GENESIS_JSON_HASH_KEY: &[u8; 17] = b"GENESIS_JSON_HASH";
 pub const GENESIS_STATE_ROOTS_KEY: &[u8; 19] = b"GENESIS_STATE_ROOTS";
 pub const GENESIS_CONGESTION_INFO_KEY: &[u8] = b"GENESIS_CONGESTION_INFO_KEY";
 pub const COLD_HEAD_KEY: &[u8; 9] = b"COLD_HEAD";
+/// Lowest height whose data is kept in cold storage when it's pruned.
+pub const COLD_TAIL_KEY: &[u8; 9] = b"COLD_TAIL";
 pub const STATE_SYNC_DUMP_KEY: &[u8; 15] = b"STATE_SYNC_DUMP";
 pub const STATE_SNAPSHOT_KEY: &[u8; 18] = b"STATE_SNAPSHOT_KEY";
 
@@ -44,6 +49,11 @@ pub const FLAT_STATE_VALUES_INLINING_MIGRATION_STATUS_KEY: &[u8] =
     b"FLAT_STATE_VALUES_INLINING_MIGRATION_STATUS";
 pub const STATE_TRANSITION_START_HEIGHTS: &[u8] = b"STATE_TRANSITION_START_HEIGHTS";
 pub const LATEST_WITNESSES_INFO: &[u8] = b"LATEST_WITNESSES_INFO";
+/// Progress of a cold storage backfill made by the cold-store tool.
+pub const COLD_STORE_BACKFILL_PROGRESS_KEY: &[u8] = b"COLD_STORE_BACKFILL_PROGRESS";
+/// Prefix of the per-shard progress of the flat storage scrubber.  The full
+/// key is the prefix followed by the shard UId.
+pub const FLAT_STORAGE_SCRUBBER_STATUS_KEY: &[u8] = b"FLAT_STORAGE_SCRUBBER_STATUS";
 
 #[derive(Default, Debug)]
 pub struct DBTransaction {
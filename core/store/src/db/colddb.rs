@@ -10,9 +10,10 @@ use crate::DBCol;
 /// reference counted columns the rc is always set to 1. This struct handles
 /// setting the rc to one transparently to the user.
 ///
-/// Lastly, since no data is ever deleted from cold storage, trying to decrease
-/// reference of a value count or delete data is ignored and if debug assertions
-/// are enabled will cause a panic.
+/// Lastly, since data is only deleted from cold storage by the pruner (see
+/// [`Self::write_pruning`]), trying to decrease reference of a value count or
+/// delete data is ignored and if debug assertions are enabled will cause
+/// a panic.
 pub struct ColdDB {
     cold: std::sync::Arc<dyn Database>,
 }
@@ -22,6 +23,19 @@ impl ColdDB {
         Self { cold }
     }
 
+    /// Writes a transaction of the cold storage pruner.  Unlike
+    /// [`Database::write`], it only accepts deletions of cold columns.
+    pub fn write_pruning(&self, transaction: DBTransaction) -> std::io::Result<()> {
+        for op in &transaction.ops {
+            let is_deletion = matches!(op, DBOp::Delete { .. } | DBOp::DeleteAll { .. });
+            if !is_deletion || !op.col().is_cold() {
+                let msg = format!("unexpected operation on {} when pruning cold store", op.col());
+                return Err(std::io::Error::other(msg));
+            }
+        }
+        self.cold.write(transaction)
+    }
+
     fn err_msg(col: DBCol) -> String {
         format!("Reading from column missing from cold storage. {col:?}")
     }
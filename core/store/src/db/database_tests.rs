@@ -6,21 +6,23 @@ mod tests {
     use std::sync::Arc;
 
     use crate::{
-        db::{DBTransaction, Database, TestDB},
-        DBCol, NodeStorage,
+        db::{refcount, DBTransaction, Database, RedbDB, TestDB},
+        DBCol, Mode, NodeStorage, StoreConfig,
     };
 
-    // Returns test & rocksDB databases.
-    fn test_and_rocksdb() -> Vec<Arc<dyn Database>> {
+    // Returns test, rocksDB & redb databases.
+    fn test_databases() -> Vec<Arc<dyn Database>> {
         let (_tmp_dir, opener) = NodeStorage::test_opener();
         let store = opener.open().unwrap().get_hot_store();
-        vec![TestDB::new(), store.storage.clone()]
+        let redb_dir = tempfile::tempdir().unwrap();
+        let redb = RedbDB::open(redb_dir.path(), &StoreConfig::test_config(), Mode::Create);
+        vec![TestDB::new(), store.storage.clone(), Arc::new(redb.unwrap())]
     }
 
     /// Tests the behavior of the iterators. Iterators don't really work over cold storage, so we're not testing it here.
     #[test]
     fn test_db_iter() {
-        for db in test_and_rocksdb() {
+        for db in test_databases() {
             let mut transaction = DBTransaction::new();
             transaction.insert(DBCol::Block, "a".into(), "val_a".into());
             transaction.insert(DBCol::Block, "aa".into(), "val_aa".into());
@@ -42,4 +44,48 @@ mod tests {
             assert_eq!(keys, vec!["aa", "aa1"]);
         }
     }
+
+    #[test]
+    fn test_db_refcount() {
+        let one = std::num::NonZeroU32::new(1).unwrap();
+        for db in test_databases() {
+            let mut transaction = DBTransaction::new();
+            transaction.update_refcount(
+                DBCol::State,
+                "a".into(),
+                refcount::add_positive_refcount(b"val_a", one),
+            );
+            transaction.update_refcount(
+                DBCol::State,
+                "a".into(),
+                refcount::add_positive_refcount(b"val_a", one),
+            );
+            transaction.update_refcount(
+                DBCol::State,
+                "b".into(),
+                refcount::add_positive_refcount(b"val_b", one),
+            );
+            db.write(transaction).unwrap();
+            assert_eq!(
+                db.get_with_rc_stripped(DBCol::State, b"a").unwrap().as_deref(),
+                Some(&b"val_a"[..])
+            );
+
+            let mut transaction = DBTransaction::new();
+            transaction.update_refcount(
+                DBCol::State,
+                "a".into(),
+                refcount::encode_negative_refcount(one).to_vec(),
+            );
+            transaction.update_refcount(
+                DBCol::State,
+                "b".into(),
+                refcount::encode_negative_refcount(one).to_vec(),
+            );
+            db.write(transaction).unwrap();
+            let items: Vec<_> = db.iter(DBCol::State).map(Result::unwrap).collect();
+            assert_eq!(items, vec![(b"a"[..].into(), b"val_a"[..].into())]);
+            assert_eq!(db.get_with_rc_stripped(DBCol::State, b"b").unwrap().as_deref(), None);
+        }
+    }
 }
@@ -0,0 +1,315 @@
+use crate::config::Mode;
+use crate::db::rocksdb::snapshot::{Snapshot, SnapshotError};
+use crate::db::{refcount, DBIterator, DBIteratorItem, DBOp, DBSlice, DBTransaction, Database};
+use crate::{metadata, DBCol, StoreConfig, StoreStatistics};
+use ::redb::{Durability, ReadOnlyTable, ReadableTable, TableDefinition, TableError};
+use std::collections::hash_map::{Entry, HashMap};
+use std::io;
+use std::ops::Bound;
+use std::path::Path;
+use strum::IntoEnumIterator;
+
+/// Name of the database file inside of the database directory.
+const DB_FILE: &str = "data.redb";
+
+/// Number of bytes after which copying data between databases commits the
+/// write transaction and starts a new one.
+const COPY_BATCH_BYTES: usize = 256 * 1024 * 1024;
+
+type Table = TableDefinition<'static, &'static [u8], &'static [u8]>;
+
+/// Each column is stored in a separate table named after it.
+fn table(col: DBCol) -> Table {
+    TableDefinition::new(col.into())
+}
+
+/// Database backed by redb, an embedded copy-on-write B-tree storage.
+///
+/// Unlike RocksDB, redb has no merge operator, so updates of reference
+/// counted columns are merged with the stored value when the transaction is
+/// written, using the same logic as RocksDB uses during compaction.  Keys
+/// whose refcount reaches zero are removed right away.
+///
+/// Transactions are committed with eventual durability which, similarly to
+/// RocksDB without WAL syncing, may lose the most recent writes on a crash
+/// but never leaves the database in an inconsistent state.  [`Self::flush`]
+/// makes everything written so far durable.
+///
+/// It's not a drop-in replacement for RocksDB:
+/// - The database file is locked by the process which opened it, even in
+///   read-only mode, so tools like `view-state` or `neard database` can't
+///   open the database of a running node.
+/// - Checkpoints copy all the data, see [`Database::create_checkpoint`].
+///   State snapshots, which are checkpoints taken every epoch, therefore
+///   duplicate the whole `State` column each time.
+pub struct RedbDB {
+    db: ::redb::Database,
+    read_only: bool,
+}
+
+impl RedbDB {
+    /// Opens the database.
+    ///
+    /// `path` is the directory of the database, resolved from the store
+    /// configuration the same way as for RocksDB.  The directory is created
+    /// if it's missing and `mode` allows creating the database.
+    pub fn open(path: &Path, store_config: &StoreConfig, mode: Mode) -> io::Result<Self> {
+        let file = path.join(DB_FILE);
+        let mut builder = ::redb::Builder::new();
+        builder
+            .set_cache_size(store_config.redb_cache_size.as_u64().try_into().unwrap_or(usize::MAX));
+        let db = if file.is_file() {
+            builder.open(&file)
+        } else if mode.can_create() {
+            std::fs::create_dir_all(path)?;
+            builder.create(&file)
+        } else {
+            let msg = format!("{}: database does not exist", path.display());
+            return Err(io::Error::new(io::ErrorKind::NotFound, msg));
+        }
+        .map_err(io::Error::other)?;
+        Ok(Self { db, read_only: mode.read_only() })
+    }
+
+    /// Returns whether there is a redb database at given path.
+    pub fn exists(path: &Path) -> bool {
+        path.join(DB_FILE).is_file()
+    }
+
+    /// Returns version and kind of the database at given path or `None` if
+    /// it doesn't exist.  See [`crate::db::RocksDB::get_metadata`].
+    pub(crate) fn get_metadata(
+        path: &Path,
+        config: &StoreConfig,
+    ) -> io::Result<Option<metadata::DbMetadata>> {
+        if !Self::exists(path) {
+            return Ok(None);
+        }
+        let db = Self::open(path, config, Mode::ReadOnly)?;
+        Some(metadata::DbMetadata::read(&db)).transpose()
+    }
+
+    /// Creates the migration snapshot of the database at given path, if the
+    /// snapshots are enabled.  See [`Snapshot::new`].
+    pub(crate) fn snapshot(path: &Path, config: &StoreConfig) -> Result<Snapshot, SnapshotError> {
+        let snapshot_path = match config.migration_snapshot.get_path(path) {
+            Some(snapshot_path) => snapshot_path,
+            None => return Ok(Snapshot::none()),
+        };
+
+        tracing::info!(target: "db", snapshot_path=%snapshot_path.display(),
+                       "Creating database snapshot");
+        if snapshot_path.exists() {
+            return Err(SnapshotError::AlreadyExists(snapshot_path));
+        }
+        let db = Self::open(path, config, Mode::ReadWriteExisting)?;
+        db.create_checkpoint(&snapshot_path, None).map_err(io::Error::other)?;
+        Ok(Snapshot(Some(snapshot_path)))
+    }
+
+    /// Opens the table of given column for reading.  Returns `None` if
+    /// nothing has been written to the column yet.
+    fn read_table(
+        &self,
+        col: DBCol,
+    ) -> io::Result<Option<ReadOnlyTable<&'static [u8], &'static [u8]>>> {
+        let txn = self.db.begin_read().map_err(io::Error::other)?;
+        match txn.open_table(table(col)) {
+            Ok(table) => Ok(Some(table)),
+            Err(TableError::TableDoesNotExist(_)) => Ok(None),
+            Err(err) => Err(io::Error::other(err)),
+        }
+    }
+
+    /// Iterates over raw items of the column with keys in given range.
+    fn iter_raw_range<'a>(
+        &'a self,
+        col: DBCol,
+        lower_bound: Bound<&[u8]>,
+        upper_bound: Bound<&[u8]>,
+    ) -> DBIterator<'a> {
+        let range = self.read_table(col).and_then(|table| {
+            table
+                .map(|table| table.range::<&[u8]>((lower_bound, upper_bound)))
+                .transpose()
+                .map_err(io::Error::other)
+        });
+        match range {
+            Ok(Some(range)) => Box::new(range.map(|item| -> DBIteratorItem {
+                let (key, value) = item.map_err(io::Error::other)?;
+                Ok((key.value().into(), value.value().into()))
+            })),
+            Ok(None) => Box::new(std::iter::empty()),
+            Err(err) => Box::new(std::iter::once(Err(err))),
+        }
+    }
+}
+
+impl Database for RedbDB {
+    fn get_raw_bytes(&self, col: DBCol, key: &[u8]) -> io::Result<Option<DBSlice<'_>>> {
+        let Some(table) = self.read_table(col)? else { return Ok(None) };
+        let value = table.get(key).map_err(io::Error::other)?;
+        Ok(value.map(|value| DBSlice::from_vec(value.value().to_vec())))
+    }
+
+    fn iter<'a>(&'a self, col: DBCol) -> DBIterator<'a> {
+        refcount::iter_with_rc_logic(col, self.iter_raw_bytes(col))
+    }
+
+    fn iter_prefix<'a>(&'a self, col: DBCol, key_prefix: &'a [u8]) -> DBIterator<'a> {
+        let iterator = self
+            .iter_raw_range(col, Bound::Included(key_prefix), Bound::Unbounded)
+            .take_while(move |item| match item {
+                Ok((key, _)) => key.starts_with(key_prefix),
+                Err(_) => true,
+            });
+        refcount::iter_with_rc_logic(col, iterator)
+    }
+
+    fn iter_range<'a>(
+        &'a self,
+        col: DBCol,
+        lower_bound: Option<&[u8]>,
+        upper_bound: Option<&[u8]>,
+    ) -> DBIterator<'a> {
+        let lower_bound = lower_bound.map_or(Bound::Unbounded, Bound::Included);
+        let upper_bound = upper_bound.map_or(Bound::Unbounded, Bound::Excluded);
+        let iterator = self.iter_raw_range(col, lower_bound, upper_bound);
+        refcount::iter_with_rc_logic(col, iterator)
+    }
+
+    fn iter_raw_bytes<'a>(&'a self, col: DBCol) -> DBIterator<'a> {
+        self.iter_raw_range(col, Bound::Unbounded, Bound::Unbounded)
+    }
+
+    fn write(&self, transaction: DBTransaction) -> io::Result<()> {
+        if self.read_only {
+            return Err(io::Error::other("cannot write to a database opened in read-only mode"));
+        }
+        let mut txn = self.db.begin_write().map_err(io::Error::other)?;
+        txn.set_durability(Durability::Eventual);
+        {
+            let mut tables = HashMap::new();
+            for op in transaction.ops {
+                let table = match tables.entry(op.col()) {
+                    Entry::Occupied(entry) => entry.into_mut(),
+                    Entry::Vacant(entry) => {
+                        entry.insert(txn.open_table(table(op.col())).map_err(io::Error::other)?)
+                    }
+                };
+                match op {
+                    DBOp::Set { key, value, .. } => {
+                        table.insert(key.as_slice(), value.as_slice()).map_err(io::Error::other)?;
+                    }
+                    DBOp::Insert { col, key, value } => {
+                        if cfg!(debug_assertions) {
+                            if let Some(old_value) =
+                                table.get(key.as_slice()).map_err(io::Error::other)?
+                            {
+                                super::assert_no_overwrite(col, &key, &value, old_value.value())
+                            }
+                        }
+                        table.insert(key.as_slice(), value.as_slice()).map_err(io::Error::other)?;
+                    }
+                    DBOp::UpdateRefcount { key, value, .. } => {
+                        let existing = table
+                            .get(key.as_slice())
+                            .map_err(io::Error::other)?
+                            .map(|existing| existing.value().to_vec());
+                        let merged = refcount::refcount_merge(existing.as_deref(), [&value[..]]);
+                        if merged.is_empty() {
+                            table.remove(key.as_slice()).map_err(io::Error::other)?;
+                        } else {
+                            table
+                                .insert(key.as_slice(), merged.as_slice())
+                                .map_err(io::Error::other)?;
+                        }
+                    }
+                    DBOp::Delete { key, .. } => {
+                        table.remove(key.as_slice()).map_err(io::Error::other)?;
+                    }
+                    DBOp::DeleteAll { .. } => {
+                        table.retain(|_, _| false).map_err(io::Error::other)?;
+                    }
+                    DBOp::DeleteRange { from, to, .. } => {
+                        table
+                            .retain_in::<&[u8], _>(from.as_slice()..to.as_slice(), |_, _| false)
+                            .map_err(io::Error::other)?;
+                    }
+                }
+            }
+        }
+        txn.commit().map_err(io::Error::other)
+    }
+
+    fn flush(&self) -> io::Result<()> {
+        if self.read_only {
+            return Ok(());
+        }
+        // An empty transaction committed with immediate durability persists
+        // all the preceding eventually durable ones.
+        let mut txn = self.db.begin_write().map_err(io::Error::other)?;
+        txn.set_durability(Durability::Immediate);
+        txn.commit().map_err(io::Error::other)
+    }
+
+    /// redb reuses the pages freed by deletions, so there's nothing to do.
+    /// Shrinking the file would require exclusive access to the database.
+    fn compact(&self) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn get_store_statistics(&self) -> Option<StoreStatistics> {
+        None
+    }
+
+    /// redb has no checkpoints, so the data is copied to a new database at
+    /// `path` instead.  The copy is taken from a single read transaction and
+    /// is therefore consistent, but unlike hard linked RocksDB files it takes
+    /// as much disk space and time as writing the copied columns anew.
+    fn create_checkpoint(
+        &self,
+        path: &Path,
+        columns_to_keep: Option<&[DBCol]>,
+    ) -> anyhow::Result<()> {
+        anyhow::ensure!(!Self::exists(path), "{}: database already exists", path.display());
+        std::fs::create_dir_all(path)?;
+        let checkpoint = ::redb::Database::create(path.join(DB_FILE))?;
+        let read_txn = self.db.begin_read()?;
+        let mut write_txn = checkpoint.begin_write()?;
+        let mut batch_bytes = 0;
+        for col in DBCol::iter() {
+            if columns_to_keep.is_some_and(|columns| !columns.contains(&col)) {
+                continue;
+            }
+            let source = match read_txn.open_table(table(col)) {
+                Ok(source) => source,
+                Err(TableError::TableDoesNotExist(_)) => continue,
+                Err(err) => return Err(err.into()),
+            };
+            let mut items = source.iter()?;
+            loop {
+                let mut target = write_txn.open_table(table(col))?;
+                let mut batch_full = false;
+                for item in items.by_ref() {
+                    let (key, value) = item?;
+                    target.insert(key.value(), value.value())?;
+                    batch_bytes += key.value().len() + value.value().len();
+                    if batch_bytes >= COPY_BATCH_BYTES {
+                        batch_full = true;
+                        break;
+                    }
+                }
+                drop(target);
+                if !batch_full {
+                    break;
+                }
+                write_txn.commit()?;
+                write_txn = checkpoint.begin_write()?;
+                batch_bytes = 0;
+            }
+        }
+        write_txn.commit()?;
+        Ok(())
+    }
+}
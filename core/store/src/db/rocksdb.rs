@@ -1,4 +1,4 @@
-use crate::config::Mode;
+use crate::config::{ColumnOptions, CompressionType, Mode};
 use crate::db::{refcount, DBIterator, DBOp, DBSlice, DBTransaction, Database, StatsValue};
 use crate::{metadata, metrics, DBCol, StoreConfig, StoreStatistics, Temperature};
 use ::rocksdb::{
@@ -13,6 +13,7 @@ use std::path::Path;
 use strum::IntoEnumIterator;
 use tracing::warn;
 
+pub(crate) mod backup;
 mod instance_tracker;
 pub(crate) mod snapshot;
 
@@ -298,6 +299,30 @@ impl RocksDB {
         Ok(())
     }
 
+    /// Rewrites all files of the column, including the bottommost level which
+    /// regular compaction leaves alone, so that they use the current
+    /// compression and table options.
+    pub fn recompress_column(&self, col: DBCol) -> io::Result<()> {
+        let none = Option::<&[u8]>::None;
+        tracing::info!(target: "store::db::rocksdb", col = %col, "RocksDB::recompress_column");
+        let mut compact_options = rocksdb::CompactOptions::default();
+        compact_options.set_bottommost_level_compaction(rocksdb::BottommostLevelCompaction::Force);
+        self.db.compact_range_cf_opt(self.cf_handle(col)?, none, none, &compact_options);
+        Ok(())
+    }
+
+    /// Returns total size of the column's SST files on disk.
+    pub fn column_sst_size(&self, col: DBCol) -> io::Result<u64> {
+        let size = self
+            .db
+            .property_int_value_cf(
+                self.cf_handle(col)?,
+                ::rocksdb::properties::TOTAL_SST_FILES_SIZE,
+            )
+            .map_err(io::Error::other)?;
+        Ok(size.unwrap_or(0))
+    }
+
     #[tracing::instrument(
         target = "store::db::rocksdb",
         level = "trace",
@@ -574,20 +599,28 @@ fn use_block_cache_for_index_and_filter_blocks(db_col: DBCol) -> bool {
 }
 
 fn rocksdb_block_based_options(store_config: &StoreConfig, db_col: DBCol) -> BlockBasedOptions {
+    let col_options = store_config.col_options(db_col);
     let cache_size = store_config.col_cache_size(db_col);
+    let block_size = col_options.block_size.unwrap_or(store_config.block_size);
 
     let mut block_opts = BlockBasedOptions::default();
-    block_opts.set_block_size(store_config.block_size.as_u64().try_into().unwrap());
+    block_opts.set_block_size(block_size.as_u64().try_into().unwrap());
     // We create block_cache for each of the columns, so the total cache size is (num_of_columns - 2) * 32MiB
     // Plus the 128MiB from FlatState and 512MiB from State columns
     block_opts.set_block_cache(&Cache::new_lru_cache(cache_size.as_u64().try_into().unwrap()));
-    if use_block_cache_for_index_and_filter_blocks(db_col) {
+    if col_options
+        .cache_index_and_filter_blocks
+        .unwrap_or_else(|| use_block_cache_for_index_and_filter_blocks(db_col))
+    {
         block_opts.set_pin_l0_filter_and_index_blocks_in_cache(true);
         block_opts.set_cache_index_and_filter_blocks(true);
     } else {
         block_opts.set_cache_index_and_filter_blocks(false);
     }
-    block_opts.set_bloom_filter(10.0, true);
+    let bloom_filter_bits_per_key = col_options.bloom_filter_bits_per_key.unwrap_or(10.0);
+    if bloom_filter_bits_per_key > 0.0 {
+        block_opts.set_bloom_filter(bloom_filter_bits_per_key, true);
+    }
 
     block_opts
 }
@@ -612,6 +645,8 @@ fn rocksdb_column_options(col: DBCol, store_config: &StoreConfig, temp: Temperat
     //      https://github.com/facebook/rocksdb/blob/c18c4a081c74251798ad2a1abf83bad417518481/options/options.cc#L588.
     let memtable_memory_budget = 128 * bytesize::MIB as usize;
     opts.optimize_level_style_compaction(memtable_memory_budget);
+    // Must come after the above since it overrides compression_per_level.
+    set_column_compression_options(&mut opts, &store_config.col_options(col));
 
     opts.set_target_file_size_base(64 * bytesize::MIB);
     if temp == Temperature::Hot && col.is_rc() {
@@ -621,6 +656,36 @@ fn rocksdb_column_options(col: DBCol, store_config: &StoreConfig, temp: Temperat
     opts
 }
 
+fn compression_type(compression: CompressionType) -> rocksdb::DBCompressionType {
+    match compression {
+        CompressionType::None => rocksdb::DBCompressionType::None,
+        CompressionType::Snappy => rocksdb::DBCompressionType::Snappy,
+        CompressionType::Lz4 => rocksdb::DBCompressionType::Lz4,
+        CompressionType::Zstd => rocksdb::DBCompressionType::Zstd,
+    }
+}
+
+/// Applies compression overrides from the column's config on top of the
+/// defaults set by [`set_compression_options`].
+fn set_column_compression_options(opts: &mut Options, col_options: &ColumnOptions) {
+    if let Some(compression) = col_options.compression {
+        // Number of levels RocksDB uses by default.
+        opts.set_compression_per_level(&[compression_type(compression); 7]);
+    }
+    if let Some(compression) = col_options.bottommost_compression {
+        opts.set_bottommost_compression_type(compression_type(compression));
+    }
+    if let Some(dict_size) = col_options.zstd_dictionary_size {
+        let dict_size: i32 = dict_size.as_u64().try_into().unwrap();
+        let max_train_bytes = dict_size.saturating_mul(100);
+        let enabled = dict_size > 0;
+        opts.set_compression_options(-14, 32767, 0, dict_size);
+        opts.set_zstd_max_train_bytes(max_train_bytes);
+        opts.set_bottommost_compression_options(-14, 32767, 0, dict_size, enabled);
+        opts.set_bottommost_zstd_max_train_bytes(max_train_bytes, enabled);
+    }
+}
+
 fn set_compression_options(opts: &mut Options) {
     opts.set_compression_type(rocksdb::DBCompressionType::Lz4);
     opts.set_bottommost_compression_type(rocksdb::DBCompressionType::Zstd);
@@ -648,12 +713,17 @@ impl RocksDB {
         instance_tracker::block_until_all_instances_are_closed();
     }
 
+    /// Returns whether there is a RocksDB database at given path.
+    pub fn exists(path: &Path) -> bool {
+        path.join("CURRENT").is_file()
+    }
+
     /// Returns metadata of the database or `None` if the db doesn’t exist.
     pub(crate) fn get_metadata(
         path: &Path,
         config: &StoreConfig,
     ) -> io::Result<Option<metadata::DbMetadata>> {
-        if !path.join("CURRENT").is_file() {
+        if !Self::exists(path) {
             return Ok(None);
         }
         // Specify only DBCol::DbVersion.  It’s ok to open db in read-only mode
@@ -898,6 +968,39 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_column_options() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let options = crate::ColumnOptions {
+            compression: Some(CompressionType::Zstd),
+            bottommost_compression: Some(CompressionType::Zstd),
+            zstd_dictionary_size: Some(bytesize::ByteSize::kib(4)),
+            bloom_filter_bits_per_key: Some(0.0),
+            block_size: Some(bytesize::ByteSize::kib(4)),
+            cache_size: Some(bytesize::ByteSize::mib(1)),
+            cache_index_and_filter_blocks: Some(false),
+        };
+        let config = StoreConfig {
+            column_options: [(DBCol::Block, options)].into_iter().collect(),
+            ..StoreConfig::test_config()
+        };
+        let db = RocksDB::open(tmp_dir.path(), &config, Mode::ReadWrite, Temperature::Hot).unwrap();
+        let mut transaction = DBTransaction::new();
+        for i in 0..1000u32 {
+            transaction.insert(DBCol::Block, i.to_be_bytes().to_vec(), vec![i as u8; 100]);
+        }
+        db.write(transaction).unwrap();
+        db.flush().unwrap();
+        if !cfg!(feature = "single_thread_rocksdb") {
+            db.recompress_column(DBCol::Block).unwrap();
+        }
+        assert!(db.column_sst_size(DBCol::Block).unwrap() > 0);
+        for i in 0..1000u32 {
+            let value = db.get_raw_bytes(DBCol::Block, &i.to_be_bytes()).unwrap();
+            assert_eq!(value.as_deref(), Some(&[i as u8; 100][..]));
+        }
+    }
+
     #[test]
     fn test_delete_range() {
         let store = NodeStorage::test_opener().1.open().unwrap().get_hot_store();
@@ -0,0 +1,389 @@
+//! Incremental file level backups of RocksDB databases.
+//!
+//! RocksDB never modifies SST files once they are written, so an SST file
+//! which is already in a backup doesn’t need to be copied again.  SST files
+//! are therefore kept in a directory shared by all the backups and each
+//! backup only stores the remaining, small files (`CURRENT`, `MANIFEST-*`,
+//! `OPTIONS-*`, the WAL) in its own directory.
+//!
+//! File numbers are only unique within a single database and databases
+//! restored from the same backup or bootstrapped from the same snapshot keep
+//! writing files with the same numbers, so like RocksDB’s BackupEngine shared
+//! files are named `<number>_s<session_id>_<size>.sst`, where the session id,
+//! read from the table properties of the file, identifies the database session
+//! which wrote it.  Only files without readable properties are named
+//! `<number>_<crc32>_<size>.sst` instead, which requires reading them whole.
+//! A shared file is never removed or overwritten while a backup refers to it.
+//!
+//! The files are taken while the database is opened in read-only mode which
+//! doesn’t lock it, so backups can be made while the node is running.  The
+//! node may however delete an SST file during compaction or append to the
+//! `MANIFEST` and WAL while they are being copied, so the result must be
+//! verified by opening it (see [`RocksDB::restore_backup`]) and the backup
+//! retried if that fails.
+
+use super::RocksDB;
+use crate::config::Mode;
+use crate::{StoreConfig, Temperature};
+use anyhow::Context;
+use std::collections::HashSet;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::Path;
+
+/// Name of the file with unique identifier of a RocksDB database.  It’s left
+/// out of backups so that every restored database gets a new identity.
+const IDENTITY_FILE: &str = "IDENTITY";
+
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct BackupFile {
+    pub name: String,
+    pub size: u64,
+}
+
+/// SST file kept in the shared directory.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct SharedBackupFile {
+    /// Name of the file in the database.
+    pub name: String,
+    pub size: u64,
+    /// Id of the database session which wrote the file, if the file has it in
+    /// its table properties.
+    pub session_id: Option<String>,
+    /// Checksum of the file, computed if the file was copied to the shared
+    /// directory by this backup or if it has no session id.
+    pub crc32: Option<u32>,
+}
+
+impl SharedBackupFile {
+    /// Name of the file in the shared directory.
+    pub fn shared_name(&self) -> String {
+        let number = self.name.trim_end_matches(".sst");
+        match (&self.session_id, self.crc32) {
+            (Some(session_id), _) => format!("{number}_s{session_id}_{}.sst", self.size),
+            (None, Some(crc32)) => format!("{number}_{crc32}_{}.sst", self.size),
+            (None, None) => {
+                panic!("shared backup file {} has no session id nor checksum", self.name)
+            }
+        }
+    }
+}
+
+/// Files which make up a backup of a database.
+#[derive(Clone, Debug, Default, serde::Serialize, serde::Deserialize)]
+pub struct BackupFiles {
+    /// SST files kept in the shared directory.
+    pub shared: Vec<SharedBackupFile>,
+    /// Remaining files kept in the directory of the backup.
+    pub private: Vec<BackupFile>,
+    /// Number of bytes copied while making the backup.  Lower than the total
+    /// size of the files if some of them were already in the shared directory.
+    pub copied_bytes: u64,
+}
+
+impl RocksDB {
+    /// Backs up files of the database at `db_path`.  SST files are placed in
+    /// `shared_dir` (unless they are already there) and all the other files in
+    /// `private_dir`, which must not exist.
+    pub fn backup(
+        db_path: &Path,
+        store_config: &StoreConfig,
+        temp: Temperature,
+        shared_dir: &Path,
+        private_dir: &Path,
+    ) -> anyhow::Result<BackupFiles> {
+        anyhow::ensure!(!private_dir.exists(), "{} already exists", private_dir.display());
+        let db = Self::open(db_path, store_config, Mode::ReadOnly, temp)?;
+        std::fs::create_dir_all(shared_dir)?;
+        std::fs::create_dir_all(private_dir)?;
+
+        let mut files = BackupFiles::default();
+        for file in db.db.live_files().map_err(io::Error::other)? {
+            let name = file.name.trim_start_matches('/').to_string();
+            let size = file.size as u64;
+            let source = db_path.join(&name);
+            let session_id = sst_session_id(&source).unwrap_or_default();
+            let crc32 = match session_id {
+                Some(_) => None,
+                None => Some(
+                    file_crc32(&source, size).with_context(|| format!("failed to read {name}"))?,
+                ),
+            };
+            let mut file = SharedBackupFile { name, size, session_id, crc32 };
+            let target = shared_dir.join(file.shared_name());
+            if !target.exists() {
+                if file.crc32.is_none() {
+                    file.crc32 = Some(
+                        file_crc32(&source, size)
+                            .with_context(|| format!("failed to read {}", file.name))?,
+                    );
+                }
+                link_or_copy(&source, &target)
+                    .with_context(|| format!("failed to back up {}", file.name))?;
+                files.copied_bytes += size;
+            }
+            files.shared.push(file);
+        }
+
+        // Everything else is small and may still change, so it’s copied.
+        // Only the `LOCK` file, the info logs and the identity are not needed
+        // to open the database.
+        for entry in std::fs::read_dir(db_path)? {
+            let entry = entry?;
+            let name = entry.file_name().to_string_lossy().into_owned();
+            if !entry.file_type()?.is_file()
+                || name.ends_with(".sst")
+                || name == "LOCK"
+                || name == IDENTITY_FILE
+                || name.starts_with("LOG")
+            {
+                continue;
+            }
+            let size = std::fs::copy(entry.path(), private_dir.join(&name))
+                .with_context(|| format!("failed to back up {name}"))?;
+            files.copied_bytes += size;
+            files.private.push(BackupFile { name, size });
+        }
+        Ok(files)
+    }
+
+    /// Puts backed up files of a database to `target` directory, which must
+    /// not exist or be empty.  SST files are hard linked if possible.
+    ///
+    /// Fails if any of the files is missing or has unexpected size or, for
+    /// files whose checksum the backup recorded, checksum.  The restored
+    /// database isn’t opened, it’s up to the caller to verify its contents.
+    pub fn restore_backup(
+        files: &BackupFiles,
+        shared_dir: &Path,
+        private_dir: &Path,
+        target: &Path,
+    ) -> anyhow::Result<()> {
+        if target.exists() {
+            anyhow::ensure!(
+                target.read_dir()?.next().is_none(),
+                "{} is not empty",
+                target.display()
+            );
+        }
+        std::fs::create_dir_all(target)?;
+        for file in &files.shared {
+            let source = shared_dir.join(file.shared_name());
+            check_size(&source, file.size)?;
+            if let Some(crc32) = file.crc32 {
+                let actual = file_crc32(&source, file.size)?;
+                anyhow::ensure!(
+                    actual == crc32,
+                    "backup file {} has checksum {actual}, expected {crc32}",
+                    source.display()
+                );
+            }
+            link_or_copy(&source, &target.join(&file.name))?;
+        }
+        for file in &files.private {
+            let source = private_dir.join(&file.name);
+            check_size(&source, file.size)?;
+            std::fs::copy(&source, target.join(&file.name))?;
+        }
+        Ok(())
+    }
+
+    /// Removes the files in `shared_dir` which none of the backups refers to,
+    /// i.e. the ones whose names aren’t in `referenced`.  They are left behind
+    /// by removed backups and by attempts which failed verification.
+    ///
+    /// Returns the number of freed bytes.
+    pub fn remove_unreferenced_backup_files(
+        shared_dir: &Path,
+        referenced: &HashSet<String>,
+    ) -> io::Result<u64> {
+        if !shared_dir.exists() {
+            return Ok(0);
+        }
+        let mut freed_bytes = 0;
+        for entry in std::fs::read_dir(shared_dir)? {
+            let entry = entry?;
+            let name = entry.file_name().to_string_lossy().into_owned();
+            if !entry.file_type()?.is_file() || referenced.contains(&name) {
+                continue;
+            }
+            freed_bytes += entry.metadata()?.len();
+            std::fs::remove_file(entry.path())?;
+        }
+        Ok(freed_bytes)
+    }
+}
+
+/// Magic number at the end of SST files in the block based table format.
+const BLOCK_BASED_TABLE_MAGIC: u64 = 0x88e2_41b7_85f4_cff7;
+/// Size of the footer of SST files with format version below 6.
+const FOOTER_SIZE: u64 = 53;
+/// Size of the compression type and checksum following each block.
+const BLOCK_TRAILER_SIZE: u64 = 5;
+
+/// Reads the id of the database session which wrote the SST file from its
+/// table properties.  Only the footer, the metaindex block and the properties
+/// block are read, which are a few kilobytes at the end of the file.
+///
+/// Returns `None` if the file has no session id or a format this parser
+/// doesn’t know.
+fn sst_session_id(path: &Path) -> io::Result<Option<String>> {
+    let mut file = std::fs::File::open(path)?;
+    let file_size = file.metadata()?.len();
+    if file_size < FOOTER_SIZE {
+        return Ok(None);
+    }
+    let footer = read_at(&mut file, file_size - FOOTER_SIZE, FOOTER_SIZE)?;
+    let magic = u64::from_le_bytes(footer[45..].try_into().unwrap());
+    let format_version = u32::from_le_bytes(footer[41..45].try_into().unwrap());
+    if magic != BLOCK_BASED_TABLE_MAGIC || !(1..6).contains(&format_version) {
+        return Ok(None);
+    }
+    // The footer starts with the checksum type followed by the metaindex handle.
+    let Some((metaindex, _)) = decode_block_handle(&footer[1..]) else { return Ok(None) };
+    let Some(metaindex) = read_block(&mut file, file_size, metaindex)? else { return Ok(None) };
+    let Some(properties) = block_entries(&metaindex)
+        .and_then(|entries| entries.into_iter().find(|(key, _)| key == b"rocksdb.properties"))
+        .and_then(|(_, handle)| decode_block_handle(&handle).map(|(handle, _)| handle))
+    else {
+        return Ok(None);
+    };
+    let Some(properties) = read_block(&mut file, file_size, properties)? else { return Ok(None) };
+    let session_id = block_entries(&properties)
+        .and_then(|entries| {
+            entries.into_iter().find(|(key, _)| key == b"rocksdb.creating.session.identity")
+        })
+        .and_then(|(_, value)| String::from_utf8(value).ok())
+        .filter(|id| !id.is_empty() && id.bytes().all(|byte| byte.is_ascii_alphanumeric()));
+    Ok(session_id)
+}
+
+fn read_at(file: &mut std::fs::File, offset: u64, len: u64) -> io::Result<Vec<u8>> {
+    let mut buf = vec![0; len as usize];
+    file.seek(SeekFrom::Start(offset))?;
+    file.read_exact(&mut buf)?;
+    Ok(buf)
+}
+
+/// Reads contents of an uncompressed block, `None` if the block is compressed
+/// or doesn’t fit in the file.
+fn read_block(
+    file: &mut std::fs::File,
+    file_size: u64,
+    (offset, size): (u64, u64),
+) -> io::Result<Option<Vec<u8>>> {
+    let end = offset.checked_add(size).and_then(|end| end.checked_add(BLOCK_TRAILER_SIZE));
+    if !matches!(end, Some(end) if end <= file_size) {
+        return Ok(None);
+    }
+    let mut block = read_at(file, offset, size + BLOCK_TRAILER_SIZE)?;
+    let compression_type = block[size as usize];
+    if compression_type != 0 {
+        return Ok(None);
+    }
+    block.truncate(size as usize);
+    Ok(Some(block))
+}
+
+/// Decodes a block handle, i.e. the offset and size of a block, returning it
+/// together with the remaining bytes.
+fn decode_block_handle(data: &[u8]) -> Option<((u64, u64), &[u8])> {
+    let (offset, data) = decode_varint(data)?;
+    let (size, data) = decode_varint(data)?;
+    Some(((offset, size), data))
+}
+
+fn decode_varint(data: &[u8]) -> Option<(u64, &[u8])> {
+    let mut value = 0u64;
+    for (i, byte) in data.iter().enumerate().take(10) {
+        value |= u64::from(byte & 0x7f) << (7 * i);
+        if byte & 0x80 == 0 {
+            return Some((value, &data[i + 1..]));
+        }
+    }
+    None
+}
+
+/// Decodes key-value entries of a block: prefix compressed entries followed
+/// by an array of restart points and their count.
+fn block_entries(block: &[u8]) -> Option<Vec<(Vec<u8>, Vec<u8>)>> {
+    let num_restarts_pos = block.len().checked_sub(4)?;
+    // The highest bit of the count is used by data blocks for the index type.
+    let num_restarts =
+        u32::from_le_bytes(block[num_restarts_pos..].try_into().unwrap()) & 0x7fff_ffff;
+    let restarts_pos = num_restarts_pos.checked_sub(4 * num_restarts as usize)?;
+    let mut data = &block[..restarts_pos];
+    let mut entries = vec![];
+    let mut key = vec![];
+    while !data.is_empty() {
+        let (shared, rest) = decode_varint(data)?;
+        let (non_shared, rest) = decode_varint(rest)?;
+        let (value_len, rest) = decode_varint(rest)?;
+        let (shared, non_shared, value_len) =
+            (shared as usize, non_shared as usize, value_len as usize);
+        if shared > key.len() || rest.len() < non_shared.checked_add(value_len)? {
+            return None;
+        }
+        key.truncate(shared);
+        key.extend_from_slice(&rest[..non_shared]);
+        entries.push((key.clone(), rest[non_shared..non_shared + value_len].to_vec()));
+        data = &rest[non_shared + value_len..];
+    }
+    Some(entries)
+}
+
+/// Computes CRC32 of the file and checks that it has given size.
+fn file_crc32(path: &Path, size: u64) -> anyhow::Result<u32> {
+    let mut file = std::fs::File::open(path)?;
+    let mut hasher = crc32fast::Hasher::new();
+    let mut buf = vec![0; 1 << 20];
+    let mut read = 0;
+    loop {
+        let len = file.read(&mut buf)?;
+        if len == 0 {
+            break;
+        }
+        hasher.update(&buf[..len]);
+        read += len as u64;
+    }
+    anyhow::ensure!(read == size, "{} has size {read}, expected {size}", path.display());
+    Ok(hasher.finalize())
+}
+
+fn check_size(path: &Path, size: u64) -> anyhow::Result<()> {
+    let actual = std::fs::metadata(path)
+        .with_context(|| format!("missing backup file {}", path.display()))?
+        .len();
+    anyhow::ensure!(
+        actual == size,
+        "backup file {} has size {actual}, expected {size}",
+        path.display()
+    );
+    Ok(())
+}
+
+/// Hard links `source` to `target` or, if that’s not possible (e.g. the
+/// paths are on different file systems), copies it.  The copy is made under
+/// a temporary name first so that `target` never has partial contents.
+///
+/// Fails if `target` already exists, it’s never replaced.
+fn link_or_copy(source: &Path, target: &Path) -> io::Result<()> {
+    match std::fs::hard_link(source, target) {
+        Ok(()) => return Ok(()),
+        Err(err)
+            if matches!(err.kind(), io::ErrorKind::NotFound | io::ErrorKind::AlreadyExists) =>
+        {
+            return Err(err)
+        }
+        Err(_) => {}
+    }
+    let tmp = target.with_extension("tmp");
+    std::fs::copy(source, &tmp)?;
+    if target.exists() {
+        std::fs::remove_file(&tmp)?;
+        return Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!("{} already exists", target.display()),
+        ));
+    }
+    std::fs::rename(&tmp, target)
+}
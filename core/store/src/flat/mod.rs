@@ -30,6 +30,7 @@ pub mod delta;
 mod inlining_migration;
 mod manager;
 mod metrics;
+mod scrubber;
 mod storage;
 pub mod store_helper;
 #[cfg(test)]
@@ -41,6 +42,11 @@ pub use delta::{FlatStateChanges, FlatStateDelta, FlatStateDeltaMetadata};
 pub use inlining_migration::{inline_flat_state_values, FlatStateValuesInliningMigrationHandle};
 pub use manager::FlatStorageManager;
 pub use metrics::FlatStorageCreationMetrics;
+pub use scrubber::{
+    get_flat_storage_scrubber_status, get_flat_storage_scrubber_statuses, scrub_flat_storage_batch,
+    FlatStorageMismatch, FlatStorageMismatchKind, FlatStorageScrubberHandle,
+    FlatStorageScrubberStatus,
+};
 pub use storage::FlatStorage;
 pub use types::{
     BlockInfo, FetchingStateStatus, FlatStateIterator, FlatStorageCreationStatus, FlatStorageError,
@@ -0,0 +1,439 @@
+//! Background check of flat storage against the trie.
+//!
+//! The scrubber walks flat storage of every shard in small batches, in key
+//! order.  For each batch it reads the same key range from the on-disk trie
+//! at the state root of the flat storage head and compares the two.  If the
+//! memtrie of the shard is loaded and still holds that root, it is compared
+//! against the on-disk trie as well.
+//!
+//! Progress is persisted in `DBCol::Misc`, so a pass survives restarts, and
+//! the results are reported through metrics and the debug page.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use itertools::{EitherOrBoth, Itertools};
+use near_primitives::shard_layout::{get_block_shard_uid, ShardUId};
+use near_primitives::state::ValueRef;
+use near_primitives::types::chunk_extra::ChunkExtra;
+use near_primitives::types::BlockHeight;
+use tracing::{error, info, warn};
+
+use crate::config::FlatStorageScrubberConfig;
+use crate::db::FLAT_STORAGE_SCRUBBER_STATUS_KEY;
+use crate::metrics::flat_state_metrics::scrubber::{
+    CHECKED_KEYS, COMPLETED_PASSES, ERRORS, MISMATCHES, READ_BYTES,
+};
+use crate::trie::nibble_slice::NibbleSlice;
+use crate::{DBCol, KeyLookupMode, ShardTries, Store, Trie, TrieDBStorage};
+
+use super::store_helper;
+use super::types::{FlatStorageReadyStatus, FlatStorageStatus};
+
+/// Number of most recent mismatches kept in the persisted status of a shard.
+const MAX_RECENT_MISMATCHES: usize = 16;
+
+/// How long to sleep when there was nothing to check in a round.
+const IDLE_SLEEP: Duration = Duration::from_secs(10);
+
+#[derive(
+    BorshSerialize, BorshDeserialize, Clone, Copy, Debug, PartialEq, Eq, strum::IntoStaticStr,
+)]
+pub enum FlatStorageMismatchKind {
+    /// The key is present in the trie but not in flat storage.
+    MissingInFlatStorage,
+    /// The key is present in flat storage but not in the trie.
+    MissingInTrie,
+    /// Flat storage and the trie hold different values for the key.
+    ValueMismatch,
+    /// The memtrie holds a different value for the key than the on-disk trie.
+    MemtrieMismatch,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct FlatStorageMismatch {
+    pub kind: FlatStorageMismatchKind,
+    pub key: Vec<u8>,
+    /// Height of the flat storage head at which the mismatch was found.
+    pub flat_head_height: BlockHeight,
+}
+
+/// Progress of the scrubber on a single shard.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct FlatStorageScrubberStatus {
+    /// Key the next batch starts from, or `None` if it starts a new pass.
+    pub cursor: Option<Vec<u8>>,
+    /// Number of full passes over flat storage of the shard completed so far.
+    pub completed_passes: u64,
+    /// Number of keys checked so far in the current pass.
+    pub keys_checked_in_pass: u64,
+    /// Number of keys checked in the last completed pass.
+    pub keys_in_last_pass: u64,
+    /// Height of the flat storage head the last batch was checked at.
+    pub flat_head_height: BlockHeight,
+    /// Number of mismatches found since the scrubber was first run.
+    pub total_mismatches: u64,
+    /// Most recently found mismatches, oldest first.
+    pub recent_mismatches: Vec<FlatStorageMismatch>,
+}
+
+impl FlatStorageScrubberStatus {
+    fn record_mismatch(&mut self, mismatch: FlatStorageMismatch) {
+        self.total_mismatches += 1;
+        if self.recent_mismatches.len() == MAX_RECENT_MISMATCHES {
+            self.recent_mismatches.remove(0);
+        }
+        self.recent_mismatches.push(mismatch);
+    }
+}
+
+fn scrubber_status_key(shard_uid: ShardUId) -> Vec<u8> {
+    [FLAT_STORAGE_SCRUBBER_STATUS_KEY, &shard_uid.to_bytes()].concat()
+}
+
+pub fn get_flat_storage_scrubber_status(
+    store: &Store,
+    shard_uid: ShardUId,
+) -> std::io::Result<Option<FlatStorageScrubberStatus>> {
+    store.get_ser(DBCol::Misc, &scrubber_status_key(shard_uid))
+}
+
+/// Returns the persisted scrubber status of every shard it has ever checked.
+pub fn get_flat_storage_scrubber_statuses(
+    store: &Store,
+) -> std::io::Result<Vec<(ShardUId, FlatStorageScrubberStatus)>> {
+    let mut statuses = Vec::new();
+    for item in store.iter_prefix_ser(DBCol::Misc, FLAT_STORAGE_SCRUBBER_STATUS_KEY) {
+        let (key, status) = item?;
+        let shard_uid = ShardUId::try_from(&key[FLAT_STORAGE_SCRUBBER_STATUS_KEY.len()..])
+            .map_err(std::io::Error::other)?;
+        statuses.push((shard_uid, status));
+    }
+    Ok(statuses)
+}
+
+fn set_flat_storage_scrubber_status(
+    store: &Store,
+    shard_uid: ShardUId,
+    status: &FlatStorageScrubberStatus,
+) -> std::io::Result<()> {
+    let mut store_update = store.store_update();
+    store_update.set_ser(DBCol::Misc, &scrubber_status_key(shard_uid), status)?;
+    store_update.commit()
+}
+
+/// Checks the next batch of at most `batch_size` flat storage entries of the
+/// shard against the trie and updates `status` accordingly.
+///
+/// Returns the approximate number of bytes read, or `None` if the shard can't
+/// be checked right now, e.g. because its flat storage isn't ready or the flat
+/// head moved while the batch was being read.
+pub fn scrub_flat_storage_batch(
+    tries: &ShardTries,
+    shard_uid: ShardUId,
+    batch_size: usize,
+    status: &mut FlatStorageScrubberStatus,
+) -> anyhow::Result<Option<u64>> {
+    let store = tries.get_store();
+    let FlatStorageStatus::Ready(FlatStorageReadyStatus { flat_head }) =
+        store_helper::get_flat_storage_status(&store, shard_uid)?
+    else {
+        return Ok(None);
+    };
+    let Some(chunk_extra) = store.get_ser::<ChunkExtra>(
+        DBCol::ChunkExtra,
+        &get_block_shard_uid(&flat_head.hash, &shard_uid),
+    )?
+    else {
+        return Ok(None);
+    };
+    let state_root = *chunk_extra.state_root();
+
+    // Read one entry more than needed; its key is the exclusive end of the
+    // batch.
+    let mut flat_entries = Vec::with_capacity(batch_size);
+    let mut batch_end = None;
+    for item in
+        store_helper::iter_flat_state_entries(shard_uid, &store, status.cursor.as_deref(), None)
+    {
+        let (key, value) = item?;
+        if flat_entries.len() == batch_size.max(1) {
+            batch_end = Some(key);
+            break;
+        }
+        flat_entries.push((key, value.to_value_ref()));
+    }
+    // Flat head is moved together with the flat state values, so if it's
+    // unchanged the entries above correspond to `state_root`.
+    let flat_head_status = FlatStorageStatus::Ready(FlatStorageReadyStatus { flat_head });
+    if store_helper::get_flat_storage_status(&store, shard_uid)? != flat_head_status {
+        return Ok(None);
+    }
+
+    let storage = Arc::new(TrieDBStorage::new(store.clone(), shard_uid));
+    let trie = Trie::new(storage.clone(), state_root, None);
+    let path_begin: Vec<u8> = match &status.cursor {
+        Some(key) => NibbleSlice::new(key).iter().collect(),
+        None => vec![],
+    };
+    let path_end: Vec<u8> = match &batch_end {
+        Some(key) => NibbleSlice::new(key).iter().collect(),
+        None => vec![16],
+    };
+    let trie_items = trie.disk_iter()?.get_trie_items(&path_begin, &path_end)?;
+
+    let mut mismatches = Vec::new();
+    // Keys of the batch along with their value refs in the on-disk trie.
+    let mut checked = Vec::with_capacity(flat_entries.len());
+    for item in flat_entries
+        .iter()
+        .merge_join_by(trie_items.iter(), |(flat_key, _), (trie_key, _)| flat_key.cmp(trie_key))
+    {
+        let (key, trie_value_ref, mismatch) = match item {
+            EitherOrBoth::Both((key, flat_value_ref), (_, trie_value)) => {
+                let trie_value_ref = ValueRef::new(trie_value);
+                let mismatch = (*flat_value_ref != trie_value_ref)
+                    .then_some(FlatStorageMismatchKind::ValueMismatch);
+                (key, Some(trie_value_ref), mismatch)
+            }
+            EitherOrBoth::Left((key, _)) => {
+                (key, None, Some(FlatStorageMismatchKind::MissingInTrie))
+            }
+            EitherOrBoth::Right((key, trie_value)) => (
+                key,
+                Some(ValueRef::new(trie_value)),
+                Some(FlatStorageMismatchKind::MissingInFlatStorage),
+            ),
+        };
+        if let Some(kind) = mismatch {
+            mismatches.push((kind, key.clone()));
+        }
+        checked.push((key, trie_value_ref));
+    }
+
+    if let Some(memtries) = tries.get_mem_tries(shard_uid) {
+        let has_root = state_root != Trie::EMPTY_ROOT
+            && memtries.read().unwrap().get_root(&state_root).is_some();
+        if has_root {
+            let memtrie = Trie::new_with_memtries(storage, Some(memtries), state_root, None);
+            for (key, trie_value_ref) in &checked {
+                // The root may be garbage collected from the memtrie at any
+                // moment, in which case the rest of the batch isn't compared.
+                let Ok(memtrie_value) = memtrie.get_optimized_ref(key, KeyLookupMode::Trie) else {
+                    break;
+                };
+                if memtrie_value.map(|value| value.into_value_ref()) != *trie_value_ref {
+                    mismatches.push((FlatStorageMismatchKind::MemtrieMismatch, key.to_vec()));
+                }
+            }
+        }
+    }
+
+    let bytes_read = flat_entries
+        .iter()
+        .map(|(key, _)| key.len() + std::mem::size_of::<ValueRef>())
+        .chain(trie_items.iter().map(|(key, value)| key.len() + value.len()))
+        .sum::<usize>() as u64;
+    let shard_label = shard_uid.to_string();
+    CHECKED_KEYS.with_label_values(&[&shard_label]).inc_by(checked.len() as u64);
+    READ_BYTES.with_label_values(&[&shard_label]).inc_by(bytes_read);
+    for (kind, key) in mismatches {
+        error!(
+            target: "store",
+            %shard_uid,
+            ?kind,
+            key = %near_fmt::StorageKey(&key),
+            flat_head_height = flat_head.height,
+            "Flat storage is inconsistent with the trie"
+        );
+        MISMATCHES.with_label_values(&[&shard_label, kind.into()]).inc();
+        status.record_mismatch(FlatStorageMismatch {
+            kind,
+            key,
+            flat_head_height: flat_head.height,
+        });
+    }
+
+    status.keys_checked_in_pass += checked.len() as u64;
+    status.flat_head_height = flat_head.height;
+    status.cursor = batch_end;
+    if status.cursor.is_none() {
+        info!(
+            target: "store",
+            %shard_uid,
+            keys = status.keys_checked_in_pass,
+            total_mismatches = status.total_mismatches,
+            "Flat storage scrubber completed a pass"
+        );
+        status.completed_passes += 1;
+        status.keys_in_last_pass = std::mem::take(&mut status.keys_checked_in_pass);
+        COMPLETED_PASSES.with_label_values(&[&shard_label]).inc();
+    }
+    Ok(Some(bytes_read))
+}
+
+pub struct FlatStorageScrubberHandle {
+    handle: JoinHandle<()>,
+    keep_running: Arc<AtomicBool>,
+}
+
+impl FlatStorageScrubberHandle {
+    pub fn start_background_scrubber(config: FlatStorageScrubberConfig, tries: ShardTries) -> Self {
+        let keep_running = Arc::new(AtomicBool::new(true));
+        let keep_running_clone = keep_running.clone();
+        let handle = std::thread::Builder::new()
+            .name("flat_storage_scrubber".to_string())
+            .spawn(move || run_scrubber(&config, &tries, &keep_running))
+            .expect("failed to spawn flat storage scrubber thread");
+        Self { handle, keep_running: keep_running_clone }
+    }
+
+    pub fn stop(self) {
+        self.keep_running.store(false, Ordering::Relaxed);
+        self.handle.join().expect("join should not fail here");
+    }
+}
+
+/// Checks a batch of every shard with flat storage in turn until stopped,
+/// sleeping between the rounds to stay within the configured I/O budget.
+fn run_scrubber(config: &FlatStorageScrubberConfig, tries: &ShardTries, keep_running: &AtomicBool) {
+    info!(target: "store", ?config, "Starting flat storage scrubber");
+    let store = tries.get_store();
+    let max_bytes_per_second = config.max_bytes_per_second.as_u64().max(1) as f64;
+    let mut statuses = HashMap::new();
+    let mut next_pass_start = HashMap::<ShardUId, Instant>::new();
+    while keep_running.load(Ordering::Relaxed) {
+        let round_start = Instant::now();
+        let mut bytes_read = 0;
+        for shard_uid in tries.get_flat_storage_manager().get_shard_uids() {
+            if next_pass_start.get(&shard_uid).is_some_and(|start| *start > round_start) {
+                continue;
+            }
+            let status = match statuses.get_mut(&shard_uid) {
+                Some(status) => status,
+                None => match get_flat_storage_scrubber_status(&store, shard_uid) {
+                    Ok(status) => statuses.entry(shard_uid).or_insert(status.unwrap_or_default()),
+                    Err(err) => {
+                        warn!(target: "store", %shard_uid, ?err, "Failed to read flat storage scrubber status");
+                        continue;
+                    }
+                },
+            };
+            match scrub_flat_storage_batch(tries, shard_uid, config.batch_size, status) {
+                Ok(Some(bytes)) => {
+                    bytes_read += bytes;
+                    if status.cursor.is_none() {
+                        next_pass_start.insert(
+                            shard_uid,
+                            Instant::now() + config.pass_interval.unsigned_abs(),
+                        );
+                    }
+                    if let Err(err) = set_flat_storage_scrubber_status(&store, shard_uid, status) {
+                        warn!(target: "store", %shard_uid, ?err, "Failed to save flat storage scrubber status");
+                    }
+                }
+                Ok(None) => {}
+                Err(err) => {
+                    ERRORS.with_label_values(&[&shard_uid.to_string()]).inc();
+                    error!(target: "store", %shard_uid, ?err, "Flat storage scrubber failed to check a batch");
+                }
+            }
+        }
+        let sleep = if bytes_read == 0 {
+            IDLE_SLEEP
+        } else {
+            Duration::from_secs_f64(bytes_read as f64 / max_bytes_per_second)
+                .saturating_sub(round_start.elapsed())
+        };
+        sleep_while_running(keep_running, sleep);
+    }
+    info!(target: "store", "Flat storage scrubber stopped");
+}
+
+fn sleep_while_running(keep_running: &AtomicBool, duration: Duration) {
+    let deadline = Instant::now() + duration;
+    while keep_running.load(Ordering::Relaxed) {
+        let now = Instant::now();
+        if now >= deadline {
+            break;
+        }
+        std::thread::sleep((deadline - now).min(Duration::from_millis(100)));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{scrub_flat_storage_batch, FlatStorageMismatchKind, FlatStorageScrubberStatus};
+    use crate::flat::store_helper;
+    use crate::test_utils::{test_populate_flat_storage, test_populate_trie, TestTriesBuilder};
+    use crate::{DBCol, ShardTries, Trie};
+    use near_primitives::hash::hash;
+    use near_primitives::shard_layout::{get_block_shard_uid, ShardUId};
+    use near_primitives::state::FlatStateValue;
+    use near_primitives::types::chunk_extra::ChunkExtra;
+
+    fn scrub_full_pass(tries: &ShardTries, shard_uid: ShardUId) -> FlatStorageScrubberStatus {
+        let mut status = FlatStorageScrubberStatus::default();
+        while status.completed_passes == 0 {
+            scrub_flat_storage_batch(tries, shard_uid, 2, &mut status).unwrap().unwrap();
+        }
+        status
+    }
+
+    #[test]
+    fn scrubber_finds_mismatches() {
+        let tries = TestTriesBuilder::new().with_flat_storage(true).build();
+        let shard_uid = ShardUId::single_shard();
+        let changes: Vec<_> =
+            (0..10u8).map(|i| (vec![i, i], Some(vec![i; 1 + i as usize]))).collect();
+        let root = test_populate_trie(&tries, &Trie::EMPTY_ROOT, shard_uid, changes.clone());
+        let block_hash = hash(b"block");
+        test_populate_flat_storage(&tries, shard_uid, &block_hash, &block_hash, &changes);
+        let mut store_update = tries.store_update();
+        store_update
+            .set_ser(
+                DBCol::ChunkExtra,
+                &get_block_shard_uid(&block_hash, &shard_uid),
+                &ChunkExtra::new_with_only_state_root(&root),
+            )
+            .unwrap();
+        store_update.commit().unwrap();
+
+        let status = scrub_full_pass(&tries, shard_uid);
+        assert_eq!(status.keys_in_last_pass, 10);
+        assert_eq!(status.total_mismatches, 0);
+
+        let mut store_update = tries.store_update();
+        store_helper::set_flat_state_value(&mut store_update, shard_uid, vec![3, 3], None);
+        store_helper::set_flat_state_value(
+            &mut store_update,
+            shard_uid,
+            vec![5, 5],
+            Some(FlatStateValue::on_disk(&[42])),
+        );
+        store_helper::set_flat_state_value(
+            &mut store_update,
+            shard_uid,
+            vec![5, 6],
+            Some(FlatStateValue::on_disk(&[42])),
+        );
+        store_update.commit().unwrap();
+
+        let status = scrub_full_pass(&tries, shard_uid);
+        assert_eq!(status.keys_in_last_pass, 11);
+        let mismatches: Vec<_> =
+            status.recent_mismatches.iter().map(|m| (m.kind, m.key.clone())).collect();
+        assert_eq!(
+            mismatches,
+            vec![
+                (FlatStorageMismatchKind::MissingInFlatStorage, vec![3, 3]),
+                (FlatStorageMismatchKind::ValueMismatch, vec![5, 5]),
+                (FlatStorageMismatchKind::MissingInTrie, vec![5, 6]),
+            ]
+        );
+    }
+}
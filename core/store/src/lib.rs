@@ -7,16 +7,16 @@ pub use crate::trie::{
     estimator, resharding, ApplyStatePartResult, KeyForStateChanges, KeyLookupMode, NibbleSlice,
     PartialStorage, PrefetchApi, PrefetchError, RawTrieNode, RawTrieNodeWithSize, ShardTries,
     StateSnapshot, StateSnapshotConfig, Trie, TrieAccess, TrieCache, TrieCachingStorage,
-    TrieChanges, TrieConfig, TrieDBStorage, TrieStorage, WrappedTrieChanges,
-    STATE_SNAPSHOT_COLUMNS,
+    TrieChanges, TrieConfig, TrieDBStorage, TrieDiff, TrieDiffItem, TrieDiffLimits, TrieStorage,
+    WrappedTrieChanges, STATE_SNAPSHOT_COLUMNS,
 };
 use borsh::{BorshDeserialize, BorshSerialize};
 pub use columns::DBCol;
 use db::GENESIS_CONGESTION_INFO_KEY;
 pub use db::{
-    CHUNK_TAIL_KEY, COLD_HEAD_KEY, FINAL_HEAD_KEY, FORK_TAIL_KEY, GENESIS_JSON_HASH_KEY,
-    GENESIS_STATE_ROOTS_KEY, HEADER_HEAD_KEY, HEAD_KEY, LARGEST_TARGET_HEIGHT_KEY,
-    LATEST_KNOWN_KEY, STATE_SNAPSHOT_KEY, STATE_SYNC_DUMP_KEY, TAIL_KEY,
+    CHUNK_TAIL_KEY, COLD_HEAD_KEY, COLD_TAIL_KEY, FINAL_HEAD_KEY, FORK_TAIL_KEY,
+    GENESIS_JSON_HASH_KEY, GENESIS_STATE_ROOTS_KEY, HEADER_HEAD_KEY, HEAD_KEY,
+    LARGEST_TARGET_HEIGHT_KEY, LATEST_KNOWN_KEY, STATE_SNAPSHOT_KEY, STATE_SYNC_DUMP_KEY, TAIL_KEY,
 };
 use metadata::{DbKind, DbVersion, KIND_KEY, VERSION_KEY};
 use near_crypto::PublicKey;
@@ -56,7 +56,9 @@ mod sync_utils;
 pub mod test_utils;
 pub mod trie;
 
-pub use crate::config::{Mode, StoreConfig};
+pub use crate::config::{
+    ColumnOptions, CompressionType, FlatStorageScrubberConfig, Mode, StoreBackend, StoreConfig,
+};
 pub use crate::opener::{
     checkpoint_hot_storage_and_cleanup_columns, StoreMigrator, StoreOpener, StoreOpenerError,
 };
@@ -122,13 +124,7 @@ impl NodeStorage {
     }
 
     /// Constructs new object backed by given database.
-    fn from_rocksdb(
-        hot_storage: crate::db::RocksDB,
-        cold_storage: Option<crate::db::RocksDB>,
-    ) -> Self {
-        let hot_storage = Arc::new(hot_storage);
-        let cold_storage = cold_storage.map(|storage| Arc::new(storage));
-
+    fn from_dbs(hot_storage: Arc<dyn Database>, cold_storage: Option<Arc<dyn Database>>) -> Self {
         let cold_db = if let Some(cold_storage) = cold_storage {
             Some(Arc::new(crate::db::ColdDB::new(cold_storage)))
         } else {
@@ -24,7 +24,16 @@ pub(super) const KIND_KEY: &[u8; 4] = b"KIND";
 
 /// Describes what kind the storage is.
 #[derive(
-    Clone, Copy, Debug, Eq, PartialEq, strum::Display, strum::EnumString, strum::IntoStaticStr,
+    Clone,
+    Copy,
+    Debug,
+    Eq,
+    PartialEq,
+    serde::Serialize,
+    serde::Deserialize,
+    strum::Display,
+    strum::EnumString,
+    strum::IntoStaticStr,
 )]
 pub enum DbKind {
     /// The database is an RPC database meaning that it is garbage collected and
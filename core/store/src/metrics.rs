@@ -232,6 +232,10 @@ pub static COLD_MIGRATION_READS: Lazy<IntCounterVec> = Lazy::new(|| {
 pub static COLD_HEAD_HEIGHT: Lazy<IntGauge> = Lazy::new(|| {
     try_create_int_gauge("near_cold_head_height", "Height of the head of cold storage").unwrap()
 });
+pub static COLD_TAIL_HEIGHT: Lazy<IntGauge> = Lazy::new(|| {
+    try_create_int_gauge("near_cold_tail_height", "Lowest height kept in pruned cold storage")
+        .unwrap()
+});
 pub static COLD_COPY_DURATION: Lazy<Histogram> = Lazy::new(|| {
     try_create_histogram(
         "near_cold_copy_duration",
@@ -470,6 +474,52 @@ pub mod flat_state_metrics {
         .unwrap()
     });
 
+    pub mod scrubber {
+        use near_o11y::metrics::{try_create_int_counter_vec, IntCounterVec};
+        use once_cell::sync::Lazy;
+
+        pub static CHECKED_KEYS: Lazy<IntCounterVec> = Lazy::new(|| {
+            try_create_int_counter_vec(
+                "near_flat_storage_scrubber_checked_keys",
+                "Number of flat storage keys compared against the trie by the scrubber",
+                &["shard_uid"],
+            )
+            .unwrap()
+        });
+        pub static READ_BYTES: Lazy<IntCounterVec> = Lazy::new(|| {
+            try_create_int_counter_vec(
+                "near_flat_storage_scrubber_read_bytes",
+                "Number of bytes read from the database by the flat storage scrubber",
+                &["shard_uid"],
+            )
+            .unwrap()
+        });
+        pub static MISMATCHES: Lazy<IntCounterVec> = Lazy::new(|| {
+            try_create_int_counter_vec(
+                "near_flat_storage_scrubber_mismatches",
+                "Number of inconsistencies between flat storage and the trie found by the scrubber",
+                &["shard_uid", "kind"],
+            )
+            .unwrap()
+        });
+        pub static COMPLETED_PASSES: Lazy<IntCounterVec> = Lazy::new(|| {
+            try_create_int_counter_vec(
+                "near_flat_storage_scrubber_completed_passes",
+                "Number of full passes over flat storage completed by the scrubber",
+                &["shard_uid"],
+            )
+            .unwrap()
+        });
+        pub static ERRORS: Lazy<IntCounterVec> = Lazy::new(|| {
+            try_create_int_counter_vec(
+                "near_flat_storage_scrubber_errors",
+                "Number of scrubber batches which failed with an error",
+                &["shard_uid"],
+            )
+            .unwrap()
+        });
+    }
+
     pub mod inlining_migration {
         use near_o11y::metrics::{
             try_create_histogram, try_create_int_counter, Histogram, IntCounter,
@@ -1,7 +1,10 @@
 use crate::db::rocksdb::snapshot::{Snapshot, SnapshotError, SnapshotRemoveError};
 use crate::db::rocksdb::RocksDB;
+use crate::db::{Database, RedbDB};
 use crate::metadata::{DbKind, DbMetadata, DbVersion, DB_VERSION};
-use crate::{DBCol, DBTransaction, Mode, NodeStorage, Store, StoreConfig, Temperature};
+use crate::{
+    DBCol, DBTransaction, Mode, NodeStorage, Store, StoreBackend, StoreConfig, Temperature,
+};
 use std::sync::Arc;
 
 #[derive(Debug, thiserror::Error)]
@@ -176,7 +179,7 @@ pub struct StoreOpener<'a> {
     migrator: Option<&'a dyn StoreMigrator>,
 }
 
-/// Opener for a single RocksDB instance.
+/// Opener for a single database instance.
 struct DBOpener<'a> {
     /// Path to the database.
     ///
@@ -193,6 +196,14 @@ struct DBOpener<'a> {
     /// counted column.  It’s important that the value is correct.  RPC and
     /// Archive databases are considered hot.
     temp: Temperature,
+
+    /// Backend of the database.
+    ///
+    /// For an existing database, this is detected from its files, so that
+    /// databases can be opened regardless of the configured backend (e.g.
+    /// checkpoints opened with the default configuration).  The configured
+    /// backend is used when creating a new database.
+    backend: StoreBackend,
 }
 
 impl<'a> StoreOpener<'a> {
@@ -228,6 +239,13 @@ impl<'a> StoreOpener<'a> {
         &self.hot.path
     }
 
+    /// Returns path to the cold database if it’s configured.
+    ///
+    /// Does not check whether the database actually exists.
+    pub fn cold_path(&self) -> Option<&std::path::Path> {
+        self.cold.as_ref().map(|cold| cold.path.as_path())
+    }
+
     #[cfg(test)]
     pub(crate) fn config(&self) -> &StoreConfig {
         self.hot.config
@@ -279,7 +297,7 @@ impl<'a> StoreOpener<'a> {
             .transpose()?
             .map(|(db, _)| db);
 
-        let storage = NodeStorage::from_rocksdb(hot_db, cold_db);
+        let storage = NodeStorage::from_dbs(hot_db, cold_db);
 
         hot_snapshot.remove()?;
         cold_snapshot.remove()?;
@@ -339,7 +357,7 @@ impl<'a> StoreOpener<'a> {
                 tracing::info!(target: "db_opener", path=%opener.path.display(), "The database doesn't exist, creating it.");
 
                 let db = opener.create()?;
-                let store = Store { storage: Arc::new(db) };
+                let store = Store { storage: db };
                 store.set_db_version(DB_VERSION)?;
                 return Ok(());
             }
@@ -467,13 +485,13 @@ impl<'a> StoreOpener<'a> {
         version: DbVersion,
     ) -> Result<Store, StoreOpenerError> {
         let (db, _) = opener.open(mode, version)?;
-        let store = Store { storage: Arc::new(db) };
+        let store = Store { storage: db };
         Ok(store)
     }
 
     fn open_store_unsafe(mode: Mode, opener: &DBOpener) -> Result<Store, StoreOpenerError> {
         let db = opener.open_unsafe(mode)?;
-        let store = Store { storage: Arc::new(db) };
+        let store = Store { storage: db };
         Ok(store)
     }
 }
@@ -487,7 +505,18 @@ impl<'a> DBOpener<'a> {
         let path = if temp == Temperature::Hot { "data" } else { "cold-data" };
         let path = config.path.as_deref().unwrap_or_else(|| std::path::Path::new(path));
         let path = home_dir.join(path);
-        Self { path, config, temp }
+        let backend = if RedbDB::exists(&path) {
+            StoreBackend::Redb
+        } else if RocksDB::exists(&path) {
+            StoreBackend::RocksDb
+        } else {
+            config.backend
+        };
+        if backend != config.backend {
+            tracing::warn!(target: "db_opener", path=%path.display(), %backend, configured_backend=%config.backend,
+                           "Database backend differs from the configured one; use `neard database migrate-backend` to change it");
+        }
+        Self { path, config, temp, backend }
     }
 
     /// Returns version and kind of the database or `None` if it doesn’t exist.
@@ -499,7 +528,10 @@ impl<'a> DBOpener<'a> {
     /// introduced, the kind is returned as `None`.  Otherwise, it’s also
     /// fetched and if it’s not there error is returned.
     fn get_metadata(&self) -> std::io::Result<Option<DbMetadata>> {
-        RocksDB::get_metadata(&self.path, self.config)
+        match self.backend {
+            StoreBackend::RocksDb => RocksDB::get_metadata(&self.path, self.config),
+            StoreBackend::Redb => RedbDB::get_metadata(&self.path, self.config),
+        }
     }
 
     /// Opens the database in given mode checking expected version and kind.
@@ -518,9 +550,13 @@ impl<'a> DBOpener<'a> {
     /// new version.
     ///
     /// Use [`Self::create`] to create a new database.
-    fn open(&self, mode: Mode, want_version: DbVersion) -> std::io::Result<(RocksDB, DbMetadata)> {
-        let db = RocksDB::open(&self.path, &self.config, mode, self.temp)?;
-        let metadata = DbMetadata::read(&db)?;
+    fn open(
+        &self,
+        mode: Mode,
+        want_version: DbVersion,
+    ) -> std::io::Result<(Arc<dyn Database>, DbMetadata)> {
+        let db = self.open_unsafe(mode)?;
+        let metadata = DbMetadata::read(db.as_ref())?;
         if want_version != metadata.version {
             let msg = format!("unexpected DbVersion {}; expected {want_version}", metadata.version);
             Err(std::io::Error::other(msg))
@@ -533,19 +569,26 @@ impl<'a> DBOpener<'a> {
     ///
     /// This is only suitable when creating the database or setting the version
     /// and kind for the first time.
-    fn open_unsafe(&self, mode: Mode) -> std::io::Result<RocksDB> {
-        let db = RocksDB::open(&self.path, &self.config, mode, self.temp)?;
-        Ok(db)
+    fn open_unsafe(&self, mode: Mode) -> std::io::Result<Arc<dyn Database>> {
+        Ok(match self.backend {
+            StoreBackend::RocksDb => {
+                Arc::new(RocksDB::open(&self.path, &self.config, mode, self.temp)?)
+            }
+            StoreBackend::Redb => Arc::new(RedbDB::open(&self.path, &self.config, mode)?),
+        })
     }
 
     /// Creates a new database.
-    fn create(&self) -> std::io::Result<RocksDB> {
-        RocksDB::open(&self.path, &self.config, Mode::Create, self.temp)
+    fn create(&self) -> std::io::Result<Arc<dyn Database>> {
+        self.open_unsafe(Mode::Create)
     }
 
     /// Creates a new snapshot for the database.
     fn snapshot(&self) -> Result<Snapshot, SnapshotError> {
-        Snapshot::new(&self.path, &self.config, self.temp)
+        match self.backend {
+            StoreBackend::RocksDb => Snapshot::new(&self.path, &self.config, self.temp),
+            StoreBackend::Redb => RedbDB::snapshot(&self.path, &self.config),
+        }
     }
 }
 
@@ -2,6 +2,7 @@ use crate::config::{PrefetchConfig, TrieCacheConfig};
 use crate::StoreConfig;
 use near_primitives::shard_layout::ShardUId;
 use near_primitives::types::AccountId;
+use std::path::PathBuf;
 use std::str::FromStr;
 use tracing::error;
 
@@ -38,10 +39,13 @@ pub struct TrieConfig {
     pub load_mem_tries_for_shards: Vec<ShardUId>,
     /// Whether mem-trie should be loaded for each tracked shard.
     pub load_mem_tries_for_tracked_shards: bool,
+    /// Directory of the in-memory trie snapshots, if they are enabled.
+    pub mem_trie_snapshot_dir: Option<PathBuf>,
 }
 
 impl TrieConfig {
     /// Create a new `TrieConfig` with default values or the values specified in `StoreConfig`.
+    /// Note that `mem_trie_snapshot_dir` is left unset, as it depends on the home directory.
     pub fn from_store_config(config: &StoreConfig) -> Self {
         let mut this = TrieConfig::default();
 
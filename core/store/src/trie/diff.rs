@@ -0,0 +1,407 @@
+//! Computing the difference between two states of a shard.
+//!
+//! Both tries are walked side by side one nibble at a time and subtrees whose
+//! roots have the same hash are skipped, so the cost is proportional to the
+//! size of the difference rather than to the size of the state.
+
+use near_primitives::errors::StorageError;
+use near_primitives::hash::CryptoHash;
+use near_primitives::state::ValueRef;
+use near_primitives::trie_key::col::COLUMNS_WITH_ACCOUNT_ID_IN_KEY;
+use near_primitives::trie_key::trie_key_parsers::parse_account_id_from_raw_key;
+use near_primitives::types::{AccountId, StateRoot};
+
+use super::{Children, NibbleSlice, RawTrieNode, Trie};
+
+/// Change of a single key between two state roots.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TrieDiffItem {
+    pub key: Vec<u8>,
+    /// Value at the old state root, `None` if the key was added.
+    pub old_value: Option<Vec<u8>>,
+    /// Value at the new state root, `None` if the key was removed.
+    pub new_value: Option<Vec<u8>>,
+}
+
+/// Bounds of the changes returned by [`Trie::diff`].
+#[derive(Clone, Copy, Debug, Default)]
+pub struct TrieDiffLimits {
+    /// Maximum number of changes.
+    pub max_changes: Option<usize>,
+    /// Maximum total size of the keys and values of the changes.  The first
+    /// change is returned even if it is larger, so that every page of a diff
+    /// makes progress.
+    pub max_bytes: Option<usize>,
+}
+
+/// Changes between two state roots, ordered by key.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TrieDiff {
+    pub changes: Vec<TrieDiffItem>,
+    /// Key of the first change left out because of the limits.  Passing it as
+    /// `start_key` continues the diff from there.
+    pub next_key: Option<Vec<u8>>,
+}
+
+impl TrieDiffItem {
+    fn size(&self) -> usize {
+        self.key.len()
+            + self.old_value.as_ref().map_or(0, Vec::len)
+            + self.new_value.as_ref().map_or(0, Vec::len)
+    }
+}
+
+/// Position in a trie reached by following some path from the root: the node
+/// the path ends in and the number of nibbles of that node's key already
+/// consumed by the path.  Equal positions at the same path are roots of
+/// identical subtrees.
+#[derive(Clone, Copy, PartialEq, Eq)]
+struct Position {
+    hash: CryptoHash,
+    key_offset: usize,
+}
+
+/// What the trie looks like at a position: the value stored exactly at its
+/// path and the positions one nibble deeper.
+struct PositionView {
+    value: Option<ValueRef>,
+    children: [Option<Position>; 16],
+}
+
+impl PositionView {
+    fn set_branch(&mut self, value: Option<ValueRef>, children: &Children) {
+        self.value = value;
+        for (nibble, child) in children.iter() {
+            self.children[usize::from(nibble)] = Some(Position { hash: *child, key_offset: 0 });
+        }
+    }
+}
+
+impl Trie {
+    /// Returns the changes between the state at this trie's root and the
+    /// state at `new_root` for keys starting with `key_prefix` and not smaller
+    /// than `start_key`, ordered by key and bounded by `limits`.
+    ///
+    /// Both roots must belong to the same shard since nodes of the new trie are
+    /// read from this trie's storage.
+    pub fn diff(
+        &self,
+        new_root: &StateRoot,
+        key_prefix: &[u8],
+        start_key: &[u8],
+        limits: TrieDiffLimits,
+    ) -> Result<TrieDiff, StorageError> {
+        let mut diff = TrieDiff::default();
+        self.diff_into(new_root, key_prefix, start_key, limits, &|_| true, &mut diff)?;
+        Ok(diff)
+    }
+
+    /// Like [`Self::diff`] but only returns changes of keys belonging to the
+    /// given account, e.g. its account record, access keys or contract data.
+    pub fn diff_for_account(
+        &self,
+        new_root: &StateRoot,
+        account_id: &AccountId,
+        start_key: &[u8],
+        limits: TrieDiffLimits,
+    ) -> Result<TrieDiff, StorageError> {
+        let mut diff = TrieDiff::default();
+        // Prefixes of other accounts' keys may match as well, e.g. `alice`
+        // is a prefix of `alice.near`, so every key is checked in full.
+        let is_account_key = |key: &[u8]| match parse_account_id_from_raw_key(key) {
+            Ok(Some(key_account_id)) => &key_account_id == account_id,
+            _ => false,
+        };
+        // Columns are in increasing order, so the changes are sorted by key.
+        for (col, _) in COLUMNS_WITH_ACCOUNT_ID_IN_KEY {
+            let key_prefix = [&[col][..], account_id.as_bytes()].concat();
+            self.diff_into(new_root, &key_prefix, start_key, limits, &is_account_key, &mut diff)?;
+            if diff.next_key.is_some() {
+                break;
+            }
+        }
+        Ok(diff)
+    }
+
+    fn diff_into(
+        &self,
+        new_root: &StateRoot,
+        key_prefix: &[u8],
+        start_key: &[u8],
+        limits: TrieDiffLimits,
+        filter: &dyn Fn(&[u8]) -> bool,
+        diff: &mut TrieDiff,
+    ) -> Result<(), StorageError> {
+        let mut bytes: usize = diff.changes.iter().map(TrieDiffItem::size).sum();
+        let prefix_nibbles: Vec<u8> = NibbleSlice::new(key_prefix).iter().collect();
+        let start_nibbles: Vec<u8> = NibbleSlice::new(start_key).iter().collect();
+        let root_position = |hash: &CryptoHash| {
+            (hash != &Self::EMPTY_ROOT).then_some(Position { hash: *hash, key_offset: 0 })
+        };
+        // Depth-first walk with children pushed in reverse order so that
+        // changes come out sorted by key.  Keys can be thousands of nibbles
+        // long, hence an explicit stack instead of recursion.
+        let mut stack = vec![(vec![], root_position(&self.root), root_position(new_root))];
+        while let Some((path, old, new)) = stack.pop() {
+            if old == new {
+                continue;
+            }
+            let old = self.view_position(old)?;
+            let new = self.view_position(new)?;
+            if old.value != new.value && path.len() >= prefix_nibbles.len() && path >= start_nibbles
+            {
+                let key = nibbles_to_bytes(&path);
+                if filter(&key) {
+                    let size = key.len() + value_len(&old.value) + value_len(&new.value);
+                    let over_changes =
+                        limits.max_changes.is_some_and(|max| diff.changes.len() >= max);
+                    let over_bytes = !diff.changes.is_empty()
+                        && limits.max_bytes.is_some_and(|max| bytes + size > max);
+                    if over_changes || over_bytes {
+                        diff.next_key = Some(key);
+                        break;
+                    }
+                    bytes += size;
+                    diff.changes.push(TrieDiffItem {
+                        key,
+                        old_value: self.retrieve_diff_value(old.value)?,
+                        new_value: self.retrieve_diff_value(new.value)?,
+                    });
+                }
+            }
+            for nibble in (0..16u8).rev() {
+                if prefix_nibbles.get(path.len()).is_some_and(|&expected| expected != nibble) {
+                    continue;
+                }
+                let old_child = old.children[usize::from(nibble)];
+                let new_child = new.children[usize::from(nibble)];
+                if old_child.is_none() && new_child.is_none() {
+                    continue;
+                }
+                let mut child_path = path.clone();
+                child_path.push(nibble);
+                if reaches_start(&child_path, &start_nibbles) {
+                    stack.push((child_path, old_child, new_child));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn view_position(&self, position: Option<Position>) -> Result<PositionView, StorageError> {
+        let mut view = PositionView { value: None, children: [None; 16] };
+        let Some(Position { hash, key_offset }) = position else {
+            return Ok(view);
+        };
+        let Some((_, node)) = self.retrieve_raw_node(&hash, false)? else {
+            return Ok(view);
+        };
+        match node.node {
+            RawTrieNode::Leaf(key, value) => {
+                let key = NibbleSlice::from_encoded(&key).0;
+                if key_offset == key.len() {
+                    view.value = Some(value);
+                } else {
+                    let next = Position { hash, key_offset: key_offset + 1 };
+                    view.children[usize::from(key.at(key_offset))] = Some(next);
+                }
+            }
+            RawTrieNode::Extension(key, child) => {
+                // Extension keys are never empty and the position right after
+                // the end of the key is the child node itself.
+                let key = NibbleSlice::from_encoded(&key).0;
+                let next = if key_offset + 1 == key.len() {
+                    Position { hash: child, key_offset: 0 }
+                } else {
+                    Position { hash, key_offset: key_offset + 1 }
+                };
+                view.children[usize::from(key.at(key_offset))] = Some(next);
+            }
+            RawTrieNode::BranchNoValue(children) => view.set_branch(None, &children),
+            RawTrieNode::BranchWithValue(value, children) => {
+                view.set_branch(Some(value), &children)
+            }
+        }
+        Ok(view)
+    }
+
+    fn retrieve_diff_value(
+        &self,
+        value_ref: Option<ValueRef>,
+    ) -> Result<Option<Vec<u8>>, StorageError> {
+        value_ref
+            .map(|value_ref| Ok(self.internal_retrieve_trie_node(&value_ref.hash, false)?.to_vec()))
+            .transpose()
+    }
+}
+
+fn value_len(value: &Option<ValueRef>) -> usize {
+    value.as_ref().map_or(0, |value| value.length as usize)
+}
+
+/// Whether the subtree at `path` may contain keys not smaller than `start`,
+/// i.e. `path` doesn't diverge from `start` towards smaller nibbles.
+fn reaches_start(path: &[u8], start: &[u8]) -> bool {
+    let len = path.len().min(start.len());
+    path[..len] >= start[..len]
+}
+
+fn nibbles_to_bytes(nibbles: &[u8]) -> Vec<u8> {
+    debug_assert!(nibbles.len() % 2 == 0, "values are only stored at whole bytes");
+    nibbles.chunks_exact(2).map(|pair| pair[0] * 16 + pair[1]).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{TrieDiff, TrieDiffItem, TrieDiffLimits};
+    use crate::test_utils::{gen_changes, test_populate_trie, TestTriesBuilder};
+    use crate::Trie;
+    use near_primitives::shard_layout::ShardUId;
+    use near_primitives::trie_key::TrieKey;
+    use near_primitives::types::AccountId;
+    use rand::Rng;
+    use std::collections::BTreeMap;
+
+    fn collect_state(trie: &Trie) -> BTreeMap<Vec<u8>, Vec<u8>> {
+        trie.disk_iter().unwrap().map(Result::unwrap).collect()
+    }
+
+    fn naive_diff(old: &Trie, new: &Trie, key_prefix: &[u8]) -> Vec<TrieDiffItem> {
+        let old_state = collect_state(old);
+        let new_state = collect_state(new);
+        let mut keys: Vec<_> = old_state.keys().chain(new_state.keys()).cloned().collect();
+        keys.sort();
+        keys.dedup();
+        keys.into_iter()
+            .filter(|key| key.starts_with(key_prefix))
+            .map(|key| TrieDiffItem {
+                old_value: old_state.get(&key).cloned(),
+                new_value: new_state.get(&key).cloned(),
+                key,
+            })
+            .filter(|item| item.old_value != item.new_value)
+            .collect()
+    }
+
+    #[test]
+    fn test_diff_random() {
+        let mut rng = rand::thread_rng();
+        for _ in 0..20 {
+            let tries = TestTriesBuilder::new().build();
+            let shard_uid = ShardUId::single_shard();
+            let changes = gen_changes(&mut rng, 50);
+            // Either no prefix or the first byte of one of the keys.
+            let key_prefix = match rng.gen_bool(0.5) {
+                true => changes[0].0[..1].to_vec(),
+                false => vec![],
+            };
+            let old_root = test_populate_trie(&tries, &Trie::EMPTY_ROOT, shard_uid, changes);
+            let changes = gen_changes(&mut rng, 20);
+            let new_root = test_populate_trie(&tries, &old_root, shard_uid, changes);
+            let old_trie = tries.get_trie_for_shard(shard_uid, old_root);
+            let new_trie = tries.get_trie_for_shard(shard_uid, new_root);
+
+            let expected = naive_diff(&old_trie, &new_trie, &key_prefix);
+            let diff = |start_key: &[u8], limits| {
+                old_trie.diff(&new_root, &key_prefix, start_key, limits).unwrap()
+            };
+            assert_eq!(
+                diff(&[], TrieDiffLimits::default()),
+                TrieDiff { changes: expected.clone(), next_key: None }
+            );
+            if expected.len() > 1 {
+                let limit = expected.len() / 2;
+                let limits = TrieDiffLimits { max_changes: Some(limit), max_bytes: None };
+                assert_eq!(
+                    diff(&[], limits),
+                    TrieDiff {
+                        changes: expected[..limit].to_vec(),
+                        next_key: Some(expected[limit].key.clone()),
+                    }
+                );
+            }
+            // Paging with a byte budget smaller than most changes returns one
+            // change at a time and every change exactly once.
+            let limits = TrieDiffLimits { max_changes: None, max_bytes: Some(1) };
+            let mut paged = vec![];
+            let mut start_key = vec![];
+            loop {
+                let page = diff(&start_key, limits);
+                assert!(page.changes.len() <= 1);
+                paged.extend(page.changes);
+                match page.next_key {
+                    Some(next_key) => start_key = next_key,
+                    None => break,
+                }
+            }
+            assert_eq!(paged, expected);
+            assert_eq!(
+                old_trie.diff(&old_root, &key_prefix, &[], TrieDiffLimits::default()).unwrap(),
+                TrieDiff::default()
+            );
+        }
+    }
+
+    #[test]
+    fn test_diff_for_account() {
+        let tries = TestTriesBuilder::new().build();
+        let shard_uid = ShardUId::single_shard();
+        let alice: AccountId = "alice".parse().unwrap();
+        let alice_near: AccountId = "alice.near".parse().unwrap();
+        let data_key = |account_id: &AccountId, key: &[u8]| {
+            TrieKey::ContractData { account_id: account_id.clone(), key: key.to_vec() }.to_vec()
+        };
+        let old_root = test_populate_trie(
+            &tries,
+            &Trie::EMPTY_ROOT,
+            shard_uid,
+            vec![
+                (data_key(&alice, b"a"), Some(b"1".to_vec())),
+                (data_key(&alice, b"b"), Some(b"2".to_vec())),
+                (data_key(&alice_near, b"a"), Some(b"3".to_vec())),
+            ],
+        );
+        let new_root = test_populate_trie(
+            &tries,
+            &old_root,
+            shard_uid,
+            vec![
+                (data_key(&alice, b"a"), None),
+                (data_key(&alice, b"c"), Some(b"4".to_vec())),
+                (data_key(&alice_near, b"a"), Some(b"5".to_vec())),
+                (TrieKey::Account { account_id: alice.clone() }.to_vec(), Some(b"6".to_vec())),
+            ],
+        );
+        let trie = tries.get_trie_for_shard(shard_uid, old_root);
+        let expected = vec![
+            TrieDiffItem {
+                key: TrieKey::Account { account_id: alice.clone() }.to_vec(),
+                old_value: None,
+                new_value: Some(b"6".to_vec()),
+            },
+            TrieDiffItem {
+                key: data_key(&alice, b"a"),
+                old_value: Some(b"1".to_vec()),
+                new_value: None,
+            },
+            TrieDiffItem {
+                key: data_key(&alice, b"c"),
+                old_value: None,
+                new_value: Some(b"4".to_vec()),
+            },
+        ];
+        let diff = |start_key: &[u8], max_changes| {
+            let limits = TrieDiffLimits { max_changes, max_bytes: None };
+            trie.diff_for_account(&new_root, &alice, start_key, limits).unwrap()
+        };
+        assert_eq!(diff(&[], None), TrieDiff { changes: expected.clone(), next_key: None });
+        assert_eq!(
+            diff(&[], Some(1)),
+            TrieDiff { changes: expected[..1].to_vec(), next_key: Some(expected[1].key.clone()) }
+        );
+        assert_eq!(
+            diff(&expected[1].key, None),
+            TrieDiff { changes: expected[1..].to_vec(), next_key: None }
+        );
+    }
+}
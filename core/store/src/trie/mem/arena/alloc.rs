@@ -4,6 +4,7 @@ use crate::trie::mem::arena::metrics::{
     MEM_TRIE_ARENA_ACTIVE_ALLOCS_BYTES, MEM_TRIE_ARENA_MEMORY_USAGE_BYTES,
 };
 use crate::trie::mem::flexible_data::encoding::BorshFixedSize;
+use borsh::{BorshDeserialize, BorshSerialize};
 use near_o11y::metrics::IntGauge;
 
 /// Simple bump allocator with freelists.
@@ -42,6 +43,16 @@ pub struct Allocator {
     memory_usage_gauge: IntGauge,
 }
 
+/// State of the allocator which has to be persisted together with the arena
+/// memory in order to continue allocating from it after loading.
+#[derive(BorshSerialize, BorshDeserialize)]
+pub(crate) struct AllocatorState {
+    freelists: Vec<ArenaPos>,
+    next_alloc_pos: ArenaPos,
+    active_allocs_bytes: u64,
+    active_allocs_count: u64,
+}
+
 const MAX_ALLOC_SIZE: usize = 16 * 1024;
 const ROUND_UP_TO_8_BYTES_UNDER: usize = 256;
 const ROUND_UP_TO_64_BYTES_UNDER: usize = 1024;
@@ -101,6 +112,26 @@ impl Allocator {
         allocator
     }
 
+    pub(crate) fn state(&self) -> AllocatorState {
+        AllocatorState {
+            freelists: self.freelists.to_vec(),
+            next_alloc_pos: self.next_alloc_pos,
+            active_allocs_bytes: self.active_allocs_bytes as u64,
+            active_allocs_count: self.active_allocs_count as u64,
+        }
+    }
+
+    pub(crate) fn new_from_state(name: String, state: AllocatorState) -> Option<Self> {
+        let mut allocator = Self::new_with_initial_stats(
+            name,
+            usize::try_from(state.active_allocs_bytes).ok()?,
+            usize::try_from(state.active_allocs_count).ok()?,
+        );
+        allocator.freelists = state.freelists.try_into().ok()?;
+        allocator.next_alloc_pos = state.next_alloc_pos;
+        Some(allocator)
+    }
+
     pub fn update_memory_usage_gauge(&self, memory: &STArenaMemory) {
         self.memory_usage_gauge.set(memory.chunks.len() as i64 * CHUNK_SIZE as i64);
     }
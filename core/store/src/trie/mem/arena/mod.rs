@@ -3,6 +3,7 @@ pub mod concurrent;
 mod metrics;
 
 use self::alloc::Allocator;
+pub(crate) use self::alloc::{AllocatorState, CHUNK_SIZE};
 use super::flexible_data::encoding::BorshFixedSize;
 use borsh::{BorshDeserialize, BorshSerialize};
 use derive_where::derive_where;
@@ -172,6 +173,30 @@ impl STArena {
         arena
     }
 
+    /// Creates an arena from the memory and the allocator state of a saved
+    /// arena. Returns `None` if the allocator state doesn't fit this build.
+    pub(crate) fn new_from_saved(
+        name: String,
+        chunks: Vec<Vec<u8>>,
+        allocator_state: AllocatorState,
+    ) -> Option<Self> {
+        let arena = Self {
+            memory: STArenaMemory { chunks },
+            allocator: Allocator::new_from_state(name, allocator_state)?,
+        };
+        arena.allocator.update_memory_usage_gauge(&arena.memory);
+        Some(arena)
+    }
+
+    /// Raw memory chunks of the arena.
+    pub(crate) fn chunks(&self) -> &[Vec<u8>] {
+        &self.memory.chunks
+    }
+
+    pub(crate) fn allocator_state(&self) -> AllocatorState {
+        self.allocator.state()
+    }
+
     /// Number of active allocations (alloc calls minus dealloc calls).
     #[cfg(test)]
     pub fn num_active_allocs(&self) -> usize {
@@ -107,7 +107,7 @@ pub fn load_trie_from_flat_state(
     Ok(tries)
 }
 
-fn get_state_root(
+pub(crate) fn get_state_root(
     store: &Store,
     block_hash: CryptoHash,
     shard_uid: ShardUId,
@@ -160,21 +160,36 @@ pub fn load_trie_from_flat_state_and_delta(
     let mut mem_tries =
         load_trie_from_flat_state(&store, shard_uid, state_root, flat_head.height, parallelize)
             .unwrap();
+    apply_flat_state_deltas(store, shard_uid, &mut mem_tries)?;
+    Ok(mem_tries)
+}
 
+/// Applies all the flat storage deltas of the shard to the tries, which must
+/// already contain the trie at the flat storage head. Deltas of blocks whose
+/// roots the tries already have at the block height are skipped.
+pub(crate) fn apply_flat_state_deltas(
+    store: &Store,
+    shard_uid: ShardUId,
+    mem_tries: &mut MemTries,
+) -> Result<(), StorageError> {
     debug!(target: "memtrie", %shard_uid, "Loading flat state deltas...");
     // We load the deltas in order of height, so that we always have the previous state root
     // already loaded.
     let mut sorted_deltas: BTreeSet<(BlockHeight, CryptoHash, CryptoHash)> = Default::default();
-    for delta in get_all_deltas_metadata(&store, shard_uid).unwrap() {
+    for delta in get_all_deltas_metadata(store, shard_uid).unwrap() {
         sorted_deltas.insert((delta.block.height, delta.block.hash, delta.block.prev_hash));
     }
 
     debug!(target: "memtrie", %shard_uid, "{} deltas to apply", sorted_deltas.len());
     for (height, hash, prev_hash) in sorted_deltas.into_iter() {
-        let delta = get_delta_changes(&store, shard_uid, hash).unwrap();
+        let delta = get_delta_changes(store, shard_uid, hash).unwrap();
         if let Some(changes) = delta {
             let old_state_root = get_state_root(store, prev_hash, shard_uid)?;
             let new_state_root = get_state_root(store, hash, shard_uid)?;
+            if mem_tries.heights.get(&height).is_some_and(|roots| roots.contains(&new_state_root)) {
+                debug!(target: "memtrie", %shard_uid, "Memtrie already has the root for height {}", height);
+                continue;
+            }
 
             let mut trie_update = mem_tries.update(old_state_root, false)?;
             for (key, value) in changes.0 {
@@ -187,15 +202,14 @@ pub fn load_trie_from_flat_state_and_delta(
             }
 
             let mem_trie_changes = trie_update.to_mem_trie_changes_only();
-            let new_root_after_apply =
-                apply_memtrie_changes(&mut mem_tries, &mem_trie_changes, height);
+            let new_root_after_apply = apply_memtrie_changes(mem_tries, &mem_trie_changes, height);
             assert_eq!(new_root_after_apply, new_state_root);
         }
         debug!(target: "memtrie", %shard_uid, "Applied memtrie changes for height {}", height);
     }
 
     debug!(target: "memtrie", %shard_uid, "Done loading memtries for shard");
-    Ok(mem_tries)
+    Ok(())
 }
 
 #[cfg(test)]
@@ -209,6 +223,9 @@ mod tests {
     };
     use crate::trie::mem::loading::load_trie_from_flat_state;
     use crate::trie::mem::lookup::memtrie_lookup;
+    use crate::trie::mem::snapshot::{
+        load_trie_from_snapshot_and_delta, save_snapshot, snapshot_path,
+    };
     use crate::{DBCol, KeyLookupMode, NibbleSlice, ShardTries, Store, Trie, TrieUpdate};
     use near_primitives::congestion_info::CongestionInfo;
     use near_primitives::hash::CryptoHash;
@@ -528,6 +545,118 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_memtrie_load_from_snapshot() {
+        let test_key = TrieKey::ContractData {
+            account_id: "test_account".parse().unwrap(),
+            key: b"test_key".to_vec(),
+        };
+        let chain = MockChain::linear_chain(4);
+        let store = create_test_store();
+        let shard_tries = TestTriesBuilder::new().with_store(store.clone()).build();
+        let shard_uid = ShardUId { version: 1, shard_id: 1 };
+        let dir = tempfile::tempdir().unwrap();
+
+        let mut store_update = shard_tries.store_update();
+        store_helper::set_flat_storage_status(
+            &mut store_update,
+            shard_uid,
+            FlatStorageStatus::Ready(FlatStorageReadyStatus { flat_head: chain.get_block(0) }),
+        );
+        store_helper::set_flat_state_value(
+            &mut store_update,
+            shard_uid,
+            test_key.to_vec(),
+            Some(FlatStateValue::inlined(b"test_val0")),
+        );
+        store_update.commit().unwrap();
+        let state_root_0 = test_populate_trie(
+            &shard_tries,
+            &Trie::EMPTY_ROOT,
+            shard_uid,
+            vec![(test_key.to_vec(), Some(b"test_val0".to_vec()))],
+        );
+        write_chunk_extra(&store, chain.get_block(0).hash, shard_uid, state_root_0);
+        let state_root_1 = apply_trie_changes(
+            &shard_tries,
+            shard_uid,
+            state_root_0,
+            chain.get_block(1),
+            vec![(test_key.clone(), b"test_val1".to_vec())],
+        );
+        write_chunk_extra(&store, chain.get_block(1).hash, shard_uid, state_root_1);
+
+        // No snapshot yet.
+        assert!(load_trie_from_snapshot_and_delta(dir.path(), &store, shard_uid)
+            .unwrap()
+            .is_none());
+        let mem_tries =
+            load_trie_from_flat_state_and_delta(&store, shard_uid, None, false).unwrap();
+        save_snapshot(dir.path(), &store, &mem_tries).unwrap();
+
+        // The block processed after the snapshot is applied from its delta.
+        let state_root_2 = apply_trie_changes(
+            &shard_tries,
+            shard_uid,
+            state_root_1,
+            chain.get_block(2),
+            vec![(test_key.clone(), b"test_val2".to_vec())],
+        );
+        write_chunk_extra(&store, chain.get_block(2).hash, shard_uid, state_root_2);
+
+        let mem_tries =
+            load_trie_from_snapshot_and_delta(dir.path(), &store, shard_uid).unwrap().unwrap();
+        assert_eq!(mem_tries.num_roots(), 3);
+        for (state_root, value) in [
+            (state_root_0, b"test_val0"),
+            (state_root_1, b"test_val1"),
+            (state_root_2, b"test_val2"),
+        ] {
+            assert_eq!(
+                memtrie_lookup(mem_tries.get_root(&state_root).unwrap(), &test_key.to_vec(), None)
+                    .map(|v| v.to_flat_value()),
+                Some(FlatStateValue::inlined(value))
+            );
+        }
+
+        // A snapshot taken at another flat storage head is ignored.
+        let mut store_update = shard_tries.store_update();
+        store_helper::set_flat_storage_status(
+            &mut store_update,
+            shard_uid,
+            FlatStorageStatus::Ready(FlatStorageReadyStatus { flat_head: chain.get_block(1) }),
+        );
+        store_update.commit().unwrap();
+        assert!(load_trie_from_snapshot_and_delta(dir.path(), &store, shard_uid)
+            .unwrap()
+            .is_none());
+
+        // A corrupted snapshot is an error.
+        save_snapshot(dir.path(), &store, &mem_tries).unwrap();
+        let path = snapshot_path(dir.path(), shard_uid);
+        let mut data = std::fs::read(&path).unwrap();
+        let len = data.len();
+        data[len - 100] ^= 1;
+        std::fs::write(&path, data).unwrap();
+        assert!(load_trie_from_snapshot_and_delta(dir.path(), &store, shard_uid).is_err());
+
+        // A header length beyond the end of the file is an error, rather than an allocation.
+        save_snapshot(dir.path(), &store, &mem_tries).unwrap();
+        let mut data = std::fs::read(&path).unwrap();
+        data[16..24].copy_from_slice(&(u64::MAX / 2).to_le_bytes());
+        std::fs::write(&path, data).unwrap();
+        assert!(load_trie_from_snapshot_and_delta(dir.path(), &store, shard_uid).is_err());
+
+        // A snapshot taken by a build with another arena layout is ignored.
+        save_snapshot(dir.path(), &store, &mem_tries).unwrap();
+        let mut data = std::fs::read(&path).unwrap();
+        data[12] ^= 1;
+        std::fs::write(&path, data).unwrap();
+        assert!(load_trie_from_snapshot_and_delta(dir.path(), &store, shard_uid)
+            .unwrap()
+            .is_none());
+    }
+
     /// Makes the given changes to both the trie and flat storage.
     fn apply_trie_changes(
         tries: &ShardTries,
@@ -0,0 +1,313 @@
+//! Snapshot files of in-memory tries.
+//!
+//! Loading memtries from flat storage takes minutes for large shards, as the
+//! whole trie has to be reconstructed and rehashed. Instead, on shutdown the
+//! arena memory of each shard is dumped to a file together with the roots it
+//! holds. On the next startup, if the flat storage head hasn't moved since,
+//! the arena is read back as is and only the flat storage deltas are applied
+//! on top of it.
+//!
+//! The file consists of a magic, a version and a fingerprint of the arena
+//! layout, a borsh serialized `SnapshotHeader` prefixed with its length, the
+//! raw memory chunks of the arena and a CRC32 checksum of everything before it.
+use super::arena::{AllocatorState, Arena, ArenaPos, STArena, CHUNK_SIZE};
+use super::construction::TrieConstructor;
+use super::loading::{apply_flat_state_deltas, get_state_root};
+use super::metrics::MEM_TRIE_NUM_ROOTS;
+use super::node::MemTrieNodeId;
+use super::MemTries;
+use crate::flat::store_helper::get_flat_storage_status;
+use crate::flat::{BlockInfo, FlatStorageStatus};
+use crate::{NibbleSlice, Store};
+use anyhow::Context;
+use borsh::{BorshDeserialize, BorshSerialize};
+use near_primitives::hash::{hash, CryptoHash};
+use near_primitives::shard_layout::ShardUId;
+use near_primitives::state::FlatStateValue;
+use near_primitives::types::{BlockHeight, StateRoot};
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+use tracing::info;
+
+const MAGIC: &[u8; 8] = b"MEMTRIE\0";
+/// Version of the file format. Changes of the arena layout are detected by
+/// `layout_fingerprint` instead.
+const VERSION: u32 = 2;
+/// Length of the magic, version, layout fingerprint and header length.
+const PREFIX_LEN: u64 = MAGIC.len() as u64 + 4 + 4 + 8;
+/// Length of the trailing checksum.
+const CHECKSUM_LEN: u64 = 4;
+/// Upper bound of the header length, to not allocate whatever length a
+/// corrupted file claims before its checksum is verified. The header mostly
+/// consists of the roots of non-final blocks, which take a few KiB.
+const MAX_HEADER_LEN: u64 = 64 * 1024 * 1024;
+
+#[derive(BorshSerialize, BorshDeserialize)]
+struct SnapshotHeader {
+    shard_uid: ShardUId,
+    /// Flat storage head at the moment the snapshot was taken. The snapshot
+    /// is only used if the flat storage head is still the same on startup.
+    flat_head_hash: CryptoHash,
+    flat_head_height: BlockHeight,
+    chunk_lens: Vec<u64>,
+    allocator: AllocatorState,
+    roots: Vec<(StateRoot, Vec<ArenaPos>)>,
+    heights: Vec<(BlockHeight, Vec<StateRoot>)>,
+}
+
+/// Path of the snapshot file of the given shard.
+pub fn snapshot_path(dir: &Path, shard_uid: ShardUId) -> PathBuf {
+    dir.join(format!("memtrie-{}.snapshot", shard_uid))
+}
+
+struct ChecksumWriter<W> {
+    inner: W,
+    hasher: crc32fast::Hasher,
+}
+
+impl<W: Write> Write for ChecksumWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.hasher.update(&buf[..written]);
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+struct ChecksumReader<R> {
+    inner: R,
+    hasher: crc32fast::Hasher,
+}
+
+impl<R: Read> Read for ChecksumReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        self.hasher.update(&buf[..read]);
+        Ok(read)
+    }
+}
+
+/// Fingerprint of the encoding of trie nodes in the arena memory, which a
+/// snapshot stores as is. It's computed by building a small trie with nodes
+/// of all kinds and checksumming the resulting memory, so any change of the
+/// node encoding, the hashing or the allocator makes older snapshots unusable
+/// without having to bump `VERSION` by hand.
+fn layout_fingerprint() -> u32 {
+    let mut arena = STArena::new("memtrie_snapshot_layout".to_string());
+    let mut constructor = TrieConstructor::new(&mut arena);
+    // A branch with an extension to a branch with value and two leaves, with
+    // an inlined value and a value reference.
+    constructor.add_leaf(NibbleSlice::new(&[0x01]), FlatStateValue::inlined(&[1]));
+    constructor.add_leaf(NibbleSlice::new(&[0x01, 0x23]), FlatStateValue::inlined(&[2; 10]));
+    constructor.add_leaf(NibbleSlice::new(&[0x45, 0x67]), FlatStateValue::value_ref(&[3; 100]));
+    let root = constructor.finalize().expect("trie is not empty");
+
+    let mut hasher = crc32fast::Hasher::new();
+    hasher.update(root.as_ptr(arena.memory()).view().node_hash().as_bytes());
+    hasher.update(&borsh::to_vec(&arena.allocator_state()).expect("borsh can't fail"));
+    for chunk in arena.chunks() {
+        hasher.update(chunk);
+    }
+    hasher.finalize()
+}
+
+fn flat_head(store: &Store, shard_uid: ShardUId) -> anyhow::Result<BlockInfo> {
+    match get_flat_storage_status(store, shard_uid)? {
+        FlatStorageStatus::Ready(status) => Ok(status.flat_head),
+        other => anyhow::bail!("flat storage is not ready, actual status: {:?}", other),
+    }
+}
+
+/// Saves the tries of a shard to a snapshot file in `dir`, replacing the
+/// previous snapshot of the shard. The tries must contain the trie at the
+/// current flat storage head.
+pub fn save_snapshot(dir: &Path, store: &Store, mem_tries: &MemTries) -> anyhow::Result<()> {
+    let shard_uid = mem_tries.shard_uid;
+    let start = Instant::now();
+    let flat_head = flat_head(store, shard_uid)?;
+    let flat_head_root = get_state_root(store, flat_head.hash, shard_uid)?;
+    if flat_head_root != CryptoHash::default() && mem_tries.get_root(&flat_head_root).is_none() {
+        anyhow::bail!(
+            "memtrie doesn't have the root {} of the flat storage head {}",
+            flat_head_root,
+            flat_head.hash
+        );
+    }
+
+    let chunks = mem_tries.arena.chunks();
+    let header = SnapshotHeader {
+        shard_uid,
+        flat_head_hash: flat_head.hash,
+        flat_head_height: flat_head.height,
+        chunk_lens: chunks.iter().map(|chunk| chunk.len() as u64).collect(),
+        allocator: mem_tries.arena.allocator_state(),
+        roots: mem_tries
+            .roots
+            .iter()
+            .map(|(root, ids)| (*root, ids.iter().map(|id| id.pos).collect()))
+            .collect(),
+        heights: mem_tries.heights.iter().map(|(height, roots)| (*height, roots.clone())).collect(),
+    };
+    let header = borsh::to_vec(&header)?;
+
+    std::fs::create_dir_all(dir)?;
+    let path = snapshot_path(dir, shard_uid);
+    let tmp_path = path.with_extension("snapshot.tmp");
+    let file = File::create(&tmp_path)
+        .with_context(|| format!("Unable to create {}", tmp_path.display()))?;
+    let mut writer =
+        ChecksumWriter { inner: BufWriter::new(file), hasher: crc32fast::Hasher::new() };
+    writer.write_all(MAGIC)?;
+    writer.write_all(&VERSION.to_le_bytes())?;
+    writer.write_all(&layout_fingerprint().to_le_bytes())?;
+    writer.write_all(&(header.len() as u64).to_le_bytes())?;
+    writer.write_all(&header)?;
+    for chunk in chunks {
+        writer.write_all(chunk)?;
+    }
+    let checksum = writer.hasher.finalize();
+    let mut writer = writer.inner;
+    writer.write_all(&checksum.to_le_bytes())?;
+    writer.into_inner().map_err(|err| err.into_error())?.sync_all()?;
+    std::fs::rename(&tmp_path, &path)?;
+
+    info!(target: "memtrie", %shard_uid, path = %path.display(), "Saved memtrie snapshot, took {:?}", start.elapsed());
+    Ok(())
+}
+
+fn read_u32(reader: &mut impl Read) -> io::Result<u32> {
+    let mut buf = [0; 4];
+    reader.read_exact(&mut buf)?;
+    Ok(u32::from_le_bytes(buf))
+}
+
+fn read_u64(reader: &mut impl Read) -> io::Result<u64> {
+    let mut buf = [0; 8];
+    reader.read_exact(&mut buf)?;
+    Ok(u64::from_le_bytes(buf))
+}
+
+/// Loads the tries of a shard from its snapshot file in `dir` and applies
+/// the flat storage deltas on top of them, so that the result is the same as
+/// of `load_trie_from_flat_state_and_delta`.
+///
+/// Returns `None` if there is no snapshot or it's been taken at another
+/// flat storage head, and an error if the snapshot is corrupted.
+pub fn load_trie_from_snapshot_and_delta(
+    dir: &Path,
+    store: &Store,
+    shard_uid: ShardUId,
+) -> anyhow::Result<Option<MemTries>> {
+    let path = snapshot_path(dir, shard_uid);
+    let file = match File::open(&path) {
+        Ok(file) => file,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(err) => return Err(err).context(format!("Unable to open {}", path.display())),
+    };
+    let start = Instant::now();
+    let file_len = file.metadata()?.len();
+    let mut reader =
+        ChecksumReader { inner: BufReader::new(file), hasher: crc32fast::Hasher::new() };
+
+    let mut magic = [0; MAGIC.len()];
+    reader.read_exact(&mut magic)?;
+    anyhow::ensure!(&magic == MAGIC, "{} is not a memtrie snapshot", path.display());
+    let version = read_u32(&mut reader)?;
+    anyhow::ensure!(version == VERSION, "unsupported memtrie snapshot version {}", version);
+    let fingerprint = read_u32(&mut reader)?;
+    if fingerprint != layout_fingerprint() {
+        info!(
+            target: "memtrie",
+            %shard_uid,
+            "Memtrie snapshot was taken by a build with another arena layout, ignoring it"
+        );
+        return Ok(None);
+    }
+    let header_len = read_u64(&mut reader)?;
+    anyhow::ensure!(
+        header_len <= MAX_HEADER_LEN && PREFIX_LEN + header_len + CHECKSUM_LEN <= file_len,
+        "memtrie snapshot header length {} is invalid",
+        header_len
+    );
+    let mut header = vec![0; usize::try_from(header_len)?];
+    reader.read_exact(&mut header)?;
+    let header = SnapshotHeader::try_from_slice(&header)?;
+    anyhow::ensure!(
+        header.shard_uid == shard_uid,
+        "snapshot is of shard {}, expected {}",
+        header.shard_uid,
+        shard_uid
+    );
+
+    let flat_head = flat_head(store, shard_uid)?;
+    if header.flat_head_hash != flat_head.hash {
+        info!(
+            target: "memtrie",
+            %shard_uid,
+            snapshot_flat_head = %header.flat_head_hash,
+            flat_head = %flat_head.hash,
+            "Memtrie snapshot was taken at another flat storage head, ignoring it"
+        );
+        return Ok(None);
+    }
+
+    let chunks_len = file_len - PREFIX_LEN - header_len - CHECKSUM_LEN;
+    anyhow::ensure!(
+        header.chunk_lens.iter().all(|len| *len == CHUNK_SIZE as u64)
+            && header.chunk_lens.len() as u64 * CHUNK_SIZE as u64 == chunks_len,
+        "memtrie snapshot chunk lengths don't match the file length {}",
+        file_len
+    );
+    let mut chunks = Vec::with_capacity(header.chunk_lens.len());
+    for len in &header.chunk_lens {
+        let mut chunk = vec![0; usize::try_from(*len)?];
+        reader.read_exact(&mut chunk)?;
+        chunks.push(chunk);
+    }
+    let checksum = reader.hasher.finalize();
+    let expected_checksum = read_u32(&mut reader.inner)?;
+    anyhow::ensure!(checksum == expected_checksum, "memtrie snapshot checksum mismatch");
+
+    let arena = STArena::new_from_saved(shard_uid.to_string(), chunks, header.allocator)
+        .context("memtrie snapshot allocator state doesn't match this build")?;
+    let mut mem_tries = MemTries {
+        arena,
+        roots: header
+            .roots
+            .into_iter()
+            .map(|(root, positions)| {
+                (root, positions.into_iter().map(|pos| MemTrieNodeId { pos }).collect())
+            })
+            .collect(),
+        heights: header.heights.into_iter().collect(),
+        shard_uid,
+    };
+    MEM_TRIE_NUM_ROOTS
+        .with_label_values(&[&shard_uid.to_string()])
+        .set(mem_tries.roots.len() as i64);
+
+    let flat_head_root = get_state_root(store, flat_head.hash, shard_uid)?;
+    if flat_head_root != CryptoHash::default() {
+        let root = mem_tries
+            .get_root(&flat_head_root)
+            .context("memtrie snapshot doesn't have the root of the flat storage head")?;
+        // The stored hash of the root is what it's looked up by, so recompute it from the
+        // node itself.
+        let root_node = root.view().to_raw_trie_node_with_size();
+        anyhow::ensure!(
+            hash(&borsh::to_vec(&root_node)?) == flat_head_root,
+            "memtrie snapshot has incorrect root {}",
+            flat_head_root
+        );
+    }
+    mem_tries.delete_until_height(flat_head.height);
+    info!(target: "memtrie", %shard_uid, "Loaded memtrie snapshot, took {:?}", start.elapsed());
+
+    apply_flat_state_deltas(store, shard_uid, &mut mem_tries)?;
+    Ok(Some(mem_tries))
+}
@@ -12,6 +12,7 @@ pub use crate::trie::config::TrieConfig;
 pub(crate) use crate::trie::config::{
     DEFAULT_SHARD_CACHE_DELETIONS_QUEUE_CAPACITY, DEFAULT_SHARD_CACHE_TOTAL_SIZE_LIMIT,
 };
+pub use crate::trie::diff::{TrieDiff, TrieDiffItem, TrieDiffLimits};
 use crate::trie::insert_delete::NodesStorage;
 use crate::trie::iterator::TrieIterator;
 pub use crate::trie::nibble_slice::NibbleSlice;
@@ -43,6 +44,7 @@ use std::sync::{Arc, RwLock, RwLockReadGuard};
 
 pub mod accounting_cache;
 mod config;
+mod diff;
 mod from_flat;
 mod insert_delete;
 pub mod iterator;
@@ -5,6 +5,7 @@ use crate::flat::store_helper::remove_all_state_values;
 use crate::flat::{FlatStorageManager, FlatStorageStatus};
 use crate::trie::config::TrieConfig;
 use crate::trie::mem::loading::load_trie_from_flat_state_and_delta;
+use crate::trie::mem::snapshot::{load_trie_from_snapshot_and_delta, save_snapshot};
 use crate::trie::mem::updating::apply_memtrie_changes;
 use crate::trie::prefetching_trie_storage::PrefetchingThreadsHandle;
 use crate::trie::trie_storage::{TrieCache, TrieCachingStorage};
@@ -21,7 +22,7 @@ use near_primitives::types::{
 use rayon::prelude::{IntoParallelRefIterator, ParallelIterator};
 use std::collections::HashMap;
 use std::sync::{Arc, Mutex, RwLock};
-use tracing::info;
+use tracing::{info, warn};
 
 struct ShardTriesInner {
     store: Store,
@@ -417,12 +418,28 @@ impl ShardTries {
         parallelize: bool,
     ) -> Result<(), StorageError> {
         info!(target: "memtrie", "Loading trie to memory for shard {:?}...", shard_uid);
-        let mem_tries = load_trie_from_flat_state_and_delta(
-            &self.0.store,
-            *shard_uid,
-            state_root,
-            parallelize,
-        )?;
+        // On catchup the state root is given because the shard's data isn't
+        // complete yet, so a snapshot can't possibly match it.
+        let snapshot_dir = self.0.trie_config.mem_trie_snapshot_dir.as_ref();
+        let snapshot = match snapshot_dir.filter(|_| state_root.is_none()) {
+            Some(dir) => match load_trie_from_snapshot_and_delta(dir, &self.0.store, *shard_uid) {
+                Ok(mem_tries) => mem_tries,
+                Err(err) => {
+                    warn!(target: "memtrie", ?shard_uid, ?err, "Failed to load memtrie snapshot, loading from flat storage");
+                    None
+                }
+            },
+            None => None,
+        };
+        let mem_tries = match snapshot {
+            Some(mem_tries) => mem_tries,
+            None => load_trie_from_flat_state_and_delta(
+                &self.0.store,
+                *shard_uid,
+                state_root,
+                parallelize,
+            )?,
+        };
         self.0.mem_tries.write().unwrap().insert(*shard_uid, Arc::new(RwLock::new(mem_tries)));
         info!(target: "memtrie", "Memtrie loading complete for shard {:?}", shard_uid);
         Ok(())
@@ -474,6 +491,21 @@ impl ShardTries {
         Ok(())
     }
 
+    /// Saves the loaded in-memory tries to snapshot files, if the snapshots
+    /// are enabled. Meant to be called on shutdown, after the last block has
+    /// been processed. Errors are logged, as the tries can always be loaded
+    /// from flat storage instead.
+    pub fn save_mem_trie_snapshots(&self) {
+        let Some(dir) = &self.0.trie_config.mem_trie_snapshot_dir else { return };
+        let mem_tries = self.0.mem_tries.read().unwrap();
+        for (shard_uid, mem_tries) in mem_tries.iter() {
+            let mem_tries = mem_tries.read().unwrap();
+            if let Err(err) = save_snapshot(dir, &self.0.store, &mem_tries) {
+                warn!(target: "memtrie", ?shard_uid, ?err, "Failed to save memtrie snapshot");
+            }
+        }
+    }
+
     /// Retrieves the in-memory tries for the shard.
     pub fn get_mem_tries(&self, shard_uid: ShardUId) -> Option<Arc<RwLock<MemTries>>> {
         let guard = self.0.mem_tries.read().unwrap();
@@ -202,6 +202,12 @@ impl TrieUpdate {
         self.prospective.clear();
     }
 
+    /// Returns the changes committed so far, ordered by raw key, without
+    /// applying them to the trie.
+    pub fn committed_changes(&self) -> impl Iterator<Item = &RawStateChangesWithTrieKey> {
+        self.committed.values()
+    }
+
     /// Prepare the accumulated state changes to be applied to the underlying storage.
     ///
     /// This Function returns the [`Trie`] with which the [`TrieUpdate`] has been initially
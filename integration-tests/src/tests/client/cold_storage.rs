@@ -14,13 +14,15 @@ use near_primitives::transaction::{
 use near_primitives_core::hash::CryptoHash;
 use near_primitives_core::types::AccountId;
 use near_store::cold_storage::{
-    copy_all_data_to_cold, test_cold_genesis_update, test_get_store_initial_writes,
-    test_get_store_reads, update_cold_db, update_cold_head,
+    add_cold_db_update, check_cold_db_update, copy_all_data_to_cold, prune_cold_db,
+    test_cold_genesis_update, test_get_store_initial_writes, test_get_store_reads, update_cold_db,
+    update_cold_head, update_cold_tail, ColdStoreRetentionPolicy,
 };
+use near_store::db::{DBTransaction, Database};
 use near_store::metadata::DbKind;
 use near_store::metadata::DB_VERSION;
 use near_store::test_utils::create_test_node_storage_with_cold;
-use near_store::{DBCol, Store, COLD_HEAD_KEY, HEAD_KEY};
+use near_store::{DBCol, Store, COLD_HEAD_KEY, COLD_TAIL_KEY, HEAD_KEY};
 use nearcore::test_utils::TestEnvNightshadeSetupExt;
 use nearcore::{cold_storage::spawn_cold_store_loop, NearConfig};
 use std::collections::HashSet;
@@ -153,7 +155,8 @@ fn test_storage_after_commit_of_cold_update() {
         let client_store = client.runtime_adapter.store();
         let epoch_id = client.epoch_manager.get_epoch_id_from_prev_block(&last_hash).unwrap();
         let shard_layout = client.epoch_manager.get_shard_layout(&epoch_id).unwrap();
-        update_cold_db(cold_db, &client_store, &shard_layout, &height, 4).unwrap();
+        update_cold_db(cold_db, &client_store, &shard_layout, &height, 4, &Default::default())
+            .unwrap();
 
         last_hash = *block.hash();
     }
@@ -208,6 +211,102 @@ fn test_storage_after_commit_of_cold_update() {
     }
 }
 
+/// Producing 4 epochs of blocks with transactions and copying them to cold
+/// storage without the StateChanges column.  Then pruning the first two
+/// epochs and checking that their blocks and transactions are gone from cold
+/// storage while the later ones and the State column are intact.
+#[test]
+fn test_cold_db_prune() {
+    init_test_logger();
+
+    let epoch_length = 5;
+    let max_height = epoch_length * 4;
+    let prune_height = epoch_length * 2;
+
+    let mut genesis = Genesis::test(vec![test0(), test1()], 1);
+    genesis.config.epoch_length = epoch_length;
+    let (storage, ..) = create_test_node_storage_with_cold(DB_VERSION, DbKind::Hot);
+    let hot_store = &storage.get_hot_store();
+    let cold_store = &storage.get_cold_store().unwrap();
+    let cold_db = storage.cold_db().unwrap();
+    let mut env = TestEnv::builder(&genesis.config)
+        .stores(vec![hot_store.clone()])
+        .nightshade_runtimes(&genesis)
+        .build();
+
+    let retention = ColdStoreRetentionPolicy {
+        num_epochs: Some(2),
+        columns: Some(
+            DBCol::iter().filter(|col| col.is_cold() && *col != DBCol::StateChanges).collect(),
+        ),
+    };
+    retention.validate().unwrap();
+
+    let signer = InMemorySigner::from_seed(test0(), KeyType::ED25519, "test0").into();
+    let mut last_hash = *env.clients[0].chain.genesis().hash();
+    let mut tx_hashes = vec![];
+    for height in 1..max_height {
+        let tx = create_tx_send_money(height, &signer, last_hash);
+        tx_hashes.push((height, tx.get_hash()));
+        assert_eq!(env.clients[0].process_tx(tx, false, false), ProcessTxResponse::ValidTx);
+        let block = env.clients[0].produce_block(height).unwrap().unwrap();
+        env.process_block(0, block.clone(), Provenance::PRODUCED);
+
+        let client = &env.clients[0];
+        let epoch_id = client.epoch_manager.get_epoch_id_from_prev_block(&last_hash).unwrap();
+        let shard_layout = client.epoch_manager.get_shard_layout(&epoch_id).unwrap();
+        update_cold_db(&cold_db, hot_store, &shard_layout, &height, 1, &retention).unwrap();
+        last_hash = *block.hash();
+    }
+    assert_eq!(cold_store.iter(DBCol::StateChanges).count(), 0);
+    let num_state_nodes = cold_store.iter(DBCol::State).count();
+    assert!(num_state_nodes > 0);
+
+    let block_hashes = (1..max_height)
+        .map(|height| {
+            hot_store
+                .get_ser::<CryptoHash>(DBCol::BlockHeight, &height.to_le_bytes())
+                .unwrap()
+                .unwrap()
+        })
+        .collect::<Vec<_>>();
+    for height in 1..prune_height {
+        let block_hash = block_hashes[height as usize - 1];
+        let epoch_id = env.clients[0].epoch_manager.get_epoch_id(&block_hash).unwrap();
+        let shard_layout = env.clients[0].epoch_manager.get_shard_layout(&epoch_id).unwrap();
+        assert!(prune_cold_db(&cold_db, cold_store, &shard_layout, height, &block_hash).unwrap());
+        // Pruning again is a no-op.
+        assert!(!prune_cold_db(&cold_db, cold_store, &shard_layout, height, &block_hash).unwrap());
+    }
+    update_cold_tail(&cold_db, hot_store, prune_height).unwrap();
+
+    for height in 1..max_height {
+        let block_hash = block_hashes[height as usize - 1];
+        let kept = height >= prune_height;
+        assert_eq!(cold_store.exists(DBCol::Block, block_hash.as_ref()).unwrap(), kept);
+        assert_eq!(cold_store.exists(DBCol::BlockPerHeight, &height.to_le_bytes()).unwrap(), kept);
+    }
+    // Transactions end up in one of the next chunks, so the ones close to
+    // the boundaries are not checked.
+    for (height, tx_hash) in tx_hashes {
+        let exists = cold_store.exists(DBCol::Transactions, tx_hash.as_ref()).unwrap();
+        if height + 2 < prune_height {
+            assert!(!exists);
+        } else if height >= prune_height && height + 2 < max_height {
+            assert!(exists);
+        }
+    }
+    assert_eq!(cold_store.iter(DBCol::State).count(), num_state_nodes);
+    assert_eq!(
+        hot_store.get_ser::<u64>(DBCol::BlockMisc, COLD_TAIL_KEY).unwrap(),
+        Some(prune_height)
+    );
+    assert_eq!(
+        cold_store.get_ser::<u64>(DBCol::BlockMisc, COLD_TAIL_KEY).unwrap(),
+        Some(prune_height)
+    );
+}
+
 /// Producing 10 * 5 blocks and updating HEAD of cold storage after each one.
 /// After every update checking that HEAD in cold db, COLD_HEAD in hot db and HEAD in hot store are equal.
 #[test]
@@ -295,8 +394,15 @@ fn test_cold_db_copy_with_height_skips() {
         let client = &env.clients[0];
         let epoch_id = client.epoch_manager.get_epoch_id_from_prev_block(&last_hash).unwrap();
         let shard_layout = client.epoch_manager.get_shard_layout(&epoch_id).unwrap();
-        update_cold_db(&cold_db, &client.runtime_adapter.store(), &shard_layout, &height, 1)
-            .unwrap();
+        update_cold_db(
+            &cold_db,
+            &client.runtime_adapter.store(),
+            &shard_layout,
+            &height,
+            1,
+            &Default::default(),
+        )
+        .unwrap();
 
         if block.is_some() {
             last_hash = *block.unwrap().hash();
@@ -340,6 +446,82 @@ fn test_cold_db_copy_with_height_skips() {
     }
 }
 
+/// Producing 4 epochs of blocks with some transactions and copying them to
+/// cold storage in a few batched writes, like the backfill of the cold-store
+/// tool does.  Checks that `check_cold_db_update` notices the blocks missing
+/// before the writes and accepts every block after them.
+#[test]
+fn test_cold_db_batched_update() {
+    init_test_logger();
+
+    let epoch_length = 5;
+    let max_height = epoch_length * 4;
+
+    let mut genesis = Genesis::test(vec![test0(), test1()], 1);
+    genesis.config.epoch_length = epoch_length;
+    genesis.config.min_gas_price = 0;
+    let mut env = TestEnv::builder(&genesis.config).nightshade_runtimes(&genesis).build();
+
+    let (storage, ..) = create_test_node_storage_with_cold(DB_VERSION, DbKind::Hot);
+    let cold_db = storage.cold_db().unwrap();
+    let cold_store = storage.get_cold_store().unwrap();
+
+    test_cold_genesis_update(&cold_db, &env.clients[0].runtime_adapter.store()).unwrap();
+
+    let mut last_hash = *env.clients[0].chain.genesis().hash();
+    for height in 1..max_height {
+        let signer = InMemorySigner::from_seed(test0(), KeyType::ED25519, "test0").into();
+        for i in 0..5 {
+            let tx = create_tx_send_money(height * 10 + i, &signer, last_hash);
+            assert_eq!(env.clients[0].process_tx(tx, false, false), ProcessTxResponse::ValidTx);
+        }
+        let block = env.clients[0].produce_block(height).unwrap().unwrap();
+        env.process_block(0, block.clone(), Provenance::PRODUCED);
+        last_hash = *block.hash();
+    }
+
+    let client = &env.clients[0];
+    let client_store = client.runtime_adapter.store();
+    let shard_layout_at = |height: u64| {
+        let block = client.chain.get_block_by_height(height).unwrap();
+        client.epoch_manager.get_shard_layout(block.header().epoch_id()).unwrap()
+    };
+    let retention = ColdStoreRetentionPolicy::default();
+
+    check_cold_db_update(&cold_store, client_store, &shard_layout_at(1), 1, &retention)
+        .unwrap_err();
+
+    for heights in [1..epoch_length * 2, epoch_length * 2..max_height] {
+        let mut transaction = DBTransaction::new();
+        for height in heights {
+            let size = add_cold_db_update(
+                &mut transaction,
+                client_store,
+                &shard_layout_at(height),
+                height,
+                &retention,
+            )
+            .unwrap();
+            assert!(size.unwrap() > 0);
+        }
+        cold_db.write(transaction).unwrap();
+    }
+
+    for height in 1..max_height {
+        let num_keys = check_cold_db_update(
+            &cold_store,
+            client_store,
+            &shard_layout_at(height),
+            height,
+            &retention,
+        )
+        .unwrap();
+        assert!(num_keys.unwrap() > 0);
+    }
+    let num_checks = check_iter(client_store, &cold_store, DBCol::Block, &vec![]);
+    assert_eq!(num_checks, max_height);
+}
+
 /// Producing 4 epochs of blocks with some transactions.
 /// Call copying full contents of cold columns to cold storage in batches of specified max_size.
 /// Checks COLD_STORE_MIGRATION_BATCH_WRITE_COUNT metric for some batch_sizes:
@@ -376,7 +558,14 @@ fn test_initial_copy_to_cold(batch_size: usize) {
     let cold_db = storage.cold_db().unwrap();
     let cold_store = storage.get_cold_store().unwrap();
     let client_store = env.clients[0].runtime_adapter.store();
-    copy_all_data_to_cold(cold_db.clone(), &client_store, batch_size, &keep_going).unwrap();
+    copy_all_data_to_cold(
+        cold_db.clone(),
+        &client_store,
+        batch_size,
+        &keep_going,
+        &Default::default(),
+    )
+    .unwrap();
 
     for col in DBCol::iter() {
         if !col.is_cold() {
@@ -458,7 +647,8 @@ fn test_cold_loop_on_gc_boundary() {
     let keep_going = std::sync::Arc::new(std::sync::atomic::AtomicBool::new(true));
 
     let cold_db = storage.cold_db().unwrap();
-    copy_all_data_to_cold(cold_db.clone(), &hot_store, 1000000, &keep_going).unwrap();
+    copy_all_data_to_cold(cold_db.clone(), &hot_store, 1000000, &keep_going, &Default::default())
+        .unwrap();
 
     update_cold_head(cold_db, &hot_store, &(height_delta - 1)).unwrap();
 
@@ -3,13 +3,14 @@ use std::{collections::HashMap, io, sync::Arc};
 use borsh::BorshDeserialize;
 
 use crate::runtime_utils::{get_runtime_and_trie, get_test_trie_viewer, TEST_SHARD_UID};
+use near_crypto::{KeyType, PublicKey};
 use near_primitives::{
-    account::Account,
+    account::{AccessKey, Account},
     hash::{hash as sha256, CryptoHash},
     serialize::to_base64,
     trie_key::trie_key_parsers,
     types::{AccountId, StateRoot},
-    views::StateItem,
+    views::{StateItem, StateProofKey},
 };
 use near_primitives::{
     test_utils::MockEpochInfoProvider,
@@ -17,7 +18,9 @@ use near_primitives::{
     types::{EpochId, StateChangeCause},
     version::PROTOCOL_VERSION,
 };
-use near_store::{set_account, NibbleSlice, RawTrieNode, RawTrieNodeWithSize, ShardUId};
+use near_store::{
+    set_access_key, set_account, NibbleSlice, RawTrieNode, RawTrieNodeWithSize, ShardUId,
+};
 use node_runtime::state_viewer::errors;
 use node_runtime::state_viewer::*;
 use testlib::runtime_utils::alice_account;
@@ -190,6 +193,42 @@ fn test_view_call_with_args() {
     assert_eq!(view_call_result.unwrap(), 3u64.to_le_bytes().to_vec());
 }
 
+#[test]
+fn test_view_call_with_gas_profile() {
+    let (viewer, root) = get_test_trie_viewer();
+    let args: Vec<_> = [1u64, 2u64].iter().flat_map(|x| (*x).to_le_bytes().to_vec()).collect();
+    let mut logs = vec![];
+    let view_state = ViewApplyState {
+        block_height: 1,
+        prev_block_hash: CryptoHash::default(),
+        block_hash: CryptoHash::default(),
+        shard_id: ShardUId::single_shard().shard_id(),
+        epoch_id: EpochId::default(),
+        epoch_height: 0,
+        block_timestamp: 1,
+        current_protocol_version: PROTOCOL_VERSION,
+        cache: None,
+    };
+    let (result, gas_profile) = viewer
+        .call_function_with_gas_profile(
+            root,
+            view_state,
+            &"test.contract".parse().unwrap(),
+            "sum_with_input",
+            &args,
+            &mut logs,
+            &MockEpochInfoProvider::default(),
+        )
+        .unwrap();
+    assert_eq!(result, 3u64.to_le_bytes().to_vec());
+    assert!(gas_profile.gas_burnt > 0);
+    let profiled_gas: u64 = gas_profile.costs.iter().map(|cost| cost.gas_used).sum();
+    assert_eq!(profiled_gas, gas_profile.gas_burnt);
+    assert!(gas_profile.costs.iter().any(|cost| cost.cost == "WASM_INSTRUCTION"));
+    assert!(gas_profile.costs.iter().any(|cost| cost.cost == "INPUT_BASE"));
+    assert_eq!(gas_profile.storage_writes, 0);
+}
+
 fn assert_view_state(
     trie_viewer: &TrieViewer,
     state_update: &near_store::TrieUpdate,
@@ -355,6 +394,71 @@ fn test_view_state() {
     }
 }
 
+#[test]
+fn test_view_state_proof() {
+    let (_, tries, root) = get_runtime_and_trie();
+    let public_key = PublicKey::from_seed(KeyType::ED25519, alice_account().as_str());
+    let mut state_update = tries.new_trie_update(TEST_SHARD_UID, root);
+    set_account(
+        &mut state_update,
+        alice_account(),
+        &Account::new(0, 0, 0, CryptoHash::default(), 100, PROTOCOL_VERSION),
+    );
+    set_access_key(
+        &mut state_update,
+        alice_account(),
+        public_key.clone(),
+        &AccessKey::full_access(),
+    );
+    state_update.set(
+        TrieKey::ContractData { account_id: alice_account(), key: b"test123".to_vec() },
+        b"123".to_vec(),
+    );
+    state_update.commit(StateChangeCause::InitialState);
+    let trie_changes = state_update.finalize().unwrap().1;
+    let mut db_changes = tries.store_update();
+    let new_root = tries.apply_all(&trie_changes, TEST_SHARD_UID, &mut db_changes);
+    db_changes.commit().unwrap();
+
+    let state_update = tries.new_trie_update(TEST_SHARD_UID, new_root);
+    let trie_viewer = TrieViewer::default();
+    let keys = vec![
+        StateProofKey::Account { account_id: alice_account() },
+        StateProofKey::AccessKey { account_id: alice_account(), public_key },
+        StateProofKey::ContractData {
+            account_id: alice_account(),
+            key: b"test123".to_vec().into(),
+        },
+        StateProofKey::ContractData { account_id: alice_account(), key: b"test12".to_vec().into() },
+        StateProofKey::Account { account_id: "alina".parse().unwrap() },
+    ];
+    let mut result = trie_viewer.view_state_proof(&state_update, &keys).unwrap();
+    assert_eq!(result.state_root, new_root);
+    let values = result.values.iter().map(|item| item.value.is_some()).collect::<Vec<_>>();
+    assert_eq!(values, vec![true, true, true, false, false]);
+    assert_eq!(result.values[2].value, Some(b"123".to_vec().into()));
+    assert!(result.verify(&new_root).unwrap());
+    assert!(result.verify(&root).is_err());
+
+    // Claiming a different value or the absence of an existing key is caught.
+    result.values[2].value = Some(b"321".to_vec().into());
+    assert!(!result.verify(&new_root).unwrap());
+    result.values[2].value = None;
+    assert!(!result.verify(&new_root).unwrap());
+    result.values[2].value = Some(b"123".to_vec().into());
+    result.values[3].value = Some(b"123".to_vec().into());
+    assert!(!result.verify(&new_root).unwrap());
+}
+
+#[test]
+fn test_view_state_proof_too_large() {
+    let (_, tries, root) = get_runtime_and_trie();
+    let state_update = tries.new_trie_update(TEST_SHARD_UID, root);
+    let keys = vec![StateProofKey::Account { account_id: alice_account() }];
+    let result = TrieViewer::new(Some(1), None).view_state_proof(&state_update, &keys);
+    assert!(matches!(result, Err(errors::ViewStateError::StateProofTooLarge { limit: 1 })));
+}
+
 #[test]
 fn test_view_state_too_large() {
     let (_, tries, root) = get_runtime_and_trie();
@@ -3,20 +3,29 @@ use std::sync::{atomic::AtomicBool, Arc};
 use near_chain::types::Tip;
 use near_epoch_manager::{EpochManagerAdapter, EpochManagerHandle};
 use near_primitives::errors::EpochError;
-use near_primitives::{hash::CryptoHash, types::BlockHeight};
-use near_store::cold_storage::{copy_all_data_to_cold, CopyAllDataToColdStatus};
+use near_primitives::{block::BlockHeader, hash::CryptoHash, types::BlockHeight};
+use near_store::cold_storage::{
+    copy_all_data_to_cold, prune_cold_columns, prune_cold_db, update_cold_tail,
+    ColdStoreRetentionPolicy, CopyAllDataToColdStatus,
+};
 use near_store::{
     cold_storage::{update_cold_db, update_cold_head},
     db::ColdDB,
-    DBCol, NodeStorage, Store, FINAL_HEAD_KEY, HEAD_KEY, TAIL_KEY,
+    DBCol, NodeStorage, Store, COLD_TAIL_KEY, FINAL_HEAD_KEY, HEAD_KEY, TAIL_KEY,
 };
 
 use crate::config::SplitStorageConfig;
 use crate::{metrics, NearConfig};
 
+/// Maximum number of heights pruned from cold storage before the pruner
+/// checks whether it should stop.
+const PRUNE_BATCH_HEIGHTS: u64 = 100;
+
 /// A handle that keeps the state of the cold store loop and can be used to stop it.
 pub struct ColdStoreLoopHandle {
     join_handle: std::thread::JoinHandle<()>,
+    /// Handle of the pruner thread, if the retention policy requires pruning.
+    prune_join_handle: Option<std::thread::JoinHandle<()>>,
     keep_going: Arc<AtomicBool>,
 }
 
@@ -31,6 +40,16 @@ impl ColdStoreLoopHandle {
                 tracing::error!(target : "cold_store", "Failed to join the cold store loop thread");
             }
         }
+        if let Some(prune_join_handle) = self.prune_join_handle {
+            match prune_join_handle.join() {
+                Ok(_) => {
+                    tracing::debug!(target : "cold_store", "Joined the cold store prune loop thread");
+                }
+                Err(_) => {
+                    tracing::error!(target : "cold_store", "Failed to join the cold store prune loop thread");
+                }
+            }
+        }
     }
 }
 
@@ -89,6 +108,7 @@ fn cold_store_copy(
     genesis_height: BlockHeight,
     epoch_manager: &EpochManagerHandle,
     num_threads: usize,
+    retention: &ColdStoreRetentionPolicy,
 ) -> anyhow::Result<ColdStoreCopyResult, ColdStoreError> {
     // If COLD_HEAD is not set for hot storage we default it to genesis_height.
     let cold_head = cold_store.get_ser::<Tip>(DBCol::BlockMisc, HEAD_KEY)?;
@@ -124,7 +144,8 @@ fn cold_store_copy(
     let shard_layout = epoch_manager.get_shard_layout(&epoch_id)?;
 
     let mut next_height = cold_head_height + 1;
-    while !update_cold_db(cold_db, hot_store, &shard_layout, &next_height, num_threads)? {
+    while !update_cold_db(cold_db, hot_store, &shard_layout, &next_height, num_threads, retention)?
+    {
         next_height += 1;
         if next_height > hot_final_head_height {
             return Err(ColdStoreError::SkippedBlocksBetweenColdHeadAndNextHeightError {
@@ -288,7 +309,8 @@ fn cold_store_migration(
     tracing::info!(target: "cold_store", new_cold_height, "Determined cold storage head height after migration");
 
     let batch_size = split_storage_config.cold_store_initial_migration_batch_size;
-    match copy_all_data_to_cold(cold_db.clone(), hot_store, batch_size, keep_going)? {
+    let retention = &split_storage_config.cold_store_retention;
+    match copy_all_data_to_cold(cold_db.clone(), hot_store, batch_size, keep_going, retention)? {
         CopyAllDataToColdStatus::EverythingCopied => {
             tracing::info!(target: "cold_store", new_cold_height, "Cold storage population was successful, writing cold head.");
             update_cold_head(cold_db, hot_store, &new_cold_height)?;
@@ -378,6 +400,7 @@ fn cold_store_loop(
             genesis_height,
             epoch_manager,
             split_storage_config.num_cold_store_read_threads,
+            &split_storage_config.cold_store_retention,
         );
         let duration = instant.elapsed();
 
@@ -412,6 +435,102 @@ fn cold_store_loop(
     }
 }
 
+/// Prunes data of heights which fall out of the retention window from cold
+/// storage, at most `PRUNE_BATCH_HEIGHTS` of them, moving the cold tail
+/// accordingly.  Returns the number of heights pruned.
+///
+/// The window is counted back from the cold head, so the pruner never
+/// removes anything the cold store loop is about to copy.
+fn cold_store_prune(
+    hot_store: &Store,
+    cold_store: &Store,
+    cold_db: &ColdDB,
+    genesis_height: BlockHeight,
+    epoch_length: BlockHeight,
+    num_epochs: u64,
+    epoch_manager: &EpochManagerHandle,
+) -> anyhow::Result<u64> {
+    let Some(cold_head) = cold_store.get_ser::<Tip>(DBCol::BlockMisc, HEAD_KEY)? else {
+        return Ok(0);
+    };
+    let cold_tail = cold_store.get_ser::<BlockHeight>(DBCol::BlockMisc, COLD_TAIL_KEY)?;
+    let cold_tail = cold_tail.unwrap_or(genesis_height);
+    let new_tail = cold_head
+        .height
+        .saturating_sub(num_epochs.saturating_mul(epoch_length))
+        .min(cold_tail + PRUNE_BATCH_HEIGHTS);
+    if new_tail <= cold_tail {
+        return Ok(0);
+    }
+
+    let _span = tracing::debug_span!(target: "cold_store", "cold_store_prune", cold_tail, new_tail)
+        .entered();
+    for height in cold_tail..new_tail {
+        // BlockHeight and BlockHeader are not garbage collected in hot storage.
+        let Some(block_hash) =
+            hot_store.get_ser::<CryptoHash>(DBCol::BlockHeight, &height.to_le_bytes())?
+        else {
+            continue;
+        };
+        let header = hot_store
+            .get_ser::<BlockHeader>(DBCol::BlockHeader, block_hash.as_ref())?
+            .ok_or_else(|| anyhow::anyhow!("block header {block_hash} not found in hot storage"))?;
+        let shard_layout = epoch_manager.get_shard_layout(header.epoch_id())?;
+        prune_cold_db(cold_db, cold_store, &shard_layout, height, &block_hash)?;
+    }
+    update_cold_tail(cold_db, hot_store, new_tail)?;
+    Ok(new_tail - cold_tail)
+}
+
+/// Runs the cold storage pruner until `keep_going` is cleared.  First removes
+/// the columns which are not kept by the retention policy and then keeps
+/// pruning heights which fall out of the retention window, if it's set.
+fn cold_store_prune_loop(
+    split_storage_config: &SplitStorageConfig,
+    keep_going: &Arc<AtomicBool>,
+    hot_store: Store,
+    cold_store: Store,
+    cold_db: Arc<ColdDB>,
+    genesis_height: BlockHeight,
+    epoch_length: BlockHeight,
+    epoch_manager: &EpochManagerHandle,
+) {
+    tracing::info!(target : "cold_store", "Starting the cold store prune loop");
+    let retention = &split_storage_config.cold_store_retention;
+    let sleep_duration = split_storage_config.cold_store_loop_sleep_duration.unsigned_abs();
+    if let Err(err) = prune_cold_columns(&cold_db, retention) {
+        tracing::error!(target : "cold_store", ?err, "Failed to remove columns from cold storage");
+    }
+    let Some(num_epochs) = retention.num_epochs else {
+        return;
+    };
+
+    loop {
+        if !keep_going.load(std::sync::atomic::Ordering::Relaxed) {
+            tracing::debug!(target : "cold_store", "Stopping the cold store prune loop");
+            break;
+        }
+        match cold_store_prune(
+            &hot_store,
+            &cold_store,
+            &cold_db,
+            genesis_height,
+            epoch_length,
+            num_epochs,
+            epoch_manager,
+        ) {
+            Ok(0) => std::thread::sleep(sleep_duration),
+            Ok(pruned) => {
+                tracing::debug!(target : "cold_store", pruned, "Pruned heights from cold storage");
+            }
+            Err(err) => {
+                tracing::error!(target : "cold_store", error = format!("{err:#?}"), "cold_store_prune failed");
+                std::thread::sleep(sleep_duration);
+            }
+        }
+    }
+}
+
 /// Spawns the cold store loop in a background thread and returns ColdStoreLoopHandle.
 /// If cold store is not configured it does nothing and returns None.
 /// The cold store loop is spawned in a rust native thread because it's quite heavy
@@ -456,6 +575,31 @@ pub fn spawn_cold_store_loop(
 
     let split_storage_config = config.config.split_storage.clone().unwrap_or_default();
 
+    let prune_join_handle = if split_storage_config.cold_store_retention.is_pruning() {
+        tracing::info!(target : "cold_store", retention = ?split_storage_config.cold_store_retention, "Spawning the cold store prune loop");
+        let split_storage_config = split_storage_config.clone();
+        let keep_going = keep_going.clone();
+        let hot_store = hot_store.clone();
+        let cold_store = cold_store.clone();
+        let cold_db = cold_db.clone();
+        let epoch_length = config.genesis.config.epoch_length;
+        let epoch_manager = epoch_manager.clone();
+        Some(std::thread::Builder::new().name("cold_store_prune".to_string()).spawn(move || {
+            cold_store_prune_loop(
+                &split_storage_config,
+                &keep_going,
+                hot_store,
+                cold_store,
+                cold_db,
+                genesis_height,
+                epoch_length,
+                epoch_manager.as_ref(),
+            )
+        })?)
+    } else {
+        None
+    };
+
     tracing::info!(target : "cold_store", "Spawning the cold store loop");
     let join_handle =
         std::thread::Builder::new().name("cold_store_copy".to_string()).spawn(move || {
@@ -478,5 +622,5 @@ pub fn spawn_cold_store_loop(
             )
         })?;
 
-    Ok(Some(ColdStoreLoopHandle { join_handle, keep_going }))
+    Ok(Some(ColdStoreLoopHandle { join_handle, prune_join_handle, keep_going }))
 }
@@ -21,7 +21,7 @@ use near_chain_configs::{
     default_view_client_throttle_period, get_initial_supply, ChunkDistributionNetworkConfig,
     ClientConfig, GCConfig, Genesis, GenesisConfig, GenesisValidationMode, LogSummaryStyle,
     MutableConfigValue, MutableValidatorSigner, ReshardingConfig, StateSyncConfig,
-    BLOCK_PRODUCER_KICKOUT_THRESHOLD, CHUNK_PRODUCER_KICKOUT_THRESHOLD,
+    TransactionPoolConfig, BLOCK_PRODUCER_KICKOUT_THRESHOLD, CHUNK_PRODUCER_KICKOUT_THRESHOLD,
     CHUNK_VALIDATOR_ONLY_KICKOUT_THRESHOLD, EXPECTED_EPOCH_LENGTH, FISHERMEN_THRESHOLD,
     GAS_PRICE_ADJUSTMENT_RATE, GENESIS_CONFIG_FILENAME, INITIAL_GAS_LIMIT, MAX_INFLATION_RATE,
     MIN_BLOCK_PRODUCTION_DELAY, MIN_GAS_PRICE, NEAR_BASE, NUM_BLOCKS_PER_YEAR,
@@ -48,6 +48,7 @@ use near_primitives::validator_signer::{InMemoryValidatorSigner, ValidatorSigner
 use near_primitives::version::PROTOCOL_VERSION;
 #[cfg(feature = "rosetta_rpc")]
 use near_rosetta_rpc::RosettaRpcConfig;
+use near_store::cold_storage::ColdStoreRetentionPolicy;
 use near_store::config::StateSnapshotType;
 use near_store::{StateSnapshotConfig, Store, TrieConfig};
 use near_telemetry::TelemetryConfig;
@@ -278,6 +279,9 @@ pub struct Config {
     /// Setting this value too low (<1MB) on the validator might lead to production of smaller
     /// chunks and underutilizing the capacity of the network.
     pub transaction_pool_size_limit: Option<u64>,
+    /// Ordering of the transactions in the per-shard transaction pool and limits of the number
+    /// of transactions per signer and receiver account.
+    pub transaction_pool: TransactionPoolConfig,
     // Configuration for resharding.
     pub resharding_config: ReshardingConfig,
     /// If the node is not a chunk producer within that many blocks, then route
@@ -358,6 +362,7 @@ impl Default for Config {
             state_sync: default_state_sync(),
             state_sync_enabled: default_state_sync_enabled(),
             transaction_pool_size_limit: default_transaction_pool_size_limit(),
+            transaction_pool: TransactionPoolConfig::default(),
             enable_multiline_logging: default_enable_multiline_logging(),
             resharding_config: ReshardingConfig::default(),
             tx_routing_height_horizon: default_tx_routing_height_horizon(),
@@ -409,6 +414,13 @@ pub struct SplitStorageConfig {
 
     #[serde(default = "default_num_cold_store_read_threads")]
     pub num_cold_store_read_threads: usize,
+
+    /// Which data cold storage keeps.  By default, the whole history of all
+    /// cold columns is kept.  Limiting the history with `num_epochs` doesn't
+    /// prune the `State` column, which can only be dropped as a whole by
+    /// leaving it out of `columns`.
+    #[serde(default)]
+    pub cold_store_retention: ColdStoreRetentionPolicy,
 }
 
 impl Default for SplitStorageConfig {
@@ -421,6 +433,7 @@ impl Default for SplitStorageConfig {
                 default_cold_store_initial_migration_loop_sleep_duration(),
             cold_store_loop_sleep_duration: default_cold_store_loop_sleep_duration(),
             num_cold_store_read_threads: default_num_cold_store_read_threads(),
+            cold_store_retention: ColdStoreRetentionPolicy::default(),
         }
     }
 }
@@ -577,6 +590,7 @@ impl NearConfig {
                 state_sync_enabled: config.state_sync_enabled,
                 state_sync: config.state_sync.unwrap_or_default(),
                 transaction_pool_size_limit: config.transaction_pool_size_limit,
+                transaction_pool: config.transaction_pool.clone(),
                 enable_multiline_logging: config.enable_multiline_logging.unwrap_or(true),
                 resharding_config: MutableConfigValue::new(
                     config.resharding_config,
@@ -676,6 +690,9 @@ impl NightshadeRuntime {
             config.config.store.path.as_ref(),
             config.config.max_loaded_contracts,
         )?;
+        let mut trie_config = TrieConfig::from_store_config(&config.config.store);
+        trie_config.mem_trie_snapshot_dir =
+            config.config.store.mem_trie_snapshot_path.as_ref().map(|path| home_dir.join(path));
         Ok(NightshadeRuntime::new(
             store,
             ContractRuntimeCache::handle(&contract_cache),
@@ -685,7 +702,7 @@ impl NightshadeRuntime {
             config.client_config.max_gas_burnt_view,
             None,
             config.config.gc.gc_num_epochs_to_keep(),
-            TrieConfig::from_store_config(&config.config.store),
+            trie_config,
             state_snapshot_config,
         ))
     }
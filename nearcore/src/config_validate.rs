@@ -43,6 +43,13 @@ impl<'a> ConfigValidator<'a> {
             self.validation_errors.push_config_semantics_error(error_message);
         }
 
+        if let Some(split_storage) = &self.config.split_storage {
+            if let Err(err) = split_storage.cold_store_retention.validate() {
+                let error_message = format!("split_storage.cold_store_retention is invalid: {err}");
+                self.validation_errors.push_config_semantics_error(error_message);
+            }
+        }
+
         if self.config.consensus.min_block_production_delay
             > self.config.consensus.max_block_production_delay
         {
@@ -90,11 +97,30 @@ impl<'a> ConfigValidator<'a> {
                 }
 
                 match &dump_config.location {
-                    ExternalStorageLocation::S3 { bucket, region } => {
+                    ExternalStorageLocation::S3 {
+                        bucket,
+                        region,
+                        endpoint,
+                        credentials_file,
+                        ..
+                    } => {
                         if bucket.is_empty() || region.is_empty() {
                             let error_message = format!("'config.state_sync.dump.location.S3.bucket' and 'config.state_sync.dump.location.S3.region' need to be specified when 'config.state_sync.dump.location.S3' is present.");
                             self.validation_errors.push_config_semantics_error(error_message);
                         }
+                        if let Some(endpoint) = endpoint {
+                            if !endpoint.starts_with("http://") && !endpoint.starts_with("https://")
+                            {
+                                let error_message = format!("'config.state_sync.dump.location.S3.endpoint' needs to be an http:// or https:// URL.");
+                                self.validation_errors.push_config_semantics_error(error_message);
+                            }
+                        }
+                        if let Some(credentials_file) = credentials_file {
+                            if !credentials_file.exists() || !credentials_file.is_file() {
+                                let error_message = format!("'config.state_sync.dump.location.S3.credentials_file' is provided but the specified file does not exist or is not a file.");
+                                self.validation_errors.push_config_semantics_error(error_message);
+                            }
+                        }
                     }
                     ExternalStorageLocation::Filesystem { root_dir } => {
                         if root_dir.as_path() == Path::new("") {
@@ -108,6 +134,10 @@ impl<'a> ConfigValidator<'a> {
                             self.validation_errors.push_config_semantics_error(error_message);
                         }
                     }
+                    ExternalStorageLocation::Http { .. } => {
+                        let error_message = format!("'config.state_sync.dump.location.Http' is read-only and can't be used for dumping state.");
+                        self.validation_errors.push_config_semantics_error(error_message);
+                    }
                 }
 
                 if let Some(credentials_file) = &dump_config.credentials_file {
@@ -121,11 +151,33 @@ impl<'a> ConfigValidator<'a> {
                 SyncConfig::Peers => {}
                 SyncConfig::ExternalStorage(config) => {
                     match &config.location {
-                        ExternalStorageLocation::S3 { bucket, region } => {
+                        ExternalStorageLocation::S3 {
+                            bucket,
+                            region,
+                            endpoint,
+                            credentials_file,
+                            ..
+                        } => {
                             if bucket.is_empty() || region.is_empty() {
                                 let error_message = format!("'config.state_sync.sync.ExternalStorage.location.S3.bucket' and 'config.state_sync.sync.ExternalStorage.location.S3.region' need to be specified when 'config.state_sync.sync.ExternalStorage.location.S3' is present.");
                                 self.validation_errors.push_config_semantics_error(error_message);
                             }
+                            if let Some(endpoint) = endpoint {
+                                if !endpoint.starts_with("http://")
+                                    && !endpoint.starts_with("https://")
+                                {
+                                    let error_message = format!("'config.state_sync.sync.ExternalStorage.location.S3.endpoint' needs to be an http:// or https:// URL.");
+                                    self.validation_errors
+                                        .push_config_semantics_error(error_message);
+                                }
+                            }
+                            if let Some(credentials_file) = credentials_file {
+                                if !credentials_file.exists() || !credentials_file.is_file() {
+                                    let error_message = format!("'config.state_sync.sync.ExternalStorage.location.S3.credentials_file' is provided but the specified file does not exist or is not a file.");
+                                    self.validation_errors
+                                        .push_config_semantics_error(error_message);
+                                }
+                            }
                         }
                         ExternalStorageLocation::Filesystem { root_dir } => {
                             if root_dir.as_path() == Path::new("") {
@@ -139,6 +191,13 @@ impl<'a> ConfigValidator<'a> {
                                 self.validation_errors.push_config_semantics_error(error_message);
                             }
                         }
+                        ExternalStorageLocation::Http { base_url } => {
+                            if !base_url.starts_with("http://") && !base_url.starts_with("https://")
+                            {
+                                let error_message = format!("'config.state_sync.sync.ExternalStorage.location.Http.base_url' needs to be an http:// or https:// URL when 'config.state_sync.sync.ExternalStorage.location.Http' is present.");
+                                self.validation_errors.push_config_semantics_error(error_message);
+                            }
+                        }
                     }
                     if config.num_concurrent_requests == 0 {
                         let error_message = format!("'config.state_sync.sync.ExternalStorage.num_concurrent_requests' needs to be greater than 0");
@@ -231,6 +290,19 @@ mod tests {
         validate_config(&config).unwrap();
     }
 
+    #[test]
+    #[should_panic(
+        expected = "\\nconfig.json semantic issue: split_storage.cold_store_retention is invalid: Block must be kept when num_epochs is set"
+    )]
+    fn test_cold_store_retention_without_block() {
+        let mut config = Config::default();
+        let mut split_storage = crate::config::SplitStorageConfig::default();
+        split_storage.cold_store_retention.num_epochs = Some(10);
+        split_storage.cold_store_retention.columns = Some(vec![near_store::DBCol::Transactions]);
+        config.split_storage = Some(split_storage);
+        validate_config(&config).unwrap();
+    }
+
     #[test]
     #[should_panic(
         expected = "\\nconfig.json semantic issue: 'config.tx_routing_height_horizon' needs to be at least 2, got 1."
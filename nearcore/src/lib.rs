@@ -11,7 +11,7 @@ use anyhow::Context;
 use cold_storage::ColdStoreLoopHandle;
 use near_async::actix::AddrWithAutoSpanContextExt;
 use near_async::actix_wrapper::{spawn_actix_actor, ActixWrapper};
-use near_async::messaging::{IntoMultiSender, IntoSender, LateBoundSender};
+use near_async::messaging::{noop, IntoMultiSender, IntoSender, LateBoundSender};
 use near_async::time::{self, Clock};
 pub use near_chain::runtime::NightshadeRuntime;
 use near_chain::state_snapshot_actor::{
@@ -35,11 +35,11 @@ use near_epoch_manager::EpochManagerAdapter;
 use near_network::PeerManagerActor;
 use near_primitives::block::GenesisId;
 use near_primitives::types::EpochId;
-use near_store::flat::FlatStateValuesInliningMigrationHandle;
+use near_store::flat::{FlatStateValuesInliningMigrationHandle, FlatStorageScrubberHandle};
 use near_store::genesis::initialize_sharded_genesis_state;
 use near_store::metadata::DbKind;
 use near_store::metrics::spawn_db_metrics_loop;
-use near_store::{DBCol, Mode, NodeStorage, Store, StoreOpenerError};
+use near_store::{DBCol, Mode, NodeStorage, ShardTries, Store, StoreOpenerError};
 use near_telemetry::TelemetryActor;
 use std::path::{Path, PathBuf};
 use std::sync::{Arc, RwLock};
@@ -224,9 +224,60 @@ pub struct NearNode {
     /// A handle to control background flat state values inlining migration.
     /// Needed temporarily, will be removed after the migration is completed.
     pub flat_state_migration_handle: FlatStateValuesInliningMigrationHandle,
+    /// A handle to the background thread checking flat storage against the
+    /// trie.  Only set if the scrubber is enabled in the store config.
+    pub flat_storage_scrubber_handle: Option<FlatStorageScrubberHandle>,
     // A handle that allows the main process to interrupt resharding if needed.
     // This typically happens when the main process is interrupted.
     pub resharding_handle: ReshardingHandle,
+    /// Tries of the node, used to save the in-memory tries on shutdown.
+    pub shard_tries: ShardTries,
+}
+
+/// Starts a view client over the node’s storage opened in read-only mode,
+/// without any other components of the node.
+///
+/// Meant for tools reading the history of an archival node which is not
+/// running.  If the storage is split into hot and cold databases, the view
+/// client reads from both of them.
+pub fn start_read_only_view_client(
+    home_dir: &Path,
+    config: &NearConfig,
+) -> anyhow::Result<Addr<ViewClientActor>> {
+    let storage = NodeStorage::opener(
+        home_dir,
+        config.client_config.archive,
+        &config.config.store,
+        config.config.cold_store.as_ref(),
+    )
+    .open_in_mode(Mode::ReadOnly)
+    .context("failed opening storage in read-only mode")?;
+    let store = match storage.get_split_store() {
+        Some(split_store) if storage.get_hot_store().get_db_kind()? == Some(DbKind::Hot) => {
+            split_store
+        }
+        _ => storage.get_hot_store(),
+    };
+
+    let epoch_manager = EpochManager::new_arc_handle(store.clone(), &config.genesis.config);
+    let shard_tracker =
+        ShardTracker::new(TrackedConfig::from_config(&config.client_config), epoch_manager.clone());
+    let runtime = NightshadeRuntime::from_config(home_dir, store, config, epoch_manager.clone())
+        .context("could not create the transaction runtime")?;
+    let chain_genesis = ChainGenesis::new(&config.genesis.config);
+    let adv = near_client::adversarial::Controls::new(config.client_config.archive);
+
+    Ok(ViewClientActorInner::spawn_actix_actor(
+        Clock::real(),
+        config.validator_signer.clone(),
+        chain_genesis,
+        epoch_manager,
+        shard_tracker,
+        runtime,
+        noop().into_multi_sender(),
+        config.client_config.clone(),
+        adv,
+    ))
 }
 
 pub fn start_with_config(home_dir: &Path, config: NearConfig) -> anyhow::Result<NearNode> {
@@ -278,6 +329,7 @@ pub fn start_with_config_and_synchronization(
         epoch_manager.clone(),
     )
     .context("could not create the transaction runtime")?;
+    let shard_tries = runtime.get_tries();
 
     // Get the split store. If split store is some then create a new set of structures for
     // the view client. Otherwise just re-use the existing ones.
@@ -423,6 +475,14 @@ pub fn start_with_config_and_synchronization(
             config.client_config.client_background_migration_threads,
         );
 
+    let flat_storage_scrubber_handle =
+        config.config.store.flat_storage_scrubber.enabled.then(|| {
+            FlatStorageScrubberHandle::start_background_scrubber(
+                config.config.store.flat_storage_scrubber.clone(),
+                runtime.get_tries(),
+            )
+        });
+
     let mut state_sync_dumper = StateSyncDumper {
         clock: Clock::real(),
         client_config: config.client_config.clone(),
@@ -510,7 +570,9 @@ pub fn start_with_config_and_synchronization(
         cold_store_loop_handle,
         state_sync_dumper,
         flat_state_migration_handle,
+        flat_storage_scrubber_handle,
         resharding_handle,
+        shard_tries,
     })
 }
 
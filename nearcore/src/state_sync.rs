@@ -9,7 +9,7 @@ use near_chain::types::RuntimeAdapter;
 use near_chain::{Chain, ChainGenesis, ChainStoreAccess, DoomslugThresholdMode, Error};
 use near_chain_configs::{ClientConfig, ExternalStorageLocation, MutableValidatorSigner};
 use near_client::sync::external::{
-    create_bucket_readwrite, external_storage_location, StateFileType,
+    create_bucket_readwrite, external_storage_location, S3BucketLocation, StateFileType,
 };
 use near_client::sync::external::{
     external_storage_location_directory, get_part_id_from_filename, is_part_filename,
@@ -59,11 +59,23 @@ impl StateSyncDumper {
         tracing::info!(target: "state_sync_dump", "Spawning the state sync dump loop");
 
         let external = match dump_config.location {
-            ExternalStorageLocation::S3 { bucket, region } => ExternalConnection::S3 {
-                bucket: Arc::new(create_bucket_readwrite(&bucket, &region, std::time::Duration::from_secs(30), dump_config.credentials_file).expect(
-                    "Failed to authenticate connection to S3. Please either provide AWS_ACCESS_KEY_ID and AWS_SECRET_ACCESS_KEY in the environment, or create a credentials file and link it in config.json as 's3_credentials_file'."))
-            },
-            ExternalStorageLocation::Filesystem { root_dir } => ExternalConnection::Filesystem { root_dir },
+            ExternalStorageLocation::S3 {
+                bucket,
+                region,
+                endpoint,
+                path_style,
+                credentials_file,
+            } => {
+                let location = S3BucketLocation { bucket, region, endpoint, path_style };
+                let credentials_file = credentials_file.or(dump_config.credentials_file);
+                ExternalConnection::S3 {
+                    bucket: Arc::new(create_bucket_readwrite(&location, std::time::Duration::from_secs(30), credentials_file).expect(
+                        "Failed to authenticate connection to S3. Please either provide AWS_ACCESS_KEY_ID and AWS_SECRET_ACCESS_KEY in the environment, or create a credentials file and link it in config.json as 's3_credentials_file'."))
+                }
+            }
+            ExternalStorageLocation::Filesystem { root_dir } => {
+                ExternalConnection::Filesystem { root_dir }
+            }
             ExternalStorageLocation::GCS { bucket } => {
                 if let Some(credentials_file) = dump_config.credentials_file {
                     if let Ok(var) = std::env::var("SERVICE_ACCOUNT") {
@@ -78,7 +90,12 @@ impl StateSyncDumper {
                     reqwest_client: Arc::new(reqwest::Client::default()),
                     bucket,
                 }
-            },
+            }
+            ExternalStorageLocation::Http { .. } => {
+                anyhow::bail!(
+                    "HTTP external storage is read-only and can't be used for dumping state"
+                )
+            }
         };
 
         // Determine how many threads to start.
@@ -553,7 +553,9 @@ impl RunCmd {
                 cold_store_loop_handle,
                 mut state_sync_dumper,
                 flat_state_migration_handle,
+                flat_storage_scrubber_handle,
                 resharding_handle,
+                shard_tries,
                 ..
             } = nearcore::start_with_config_and_synchronization(
                 home_dir,
@@ -580,11 +582,15 @@ impl RunCmd {
             state_sync_dumper.stop();
             resharding_handle.stop();
             flat_state_migration_handle.stop();
+            if let Some(handle) = flat_storage_scrubber_handle {
+                handle.stop();
+            }
             futures::future::join_all(rpc_servers.iter().map(|(name, server)| async move {
                 server.stop(true).await;
                 debug!(target: "neard", "{} server stopped", name);
             }))
             .await;
+            shard_tries.save_mem_trie_snapshots();
             actix::System::current().stop();
             // Disable the subscriber to properly shutdown the tracer.
             near_o11y::reload(Some("error"), None, Some("off")).unwrap();
@@ -0,0 +1,72 @@
+#!/usr/bin/env python3
+# test controlling block production of a sandbox node: pausing it, producing an
+# exact number of blocks on demand, setting the timestamp of the next block and
+# advancing to the next epoch.
+
+import sys
+import time
+import pathlib
+
+sys.path.append(str(pathlib.Path(__file__).resolve().parents[2] / 'lib'))
+
+import utils
+from cluster import start_cluster
+
+EPOCH_LENGTH = 50
+CONFIG = utils.figure_out_sandbox_binary()
+
+nodes = start_cluster(1, 0, 1, CONFIG, [["epoch_length", EPOCH_LENGTH]], {})
+utils.wait_for_blocks(nodes[0], target=5)
+
+
+def head():
+    return nodes[0].json_rpc("block", {"finality": "optimistic"})['result']
+
+
+# no blocks are produced while block production is paused
+nodes[0].json_rpc('sandbox_set_block_production', {"paused": True})
+# let the block being produced, if any, get processed
+time.sleep(2)
+paused_height = head()['header']['height']
+time.sleep(3)
+assert head()['header']['height'] == paused_height
+
+# exactly the requested number of blocks is produced while paused
+nodes[0].json_rpc('sandbox_produce_blocks', {"num_blocks": 3}, timeout=60)
+time.sleep(1)
+block = head()
+heights = []
+while block['header']['height'] > paused_height:
+    heights.append(block['header']['height'])
+    block = nodes[0].json_rpc("block", {
+        "block_id": block['header']['prev_hash']
+    })['result']
+assert len(heights) == 3, heights
+
+# the next block gets exactly the given timestamp and later blocks continue from it
+timestamp = int(head()['header']['timestamp_nanosec']) + 3600 * 10**9
+nodes[0].json_rpc('sandbox_set_next_block_timestamp',
+                  {"timestamp_nanosec": str(timestamp)})
+nodes[0].json_rpc('sandbox_produce_blocks', {"num_blocks": 2}, timeout=60)
+time.sleep(1)
+block = head()
+prev = nodes[0].json_rpc("block", {
+    "block_id": block['header']['prev_hash']
+})['result']
+assert int(prev['header']['timestamp_nanosec']) == timestamp
+assert int(block['header']['timestamp_nanosec']) > timestamp
+
+# timestamps in the past of the head block are rejected
+resp = nodes[0].json_rpc('sandbox_set_next_block_timestamp',
+                         {"timestamp_nanosec": str(timestamp)})
+assert 'error' in resp, resp
+
+# advancing to the next epoch works while block production is paused
+epoch_height = nodes[0].get_validators()['result']['epoch_height']
+nodes[0].json_rpc('sandbox_advance_epoch', {}, timeout=60)
+assert nodes[0].get_validators()['result']['epoch_height'] == epoch_height + 1
+
+# block production continues after resuming it
+nodes[0].json_rpc('sandbox_set_block_production', {"paused": False})
+height = head()['header']['height']
+utils.wait_for_blocks(nodes[0], target=height + 3, timeout=30)
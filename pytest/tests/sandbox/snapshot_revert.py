@@ -0,0 +1,85 @@
+#!/usr/bin/env python3
+# test reverting a sandbox node to a snapshot: state written after the snapshot
+# is gone after the revert, the head is back at the snapshot block and new
+# blocks are built on top of it.
+
+import sys, time
+import base64
+import pathlib
+
+sys.path.append(str(pathlib.Path(__file__).resolve().parents[2] / 'lib'))
+
+import utils
+from cluster import start_cluster
+from transaction import sign_deploy_contract_tx, sign_function_call_tx
+
+CONFIG = utils.figure_out_sandbox_binary()
+
+nodes = start_cluster(1, 0, 1, CONFIG, [["epoch_length", 10]], {})
+
+# deploy contract
+hash_ = nodes[0].get_latest_block().hash_bytes
+tx = sign_deploy_contract_tx(nodes[0].signer_key, utils.load_test_contract(),
+                             10, hash_)
+nodes[0].send_tx(tx)
+time.sleep(3)
+
+k = (10).to_bytes(8, byteorder="little")
+
+
+def write_value(value, nonce):
+    hash_ = nodes[0].get_latest_block().hash_bytes
+    tx = sign_function_call_tx(nodes[0].signer_key,
+                               nodes[0].signer_key.account_id,
+                               'write_key_value', k + value, 1000000000000, 0,
+                               nonce, hash_)
+    res = nodes[0].send_tx_and_wait(tx, 20)
+    assert 'SuccessValue' in res['result']['status'], res
+
+
+def read_value():
+    res = nodes[0].call_function("test0", "read_value",
+                                 base64.b64encode(k).decode('ascii'))
+    return res['result']['result']
+
+
+v1 = (20).to_bytes(8, byteorder="little")
+write_value(v1, 20)
+assert read_value() == list(v1)
+
+snapshot = nodes[0].json_rpc('sandbox_snapshot', {})['result']
+
+v2 = (30).to_bytes(8, byteorder="little")
+write_value(v2, 30)
+assert read_value() == list(v2)
+utils.wait_for_blocks(nodes[0], target=snapshot['block_height'] + 15)
+
+# revert and check that both the head and the state are back at the snapshot
+res = nodes[0].json_rpc('sandbox_revert',
+                        {"snapshot_id": snapshot['snapshot_id']})['result']
+assert res['block_hash'] == snapshot['block_hash'], res
+assert res['block_height'] == snapshot['block_height'], res
+assert read_value() == list(v1)
+
+# new blocks are produced on top of the snapshot block and keep its state
+utils.wait_for_blocks(nodes[0], target=snapshot['block_height'] + 25)
+block = nodes[0].json_rpc("block", {"finality": "optimistic"})['result']
+while block['header']['height'] > snapshot['block_height']:
+    block = nodes[0].json_rpc("block", {
+        "block_id": block['header']['prev_hash']
+    })['result']
+assert block['header']['hash'] == snapshot['block_hash']
+assert read_value() == list(v1)
+
+# the snapshot can be reverted to again, until it is dropped
+res = nodes[0].json_rpc('sandbox_revert',
+                        {"snapshot_id": snapshot['snapshot_id']})['result']
+assert res['block_hash'] == snapshot['block_hash'], res
+nodes[0].json_rpc('sandbox_drop_snapshot',
+                  {"snapshot_id": snapshot['snapshot_id']})
+res = nodes[0].json_rpc('sandbox_revert',
+                        {"snapshot_id": snapshot['snapshot_id']})
+assert 'error' in res, res
+res = nodes[0].json_rpc('sandbox_drop_snapshot',
+                        {"snapshot_id": snapshot['snapshot_id']})
+assert 'error' in res, res
@@ -263,6 +263,12 @@ pub trait External {
     /// Size of the recorded trie storage proof.
     fn get_recorded_storage_size(&self) -> usize;
 
+    /// Whether the contract execution should record an execution trace.
+    #[cfg(feature = "sandbox")]
+    fn is_tracing_enabled(&self) -> bool {
+        false
+    }
+
     /// Returns the validator stake for given account in the current epoch.
     /// If the account is not a validator, returns `None`.
     fn validator_stake(&self, account_id: &AccountId) -> Result<Option<Balance>>;
@@ -51,6 +51,9 @@ pub struct ExecutionResultState {
     current_account_balance: Balance,
     /// Storage usage of the current account at the moment
     current_storage_usage: StorageUsage,
+    /// Execution trace, recorded only if tracing was enabled when the execution started.
+    #[cfg(feature = "sandbox")]
+    trace: Option<super::trace::ExecutionTrace>,
 }
 
 impl ExecutionResultState {
@@ -85,6 +88,8 @@ impl ExecutionResultState {
             return_data: ReturnData::None,
             current_account_balance,
             current_storage_usage,
+            #[cfg(feature = "sandbox")]
+            trace: None,
         }
     }
 
@@ -153,6 +158,8 @@ impl ExecutionResultState {
             logs: self.logs,
             profile,
             aborted: None,
+            #[cfg(feature = "sandbox")]
+            trace: self.trace,
         }
     }
 
@@ -302,9 +309,14 @@ impl<'a> VMLogic<'a> {
         ext: &'a mut dyn External,
         context: &'a VMContext,
         fees_config: Arc<RuntimeFeesConfig>,
-        result_state: ExecutionResultState,
+        #[cfg_attr(not(feature = "sandbox"), allow(unused_mut))]
+        mut result_state: ExecutionResultState,
         memory: impl MemoryLike + 'static,
     ) -> Self {
+        #[cfg(feature = "sandbox")]
+        {
+            result_state.trace = ext.is_tracing_enabled().then(Default::default);
+        }
         let current_account_locked_balance = context.account_locked_balance;
         let config = Arc::clone(&result_state.config);
         let recorded_storage_counter = RecordedStorageCounter::new(
@@ -3135,6 +3147,8 @@ impl<'a> VMLogic<'a> {
         }
         self.result_state.gas_counter.pay_per(storage_write_key_byte, key.len() as u64)?;
         self.result_state.gas_counter.pay_per(storage_write_value_byte, value.len() as u64)?;
+        #[cfg(feature = "sandbox")]
+        self.trace_storage_access(super::trace::StorageAccessKind::Write, &key);
         let nodes_before = self.ext.get_trie_nodes_count();
         // For storage write, we need to first perform a read on the key to calculate the TTN cost.
         // This storage_get must be performed through trie instead of through FlatStorage
@@ -3246,6 +3260,8 @@ impl<'a> VMLogic<'a> {
             .into());
         }
         self.result_state.gas_counter.pay_per(storage_read_key_byte, key.len() as u64)?;
+        #[cfg(feature = "sandbox")]
+        self.trace_storage_access(super::trace::StorageAccessKind::Read, &key);
         let nodes_before = self.ext.get_trie_nodes_count();
         let read = self.ext.storage_get(&key, self.config.storage_get_mode);
         let nodes_delta = self
@@ -3318,6 +3334,8 @@ impl<'a> VMLogic<'a> {
             .into());
         }
         self.result_state.gas_counter.pay_per(storage_remove_key_byte, key.len() as u64)?;
+        #[cfg(feature = "sandbox")]
+        self.trace_storage_access(super::trace::StorageAccessKind::Remove, &key);
         let nodes_before = self.ext.get_trie_nodes_count();
         // To delete a key, we need to first perform a read on the key to calculate the TTN cost.
         // This storage_get must be performed through trie instead of through FlatStorage
@@ -3396,6 +3414,8 @@ impl<'a> VMLogic<'a> {
             .into());
         }
         self.result_state.gas_counter.pay_per(storage_has_key_byte, key.len() as u64)?;
+        #[cfg(feature = "sandbox")]
+        self.trace_storage_access(super::trace::StorageAccessKind::HasKey, &key);
         let nodes_before = self.ext.get_trie_nodes_count();
         let res = self.ext.storage_has_key(&key, self.config.storage_get_mode);
         let nodes_delta = self
@@ -3435,6 +3455,39 @@ impl<'a> VMLogic<'a> {
         Ok(())
     }
 
+    /// Returns the execution trace, if tracing is enabled for this execution.
+    #[cfg(feature = "sandbox")]
+    pub(crate) fn execution_trace(&mut self) -> Option<&mut super::trace::ExecutionTrace> {
+        self.result_state.trace.as_mut()
+    }
+
+    /// Records a host function invocation in the execution trace.
+    #[cfg(feature = "sandbox")]
+    pub(crate) fn trace_host_call(&mut self, name: &'static str, args: &[u64]) {
+        if let Some(trace) = self.execution_trace() {
+            trace.record_host_call(name, args);
+        }
+    }
+
+    #[cfg(feature = "sandbox")]
+    fn trace_storage_access(&mut self, kind: super::trace::StorageAccessKind, key: &[u8]) {
+        if let Some(trace) = self.execution_trace() {
+            trace.record_storage_access(kind, key);
+        }
+    }
+
+    /// Returns the function names declared in the `name` section of the executed contract.
+    ///
+    /// Contracts are stripped of custom sections before compilation, so the names are read from
+    /// the original code.
+    #[cfg(all(feature = "sandbox", feature = "near_vm", target_arch = "x86_64"))]
+    pub(crate) fn contract_function_names(&self) -> std::collections::HashMap<u32, String> {
+        self.ext
+            .get_contract()
+            .map(|code| super::trace::function_names(code.code()))
+            .unwrap_or_default()
+    }
+
     /// DEPRECATED
     /// Creates an iterator object inside the host. Returns the identifier that uniquely
     /// differentiates the given iterator from other iterators that can be simultaneously created.
@@ -3578,6 +3631,9 @@ pub struct VMOutcome {
     /// Data collected from making a contract call
     pub profile: ProfileDataV3,
     pub aborted: Option<FunctionCallError>,
+    /// Execution trace, present only if tracing is enabled.
+    #[cfg(feature = "sandbox")]
+    pub trace: Option<super::trace::ExecutionTrace>,
 }
 
 impl VMOutcome {
@@ -3610,6 +3666,8 @@ impl VMOutcome {
             logs: Vec::new(),
             profile: ProfileDataV3::default(),
             aborted: Some(error),
+            #[cfg(feature = "sandbox")]
+            trace: None,
         }
     }
 
@@ -11,6 +11,8 @@ pub mod recorded_storage_counter;
 pub mod test_utils;
 #[cfg(test)]
 mod tests;
+#[cfg(feature = "sandbox")]
+pub mod trace;
 pub mod types;
 mod utils;
 mod vmstate;
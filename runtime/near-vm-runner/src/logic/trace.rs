@@ -0,0 +1,138 @@
+//! Execution tracing for debugging contracts on sandbox nodes.
+//!
+//! Tracing is requested by the caller through [`External::is_tracing_enabled`]. A traced
+//! execution records the host functions it invokes, the storage keys it touches and, when the
+//! execution traps, the wasm call stack reported by the VM. The resulting [`ExecutionTrace`] is
+//! returned as part of the [`VMOutcome`](super::VMOutcome).
+//!
+//! [`External::is_tracing_enabled`]: super::External::is_tracing_enabled
+
+/// Maximum number of host calls and storage accesses recorded for a single execution.
+///
+/// Anything beyond this limit is dropped and the trace is marked as truncated, so that a
+/// contract stuck in a loop does not exhaust the node's memory.
+pub const MAX_TRACE_ENTRIES: usize = 10_000;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ExecutionTrace {
+    /// Wasm frames active when the execution trapped, innermost first.
+    ///
+    /// Empty if the execution finished successfully or the VM did not report any frames.
+    pub call_stack: Vec<WasmFrame>,
+    /// Host functions invoked by the contract, in invocation order.
+    pub host_calls: Vec<HostFunctionCall>,
+    /// Storage keys accessed by the contract, in access order.
+    pub storage_accesses: Vec<StorageAccess>,
+    /// Whether some host calls or storage accesses were dropped due to [`MAX_TRACE_ENTRIES`].
+    pub truncated: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WasmFrame {
+    /// Index of the function in the function index space of the contract.
+    pub func_index: u32,
+    /// Function name taken from the `name` custom section, if the contract has one.
+    pub name: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HostFunctionCall {
+    pub name: &'static str,
+    /// Raw arguments as passed by the contract.
+    pub args: Vec<u64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StorageAccessKind {
+    Read,
+    Write,
+    Remove,
+    HasKey,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StorageAccess {
+    pub kind: StorageAccessKind,
+    pub key: Vec<u8>,
+}
+
+impl ExecutionTrace {
+    fn has_capacity(&mut self) -> bool {
+        if self.host_calls.len() + self.storage_accesses.len() < MAX_TRACE_ENTRIES {
+            true
+        } else {
+            self.truncated = true;
+            false
+        }
+    }
+
+    pub(crate) fn record_host_call(&mut self, name: &'static str, args: &[u64]) {
+        if self.has_capacity() {
+            self.host_calls.push(HostFunctionCall { name, args: args.to_vec() });
+        }
+    }
+
+    pub(crate) fn record_storage_access(&mut self, kind: StorageAccessKind, key: &[u8]) {
+        if self.has_capacity() {
+            self.storage_accesses.push(StorageAccess { kind, key: key.to_vec() });
+        }
+    }
+}
+
+/// Extracts function names from the `name` custom section of the given wasm module.
+///
+/// Malformed name sections are ignored, as the names are only used for diagnostics.
+#[cfg(all(feature = "near_vm", target_arch = "x86_64"))]
+pub(crate) fn function_names(code: &[u8]) -> std::collections::HashMap<u32, String> {
+    use finite_wasm::wasmparser as wp;
+    let mut names = std::collections::HashMap::new();
+    for payload in wp::Parser::new(0).parse_all(code) {
+        let Ok(wp::Payload::CustomSection(reader)) = payload else { continue };
+        if reader.name() != "name" {
+            continue;
+        }
+        for subsection in wp::NameSectionReader::new(reader.data(), reader.data_offset()) {
+            let Ok(wp::Name::Function(map)) = subsection else { continue };
+            for naming in map.into_iter().flatten() {
+                names.insert(naming.index, naming.name.to_string());
+            }
+        }
+    }
+    names
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_trace_truncation() {
+        let mut trace = ExecutionTrace::default();
+        for i in 0..MAX_TRACE_ENTRIES {
+            trace.record_host_call("storage_read", &[i as u64]);
+        }
+        assert!(!trace.truncated);
+        trace.record_storage_access(StorageAccessKind::Read, b"key");
+        assert!(trace.truncated);
+        assert_eq!(trace.host_calls.len(), MAX_TRACE_ENTRIES);
+        assert!(trace.storage_accesses.is_empty());
+    }
+
+    #[cfg(all(feature = "near_vm", target_arch = "x86_64"))]
+    #[test]
+    fn test_function_names() {
+        let code = wat::parse_str(
+            r#"
+            (module
+              (import "env" "panic" (func $panic))
+              (func $helper (call $panic))
+              (func (export "main") (call $helper)))
+            "#,
+        )
+        .unwrap();
+        let names = function_names(&code);
+        assert_eq!(names.get(&0).map(String::as_str), Some("panic"));
+        assert_eq!(names.get(&1).map(String::as_str), Some("helper"));
+        assert_eq!(names.get(&2), None);
+    }
+}
@@ -56,6 +56,8 @@ fn translate_runtime_error(
     error: near_vm_engine::RuntimeError,
     logic: &mut VMLogic,
 ) -> Result<FunctionCallError, VMRunnerError> {
+    #[cfg(feature = "sandbox")]
+    trace_call_stack(&error, logic);
     // Errors produced by host function calls also become `RuntimeError`s that wrap a dynamic
     // instance of `VMLogicError` internally. See the implementation of `NearVmImports`.
     let error = match error.downcast::<crate::logic::VMLogicError>() {
@@ -91,6 +93,31 @@ fn translate_runtime_error(
     })
 }
 
+/// Records the wasm frames of a trap in the execution trace, if tracing is enabled.
+#[cfg(feature = "sandbox")]
+fn trace_call_stack(error: &near_vm_engine::RuntimeError, logic: &mut VMLogic) {
+    if logic.execution_trace().is_none() {
+        return;
+    }
+    // Preparation does not change the function index space apart from importing the memory, so
+    // the indices reported by the VM match the ones in the original code.
+    let names = logic.contract_function_names();
+    let call_stack = error
+        .trace()
+        .iter()
+        .map(|frame| crate::logic::trace::WasmFrame {
+            func_index: frame.func_index(),
+            name: frame
+                .function_name()
+                .map(str::to_owned)
+                .or_else(|| names.get(&frame.func_index()).cloned()),
+        })
+        .collect();
+    if let Some(trace) = logic.execution_trace() {
+        trace.call_stack = call_stack;
+    }
+}
+
 pub(crate) struct NearVM {
     pub(crate) config: Arc<Config>,
     pub(crate) engine: UniversalEngine,
@@ -721,6 +748,11 @@ impl<'e, 'l, 'lr> Resolver for NearVmImports<'e, 'l, 'lr> {
                             let _span = TRACE.then(|| {
                                 tracing::trace_span!(target: "vm::host_function", stringify!($name)).entered()
                             });
+                            #[cfg(feature = "sandbox")]
+                            if stringify!($mod) == "env" {
+                                // SAFETY: same as for the host function call below.
+                                unsafe { (*env).trace_host_call(stringify!($name), &[$( u64::from($arg_name) ),*]) };
+                            }
 
                             // SAFETY: This code should only be executable within `'vmlogic`
                             // lifetime and so it is safe to dereference the `env` pointer which is
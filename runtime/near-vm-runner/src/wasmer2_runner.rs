@@ -735,6 +735,11 @@ impl<'e, 'l, 'lr> Resolver for Wasmer2Imports<'e, 'l, 'lr> {
                         let _span = TRACE.then(|| {
                             tracing::trace_span!(target: "vm::host_function", stringify!($name)).entered()
                         });
+                        #[cfg(feature = "sandbox")]
+                        if stringify!($mod) == "env" {
+                            // SAFETY: same as for the host function call below.
+                            unsafe { (*env).trace_host_call(stringify!($name), &[$( u64::from($arg_name) ),*]) };
+                        }
 
                         // SAFETY: This code should only be executable within `'vmlogic`
                         // lifetime and so it is safe to dereference the `env` pointer which is
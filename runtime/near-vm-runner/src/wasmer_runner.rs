@@ -565,6 +565,10 @@ pub(crate) fn build_imports(
                         tracing::trace_span!(target: "vm::host_function", stringify!($name)).entered()
                     });
                     let logic: &mut VMLogic<'_> = unsafe { &mut *(ctx.data as *mut VMLogic<'_>) };
+                    #[cfg(feature = "sandbox")]
+                    if stringify!($mod) == "env" {
+                        logic.trace_host_call(stringify!($name), &[$( u64::from($arg_name) ),*]);
+                    }
                     logic.$func( $( $arg_name, )* )
                 }
 
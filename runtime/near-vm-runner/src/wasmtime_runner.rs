@@ -374,7 +374,11 @@ impl crate::PreparedContract for VMResult<PreparedContract> {
                 Some(func) => match func.typed::<(), ()>(&mut store) {
                     Ok(run) => match run.call(&mut store, ()) {
                         Ok(_) => Ok(VMOutcome::ok(logic.result_state)),
-                        Err(err) => Ok(VMOutcome::abort(logic.result_state, err.into_vm_error()?)),
+                        Err(err) => {
+                            #[cfg(feature = "sandbox")]
+                            trace_call_stack(&err, &mut logic);
+                            Ok(VMOutcome::abort(logic.result_state, err.into_vm_error()?))
+                        }
                     },
                     Err(err) => Ok(VMOutcome::abort(logic.result_state, err.into_vm_error()?)),
                 },
@@ -390,6 +394,25 @@ impl crate::PreparedContract for VMResult<PreparedContract> {
     }
 }
 
+/// Records the wasm frames of a trap in the execution trace, if tracing is enabled.
+///
+/// Function indices refer to the instrumented module, which imports additional functions, so
+/// only names known to wasmtime itself are reported.
+#[cfg(feature = "sandbox")]
+fn trace_call_stack(error: &anyhow::Error, logic: &mut VMLogic) {
+    let Some(trace) = logic.execution_trace() else { return };
+    if let Some(backtrace) = error.downcast_ref::<wasmtime::WasmBacktrace>() {
+        trace.call_stack = backtrace
+            .frames()
+            .iter()
+            .map(|frame| crate::logic::trace::WasmFrame {
+                func_index: frame.func_index(),
+                name: frame.func_name().map(str::to_owned),
+            })
+            .collect();
+    }
+}
+
 /// This is a container from which an error can be taken out by value. This is necessary as
 /// `anyhow` does not really give any opportunity to grab causes by value and the VM Logic
 /// errors end up a couple layers deep in a causal chain.
@@ -451,6 +474,10 @@ fn link<'a, 'b>(
                     crate::wasmtime_runner::CALLER.with(|runner_caller| *runner_caller.borrow_mut() = std::mem::transmute(caller));
                 }
                 let logic: &mut VMLogic<'_> = unsafe { &mut *(data as *mut VMLogic<'_>) };
+                #[cfg(feature = "sandbox")]
+                if stringify!($mod) == "env" {
+                    logic.trace_host_call(stringify!($name), &[$( u64::from($arg_name) ),*]);
+                }
                 match logic.$func( $( $arg_name as $arg_type, )* ) {
                     Ok(result) => Ok(result as ($( $returns ),* ) ),
                     Err(err) => {
@@ -181,6 +181,7 @@ pub(crate) fn action_function_call(
     config: &RuntimeConfig,
     is_last_action: bool,
     epoch_info_provider: &(dyn EpochInfoProvider),
+    #[cfg_attr(not(feature = "sandbox"), allow(unused_variables))] tracing: bool,
 ) -> Result<(), RuntimeError> {
     if account.amount().checked_add(function_call.deposit).is_none() {
         return Err(StorageError::StorageInconsistentState(
@@ -204,6 +205,8 @@ pub(crate) fn action_function_call(
         epoch_info_provider,
         apply_state.current_protocol_version,
     );
+    #[cfg(feature = "sandbox")]
+    runtime_ext.set_tracing_enabled(tracing);
     let outcome = execute_function_call(
         apply_state,
         &mut runtime_ext,
@@ -226,6 +229,11 @@ pub(crate) fn action_function_call(
         }
     }
 
+    #[cfg(feature = "sandbox")]
+    if let Some(trace) = outcome.trace {
+        result.execution_traces.push((*action_hash, function_call.method_name.clone(), trace));
+    }
+
     let execution_succeeded = outcome.aborted.is_none();
     if let Some(err) = outcome.aborted {
         // collect metrics for failed function calls
@@ -6,7 +6,9 @@ use near_primitives::types::{
     AccountId, BlockHeight, EpochHeight, EpochId, EpochInfoProvider, MerkleHash,
 };
 use near_primitives::version::ProtocolVersion;
-use near_primitives::views::ViewStateResult;
+use near_primitives::views::{
+    CallGasProfileView, StateProofKey, ViewStateProofResult, ViewStateResult,
+};
 use near_vm_runner::ContractCode;
 
 /// Adapter for querying runtime.
@@ -41,7 +43,7 @@ pub trait ViewRuntimeAdapter {
         logs: &mut Vec<String>,
         epoch_info_provider: &dyn EpochInfoProvider,
         current_protocol_version: ProtocolVersion,
-    ) -> Result<Vec<u8>, crate::state_viewer::errors::CallFunctionError>;
+    ) -> Result<(Vec<u8>, CallGasProfileView), crate::state_viewer::errors::CallFunctionError>;
 
     fn view_access_key(
         &self,
@@ -66,4 +68,11 @@ pub trait ViewRuntimeAdapter {
         prefix: &[u8],
         include_proof: bool,
     ) -> Result<ViewStateResult, crate::state_viewer::errors::ViewStateError>;
+
+    fn view_state_proof(
+        &self,
+        shard_uid: &ShardUId,
+        state_root: MerkleHash,
+        keys: &[StateProofKey],
+    ) -> Result<ViewStateProofResult, crate::state_viewer::errors::ViewStateError>;
 }
@@ -0,0 +1,153 @@
+//! Sandbox-only store of contract execution traces.
+//!
+//! When tracing is enabled, the VM records a trace of every function call it executes. The
+//! runtime keeps the traces of the most recently executed receipts in memory, so that they can
+//! be retrieved through the sandbox RPC after the receipt was applied.
+use near_primitives::hash::CryptoHash;
+use near_primitives::views::{
+    FunctionCallTraceView, HostFunctionCallView, StorageAccessKindView, StorageAccessView,
+    WasmFrameView,
+};
+use near_vm_runner::logic::trace::{ExecutionTrace, StorageAccessKind};
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+/// Maximum number of receipts whose traces are kept in memory.
+const MAX_TRACED_RECEIPTS: usize = 1000;
+
+/// Maximum total size in bytes of the traces kept in memory.
+///
+/// A single trace is bounded by [`near_vm_runner::logic::trace::MAX_TRACE_ENTRIES`], but the
+/// entries may carry large storage keys, so the receipt count alone does not bound the memory.
+const MAX_TRACE_BYTES: usize = 64 * 1024 * 1024;
+
+/// Traces of the function calls executed by a [`Runtime`](crate::Runtime).
+#[derive(Default)]
+pub struct ExecutionTraces {
+    enabled: AtomicBool,
+    store: Mutex<TraceStore>,
+}
+
+#[derive(Default)]
+struct TraceStore {
+    /// Traces of each receipt together with the hashes of the traced actions.
+    traces: HashMap<CryptoHash, Vec<(CryptoHash, FunctionCallTraceView)>>,
+    /// Receipt ids in insertion order, used to evict the oldest traces.
+    order: VecDeque<CryptoHash>,
+    /// Estimated size in bytes of all stored traces.
+    total_bytes: usize,
+}
+
+impl ExecutionTraces {
+    /// Enables or disables tracing of contract executions.
+    ///
+    /// Disabling tracing keeps the traces recorded so far.
+    pub fn set_enabled(&self, enabled: bool) {
+        self.enabled.store(enabled, Ordering::Relaxed);
+    }
+
+    /// Returns whether contract executions are currently being traced.
+    pub fn is_enabled(&self) -> bool {
+        self.enabled.load(Ordering::Relaxed)
+    }
+
+    /// Returns traces of the function calls executed by the given receipt, in action order.
+    pub fn get(&self, receipt_id: &CryptoHash) -> Option<Vec<FunctionCallTraceView>> {
+        let store = self.store.lock().unwrap();
+        let traces = store.traces.get(receipt_id)?;
+        Some(traces.iter().map(|(_, trace)| trace.clone()).collect())
+    }
+
+    /// Stores the trace of a function call action.
+    ///
+    /// If the action was already traced, e.g. because the chunk containing it was applied again,
+    /// its previous trace is replaced.
+    pub(crate) fn record(
+        &self,
+        receipt_id: CryptoHash,
+        action_hash: CryptoHash,
+        method_name: &str,
+        trace: ExecutionTrace,
+    ) {
+        let view = FunctionCallTraceView {
+            method_name: method_name.to_string(),
+            call_stack: trace
+                .call_stack
+                .into_iter()
+                .map(|frame| WasmFrameView { func_index: frame.func_index, name: frame.name })
+                .collect(),
+            host_calls: trace
+                .host_calls
+                .into_iter()
+                .map(|call| HostFunctionCallView { name: call.name.to_string(), args: call.args })
+                .collect(),
+            storage_accesses: trace
+                .storage_accesses
+                .into_iter()
+                .map(|access| StorageAccessView {
+                    kind: match access.kind {
+                        StorageAccessKind::Read => StorageAccessKindView::Read,
+                        StorageAccessKind::Write => StorageAccessKindView::Write,
+                        StorageAccessKind::Remove => StorageAccessKindView::Remove,
+                        StorageAccessKind::HasKey => StorageAccessKindView::HasKey,
+                    },
+                    key: access.key.into(),
+                })
+                .collect(),
+            truncated: trace.truncated,
+        };
+        let view_bytes = trace_size(&view);
+        let mut store = self.store.lock().unwrap();
+        if !store.traces.contains_key(&receipt_id) {
+            store.order.push_back(receipt_id);
+        }
+        let traces = store.traces.entry(receipt_id).or_default();
+        let replaced_bytes = match traces.iter_mut().find(|(hash, _)| *hash == action_hash) {
+            Some((_, existing)) => trace_size(&std::mem::replace(existing, view)),
+            None => {
+                traces.push((action_hash, view));
+                0
+            }
+        };
+        store.total_bytes = store.total_bytes - replaced_bytes + view_bytes;
+        // Evict the oldest receipts, but always keep the traces of the newest one.
+        while store.order.len() > 1
+            && (store.order.len() > MAX_TRACED_RECEIPTS || store.total_bytes > MAX_TRACE_BYTES)
+        {
+            let Some(oldest) = store.order.pop_front() else { break };
+            let evicted = store.traces.remove(&oldest).unwrap_or_default();
+            store.total_bytes -= evicted.iter().map(|(_, trace)| trace_size(trace)).sum::<usize>();
+        }
+    }
+}
+
+/// Estimates the memory taken by a trace.
+fn trace_size(trace: &FunctionCallTraceView) -> usize {
+    let frames: usize = trace
+        .call_stack
+        .iter()
+        .map(|frame| {
+            std::mem::size_of::<WasmFrameView>() + frame.name.as_ref().map_or(0, String::len)
+        })
+        .sum();
+    let host_calls: usize = trace
+        .host_calls
+        .iter()
+        .map(|call| {
+            std::mem::size_of::<HostFunctionCallView>()
+                + call.name.len()
+                + call.args.len() * std::mem::size_of::<u64>()
+        })
+        .sum();
+    let storage_accesses: usize = trace
+        .storage_accesses
+        .iter()
+        .map(|access| std::mem::size_of::<StorageAccessView>() + access.key.len())
+        .sum();
+    std::mem::size_of::<FunctionCallTraceView>()
+        + trace.method_name.len()
+        + frames
+        + host_calls
+        + storage_accesses
+}
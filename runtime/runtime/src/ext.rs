@@ -29,6 +29,8 @@ pub struct RuntimeExt<'a> {
     last_block_hash: CryptoHash,
     epoch_info_provider: &'a (dyn EpochInfoProvider),
     current_protocol_version: ProtocolVersion,
+    #[cfg(feature = "sandbox")]
+    tracing_enabled: bool,
 }
 
 /// Error used by `RuntimeExt`.
@@ -84,9 +86,17 @@ impl<'a> RuntimeExt<'a> {
             last_block_hash,
             epoch_info_provider,
             current_protocol_version,
+            #[cfg(feature = "sandbox")]
+            tracing_enabled: false,
         }
     }
 
+    /// Requests an execution trace of the contract call made with this external.
+    #[cfg(feature = "sandbox")]
+    pub fn set_tracing_enabled(&mut self, enabled: bool) {
+        self.tracing_enabled = enabled;
+    }
+
     #[inline]
     pub fn account_id(&self) -> &AccountId {
         &self.account_id
@@ -206,6 +216,11 @@ impl<'a> External for RuntimeExt<'a> {
         self.trie_update.trie().recorded_storage_size_upper_bound()
     }
 
+    #[cfg(feature = "sandbox")]
+    fn is_tracing_enabled(&self) -> bool {
+        self.tracing_enabled
+    }
+
     fn validator_stake(&self, account_id: &AccountId) -> ExtResult<Option<Balance>> {
         self.epoch_info_provider
             .validator_stake(&self.epoch_id, &self.prev_block_hash, account_id)
@@ -63,6 +63,7 @@ pub use near_vm_runner::with_ext_cost_counter;
 use near_vm_runner::ContractCode;
 use near_vm_runner::ContractRuntimeCache;
 use near_vm_runner::ProfileDataV3;
+pub use simulation::{AccountBalanceChange, TransactionSimulationResult};
 use std::cmp::max;
 use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
@@ -74,10 +75,13 @@ mod balance_checker;
 pub mod config;
 mod congestion_control;
 mod conversions;
+#[cfg(feature = "sandbox")]
+pub mod execution_trace;
 pub mod ext;
 mod metrics;
 mod prefetch;
 pub mod receipt_manager;
+mod simulation;
 pub mod state_viewer;
 mod verifier;
 
@@ -196,6 +200,9 @@ pub struct ActionResult {
     pub new_receipts: Vec<Receipt>,
     pub validator_proposals: Vec<ValidatorStake>,
     pub profile: Box<ProfileDataV3>,
+    /// Execution traces of the function calls, together with the action hash and method name.
+    #[cfg(feature = "sandbox")]
+    pub execution_traces: Vec<(CryptoHash, String, near_vm_runner::logic::trace::ExecutionTrace)>,
 }
 
 impl ActionResult {
@@ -217,6 +224,8 @@ impl ActionResult {
         self.profile.merge(&next_result.profile);
         self.result = next_result.result;
         self.logs.append(&mut next_result.logs);
+        #[cfg(feature = "sandbox")]
+        self.execution_traces.append(&mut next_result.execution_traces);
         if let Ok(ReturnData::ReceiptIndex(ref mut receipt_index)) = self.result {
             // Shifting local receipt index to be global receipt index.
             *receipt_index += self.new_receipts.len() as u64;
@@ -244,15 +253,40 @@ impl Default for ActionResult {
             new_receipts: vec![],
             validator_proposals: vec![],
             profile: Default::default(),
+            #[cfg(feature = "sandbox")]
+            execution_traces: vec![],
         }
     }
 }
 
-pub struct Runtime {}
+pub struct Runtime {
+    #[cfg(feature = "sandbox")]
+    execution_traces: execution_trace::ExecutionTraces,
+}
 
 impl Runtime {
     pub fn new() -> Self {
-        Self {}
+        Self {
+            #[cfg(feature = "sandbox")]
+            execution_traces: Default::default(),
+        }
+    }
+
+    /// Returns the traces of the function calls executed by this runtime.
+    #[cfg(feature = "sandbox")]
+    pub fn execution_traces(&self) -> &execution_trace::ExecutionTraces {
+        &self.execution_traces
+    }
+
+    /// Whether function calls should record an execution trace.
+    #[cfg(feature = "sandbox")]
+    fn tracing_enabled(&self) -> bool {
+        self.execution_traces.is_enabled()
+    }
+
+    #[cfg(not(feature = "sandbox"))]
+    fn tracing_enabled(&self) -> bool {
+        false
     }
 
     fn print_log(log: &[LogEntry]) {
@@ -288,6 +322,7 @@ impl Runtime {
         state_update: &mut TrieUpdate,
         apply_state: &ApplyState,
         signed_transaction: &SignedTransaction,
+        verify_signature: bool,
         stats: &mut ApplyStats,
     ) -> Result<(Receipt, ExecutionOutcomeWithId), InvalidTxError> {
         let span = tracing::Span::current();
@@ -298,7 +333,7 @@ impl Runtime {
             state_update,
             apply_state.gas_price,
             signed_transaction,
-            true,
+            verify_signature,
             Some(apply_state.block_height),
             apply_state.current_protocol_version,
         ) {
@@ -448,6 +483,7 @@ impl Runtime {
                     &apply_state.config,
                     action_index + 1 == actions.len(),
                     epoch_info_provider,
+                    self.tracing_enabled(),
                 )?;
             }
             Action::Transfer(TransferAction { deposit }) => {
@@ -651,6 +687,11 @@ impl Runtime {
             }
         }
 
+        #[cfg(feature = "sandbox")]
+        for (action_hash, method_name, trace) in std::mem::take(&mut result.execution_traces) {
+            self.execution_traces.record(*receipt.receipt_id(), action_hash, &method_name, trace);
+        }
+
         // Going to check balance covers account's storage.
         if result.result.is_ok() {
             if let Some(ref mut account) = account {
@@ -1518,6 +1559,7 @@ impl Runtime {
                 state_update,
                 apply_state,
                 signed_transaction,
+                true,
                 &mut processing_state.stats,
             )?;
             if receipt.receiver_id() == signed_transaction.transaction.signer_id() {
@@ -3240,6 +3282,70 @@ mod tests {
         assert_eq!(initial_account_state.storage_usage(), final_account_state.storage_usage());
     }
 
+    #[test]
+    fn test_simulate_transaction() {
+        let initial_balance = to_yocto(1_000_000);
+        let simulate = |max_receipts, max_gas_burnt| {
+            let (runtime, tries, root, apply_state, signer, epoch_info_provider) =
+                setup_runtime(initial_balance, 0, 10u64.pow(15));
+            // Bob doesn't exist so the transfer fails and the deposit is
+            // refunded.
+            let transaction = SignedTransaction::send_money(
+                1,
+                alice_account(),
+                bob_account(),
+                &*signer,
+                to_yocto(10),
+                CryptoHash::default(),
+            );
+            let result = runtime
+                .simulate_transaction(
+                    apply_state,
+                    [(0, tries.get_trie_for_shard(ShardUId::single_shard(), root))].into(),
+                    &transaction,
+                    true,
+                    max_receipts,
+                    max_gas_burnt,
+                    &epoch_info_provider,
+                )
+                .unwrap();
+            // Nothing was written to the state.
+            let state_update = tries.new_trie_update(ShardUId::single_shard(), root);
+            let account = get_account(&state_update, &alice_account()).unwrap().unwrap();
+            assert_eq!(account.amount(), initial_balance);
+            result
+        };
+
+        let result = simulate(10, Gas::MAX);
+        assert_eq!(result.receipt_outcomes.len(), 2);
+        assert_matches!(
+            result.receipt_outcomes[0].outcome.status,
+            ExecutionStatus::Failure(TxExecutionError::ActionError(_))
+        );
+        assert_matches!(
+            result.receipt_outcomes[1].outcome.status,
+            ExecutionStatus::SuccessValue(_)
+        );
+        assert!(result.pending_receipts.is_empty());
+        assert_eq!(result.balance_changes.len(), 1);
+        let change = &result.balance_changes[0];
+        assert_eq!(change.account_id, alice_account());
+        assert_eq!(change.amount_before, Some(initial_balance));
+        // Only the burnt gas is missing after the refund.
+        let burnt = result.transaction_outcome.outcome.tokens_burnt
+            + result.receipt_outcomes[0].outcome.tokens_burnt;
+        assert_eq!(change.amount_after, Some(initial_balance - burnt));
+
+        let result = simulate(1, Gas::MAX);
+        assert_eq!(result.receipt_outcomes.len(), 1);
+        assert_eq!(result.pending_receipts.len(), 1);
+
+        // Converting the transaction already burns the whole budget.
+        let result = simulate(10, 1);
+        assert!(result.receipt_outcomes.is_empty());
+        assert_eq!(result.pending_receipts.len(), 1);
+    }
+
     #[test]
     fn test_delete_key_underflow() {
         let initial_locked = to_yocto(500_000);
@@ -3287,6 +3393,71 @@ mod tests {
         assert_eq!(final_account_state.storage_usage(), 0);
     }
 
+    #[test]
+    #[cfg(feature = "sandbox")]
+    fn test_execution_trace_of_trapping_call() {
+        let (runtime, tries, root, apply_state, signer, epoch_info_provider) =
+            setup_runtime(to_yocto(1_000_000), to_yocto(500_000), 10u64.pow(15));
+        runtime.execution_traces().set_enabled(true);
+
+        let code = near_test_contracts::wat_contract(
+            r#"(module
+              (import "env" "storage_write"
+                (func $storage_write (param i64 i64 i64 i64 i64) (result i64)))
+              (memory (export "memory") 1)
+              (data (i32.const 0) "key")
+              (func $fail
+                (drop (call $storage_write
+                  (i64.const 3) (i64.const 0) (i64.const 3) (i64.const 0) (i64.const 0)))
+                unreachable)
+              (func (export "main") (call $fail)))"#,
+        );
+        let receipts = vec![
+            create_receipt_with_actions(
+                alice_account(),
+                signer.clone(),
+                vec![Action::DeployContract(DeployContractAction { code })],
+            ),
+            create_receipt_with_actions(
+                alice_account(),
+                signer,
+                vec![Action::FunctionCall(Box::new(FunctionCallAction {
+                    method_name: "main".to_string(),
+                    args: vec![],
+                    gas: 10u64.pow(14),
+                    deposit: 0,
+                }))],
+            ),
+        ];
+        let apply_result = runtime
+            .apply(
+                tries.get_trie_for_shard(ShardUId::single_shard(), root),
+                &None,
+                &apply_state,
+                &receipts,
+                &[],
+                &epoch_info_provider,
+                Default::default(),
+            )
+            .unwrap();
+        assert_matches!(
+            apply_result.outcomes[1].outcome.status,
+            ExecutionStatus::Failure(TxExecutionError::ActionError(_))
+        );
+
+        let traces = runtime.execution_traces().get(receipts[1].receipt_id()).unwrap();
+        assert_matches!(&traces[..], [trace] => {
+            assert_eq!(trace.method_name, "main");
+            assert!(trace.host_calls.iter().any(|call| call.name == "storage_write"));
+            assert_matches!(&trace.storage_accesses[..], [access] => {
+                assert_eq!(access.kind, near_primitives::views::StorageAccessKindView::Write);
+                assert_eq!(&access.key[..], b"key");
+            });
+            assert!(!trace.truncated);
+        });
+        assert!(runtime.execution_traces().get(receipts[0].receipt_id()).is_none());
+    }
+
     // This test only works on platforms that support wasmer2.
     #[test]
     #[cfg(target_arch = "x86_64")]
@@ -3893,7 +4064,7 @@ pub mod estimator {
             outgoing_buffers: ShardsOutgoingReceiptBuffer::load(&state_update.trie)?,
             outgoing_receipts,
         });
-        Runtime {}.apply_action_receipt(
+        Runtime::new().apply_action_receipt(
             state_update,
             apply_state,
             receipt,
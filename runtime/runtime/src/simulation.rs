@@ -0,0 +1,169 @@
+//! Execution of a transaction and all receipts it generates without
+//! persisting any of the changes, used to preview transactions.
+
+use crate::congestion_control::{ReceiptSink, ReceiptSinkV1};
+use crate::{ApplyState, ApplyStats, Runtime};
+use near_primitives::errors::RuntimeError;
+use near_primitives::receipt::Receipt;
+use near_primitives::transaction::{ExecutionOutcomeWithId, SignedTransaction};
+use near_primitives::trie_key::TrieKey;
+use near_primitives::types::{
+    AccountId, Balance, EpochInfoProvider, Gas, RawStateChangesWithTrieKey, ShardId,
+};
+use near_store::{get_account, StorageError, Trie, TrieUpdate};
+use std::collections::{HashMap, VecDeque};
+
+/// Result of [`Runtime::simulate_transaction`].
+#[derive(Debug)]
+pub struct TransactionSimulationResult {
+    /// Outcome of converting the transaction into a receipt.
+    pub transaction_outcome: ExecutionOutcomeWithId,
+    /// Outcomes of executed action receipts, refunds included, in execution
+    /// order.
+    pub receipt_outcomes: Vec<ExecutionOutcomeWithId>,
+    /// Receipts left unexecuted because `max_receipts` or `max_gas_burnt` was
+    /// reached or because the state of their receiver's shard is not in
+    /// `tries`.
+    pub pending_receipts: Vec<Receipt>,
+    /// State changes of every touched shard.
+    pub state_changes: Vec<RawStateChangesWithTrieKey>,
+    /// Balances of accounts whose records changed, before and after.
+    pub balance_changes: Vec<AccountBalanceChange>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct AccountBalanceChange {
+    pub account_id: AccountId,
+    /// `None` if the account did not exist before.
+    pub amount_before: Option<Balance>,
+    /// `None` if the account was deleted.
+    pub amount_after: Option<Balance>,
+    pub locked_before: Option<Balance>,
+    pub locked_after: Option<Balance>,
+}
+
+impl Runtime {
+    /// Executes the transaction followed by all receipts it generates,
+    /// including cross-contract calls and refunds, until there are none left,
+    /// `max_receipts` receipts were processed or `max_gas_burnt` gas was
+    /// burnt in total.  The budget is checked before each receipt, so it can
+    /// only be exceeded by the gas burnt by the last executed receipt.
+    ///
+    /// `tries` are the states of the shards after the block described by
+    /// `apply_state` and are never modified.  All receipts are executed as if
+    /// they were included in that block, so anything depending on block
+    /// height or timestamp sees the same values throughout.  Signature is
+    /// only checked if `verify_signature` is set, which allows previewing
+    /// transactions that were not signed yet.  Receipts sent to shards whose
+    /// state is not in `tries` are returned unexecuted.
+    pub fn simulate_transaction(
+        &self,
+        mut apply_state: ApplyState,
+        tries: HashMap<ShardId, Trie>,
+        signed_transaction: &SignedTransaction,
+        verify_signature: bool,
+        max_receipts: usize,
+        max_gas_burnt: Gas,
+        epoch_info_provider: &dyn EpochInfoProvider,
+    ) -> Result<TransactionSimulationResult, RuntimeError> {
+        let mut state_updates: HashMap<ShardId, TrieUpdate> =
+            tries.into_iter().map(|(shard_id, trie)| (shard_id, TrieUpdate::new(trie))).collect();
+        // Points `apply_state` to the shard of the account and returns it.
+        let switch_shard = |account_id: &AccountId, apply_state: &mut ApplyState| {
+            let shard_id = epoch_info_provider
+                .account_id_to_shard_id(account_id, &apply_state.epoch_id)
+                .map_err(RuntimeError::ValidatorError)?;
+            apply_state.shard_id = shard_id;
+            Ok::<_, RuntimeError>(shard_id)
+        };
+
+        let mut stats = ApplyStats::default();
+        let signer_shard_id =
+            switch_shard(signed_transaction.transaction.signer_id(), &mut apply_state)?;
+        let (receipt, transaction_outcome) = self.process_transaction(
+            shard_state_update(&mut state_updates, signer_shard_id)?,
+            &apply_state,
+            signed_transaction,
+            verify_signature,
+            &mut stats,
+        )?;
+
+        let mut gas_burnt = transaction_outcome.outcome.gas_burnt;
+        let mut receipt_outcomes = Vec::new();
+        let mut validator_proposals = Vec::new();
+        let mut pending_receipts = Vec::new();
+        let mut receipts = VecDeque::from([receipt]);
+        let mut processed_receipts = 0;
+        while processed_receipts < max_receipts && gas_burnt < max_gas_burnt {
+            let Some(receipt) = receipts.pop_front() else {
+                break;
+            };
+            let shard_id = switch_shard(receipt.receiver_id(), &mut apply_state)?;
+            if !state_updates.contains_key(&shard_id) {
+                pending_receipts.push(receipt);
+                continue;
+            }
+            processed_receipts += 1;
+            let mut new_receipts = Vec::new();
+            let mut receipt_sink =
+                ReceiptSink::V1(ReceiptSinkV1 { outgoing_receipts: &mut new_receipts });
+            let outcome = self.process_receipt(
+                shard_state_update(&mut state_updates, shard_id)?,
+                &apply_state,
+                &receipt,
+                &mut receipt_sink,
+                &mut validator_proposals,
+                &mut stats,
+                epoch_info_provider,
+            )?;
+            if let Some(outcome) = &outcome {
+                gas_burnt = gas_burnt.saturating_add(outcome.outcome.gas_burnt);
+            }
+            receipt_outcomes.extend(outcome);
+            receipts.extend(new_receipts);
+        }
+        pending_receipts.extend(receipts);
+
+        let mut state_changes = Vec::new();
+        let mut balance_changes = Vec::new();
+        let mut shard_ids: Vec<_> = state_updates.keys().copied().collect();
+        shard_ids.sort();
+        for shard_id in shard_ids {
+            let state_update = &state_updates[&shard_id];
+            for change in state_update.committed_changes() {
+                if let TrieKey::Account { account_id } = &change.trie_key {
+                    let before = get_account(state_update.trie(), account_id)?;
+                    let after = get_account(state_update, account_id)?;
+                    balance_changes.push(AccountBalanceChange {
+                        account_id: account_id.clone(),
+                        amount_before: before.as_ref().map(|account| account.amount()),
+                        amount_after: after.as_ref().map(|account| account.amount()),
+                        locked_before: before.as_ref().map(|account| account.locked()),
+                        locked_after: after.as_ref().map(|account| account.locked()),
+                    });
+                }
+                state_changes.push(change.clone());
+            }
+        }
+        balance_changes.sort_by(|a, b| a.account_id.cmp(&b.account_id));
+
+        Ok(TransactionSimulationResult {
+            transaction_outcome,
+            receipt_outcomes,
+            pending_receipts,
+            state_changes,
+            balance_changes,
+        })
+    }
+}
+
+fn shard_state_update(
+    state_updates: &mut HashMap<ShardId, TrieUpdate>,
+    shard_id: ShardId,
+) -> Result<&mut TrieUpdate, StorageError> {
+    state_updates.get_mut(&shard_id).ok_or_else(|| {
+        StorageError::StorageInconsistentState(format!(
+            "state of shard {shard_id} is not available"
+        ))
+    })
+}
@@ -1,4 +1,5 @@
 use crate::actions::execute_function_call;
+use crate::conversions::Convert;
 use crate::ext::RuntimeExt;
 use crate::receipt_manager::ReceiptManager;
 use crate::ApplyState;
@@ -6,6 +7,7 @@ use near_crypto::{KeyType, PublicKey};
 use near_parameters::RuntimeConfigStore;
 use near_primitives::account::{AccessKey, Account};
 use near_primitives::borsh::BorshDeserialize;
+use near_primitives::challenge::PartialState;
 use near_primitives::hash::CryptoHash;
 use near_primitives::receipt::ActionReceipt;
 use near_primitives::runtime::migration_data::{MigrationData, MigrationFlags};
@@ -15,7 +17,10 @@ use near_primitives::types::{
     AccountId, BlockHeight, EpochHeight, EpochId, EpochInfoProvider, Gas, ShardId,
 };
 use near_primitives::version::PROTOCOL_VERSION;
-use near_primitives::views::{StateItem, ViewStateResult};
+use near_primitives::views::{
+    CallGasProfileView, StateItem, StateProofItem, StateProofKey, ViewStateProofResult,
+    ViewStateResult,
+};
 use near_primitives_core::config::ViewConfig;
 use near_store::{get_access_key, get_account, get_code, TrieUpdate};
 use near_vm_runner::logic::{ProtocolVersion, ReturnData};
@@ -49,7 +54,8 @@ pub struct ViewApplyState {
 }
 
 pub struct TrieViewer {
-    /// Upper bound of the byte size of contract state that is still viewable. None is no limit
+    /// Upper bound of the byte size of contract state that is still viewable, and of the trie
+    /// nodes of a state proof. None is no limit
     state_size_limit: Option<u64>,
     /// Gas limit used when handling call_function queries.
     max_gas_burnt_view: Gas,
@@ -71,6 +77,11 @@ impl TrieViewer {
         Self { state_size_limit, max_gas_burnt_view }
     }
 
+    /// Gas limit used when handling call_function queries.
+    pub fn max_gas_burnt_view(&self) -> Gas {
+        self.max_gas_burnt_view
+    }
+
     pub fn view_account(
         &self,
         state_update: &TrieUpdate,
@@ -178,9 +189,35 @@ impl TrieViewer {
         Ok(ViewStateResult { values, proof })
     }
 
+    /// Looks the keys up, recording every visited trie node into a single
+    /// proof for all of them.  Fails as soon as the proof grows past the
+    /// state size limit.
+    pub fn view_state_proof(
+        &self,
+        state_update: &TrieUpdate,
+        keys: &[StateProofKey],
+    ) -> Result<ViewStateProofResult, errors::ViewStateError> {
+        let trie = state_update.trie().recording_reads();
+        let values = keys
+            .iter()
+            .map(|key| {
+                let value = trie.get(&key.to_trie_key().to_vec())?;
+                if let Some(limit) = self.state_size_limit {
+                    if trie.recorded_storage_size() as u64 > limit {
+                        return Err(errors::ViewStateError::StateProofTooLarge { limit });
+                    }
+                }
+                Ok(StateProofItem { key: key.clone(), value: value.map(Into::into) })
+            })
+            .collect::<Result<Vec<_>, errors::ViewStateError>>()?;
+        let PartialState::TrieValues(proof) =
+            trie.recorded_storage().expect("recording is enabled").nodes;
+        Ok(ViewStateProofResult { state_root: *trie.get_root(), values, proof })
+    }
+
     pub fn call_function(
         &self,
-        mut state_update: TrieUpdate,
+        state_update: TrieUpdate,
         view_state: ViewApplyState,
         contract_id: &AccountId,
         method_name: &str,
@@ -188,6 +225,30 @@ impl TrieViewer {
         logs: &mut Vec<String>,
         epoch_info_provider: &(dyn EpochInfoProvider),
     ) -> Result<Vec<u8>, errors::CallFunctionError> {
+        self.call_function_with_gas_profile(
+            state_update,
+            view_state,
+            contract_id,
+            method_name,
+            args,
+            logs,
+            epoch_info_provider,
+        )
+        .map(|(result, _)| result)
+    }
+
+    /// Same as [`Self::call_function`] but also returns the gas used by the
+    /// call broken down by cost.
+    pub fn call_function_with_gas_profile(
+        &self,
+        mut state_update: TrieUpdate,
+        view_state: ViewApplyState,
+        contract_id: &AccountId,
+        method_name: &str,
+        args: &[u8],
+        logs: &mut Vec<String>,
+        epoch_info_provider: &(dyn EpochInfoProvider),
+    ) -> Result<(Vec<u8>, CallGasProfileView), errors::CallFunctionError> {
         let now = Instant::now();
         let root = *state_update.get_root();
         let account = get_account(&state_update, contract_id)?.ok_or_else(|| {
@@ -279,7 +340,12 @@ impl TrieViewer {
                 ReturnData::Value(buf) => buf,
                 ReturnData::ReceiptIndex(_) | ReturnData::None => vec![],
             };
-            Ok(result)
+            let gas_profile = CallGasProfileView::new(
+                outcome.burnt_gas,
+                Convert::convert(outcome.profile),
+                &config.wasm_config.ext_costs,
+            );
+            Ok((result, gas_profile))
         }
     }
 }
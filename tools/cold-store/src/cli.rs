@@ -4,10 +4,16 @@ use anyhow::Context;
 use borsh::BorshDeserialize;
 use clap;
 use near_epoch_manager::{EpochManager, EpochManagerAdapter, EpochManagerHandle};
+use near_primitives::block::BlockHeader;
 use near_primitives::block::Tip;
 use near_primitives::epoch_manager::block_info::BlockInfo;
 use near_primitives::hash::CryptoHash;
-use near_store::cold_storage::{copy_all_data_to_cold, update_cold_db, update_cold_head};
+use near_primitives::types::BlockHeight;
+use near_store::cold_storage::{
+    add_cold_db_update, check_cold_db_update, copy_all_data_to_cold, update_cold_db,
+    update_cold_head, ColdStoreRetentionPolicy,
+};
+use near_store::db::{ColdDB, DBTransaction, Database, COLD_STORE_BACKFILL_PROGRESS_KEY};
 use near_store::metadata::DbKind;
 use near_store::{DBCol, NodeStorage, Store, StoreOpener};
 use near_store::{COLD_HEAD_KEY, FINAL_HEAD_KEY, HEAD_KEY, TAIL_KEY};
@@ -15,6 +21,8 @@ use nearcore::NearConfig;
 use rand::seq::SliceRandom;
 use std::io::Result;
 use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Mutex;
 use strum::IntoEnumIterator;
 
 #[derive(clap::Parser)]
@@ -56,6 +64,12 @@ enum SubCommand {
     /// Modifies cold db from config to be considered not initialised.
     /// Doesn't actually delete any data, except for HEAD and COLD_HEAD in BlockMisc.
     ResetCold(ResetColdCmd),
+    /// Copy a range of blocks to cold storage using multiple threads.
+    /// The range is split into smaller ranges which are copied in parallel,
+    /// and completed ranges are recorded in hot db so that an interrupted
+    /// backfill can be resumed by running the same command again.
+    /// Cold HEAD is updated at the end if the range starts right after it.
+    Backfill(BackfillCmd),
 }
 
 impl ColdStoreCommand {
@@ -85,12 +99,16 @@ impl ColdStoreCommand {
                 Ok(())
             }
             SubCommand::CopyAllBlocks(cmd) => {
-                copy_all_blocks(&storage, cmd.batch_size, !cmd.no_check_after);
+                copy_all_blocks(&storage, &near_config, cmd.batch_size, !cmd.no_check_after);
                 Ok(())
             }
             SubCommand::PrepareHot(cmd) => cmd.run(&storage, &home_dir, &near_config),
             SubCommand::CheckStateRoot(cmd) => cmd.run(&storage),
             SubCommand::ResetCold(cmd) => cmd.run(&storage),
+            SubCommand::Backfill(cmd) => {
+                anyhow::ensure!(self.readwrite, "backfill requires --readwrite");
+                cmd.run(&storage, &near_config, epoch_manager.as_ref())
+            }
         }
     }
 
@@ -231,6 +249,7 @@ fn copy_next_block(store: &NodeStorage, config: &NearConfig, epoch_manager: &Epo
             .unwrap(),
         &next_height,
         1,
+        &config.config.split_storage.clone().unwrap_or_default().cold_store_retention,
     )
     .unwrap_or_else(|_| panic!("Failed to copy block at height {} to cold db", next_height));
 
@@ -238,7 +257,7 @@ fn copy_next_block(store: &NodeStorage, config: &NearConfig, epoch_manager: &Epo
         .unwrap_or_else(|_| panic!("Failed to update cold HEAD to {}", next_height));
 }
 
-fn copy_all_blocks(storage: &NodeStorage, batch_size: usize, check: bool) {
+fn copy_all_blocks(storage: &NodeStorage, config: &NearConfig, batch_size: usize, check: bool) {
     // If FINAL_HEAD is not set for hot storage we default it to 0
     // not genesis_height, because hot db needs to contain genesis block for that
     let hot_final_head = storage
@@ -255,6 +274,7 @@ fn copy_all_blocks(storage: &NodeStorage, batch_size: usize, check: bool) {
         &storage.get_hot_store(),
         batch_size,
         &keep_going,
+        &config.config.split_storage.clone().unwrap_or_default().cold_store_retention,
     )
     .expect("Failed to do migration to cold db");
 
@@ -673,3 +693,277 @@ impl ResetColdCmd {
         Ok(())
     }
 }
+
+#[derive(clap::Args)]
+struct BackfillCmd {
+    /// First height to copy.  Defaults to the height right after cold HEAD.
+    #[clap(long)]
+    start_height: Option<BlockHeight>,
+    /// Last height to copy.  Defaults to hot FINAL_HEAD.
+    #[clap(long)]
+    end_height: Option<BlockHeight>,
+    /// Number of threads copying the blocks.
+    #[clap(long, default_value_t = 8)]
+    num_threads: usize,
+    /// Number of heights in a range copied by a single thread at a time.
+    /// Progress is recorded after each range.
+    #[clap(long, default_value_t = 1000)]
+    range_size: u64,
+    /// Threshold size of the write transaction.
+    #[clap(short = 'b', long, default_value_t = 100_000_000)]
+    batch_size: usize,
+    /// Number of randomly chosen heights whose data is compared between hot
+    /// and cold db after copying.
+    #[clap(long, default_value_t = 100)]
+    num_verified_blocks: usize,
+}
+
+/// Progress of a backfill stored in hot db under
+/// `COLD_STORE_BACKFILL_PROGRESS_KEY`.  Heights are split into ranges of
+/// `range_size` starting at `start_height` and indices of copied ranges are
+/// kept in `done`.
+#[derive(borsh::BorshSerialize, borsh::BorshDeserialize, Debug, PartialEq)]
+struct BackfillProgress {
+    start_height: BlockHeight,
+    end_height: BlockHeight,
+    range_size: u64,
+    done: Vec<u64>,
+}
+
+impl BackfillProgress {
+    fn num_ranges(&self) -> u64 {
+        (self.end_height - self.start_height) / self.range_size + 1
+    }
+
+    fn range(&self, index: u64) -> std::ops::RangeInclusive<BlockHeight> {
+        let start = self.start_height + index * self.range_size;
+        start..=(start + self.range_size - 1).min(self.end_height)
+    }
+}
+
+impl BackfillCmd {
+    pub fn run(
+        self,
+        storage: &NodeStorage,
+        config: &NearConfig,
+        epoch_manager: &dyn EpochManagerAdapter,
+    ) -> anyhow::Result<()> {
+        anyhow::ensure!(self.num_threads > 0, "--num-threads must be positive");
+        anyhow::ensure!(self.range_size > 0, "--range-size must be positive");
+        let hot_store = storage.get_hot_store();
+        let cold_db = storage.cold_db().context("Cold storage is not configured")?;
+        let cold_store = storage.get_cold_store().unwrap();
+        let retention =
+            config.config.split_storage.clone().unwrap_or_default().cold_store_retention;
+
+        let cold_head = cold_store.get_ser::<Tip>(DBCol::BlockMisc, HEAD_KEY)?.map(|t| t.height);
+        let start_height = match (self.start_height, cold_head) {
+            (Some(height), _) => height,
+            (None, Some(cold_head)) => cold_head + 1,
+            (None, None) => config.genesis.config.genesis_height,
+        };
+        let end_height = match self.end_height {
+            Some(height) => height,
+            None => {
+                hot_store
+                    .get_ser::<Tip>(DBCol::BlockMisc, FINAL_HEAD_KEY)?
+                    .context("hot FINAL_HEAD is not set")?
+                    .height
+            }
+        };
+        anyhow::ensure!(
+            start_height <= end_height,
+            "nothing to copy, start height {start_height} is past end height {end_height}"
+        );
+        if let Some(tail) = hot_store.get_ser::<BlockHeight>(DBCol::BlockMisc, TAIL_KEY)? {
+            anyhow::ensure!(
+                tail <= start_height,
+                "start height {start_height} is below hot TAIL {tail}, its data is garbage collected"
+            );
+        }
+
+        let mut progress = BackfillProgress {
+            start_height,
+            end_height,
+            range_size: self.range_size,
+            done: vec![],
+        };
+        match hot_store
+            .get_ser::<BackfillProgress>(DBCol::Misc, COLD_STORE_BACKFILL_PROGRESS_KEY)?
+        {
+            Some(saved)
+                if (saved.start_height, saved.end_height, saved.range_size)
+                    == (start_height, end_height, self.range_size) =>
+            {
+                println!("Resuming backfill, {} ranges already copied", saved.done.len());
+                progress = saved;
+            }
+            Some(saved) => tracing::warn!(
+                target: "cold_store",
+                ?saved,
+                "Ignoring progress of a backfill with different parameters"
+            ),
+            None => {}
+        }
+        let num_ranges = progress.num_ranges();
+        println!(
+            "Copying heights {start_height}..={end_height} in {num_ranges} ranges using {} threads",
+            self.num_threads
+        );
+
+        let remaining: Vec<u64> =
+            (0..num_ranges).filter(|index| !progress.done.contains(index)).collect();
+        let next = AtomicU64::new(0);
+        let failed = AtomicBool::new(false);
+        let progress = Mutex::new(progress);
+        let results: Vec<anyhow::Result<()>> = std::thread::scope(|scope| {
+            let workers: Vec<_> = (0..self.num_threads)
+                .map(|_| {
+                    scope.spawn(|| -> anyhow::Result<()> {
+                        while !failed.load(Ordering::Relaxed) {
+                            let Some(&index) =
+                                remaining.get(next.fetch_add(1, Ordering::Relaxed) as usize)
+                            else {
+                                break;
+                            };
+                            let range = progress.lock().unwrap().range(index);
+                            let result = self.copy_range(
+                                &cold_db,
+                                &hot_store,
+                                epoch_manager,
+                                range.clone(),
+                                &retention,
+                            );
+                            if let Err(err) = result {
+                                failed.store(true, Ordering::Relaxed);
+                                return Err(err.context(format!("failed to copy {range:?}")));
+                            }
+
+                            let mut progress = progress.lock().unwrap();
+                            progress.done.push(index);
+                            let mut update = hot_store.store_update();
+                            update.set_ser(
+                                DBCol::Misc,
+                                COLD_STORE_BACKFILL_PROGRESS_KEY,
+                                &*progress,
+                            )?;
+                            update.commit()?;
+                            println!(
+                                "Copied heights {range:?}, {}/{num_ranges} ranges done",
+                                progress.done.len()
+                            );
+                        }
+                        Ok(())
+                    })
+                })
+                .collect();
+            workers.into_iter().map(|worker| worker.join().unwrap()).collect()
+        });
+        results.into_iter().collect::<anyhow::Result<()>>()?;
+
+        // Cold HEAD can only move if there is no gap between it and the
+        // copied heights.
+        let genesis_height = config.genesis.config.genesis_height;
+        if cold_head
+            .map_or(start_height <= genesis_height, |cold_head| start_height <= cold_head + 1)
+        {
+            let last_height = (start_height..=end_height)
+                .rev()
+                .find(|height| {
+                    hot_store.exists(DBCol::BlockHeight, &height.to_le_bytes()).unwrap_or(false)
+                })
+                .context("no blocks in the copied range")?;
+            if cold_head.map_or(true, |cold_head| cold_head < last_height) {
+                update_cold_head(&cold_db, &hot_store, &last_height)?;
+                println!("Updated cold HEAD to {last_height}");
+            }
+        }
+
+        self.verify(&cold_store, &hot_store, epoch_manager, start_height, end_height, &retention)?;
+
+        let mut update = hot_store.store_update();
+        update.delete(DBCol::Misc, COLD_STORE_BACKFILL_PROGRESS_KEY);
+        update.commit()?;
+        Ok(())
+    }
+
+    /// Copies all blocks in `range` to cold db, writing the data in batches of
+    /// at least `batch_size` bytes.
+    fn copy_range(
+        &self,
+        cold_db: &ColdDB,
+        hot_store: &Store,
+        epoch_manager: &dyn EpochManagerAdapter,
+        range: std::ops::RangeInclusive<BlockHeight>,
+        retention: &ColdStoreRetentionPolicy,
+    ) -> anyhow::Result<()> {
+        let mut transaction = DBTransaction::new();
+        let mut transaction_size = 0;
+        for height in range {
+            let Some(shard_layout) = get_shard_layout(hot_store, epoch_manager, height)? else {
+                continue;
+            };
+            transaction_size +=
+                add_cold_db_update(&mut transaction, hot_store, &shard_layout, height, retention)?
+                    .unwrap_or(0);
+            if transaction_size > self.batch_size {
+                cold_db.write(std::mem::take(&mut transaction))?;
+                transaction_size = 0;
+            }
+        }
+        cold_db.write(transaction)?;
+        Ok(())
+    }
+
+    /// Checks data of `num_verified_blocks` random heights in the copied range.
+    fn verify(
+        &self,
+        cold_store: &Store,
+        hot_store: &Store,
+        epoch_manager: &dyn EpochManagerAdapter,
+        start_height: BlockHeight,
+        end_height: BlockHeight,
+        retention: &ColdStoreRetentionPolicy,
+    ) -> anyhow::Result<()> {
+        let num_heights = (end_height - start_height + 1) as usize;
+        let heights = rand::seq::index::sample(
+            &mut rand::thread_rng(),
+            num_heights,
+            self.num_verified_blocks.min(num_heights),
+        );
+        let mut num_blocks = 0;
+        let mut num_keys = 0;
+        for offset in heights {
+            let height = start_height + offset as BlockHeight;
+            let Some(shard_layout) = get_shard_layout(hot_store, epoch_manager, height)? else {
+                continue;
+            };
+            if let Some(keys) =
+                check_cold_db_update(cold_store, hot_store, &shard_layout, height, retention)?
+            {
+                num_blocks += 1;
+                num_keys += keys;
+            }
+        }
+        println!("Verified {num_keys} keys of {num_blocks} blocks");
+        Ok(())
+    }
+}
+
+/// Returns shard layout of the block at `height` or `None` if there is no
+/// block at that height.
+fn get_shard_layout(
+    hot_store: &Store,
+    epoch_manager: &dyn EpochManagerAdapter,
+    height: BlockHeight,
+) -> anyhow::Result<Option<near_primitives::shard_layout::ShardLayout>> {
+    let Some(block_hash) =
+        hot_store.get_ser::<CryptoHash>(DBCol::BlockHeight, &height.to_le_bytes())?
+    else {
+        return Ok(None);
+    };
+    let header = hot_store
+        .get_ser::<BlockHeader>(DBCol::BlockHeader, block_hash.as_bytes())?
+        .with_context(|| format!("no header of block {block_hash} at height {height}"))?;
+    Ok(Some(epoch_manager.get_shard_layout(header.epoch_id())?))
+}
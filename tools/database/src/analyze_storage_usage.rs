@@ -0,0 +1,347 @@
+//! Ranked report of state storage consumed by accounts and by key prefixes of
+//! contract data, computed in one pass over flat storage.
+
+use crate::utils::open_rocksdb;
+use near_epoch_manager::{EpochManager, EpochManagerAdapter};
+use near_primitives::block::Tip;
+use near_primitives::block_header::BlockHeader;
+use near_primitives::trie_key::col;
+use near_primitives::trie_key::trie_key_parsers::{
+    parse_account_id_from_raw_key, parse_data_key_from_contract_data_key,
+};
+use near_primitives::types::{AccountId, ShardId};
+use near_store::flat::{store_helper, FlatStorageStatus};
+use near_store::{DBCol, ShardUId, Store, HEAD_KEY};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+
+#[derive(Clone, Copy, clap::ValueEnum)]
+enum ReportFormat {
+    Csv,
+    Json,
+}
+
+impl ReportFormat {
+    fn extension(self) -> &'static str {
+        match self {
+            Self::Csv => "csv",
+            Self::Json => "json",
+        }
+    }
+}
+
+#[derive(clap::Parser)]
+pub(crate) struct AnalyzeStorageUsageCommand {
+    /// Shards to analyze.  By default, all shards of the head epoch.
+    #[clap(long, use_value_delimiter = true, value_delimiter = ',')]
+    shard_id: Option<Vec<ShardId>>,
+    /// Number of leading bytes of contract data keys forming a prefix.
+    /// Collections of near-sdk put their prefix there.
+    #[clap(long, default_value_t = 1)]
+    prefix_len: usize,
+    /// Number of top accounts and prefixes to report, 0 to report all.
+    #[clap(long, default_value_t = 1000)]
+    top_n: usize,
+    #[clap(long, value_enum, default_value = "csv")]
+    format: ReportFormat,
+    /// Directory to write `accounts`, `prefixes` and `metadata.json` to.
+    #[clap(long, default_value = "storage_usage")]
+    output: PathBuf,
+}
+
+/// Bytes of keys and values of a group of records.
+#[derive(Default, Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+struct Usage {
+    records: u64,
+    bytes: u64,
+}
+
+impl Usage {
+    fn add(&mut self, bytes: usize) {
+        self.records += 1;
+        self.bytes += bytes as u64;
+    }
+}
+
+#[derive(Default, Debug, PartialEq, Eq)]
+struct AccountUsage {
+    account: Usage,
+    access_keys: Usage,
+    contract_code: Usage,
+    contract_data: Usage,
+    /// Postponed receipts, received data and other records keyed by the
+    /// account.
+    other: Usage,
+}
+
+impl AccountUsage {
+    fn total_bytes(&self) -> u64 {
+        self.account.bytes
+            + self.access_keys.bytes
+            + self.contract_code.bytes
+            + self.contract_data.bytes
+            + self.other.bytes
+    }
+}
+
+#[derive(Serialize)]
+struct AccountRow<'a> {
+    shard_uid: String,
+    account_id: &'a AccountId,
+    total_bytes: u64,
+    account_bytes: u64,
+    access_keys: u64,
+    access_keys_bytes: u64,
+    contract_code_bytes: u64,
+    contract_data_records: u64,
+    contract_data_bytes: u64,
+    other_records: u64,
+    other_bytes: u64,
+}
+
+#[derive(Serialize)]
+struct PrefixRow<'a> {
+    shard_uid: String,
+    account_id: &'a AccountId,
+    /// Hex encoded prefix of the data keys.
+    prefix: String,
+    records: u64,
+    bytes: u64,
+}
+
+/// Storage usage of the records of a single shard.
+#[derive(Default)]
+struct ShardStorageUsage {
+    accounts: HashMap<AccountId, AccountUsage>,
+    prefixes: HashMap<(AccountId, Vec<u8>), Usage>,
+    /// Records not keyed by an account, e.g. delayed receipts.
+    unattributed: Usage,
+}
+
+impl ShardStorageUsage {
+    fn add(&mut self, key: &[u8], value_len: usize, prefix_len: usize) -> anyhow::Result<()> {
+        let bytes = key.len() + value_len;
+        let Some(account_id) = parse_account_id_from_raw_key(key)? else {
+            self.unattributed.add(bytes);
+            return Ok(());
+        };
+        if key[0] == col::CONTRACT_DATA {
+            let data_key = parse_data_key_from_contract_data_key(key, &account_id)?;
+            let prefix = data_key[..prefix_len.min(data_key.len())].to_vec();
+            self.prefixes.entry((account_id.clone(), prefix)).or_default().add(bytes);
+        }
+        let usage = self.accounts.entry(account_id).or_default();
+        match key[0] {
+            col::ACCOUNT => usage.account.add(bytes),
+            col::ACCESS_KEY => usage.access_keys.add(bytes),
+            col::CONTRACT_CODE => usage.contract_code.add(bytes),
+            col::CONTRACT_DATA => usage.contract_data.add(bytes),
+            _ => usage.other.add(bytes),
+        }
+        Ok(())
+    }
+}
+
+impl AnalyzeStorageUsageCommand {
+    pub(crate) fn run(&self, home: &Path) -> anyhow::Result<()> {
+        let near_config = nearcore::config::load_config(
+            &home,
+            near_chain_configs::GenesisValidationMode::UnsafeFast,
+        )
+        .unwrap_or_else(|e| panic!("Error loading config: {:#}", e));
+        let rocksdb = open_rocksdb(home, near_store::Mode::ReadOnly)?;
+        let store = near_store::NodeStorage::new(std::sync::Arc::new(rocksdb)).get_hot_store();
+        let head = store
+            .get_ser::<Tip>(DBCol::BlockMisc, HEAD_KEY)?
+            .ok_or_else(|| anyhow::anyhow!("head not found"))?;
+        let block_header = store
+            .get_ser::<BlockHeader>(DBCol::BlockHeader, &borsh::to_vec(&head.last_block_hash)?)?
+            .ok_or_else(|| anyhow::anyhow!("head block header not found"))?;
+        let epoch_manager =
+            EpochManager::new_arc_handle(store.clone(), &near_config.genesis.config);
+        let shard_uids: Vec<ShardUId> = epoch_manager
+            .get_shard_layout(block_header.epoch_id())?
+            .shard_uids()
+            .filter(|uid| self.shard_id.as_ref().map_or(true, |ids| ids.contains(&uid.shard_id())))
+            .collect();
+
+        std::fs::create_dir_all(&self.output)?;
+        let mut accounts = Vec::new();
+        let mut prefixes = Vec::new();
+        let mut shards = BTreeMap::new();
+        for shard_uid in shard_uids {
+            let flat_head = match store_helper::get_flat_storage_status(&store, shard_uid)? {
+                FlatStorageStatus::Ready(status) => status.flat_head,
+                status => anyhow::bail!("flat storage of {shard_uid} is not ready: {status:?}"),
+            };
+            println!("Analyzing {shard_uid} at flat head #{}", flat_head.height);
+            let usage = self.analyze_shard(&store, shard_uid)?;
+            println!(
+                "{shard_uid}: {} accounts, {} bytes not attributed to any account",
+                usage.accounts.len(),
+                usage.unattributed.bytes
+            );
+            shards.insert(
+                shard_uid.to_string(),
+                serde_json::json!({
+                    "flat_head_height": flat_head.height,
+                    "flat_head_hash": flat_head.hash,
+                    "accounts": usage.accounts.len(),
+                    "unattributed": usage.unattributed,
+                }),
+            );
+            accounts.extend(usage.accounts.into_iter().map(|(id, usage)| (shard_uid, id, usage)));
+            prefixes.extend(usage.prefixes.into_iter().map(|(key, usage)| (shard_uid, key, usage)));
+        }
+
+        accounts.sort_by(|(_, a_id, a), (_, b_id, b)| {
+            b.total_bytes().cmp(&a.total_bytes()).then_with(|| a_id.cmp(b_id))
+        });
+        prefixes.sort_by(|(_, a_key, a), (_, b_key, b)| {
+            b.bytes.cmp(&a.bytes).then_with(|| a_key.cmp(b_key))
+        });
+        if self.top_n > 0 {
+            accounts.truncate(self.top_n);
+            prefixes.truncate(self.top_n);
+        }
+        let account_rows = accounts.iter().map(|(shard_uid, account_id, usage)| AccountRow {
+            shard_uid: shard_uid.to_string(),
+            account_id,
+            total_bytes: usage.total_bytes(),
+            account_bytes: usage.account.bytes,
+            access_keys: usage.access_keys.records,
+            access_keys_bytes: usage.access_keys.bytes,
+            contract_code_bytes: usage.contract_code.bytes,
+            contract_data_records: usage.contract_data.records,
+            contract_data_bytes: usage.contract_data.bytes,
+            other_records: usage.other.records,
+            other_bytes: usage.other.bytes,
+        });
+        self.write_report("accounts", account_rows)?;
+        let prefix_rows =
+            prefixes.iter().map(|(shard_uid, (account_id, prefix), usage)| PrefixRow {
+                shard_uid: shard_uid.to_string(),
+                account_id,
+                prefix: hex::encode(prefix),
+                records: usage.records,
+                bytes: usage.bytes,
+            });
+        self.write_report("prefixes", prefix_rows)?;
+
+        let metadata = serde_json::json!({
+            "head_height": head.height,
+            "head_hash": head.last_block_hash,
+            "prefix_len": self.prefix_len,
+            "shards": shards,
+        });
+        std::fs::write(
+            self.output.join("metadata.json"),
+            serde_json::to_string_pretty(&metadata)?,
+        )?;
+        println!("Report written to {}", self.output.display());
+        Ok(())
+    }
+
+    fn analyze_shard(
+        &self,
+        store: &Store,
+        shard_uid: ShardUId,
+    ) -> anyhow::Result<ShardStorageUsage> {
+        let mut usage = ShardStorageUsage::default();
+        let progress = indicatif::ProgressBar::new_spinner();
+        for (i, item) in
+            store_helper::iter_flat_state_entries(shard_uid, store, None, None).enumerate()
+        {
+            let (key, value) = item?;
+            // Only the length of values is needed, which references to
+            // values stored outside of flat storage already have.
+            usage.add(&key, value.value_len(), self.prefix_len)?;
+            if i % 100_000 == 0 {
+                progress.set_message(format!("{shard_uid}: {i} records"));
+                progress.tick();
+            }
+        }
+        progress.finish_and_clear();
+        Ok(usage)
+    }
+
+    fn write_report<T: Serialize>(
+        &self,
+        name: &str,
+        rows: impl Iterator<Item = T>,
+    ) -> anyhow::Result<()> {
+        let path = self.output.join(format!("{name}.{}", self.format.extension()));
+        let file = std::io::BufWriter::new(std::fs::File::create(path)?);
+        match self.format {
+            ReportFormat::Csv => {
+                let mut writer = csv::Writer::from_writer(file);
+                for row in rows {
+                    writer.serialize(row)?;
+                }
+                writer.flush()?;
+            }
+            ReportFormat::Json => {
+                serde_json::to_writer_pretty(file, &rows.collect::<Vec<_>>())?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AccountUsage, ShardStorageUsage, Usage};
+    use near_primitives::trie_key::TrieKey;
+    use near_primitives::types::AccountId;
+
+    #[test]
+    fn test_storage_usage() {
+        let alice: AccountId = "alice.near".parse().unwrap();
+        let data_key = |key: &[u8]| {
+            TrieKey::ContractData { account_id: alice.clone(), key: key.to_vec() }.to_vec()
+        };
+        let account_key = TrieKey::Account { account_id: alice.clone() }.to_vec();
+        let code_key = TrieKey::ContractCode { account_id: alice.clone() }.to_vec();
+        let delayed_receipt_key = TrieKey::DelayedReceipt { index: 0 }.to_vec();
+
+        let mut usage = ShardStorageUsage::default();
+        usage.add(&account_key, 100, 1).unwrap();
+        usage.add(&code_key, 10, 1).unwrap();
+        usage.add(&data_key(b"ab"), 5, 1).unwrap();
+        usage.add(&data_key(b"ac"), 5, 1).unwrap();
+        usage.add(&data_key(b"b"), 5, 1).unwrap();
+        usage.add(&data_key(b""), 5, 1).unwrap();
+        usage.add(&delayed_receipt_key, 50, 1).unwrap();
+
+        let data_bytes = |key: &[u8]| (data_key(key).len() + 5) as u64;
+        assert_eq!(
+            usage.accounts[&alice],
+            AccountUsage {
+                account: Usage { records: 1, bytes: account_key.len() as u64 + 100 },
+                access_keys: Usage::default(),
+                contract_code: Usage { records: 1, bytes: code_key.len() as u64 + 10 },
+                contract_data: Usage {
+                    records: 4,
+                    bytes: data_bytes(b"ab")
+                        + data_bytes(b"ac")
+                        + data_bytes(b"b")
+                        + data_bytes(b""),
+                },
+                other: Usage::default(),
+            }
+        );
+        assert_eq!(
+            usage.prefixes[&(alice.clone(), b"a".to_vec())],
+            Usage { records: 2, bytes: data_bytes(b"ab") + data_bytes(b"ac") }
+        );
+        assert_eq!(
+            usage.prefixes[&(alice.clone(), b"".to_vec())],
+            Usage { records: 1, bytes: data_bytes(b"") }
+        );
+        assert_eq!(
+            usage.unattributed,
+            Usage { records: 1, bytes: delayed_receipt_key.len() as u64 + 50 }
+        );
+    }
+}
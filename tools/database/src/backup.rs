@@ -0,0 +1,483 @@
+use anyhow::Context;
+use near_primitives::block::Tip;
+use near_primitives::hash::CryptoHash;
+use near_primitives::types::BlockHeight;
+use near_store::db::{BackupFiles, RedbDB, RocksDB, SharedBackupFile};
+use near_store::metadata::{DbKind, DbVersion, DB_VERSION};
+use near_store::{DBCol, Mode, NodeStorage, Store, StoreConfig, Temperature, HEAD_KEY};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// Name of the file describing a backup.
+const MANIFEST_FILE: &str = "manifest.json";
+
+#[derive(clap::Args)]
+pub(crate) struct BackupCommand {
+    /// Directory with the backups.  SST files are shared between backups in
+    /// the same directory, so only the files added since the previous backup
+    /// are copied.  Shared files no longer used by any backup, e.g. after its
+    /// directory was removed, are deleted after the backup is made.
+    #[clap(long)]
+    destination: PathBuf,
+    /// Back up the cold database as well.
+    #[clap(long)]
+    include_cold: bool,
+    /// How many times to try making the backup.  A backup taken while the
+    /// node is running may fail verification if the node changes the database
+    /// files at the same time, in which case it’s retried.
+    #[clap(long, default_value_t = 5)]
+    attempts: usize,
+}
+
+#[derive(clap::Args)]
+pub(crate) struct RestoreCommand {
+    /// Directory with the backups.
+    #[clap(long)]
+    source: PathBuf,
+    /// Id of the backup to restore.  Defaults to the latest backup.
+    #[clap(long)]
+    id: Option<u64>,
+    /// Restore the cold database as well.  The backup must include it.
+    #[clap(long)]
+    include_cold: bool,
+}
+
+/// Description of a backup, stored in its directory.
+#[derive(serde::Serialize, serde::Deserialize)]
+struct BackupManifest {
+    id: u64,
+    /// Unix timestamp of the backup in seconds.
+    created_at: u64,
+    hot: DbBackup,
+    cold: Option<DbBackup>,
+}
+
+/// Description of a backup of a single database.
+#[derive(serde::Serialize, serde::Deserialize)]
+struct DbBackup {
+    db_version: DbVersion,
+    db_kind: Option<DbKind>,
+    head_height: BlockHeight,
+    head_hash: CryptoHash,
+    files: BackupFiles,
+}
+
+fn temperature_dir(temp: Temperature) -> &'static str {
+    match temp {
+        Temperature::Hot => "hot",
+        Temperature::Cold => "cold",
+    }
+}
+
+fn backup_dir(dir: &Path, id: u64) -> PathBuf {
+    dir.join("backups").join(id.to_string())
+}
+
+fn shared_dir(dir: &Path, temp: Temperature) -> PathBuf {
+    dir.join("shared").join(temperature_dir(temp))
+}
+
+/// Returns ids of the existing backups in ascending order.
+fn backup_ids(dir: &Path) -> anyhow::Result<Vec<u64>> {
+    let backups_dir = dir.join("backups");
+    if !backups_dir.exists() {
+        return Ok(vec![]);
+    }
+    let mut ids = vec![];
+    for entry in std::fs::read_dir(backups_dir)? {
+        let entry = entry?;
+        // Directories of unfinished backups have a `.tmp` suffix.
+        if let Some(id) = entry.file_name().to_str().and_then(|name| name.parse().ok()) {
+            if entry.path().join(MANIFEST_FILE).is_file() {
+                ids.push(id);
+            }
+        }
+    }
+    ids.sort();
+    Ok(ids)
+}
+
+fn read_manifest(dir: &Path, id: u64) -> anyhow::Result<BackupManifest> {
+    let dir = backup_dir(dir, id);
+    let manifest = std::fs::read(dir.join(MANIFEST_FILE))
+        .with_context(|| format!("failed to read manifest of backup {id} in {}", dir.display()))?;
+    Ok(serde_json::from_slice(&manifest)?)
+}
+
+/// Removes the shared files which none of the backups in `dir` refers to.
+/// Nothing is removed if any of the manifests can’t be read.
+///
+/// Returns the number of freed bytes.
+fn remove_unreferenced_files(dir: &Path) -> anyhow::Result<u64> {
+    let mut hot = HashSet::new();
+    let mut cold = HashSet::new();
+    for id in backup_ids(dir)? {
+        let manifest = read_manifest(dir, id)?;
+        hot.extend(manifest.hot.files.shared.iter().map(SharedBackupFile::shared_name));
+        if let Some(backup) = manifest.cold {
+            cold.extend(backup.files.shared.iter().map(SharedBackupFile::shared_name));
+        }
+    }
+    Ok(RocksDB::remove_unreferenced_backup_files(&shared_dir(dir, Temperature::Hot), &hot)?
+        + RocksDB::remove_unreferenced_backup_files(&shared_dir(dir, Temperature::Cold), &cold)?)
+}
+
+/// Reads version, kind and head of the database at given path.
+fn read_db_info(
+    path: &Path,
+    store_config: &StoreConfig,
+    temp: Temperature,
+) -> anyhow::Result<(DbVersion, Option<DbKind>, Tip)> {
+    let store = Store::new(Arc::new(RocksDB::open(path, store_config, Mode::ReadOnly, temp)?));
+    let version = store.get_db_version()?.context("database has no DbVersion")?;
+    let kind = store.get_db_kind()?;
+    let head = store.get_ser::<Tip>(DBCol::BlockMisc, HEAD_KEY)?.context("database has no head")?;
+    anyhow::ensure!(
+        store.exists(DBCol::BlockHeader, head.last_block_hash.as_ref())?,
+        "database is missing the header of its head block {}",
+        head.last_block_hash
+    );
+    Ok((version, kind, head))
+}
+
+fn ensure_rocksdb(path: &Path) -> anyhow::Result<()> {
+    anyhow::ensure!(
+        !RedbDB::exists(path),
+        "{}: backups are only supported for RocksDB databases",
+        path.display()
+    );
+    Ok(())
+}
+
+impl BackupCommand {
+    pub(crate) fn run(
+        &self,
+        home_dir: &Path,
+        archive: bool,
+        store_config: &StoreConfig,
+        cold_store_config: Option<&StoreConfig>,
+    ) -> anyhow::Result<()> {
+        let opener = NodeStorage::opener(home_dir, archive, store_config, cold_store_config);
+        let cold = if self.include_cold {
+            let path = opener.cold_path().context("cold store is not configured")?;
+            Some((path, cold_store_config.unwrap()))
+        } else {
+            None
+        };
+
+        let id = backup_ids(&self.destination)?.last().map_or(1, |id| id + 1);
+        let tmp_dir = backup_dir(&self.destination, id).with_extension("tmp");
+        if tmp_dir.exists() {
+            std::fs::remove_dir_all(&tmp_dir)?;
+        }
+        let hot = self.backup_db(opener.path(), store_config, Temperature::Hot, &tmp_dir)?;
+        let cold = cold
+            .map(|(path, config)| self.backup_db(path, config, Temperature::Cold, &tmp_dir))
+            .transpose()?;
+
+        let created_at = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH)?;
+        let manifest = BackupManifest { id, created_at: created_at.as_secs(), hot, cold };
+        let copied_bytes = manifest.hot.files.copied_bytes
+            + manifest.cold.as_ref().map_or(0, |cold| cold.files.copied_bytes);
+        std::fs::write(tmp_dir.join(MANIFEST_FILE), serde_json::to_vec_pretty(&manifest)?)?;
+        std::fs::rename(&tmp_dir, backup_dir(&self.destination, id))?;
+        eprintln!(
+            "Created backup {id} at head #{} {}, copied {}",
+            manifest.hot.head_height,
+            manifest.hot.head_hash,
+            bytesize::ByteSize(copied_bytes)
+        );
+        match remove_unreferenced_files(&self.destination) {
+            Ok(0) => {}
+            Ok(freed_bytes) => {
+                eprintln!("Removed {} of unreferenced files", bytesize::ByteSize(freed_bytes))
+            }
+            Err(err) => eprintln!("Failed to remove unreferenced backup files: {err:#}"),
+        }
+        Ok(())
+    }
+
+    /// Backs up a single database into `temperature` subdirectory of `dir`
+    /// and verifies that the backup can be opened, retrying if it can’t.
+    fn backup_db(
+        &self,
+        db_path: &Path,
+        store_config: &StoreConfig,
+        temp: Temperature,
+        dir: &Path,
+    ) -> anyhow::Result<DbBackup> {
+        ensure_rocksdb(db_path)?;
+        let private_dir = dir.join(temperature_dir(temp));
+        let verify_dir = dir.join(format!("verify-{}", temperature_dir(temp)));
+        let shared_dir = shared_dir(&self.destination, temp);
+        let mut attempt = 0;
+        loop {
+            attempt += 1;
+            let result = RocksDB::backup(db_path, store_config, temp, &shared_dir, &private_dir)
+                .and_then(|files| {
+                    RocksDB::restore_backup(&files, &shared_dir, &private_dir, &verify_dir)?;
+                    let (db_version, db_kind, head) = read_db_info(&verify_dir, store_config, temp)
+                        .context("backup verification failed")?;
+                    Ok(DbBackup {
+                        db_version,
+                        db_kind,
+                        head_height: head.height,
+                        head_hash: head.last_block_hash,
+                        files,
+                    })
+                });
+            if verify_dir.exists() {
+                std::fs::remove_dir_all(&verify_dir)?;
+            }
+            match result {
+                Ok(backup) => return Ok(backup),
+                Err(err) if attempt < self.attempts => {
+                    eprintln!("Backup of {} failed, retrying: {err:#}", db_path.display());
+                    if private_dir.exists() {
+                        std::fs::remove_dir_all(&private_dir)?;
+                    }
+                }
+                Err(err) => return Err(err),
+            }
+        }
+    }
+}
+
+impl RestoreCommand {
+    pub(crate) fn run(
+        &self,
+        home_dir: &Path,
+        archive: bool,
+        store_config: &StoreConfig,
+        cold_store_config: Option<&StoreConfig>,
+    ) -> anyhow::Result<()> {
+        let id = match self.id {
+            Some(id) => id,
+            None => *backup_ids(&self.source)?.last().context("no backups found")?,
+        };
+        let dir = backup_dir(&self.source, id);
+        let manifest = read_manifest(&self.source, id)?;
+
+        let opener = NodeStorage::opener(home_dir, archive, store_config, cold_store_config);
+        self.restore_db(&dir, &manifest.hot, opener.path(), store_config, Temperature::Hot)?;
+        if self.include_cold {
+            let backup = manifest.cold.as_ref().context("backup doesn’t include cold database")?;
+            let path = opener.cold_path().context("cold store is not configured")?;
+            self.restore_db(&dir, backup, path, cold_store_config.unwrap(), Temperature::Cold)?;
+        }
+        eprintln!(
+            "Restored backup {id} at head #{} {}",
+            manifest.hot.head_height, manifest.hot.head_hash
+        );
+        Ok(())
+    }
+
+    /// Restores a single database to `target` and checks that its version,
+    /// kind and head match the manifest.  On failure, the restored files are
+    /// removed.
+    fn restore_db(
+        &self,
+        dir: &Path,
+        backup: &DbBackup,
+        target: &Path,
+        store_config: &StoreConfig,
+        temp: Temperature,
+    ) -> anyhow::Result<()> {
+        anyhow::ensure!(
+            backup.db_version <= DB_VERSION,
+            "backup has DbVersion {} which is newer than {DB_VERSION} supported by this binary",
+            backup.db_version
+        );
+        anyhow::ensure!(!target.exists(), "{} already exists", target.display());
+        let result = RocksDB::restore_backup(
+            &backup.files,
+            &shared_dir(&self.source, temp),
+            &dir.join(temperature_dir(temp)),
+            target,
+        )
+        .and_then(|()| {
+            let (db_version, db_kind, head) = read_db_info(target, store_config, temp)?;
+            anyhow::ensure!(
+                db_version == backup.db_version && db_kind == backup.db_kind,
+                "restored database has DbVersion {db_version} and DbKind {db_kind:?}, expected {} and {:?}",
+                backup.db_version,
+                backup.db_kind
+            );
+            anyhow::ensure!(
+                head.height == backup.head_height && head.last_block_hash == backup.head_hash,
+                "restored database has head #{} {}, expected #{} {}",
+                head.height,
+                head.last_block_hash,
+                backup.head_height,
+                backup.head_hash
+            );
+            Ok(())
+        });
+        if result.is_err() && target.exists() {
+            std::fs::remove_dir_all(target)?;
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::backup::{backup_ids, read_manifest, BackupCommand, RestoreCommand};
+    use near_primitives::block::Tip;
+    use near_primitives::hash::CryptoHash;
+    use near_store::{DBCol, Mode, NodeStorage, Store, StoreConfig, HEAD_KEY};
+
+    fn set_head(store: &Store, height: u64) {
+        let hash = CryptoHash::hash_bytes(&height.to_le_bytes());
+        let tip = Tip {
+            height,
+            last_block_hash: hash,
+            prev_block_hash: CryptoHash::default(),
+            epoch_id: Default::default(),
+            next_epoch_id: Default::default(),
+        };
+        let mut store_update = store.store_update();
+        store_update.set_ser(DBCol::BlockMisc, HEAD_KEY, &tip).unwrap();
+        store_update.insert(DBCol::BlockHeader, hash.as_ref().to_vec(), vec![42]);
+        store_update.insert(DBCol::Block, height.to_le_bytes().to_vec(), vec![height as u8]);
+        store_update.commit().unwrap();
+    }
+
+    /// Makes two backups with changes in between and restores the first one.
+    /// Checks that the second backup reuses the files of the first one and
+    /// that the restored database has the data as of the first backup.
+    #[test]
+    fn test_backup_and_restore() {
+        let home_dir = tempfile::tempdir().unwrap();
+        let backups = home_dir.path().join("backups");
+        let store_config = StoreConfig::test_config();
+        let opener = NodeStorage::opener(home_dir.path(), false, &store_config, None);
+
+        let cmd = BackupCommand { destination: backups.clone(), include_cold: false, attempts: 1 };
+        {
+            let store = opener.open().unwrap().get_hot_store();
+            set_head(&store, 1);
+            store.compact().unwrap();
+            set_head(&store, 2);
+            cmd.run(home_dir.path(), false, &store_config, None).unwrap();
+            set_head(&store, 3);
+            cmd.run(home_dir.path(), false, &store_config, None).unwrap();
+        }
+        assert_eq!(backup_ids(&backups).unwrap(), vec![1, 2]);
+        let manifest = |id| -> serde_json::Value {
+            let path = backups.join("backups").join(id).join("manifest.json");
+            serde_json::from_slice(&std::fs::read(path).unwrap()).unwrap()
+        };
+        let (first, second) = (manifest("1"), manifest("2"));
+        assert_eq!(first["hot"]["head_height"], 2);
+        assert_eq!(second["hot"]["head_height"], 3);
+        let first_shared = first["hot"]["files"]["shared"].as_array().unwrap();
+        assert!(!first_shared.is_empty());
+        for file in first_shared {
+            // Files are identified by the session id in their properties, so the
+            // checksum is only computed for the files which are copied.
+            assert!(file["session_id"].is_string());
+            assert!(file["crc32"].is_u64());
+            let reused = second["hot"]["files"]["shared"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .find(|second_file| second_file["name"] == file["name"])
+                .unwrap();
+            assert_eq!(reused["session_id"], file["session_id"]);
+            assert!(reused["crc32"].is_null());
+        }
+
+        // Restoring into an existing database is refused.
+        let restore = RestoreCommand { source: backups.clone(), id: Some(1), include_cold: false };
+        assert!(restore.run(home_dir.path(), false, &store_config, None).is_err());
+
+        let restored_home = tempfile::tempdir().unwrap();
+        restore.run(restored_home.path(), false, &store_config, None).unwrap();
+        let store = NodeStorage::opener(restored_home.path(), false, &store_config, None)
+            .open_in_mode(Mode::ReadOnly)
+            .unwrap()
+            .get_hot_store();
+        assert!(store.exists(DBCol::Block, &2u64.to_le_bytes()).unwrap());
+        assert!(!store.exists(DBCol::Block, &3u64.to_le_bytes()).unwrap());
+        let head = store.get_ser::<Tip>(DBCol::BlockMisc, HEAD_KEY).unwrap().unwrap();
+        assert_eq!(head.height, 2);
+    }
+
+    fn copy_dir(source: &std::path::Path, target: &std::path::Path) {
+        std::fs::create_dir_all(target).unwrap();
+        for entry in std::fs::read_dir(source).unwrap() {
+            let entry = entry.unwrap();
+            if entry.file_type().unwrap().is_file() {
+                std::fs::copy(entry.path(), target.join(entry.file_name())).unwrap();
+            }
+        }
+    }
+
+    /// Backs up two databases with the same identity, which write different
+    /// SST files with the same numbers, into one destination.  Checks that
+    /// both backups can be restored and that the shared files of a removed
+    /// backup are deleted by the next one.
+    #[test]
+    fn test_backups_of_databases_with_same_identity() {
+        let backups = tempfile::tempdir().unwrap();
+        let store_config = StoreConfig::test_config();
+        let cmd = BackupCommand {
+            destination: backups.path().to_path_buf(),
+            include_cold: false,
+            attempts: 1,
+        };
+        let first_home = tempfile::tempdir().unwrap();
+        let second_home = tempfile::tempdir().unwrap();
+        let first_opener = NodeStorage::opener(first_home.path(), false, &store_config, None);
+        let second_opener = NodeStorage::opener(second_home.path(), false, &store_config, None);
+        {
+            let store = first_opener.open().unwrap().get_hot_store();
+            set_head(&store, 1);
+            store.compact().unwrap();
+        }
+        copy_dir(first_opener.path(), second_opener.path());
+        for (opener, home, height) in
+            [(&first_opener, &first_home, 2), (&second_opener, &second_home, 7)]
+        {
+            let store = opener.open().unwrap().get_hot_store();
+            set_head(&store, height);
+            store.compact().unwrap();
+            drop(store);
+            cmd.run(home.path(), false, &store_config, None).unwrap();
+        }
+        assert_eq!(backup_ids(backups.path()).unwrap(), vec![1, 2]);
+
+        for (id, height) in [(1, 2), (2, 7)] {
+            let restored_home = tempfile::tempdir().unwrap();
+            let restore = RestoreCommand {
+                source: backups.path().to_path_buf(),
+                id: Some(id),
+                include_cold: false,
+            };
+            restore.run(restored_home.path(), false, &store_config, None).unwrap();
+            let store = NodeStorage::opener(restored_home.path(), false, &store_config, None)
+                .open_in_mode(Mode::ReadOnly)
+                .unwrap()
+                .get_hot_store();
+            let head = store.get_ser::<Tip>(DBCol::BlockMisc, HEAD_KEY).unwrap().unwrap();
+            assert_eq!(head.height, height);
+        }
+
+        // Only the files of the remaining backups are kept.
+        std::fs::remove_dir_all(backups.path().join("backups").join("1")).unwrap();
+        cmd.run(second_home.path(), false, &store_config, None).unwrap();
+        let mut referenced = std::collections::HashSet::new();
+        for id in backup_ids(backups.path()).unwrap() {
+            let manifest = read_manifest(backups.path(), id).unwrap();
+            referenced.extend(manifest.hot.files.shared.iter().map(|file| file.shared_name()));
+        }
+        let shared: std::collections::HashSet<_> =
+            std::fs::read_dir(backups.path().join("shared").join("hot"))
+                .unwrap()
+                .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+                .collect();
+        assert_eq!(shared, referenced);
+    }
+}
@@ -4,10 +4,14 @@ use crate::analyse_gas_usage::AnalyseGasUsageCommand;
 use crate::analyse_high_load::HighLoadStatsCommand;
 use crate::analyze_contract_sizes::AnalyzeContractSizesCommand;
 use crate::analyze_delayed_receipt::AnalyzeDelayedReceiptCommand;
+use crate::analyze_storage_usage::AnalyzeStorageUsageCommand;
+use crate::backup::{BackupCommand, RestoreCommand};
 use crate::compact::RunCompactionCommand;
 use crate::corrupt::CorruptStateSnapshotCommand;
 use crate::make_snapshot::MakeSnapshotCommand;
 use crate::memtrie::LoadMemTrieCommand;
+use crate::migrate_backend::MigrateBackendCommand;
+use crate::recompress::RecompressCommand;
 use crate::run_migrations::RunMigrationsCommand;
 use crate::state_perf::StatePerfCommand;
 use crate::write_to_db::WriteCryptoHashCommand;
@@ -29,6 +33,9 @@ enum SubCommand {
     /// Analyse gas usage in a chosen sequnce of blocks
     AnalyseGasUsage(AnalyseGasUsageCommand),
 
+    /// Make an incremental backup of the database, also while the node is running
+    Backup(BackupCommand),
+
     /// Change DbKind of hot or cold db.
     ChangeDbKind(ChangeDbKindCommand),
 
@@ -41,6 +48,15 @@ enum SubCommand {
     /// Make snapshot of the database
     MakeSnapshot(MakeSnapshotCommand),
 
+    /// Copy the database to a new one with another storage backend
+    MigrateBackend(MigrateBackendCommand),
+
+    /// Rewrite SST files with the current per column compression options
+    Recompress(RecompressCommand),
+
+    /// Restore the database from a backup
+    Restore(RestoreCommand),
+
     /// Run migrations,
     RunMigrations(RunMigrationsCommand),
 
@@ -59,6 +75,9 @@ enum SubCommand {
     AnalyzeDelayedReceipt(AnalyzeDelayedReceiptCommand),
     /// Analyze size of contracts present in the current state
     AnalyzeContractSizes(AnalyzeContractSizesCommand),
+    /// Rank accounts and contract data key prefixes by the state storage
+    /// they use, computed from flat storage
+    AnalyzeStorageUsage(AnalyzeStorageUsageCommand),
 }
 
 impl DatabaseCommand {
@@ -66,6 +85,19 @@ impl DatabaseCommand {
         match &self.subcmd {
             SubCommand::AnalyseDataSizeDistribution(cmd) => cmd.run(home),
             SubCommand::AnalyseGasUsage(cmd) => cmd.run(home),
+            SubCommand::Backup(cmd) => {
+                let near_config = nearcore::config::load_config(
+                    &home,
+                    near_chain_configs::GenesisValidationMode::UnsafeFast,
+                )
+                .unwrap_or_else(|e| panic!("Error loading config: {:#}", e));
+                cmd.run(
+                    home,
+                    near_config.config.archive,
+                    &near_config.config.store,
+                    near_config.config.cold_store.as_ref(),
+                )
+            }
             SubCommand::ChangeDbKind(cmd) => cmd.run(home),
             SubCommand::CompactDatabase(cmd) => cmd.run(home),
             SubCommand::CorruptStateSnapshot(cmd) => cmd.run(home),
@@ -77,6 +109,28 @@ impl DatabaseCommand {
                 .unwrap_or_else(|e| panic!("Error loading config: {:#}", e));
                 cmd.run(home, near_config.config.archive, &near_config.config.store)
             }
+            SubCommand::MigrateBackend(cmd) => {
+                let near_config = nearcore::config::load_config(
+                    &home,
+                    near_chain_configs::GenesisValidationMode::UnsafeFast,
+                )
+                .unwrap_or_else(|e| panic!("Error loading config: {:#}", e));
+                cmd.run(home, near_config.config.archive, &near_config.config.store)
+            }
+            SubCommand::Recompress(cmd) => cmd.run(home),
+            SubCommand::Restore(cmd) => {
+                let near_config = nearcore::config::load_config(
+                    &home,
+                    near_chain_configs::GenesisValidationMode::UnsafeFast,
+                )
+                .unwrap_or_else(|e| panic!("Error loading config: {:#}", e));
+                cmd.run(
+                    home,
+                    near_config.config.archive,
+                    &near_config.config.store,
+                    near_config.config.cold_store.as_ref(),
+                )
+            }
             SubCommand::RunMigrations(cmd) => cmd.run(home),
             SubCommand::StatePerf(cmd) => cmd.run(home),
             SubCommand::LoadMemTrie(cmd) => cmd.run(home),
@@ -84,6 +138,7 @@ impl DatabaseCommand {
             SubCommand::HighLoadStats(cmd) => cmd.run(home),
             SubCommand::AnalyzeDelayedReceipt(cmd) => cmd.run(home),
             SubCommand::AnalyzeContractSizes(cmd) => cmd.run(home),
+            SubCommand::AnalyzeStorageUsage(cmd) => cmd.run(home),
         }
     }
 }
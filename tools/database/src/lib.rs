@@ -4,12 +4,16 @@ mod analyse_gas_usage;
 mod analyse_high_load;
 mod analyze_contract_sizes;
 mod analyze_delayed_receipt;
+mod analyze_storage_usage;
+mod backup;
 mod block_iterators;
 pub mod commands;
 mod compact;
 mod corrupt;
 mod make_snapshot;
 mod memtrie;
+mod migrate_backend;
+mod recompress;
 mod run_migrations;
 mod state_perf;
 mod utils;
@@ -0,0 +1,141 @@
+use near_store::db::{DBTransaction, Database};
+use near_store::{DBCol, Mode, NodeStorage, StoreBackend, StoreConfig, Temperature};
+use std::path::{Path, PathBuf};
+use strum::IntoEnumIterator;
+
+#[derive(clap::Args)]
+pub(crate) struct MigrateBackendCommand {
+    /// Backend of the new database, `RocksDb` or `Redb`.
+    #[clap(long)]
+    backend: StoreBackend,
+    /// Directory of the new database.  Must not contain a database.
+    #[clap(long)]
+    destination: PathBuf,
+    /// Amount of data written to the new database in a single transaction.
+    #[clap(long, default_value = "64MiB")]
+    batch_size: bytesize::ByteSize,
+}
+
+impl MigrateBackendCommand {
+    /// Copies all the data of the hot database to a new database with the
+    /// given backend.  The values of reference counted columns are copied
+    /// together with their refcounts.
+    pub(crate) fn run(
+        &self,
+        home_dir: &Path,
+        archive: bool,
+        store_config: &StoreConfig,
+    ) -> anyhow::Result<()> {
+        let source = NodeStorage::opener(home_dir, archive, store_config, None)
+            .open_in_mode(Mode::ReadOnly)?
+            .into_inner(Temperature::Hot);
+
+        let mut destination_config = store_config.clone();
+        destination_config.backend = self.backend;
+        destination_config.path = Some(self.destination.clone());
+        let destination = NodeStorage::opener(home_dir, archive, &destination_config, None)
+            .open_in_mode(Mode::Create)?
+            .into_inner(Temperature::Hot);
+
+        for col in DBCol::iter() {
+            let (items, bytes) =
+                copy_column(source.as_ref(), destination.as_ref(), col, self.batch_size)?;
+            if items > 0 {
+                eprintln!("Copied {col}: {items} items, {}", bytesize::ByteSize(bytes));
+            }
+        }
+        destination.flush()?;
+
+        eprintln!(
+            "Done! To use the new database, set `store.path` to {} and `store.backend` to {:?} in config.json",
+            self.destination.display(),
+            self.backend
+        );
+        Ok(())
+    }
+}
+
+/// Copies raw items of a column.  Returns the number of items and bytes copied.
+fn copy_column(
+    source: &dyn Database,
+    destination: &dyn Database,
+    col: DBCol,
+    batch_size: bytesize::ByteSize,
+) -> anyhow::Result<(u64, u64)> {
+    let mut items = 0;
+    let mut bytes = 0;
+    let mut transaction = DBTransaction::new();
+    let mut transaction_bytes = 0;
+    for item in source.iter_raw_bytes(col) {
+        let (key, value) = item?;
+        let item_bytes = (key.len() + value.len()) as u64;
+        transaction.set(col, key.into_vec(), value.into_vec());
+        items += 1;
+        bytes += item_bytes;
+        transaction_bytes += item_bytes;
+        if transaction_bytes >= batch_size.as_u64() {
+            destination.write(std::mem::take(&mut transaction))?;
+            transaction_bytes = 0;
+        }
+    }
+    destination.write(transaction)?;
+    Ok((items, bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::migrate_backend::MigrateBackendCommand;
+    use near_store::db::refcount::decode_value_with_rc;
+    use near_store::{DBCol, Mode, NodeStorage, StoreBackend, StoreConfig, Temperature};
+
+    /// Populates a RocksDB database, migrates it to redb and back and checks
+    /// that the data, including refcounts, is the same in all of them.
+    #[test]
+    fn test_migrate_backend() {
+        let home_dir = tempfile::tempdir().unwrap();
+        let store_config = StoreConfig::test_config();
+        let opener = NodeStorage::opener(home_dir.path(), false, &store_config, None);
+
+        {
+            let store = opener.open().unwrap().get_hot_store();
+            let mut store_update = store.store_update();
+            for key in 0..10u8 {
+                store_update.insert(DBCol::Block, vec![key], vec![key; 100]);
+            }
+            store_update.increment_refcount(DBCol::State, &[1], &[42]);
+            store_update.increment_refcount(DBCol::State, &[1], &[42]);
+            store_update.commit().unwrap();
+        }
+
+        let redb_dir = home_dir.path().join("redb");
+        let cmd = MigrateBackendCommand {
+            backend: StoreBackend::Redb,
+            destination: redb_dir.clone(),
+            batch_size: bytesize::ByteSize(256),
+        };
+        cmd.run(home_dir.path(), false, &store_config).unwrap();
+
+        let rocksdb_dir = home_dir.path().join("rocksdb");
+        let redb_config = StoreConfig { path: Some(redb_dir), ..StoreConfig::test_config() };
+        let cmd = MigrateBackendCommand {
+            backend: StoreBackend::RocksDb,
+            destination: rocksdb_dir.clone(),
+            batch_size: bytesize::ByteSize(256),
+        };
+        cmd.run(home_dir.path(), false, &redb_config).unwrap();
+
+        let rocksdb_config = StoreConfig { path: Some(rocksdb_dir), ..StoreConfig::test_config() };
+        for config in [&store_config, &redb_config, &rocksdb_config] {
+            let db = NodeStorage::opener(home_dir.path(), false, config, None)
+                .open_in_mode(Mode::ReadOnly)
+                .unwrap()
+                .into_inner(Temperature::Hot);
+            for key in 0..10u8 {
+                let value = db.get_raw_bytes(DBCol::Block, &[key]).unwrap().unwrap();
+                assert_eq!(value.as_slice(), &[key; 100][..]);
+            }
+            let state = db.get_raw_bytes(DBCol::State, &[1]).unwrap().unwrap();
+            assert_eq!(decode_value_with_rc(&state), (Some(&[42][..]), 2));
+        }
+    }
+}
@@ -0,0 +1,132 @@
+use crate::utils::{open_rocksdb, resolve_column};
+use bytesize::ByteSize;
+use clap::Parser;
+use near_store::db::{Database, RocksDB};
+use near_store::DBCol;
+use rand::Rng;
+use std::path::Path;
+use std::time::{Duration, Instant};
+use strum::IntoEnumIterator;
+
+/// Rewrites SST files of the given columns so that they use the compression
+/// and table options currently set in `store.column_options`, and reports how
+/// the size of the columns and the latency of reads changed.
+#[derive(Parser)]
+pub(crate) struct RecompressCommand {
+    /// Columns to recompress.  By default, all columns.
+    #[arg(long, use_value_delimiter = true, value_delimiter = ',')]
+    column: Vec<String>,
+    /// Number of random keys of each column read before and after
+    /// recompression to measure read latency.  Zero skips the measurement.
+    #[arg(long, default_value_t = 1000)]
+    samples: usize,
+}
+
+struct ColumnReport {
+    col: DBCol,
+    size_before: u64,
+    size_after: u64,
+    duration: Duration,
+    latency_before: Option<Duration>,
+    latency_after: Option<Duration>,
+}
+
+impl RecompressCommand {
+    pub(crate) fn run(&self, home: &Path) -> anyhow::Result<()> {
+        let columns = if self.column.is_empty() {
+            DBCol::iter().collect::<Vec<_>>()
+        } else {
+            self.column.iter().map(|name| resolve_column(name)).collect::<anyhow::Result<_>>()?
+        };
+
+        let mut reports = Vec::new();
+        for col in columns {
+            // The database is reopened for every measurement so that reads
+            // don't hit blocks cached by the previous step.
+            let (size_before, keys) = {
+                let db = open_rocksdb(home, near_store::Mode::ReadOnly)?;
+                (db.column_sst_size(col)?, sample_keys(&db, col, self.samples)?)
+            };
+            let latency_before = {
+                let db = open_rocksdb(home, near_store::Mode::ReadOnly)?;
+                measure_reads(&db, col, &keys)?
+            };
+            eprintln!("Recompressing {col} ({})...", ByteSize::b(size_before));
+            let start = Instant::now();
+            {
+                let db = open_rocksdb(home, near_store::Mode::ReadWriteExisting)?;
+                db.recompress_column(col)?;
+            }
+            let duration = start.elapsed();
+            let db = open_rocksdb(home, near_store::Mode::ReadOnly)?;
+            let size_after = db.column_sst_size(col)?;
+            let latency_after = measure_reads(&db, col, &keys)?;
+            reports.push(ColumnReport {
+                col,
+                size_before,
+                size_after,
+                duration,
+                latency_before,
+                latency_after,
+            });
+        }
+
+        println!(
+            "{:<40} {:>12} {:>12} {:>8} {:>10} {:>12} {:>12}",
+            "column", "before", "after", "ratio", "took", "read before", "read after"
+        );
+        for report in &reports {
+            let ratio = if report.size_before == 0 {
+                1.0
+            } else {
+                report.size_after as f64 / report.size_before as f64
+            };
+            let format_latency =
+                |latency: Option<Duration>| latency.map_or("-".to_string(), |l| format!("{l:?}"));
+            println!(
+                "{:<40} {:>12} {:>12} {:>8.3} {:>10} {:>12} {:>12}",
+                report.col.to_string(),
+                ByteSize::b(report.size_before).to_string(),
+                ByteSize::b(report.size_after).to_string(),
+                ratio,
+                format!("{:.1?}", report.duration),
+                format_latency(report.latency_before),
+                format_latency(report.latency_after),
+            );
+        }
+        Ok(())
+    }
+}
+
+/// Picks up to `samples` keys of the column by seeking to random positions.
+fn sample_keys(db: &RocksDB, col: DBCol, samples: usize) -> anyhow::Result<Vec<Box<[u8]>>> {
+    let mut rng = rand::thread_rng();
+    let mut keys = Vec::with_capacity(samples);
+    for _ in 0..samples {
+        let seek_key: [u8; 8] = rng.gen();
+        let item = db.iter_range(col, Some(&seek_key), None).next();
+        let item = match item {
+            Some(item) => Some(item),
+            // Seeked past the last key, wrap around.
+            None => db.iter_range(col, None, None).next(),
+        };
+        match item.transpose()? {
+            Some((key, _)) => keys.push(key),
+            None => break,
+        }
+    }
+    Ok(keys)
+}
+
+/// Returns the average time of reading the given keys or `None` if there are
+/// no keys.
+fn measure_reads(db: &RocksDB, col: DBCol, keys: &[Box<[u8]>]) -> anyhow::Result<Option<Duration>> {
+    if keys.is_empty() {
+        return Ok(None);
+    }
+    let start = Instant::now();
+    for key in keys {
+        db.get_raw_bytes(col, key)?;
+    }
+    Ok(Some(start.elapsed() / keys.len() as u32))
+}
@@ -3,7 +3,7 @@ use anyhow::anyhow;
 use borsh::BorshDeserialize;
 use near_client::sync::external::{
     create_bucket_readonly, external_storage_location, external_storage_location_directory,
-    get_num_parts_from_filename, ExternalConnection, StateFileType,
+    get_num_parts_from_filename, ExternalConnection, S3BucketLocation, StateFileType,
 };
 use near_jsonrpc::client::{new_client, JsonRpcClient};
 use near_primitives::hash::CryptoHash;
@@ -219,7 +219,8 @@ fn create_external_connection(
     if let Some(root_dir) = root_dir {
         ExternalConnection::Filesystem { root_dir }
     } else if let (Some(bucket), Some(region)) = (bucket, region) {
-        let bucket = create_bucket_readonly(&bucket, &region, Duration::from_secs(5))
+        let location = S3BucketLocation::aws(&bucket, &region);
+        let bucket = create_bucket_readonly(&location, Duration::from_secs(5), None)
             .expect("Failed to create an S3 bucket");
         ExternalConnection::S3 { bucket: Arc::new(bucket) }
     } else if let Some(bucket) = gcs_bucket {
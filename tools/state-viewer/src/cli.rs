@@ -2,6 +2,9 @@ use crate::commands::*;
 use crate::congestion_control::CongestionControlCmd;
 use crate::contract_accounts::ContractAccountFilter;
 use crate::rocksdb_stats::get_rocksdb_stats;
+use crate::state_diff::StateDiffCmd;
+#[cfg(feature = "state_dump_columnar")]
+use crate::state_dump_columnar::DumpStateColumnarCmd;
 use crate::trie_iteration_benchmark::TrieIterationBenchmarkCmd;
 
 use crate::latest_witnesses::StateWitnessCmd;
@@ -61,6 +64,12 @@ pub enum StateViewerSubCommand {
     /// Writes state to a remote redis server.
     #[clap(alias = "dump_state_redis")]
     DumpStateRedis(DumpStateRedisCmd),
+    /// Writes accounts, access keys, contract data, contract code hashes and
+    /// postponed receipts at a block to Parquet or Arrow files, one per
+    /// record type and shard.
+    #[cfg(feature = "state_dump_columnar")]
+    #[clap(alias = "dump_state_columnar")]
+    DumpStateColumnar(DumpStateColumnarCmd),
     /// Generate a file that contains all transactions from a block.
     #[clap(alias = "dump_tx")]
     DumpTx(DumpTxCmd),
@@ -87,6 +96,10 @@ pub enum StateViewerSubCommand {
     /// Dumps or applies StateChanges.
     /// Experimental tool for shard shadowing development.
     StateChanges(StateChangesCmd),
+    /// Prints keys of a shard, or of a single account, whose values differ
+    /// between the states after two blocks.
+    #[clap(alias = "state_diff")]
+    StateDiff(StateDiffCmd),
     /// Dump or apply state parts.
     StateParts(StatePartsCmd),
     /// Iterates over the Flat State and prints some statistics.
@@ -159,6 +172,8 @@ impl StateViewerSubCommand {
             StateViewerSubCommand::DumpCode(cmd) => cmd.run(home_dir, near_config, store),
             StateViewerSubCommand::DumpState(cmd) => cmd.run(home_dir, near_config, store),
             StateViewerSubCommand::DumpStateRedis(cmd) => cmd.run(home_dir, near_config, store),
+            #[cfg(feature = "state_dump_columnar")]
+            StateViewerSubCommand::DumpStateColumnar(cmd) => cmd.run(home_dir, near_config, store),
             StateViewerSubCommand::DumpTx(cmd) => cmd.run(home_dir, near_config, store),
             StateViewerSubCommand::EpochInfo(cmd) => cmd.run(near_config, store),
             StateViewerSubCommand::EpochAnalysis(cmd) => cmd.run(near_config, store),
@@ -169,6 +184,7 @@ impl StateViewerSubCommand {
             StateViewerSubCommand::ScanDbColumn(cmd) => cmd.run(store),
             StateViewerSubCommand::State => state(home_dir, near_config, store),
             StateViewerSubCommand::StateChanges(cmd) => cmd.run(home_dir, near_config, store),
+            StateViewerSubCommand::StateDiff(cmd) => cmd.run(near_config, store),
             StateViewerSubCommand::StateParts(cmd) => cmd.run(home_dir, near_config, store),
             StateViewerSubCommand::StateStats(cmd) => cmd.run(home_dir, near_config, store),
             StateViewerSubCommand::ViewChain(cmd) => cmd.run(near_config, store),
@@ -11,7 +11,10 @@ mod latest_witnesses;
 mod rocksdb_stats;
 mod scan_db;
 mod state_changes;
+mod state_diff;
 mod state_dump;
+#[cfg(feature = "state_dump_columnar")]
+mod state_dump_columnar;
 mod state_parts;
 mod trie_iteration_benchmark;
 mod tx_dump;
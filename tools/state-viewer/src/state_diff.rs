@@ -0,0 +1,116 @@
+//! Difference between the states of a shard after two blocks.
+
+use anyhow::Context;
+use near_chain::{ChainStore, ChainStoreAccess};
+use near_epoch_manager::{EpochManager, EpochManagerAdapter};
+use near_primitives::hash::CryptoHash;
+use near_primitives::shard_layout::ShardUId;
+use near_primitives::state_record::StateRecord;
+use near_primitives::types::{AccountId, BlockHeight, ShardId, StateRoot};
+use near_primitives_core::serialize::to_base64;
+use near_store::{Store, Trie, TrieDBStorage, TrieDiffLimits};
+use nearcore::NearConfig;
+use std::sync::Arc;
+
+#[derive(clap::Parser)]
+pub struct StateDiffCmd {
+    /// Height of the block after which the old state is taken.
+    #[clap(long)]
+    from_height: BlockHeight,
+    /// Height of the block after which the new state is taken.  Defaults to
+    /// the final head.
+    #[clap(long)]
+    to_height: Option<BlockHeight>,
+    /// Shard to compare.  Required unless `--account-id` is set.
+    #[clap(long)]
+    shard_id: Option<ShardId>,
+    /// Only print changes of keys belonging to this account.
+    #[clap(long)]
+    account_id: Option<AccountId>,
+    /// Stop after printing this many changes.
+    #[clap(long)]
+    limit: Option<usize>,
+}
+
+impl StateDiffCmd {
+    pub fn run(self, near_config: NearConfig, store: Store) {
+        self.diff(near_config, store).unwrap();
+    }
+
+    fn diff(self, near_config: NearConfig, store: Store) -> anyhow::Result<()> {
+        let chain_store = ChainStore::new(
+            store.clone(),
+            near_config.genesis.config.genesis_height,
+            near_config.client_config.save_trie_changes,
+        );
+        let epoch_manager =
+            EpochManager::new_arc_handle(store.clone(), &near_config.genesis.config);
+        let from_block_hash = chain_store.get_block_hash_by_height(self.from_height)?;
+        let to_block_hash = match self.to_height {
+            Some(height) => chain_store.get_block_hash_by_height(height)?,
+            None => chain_store.final_head()?.last_block_hash,
+        };
+
+        let shard_uid = |block_hash: &CryptoHash| -> anyhow::Result<ShardUId> {
+            let epoch_id = epoch_manager.get_epoch_id(block_hash)?;
+            let shard_id = match (&self.account_id, self.shard_id) {
+                (Some(account_id), shard_id) => {
+                    let account_shard_id =
+                        epoch_manager.account_id_to_shard_id(account_id, &epoch_id)?;
+                    if shard_id.is_some_and(|shard_id| shard_id != account_shard_id) {
+                        anyhow::bail!("{account_id} does not belong to shard {shard_id:?}");
+                    }
+                    account_shard_id
+                }
+                (None, Some(shard_id)) => shard_id,
+                (None, None) => anyhow::bail!("either --shard-id or --account-id must be set"),
+            };
+            Ok(epoch_manager.shard_id_to_uid(shard_id, &epoch_id)?)
+        };
+        let shard_uid_at_from = shard_uid(&from_block_hash)?;
+        let shard_uid_at_to = shard_uid(&to_block_hash)?;
+        if shard_uid_at_from != shard_uid_at_to {
+            anyhow::bail!(
+                "shard layout changed between the blocks: {shard_uid_at_from} vs {shard_uid_at_to}"
+            );
+        }
+        let state_root = |block_hash: &CryptoHash| -> anyhow::Result<StateRoot> {
+            let chunk_extra =
+                chain_store.get_chunk_extra(block_hash, &shard_uid_at_from).with_context(|| {
+                    format!("no chunk extra for {shard_uid_at_from} at {block_hash}")
+                })?;
+            Ok(*chunk_extra.state_root())
+        };
+        let from_state_root = state_root(&from_block_hash)?;
+        let to_state_root = state_root(&to_block_hash)?;
+        println!("Diff of {shard_uid_at_from} from {from_block_hash} ({from_state_root}) to {to_block_hash} ({to_state_root})");
+
+        let storage = TrieDBStorage::new(store, shard_uid_at_from);
+        let trie = Trie::new(Arc::new(storage), from_state_root, None);
+        let limits = TrieDiffLimits { max_changes: self.limit, max_bytes: None };
+        let diff = match &self.account_id {
+            Some(account_id) => trie.diff_for_account(&to_state_root, account_id, &[], limits)?,
+            None => trie.diff(&to_state_root, &[], &[], limits)?,
+        };
+        for change in &diff.changes {
+            if let Some(old_value) = &change.old_value {
+                println!("- {}", format_record(&change.key, old_value));
+            }
+            if let Some(new_value) = &change.new_value {
+                println!("+ {}", format_record(&change.key, new_value));
+            }
+        }
+        println!("{} changed keys", diff.changes.len());
+        if let Some(next_key) = &diff.next_key {
+            println!("Stopped at the limit, next changed key is {}", to_base64(next_key));
+        }
+        Ok(())
+    }
+}
+
+fn format_record(key: &[u8], value: &[u8]) -> String {
+    match StateRecord::from_raw_key_value(key.to_vec(), value.to_vec()) {
+        Some(record) => record.to_string(),
+        None => format!("{} = {}", to_base64(key), to_base64(value)),
+    }
+}
@@ -0,0 +1,756 @@
+//! Dump of the state at a given block to columnar files for analytics.
+//!
+//! Each record type gets its own table and each table is written as one file
+//! per shard to `<output>/<table>/<shard_uid>.<format>`.  Balances are stored
+//! as `Decimal128(38, 0)` and hashes and keys as their usual string forms so
+//! the files can be queried with standard tools without knowing borsh.
+
+use anyhow::Context;
+use arrow::array::{
+    ArrayBuilder, ArrayRef, BinaryBuilder, Decimal128Builder, ListBuilder, StringBuilder,
+    UInt64Builder,
+};
+use arrow::datatypes::{DataType, Field, Schema, SchemaRef};
+use arrow::record_batch::RecordBatch;
+use near_chain::{ChainStore, ChainStoreAccess};
+use near_epoch_manager::{EpochManager, EpochManagerAdapter};
+use near_primitives::account::{AccessKey, AccessKeyPermission, Account};
+use near_primitives::hash::{hash, CryptoHash};
+use near_primitives::receipt::Receipt;
+use near_primitives::state::FlatStateValue;
+use near_primitives::state_record::StateRecord;
+use near_primitives::trie_key::col;
+use near_primitives::trie_key::trie_key_parsers::parse_account_id_from_contract_code_key;
+use near_primitives::types::{AccountId, Balance, BlockHeight};
+use near_store::flat::{store_helper, FlatStorageStatus};
+use near_store::{ShardUId, Store, Trie, TrieDBStorage, TrieStorage};
+use nearcore::NearConfig;
+use rayon::prelude::{IntoParallelIterator, ParallelIterator};
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+#[derive(Clone, Copy, clap::ValueEnum)]
+pub enum ColumnarFormat {
+    /// Apache Parquet files compressed with zstd.
+    Parquet,
+    /// Arrow IPC files.
+    Arrow,
+}
+
+impl ColumnarFormat {
+    fn extension(self) -> &'static str {
+        match self {
+            Self::Parquet => "parquet",
+            Self::Arrow => "arrow",
+        }
+    }
+}
+
+#[derive(Clone, Copy, clap::ValueEnum)]
+pub enum ColumnarSource {
+    /// Iterate the trie at the state root of the block.
+    Trie,
+    /// Iterate flat storage, whose head must be at the block.  Faster than
+    /// the trie but only possible for the flat head.
+    Flat,
+}
+
+#[derive(clap::Parser)]
+pub struct DumpStateColumnarCmd {
+    /// Height of the block after which the state is dumped.  Defaults to the
+    /// final head.
+    #[clap(long)]
+    height: Option<BlockHeight>,
+    /// Directory to write the tables to.  Defaults to `state_columnar` in
+    /// the home directory.
+    #[clap(long)]
+    output: Option<PathBuf>,
+    #[clap(long, value_enum, default_value = "parquet")]
+    format: ColumnarFormat,
+    #[clap(long, value_enum, default_value = "trie")]
+    source: ColumnarSource,
+    /// Maximum number of rows in a record batch.
+    #[clap(long, default_value_t = 65536)]
+    batch_size: usize,
+}
+
+impl DumpStateColumnarCmd {
+    pub fn run(self, home_dir: &Path, near_config: NearConfig, store: Store) {
+        self.dump(home_dir, near_config, store).unwrap();
+    }
+
+    fn dump(self, home_dir: &Path, near_config: NearConfig, store: Store) -> anyhow::Result<()> {
+        let chain_store = ChainStore::new(
+            store.clone(),
+            near_config.genesis.config.genesis_height,
+            near_config.client_config.save_trie_changes,
+        );
+        let block_hash = match self.height {
+            Some(height) => chain_store.get_block_hash_by_height(height)?,
+            None => chain_store.final_head()?.last_block_hash,
+        };
+        let header = chain_store.get_block_header(&block_hash)?;
+        let epoch_manager =
+            EpochManager::new_arc_handle(store.clone(), &near_config.genesis.config);
+        let shard_layout = epoch_manager.get_shard_layout(header.epoch_id())?;
+        let output = self.output.clone().unwrap_or_else(|| home_dir.join("state_columnar"));
+        println!(
+            "Dumping state after block #{} {} to {}",
+            header.height(),
+            block_hash,
+            output.display()
+        );
+        for table in TABLES {
+            std::fs::create_dir_all(output.join(table))?;
+        }
+
+        let mut shards = vec![];
+        for shard_uid in shard_layout.shard_uids() {
+            match chain_store.get_chunk_extra(&block_hash, &shard_uid) {
+                Ok(chunk_extra) => shards.push((shard_uid, *chunk_extra.state_root())),
+                Err(err) => println!("Skipping {shard_uid}, no chunk extra: {err}"),
+            }
+        }
+        let dumped = shards
+            .into_par_iter()
+            .map(|(shard_uid, state_root)| {
+                let rows = self
+                    .dump_shard(&store, &output, shard_uid, block_hash, state_root)
+                    .with_context(|| format!("failed to dump {shard_uid}"))?;
+                println!("Dumped {shard_uid}: {rows} rows");
+                Ok((shard_uid.to_string(), rows))
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+
+        let metadata = serde_json::json!({
+            "block_height": header.height(),
+            "block_hash": block_hash,
+            "epoch_id": header.epoch_id(),
+            "shards": dumped.into_iter().collect::<std::collections::BTreeMap<_, _>>(),
+        });
+        std::fs::write(output.join("metadata.json"), serde_json::to_string_pretty(&metadata)?)?;
+        Ok(())
+    }
+
+    /// Writes tables of a single shard and returns the total number of rows.
+    fn dump_shard(
+        &self,
+        store: &Store,
+        output: &Path,
+        shard_uid: ShardUId,
+        block_hash: CryptoHash,
+        state_root: CryptoHash,
+    ) -> anyhow::Result<usize> {
+        let mut dump = ShardDump::new(output, shard_uid, self.format, self.batch_size)?;
+        let trie_storage = TrieDBStorage::new(store.clone(), shard_uid);
+        match self.source {
+            ColumnarSource::Trie => {
+                let trie = Trie::new(Arc::new(trie_storage), state_root, None);
+                for item in trie.disk_iter()? {
+                    let (key, value) = item?;
+                    dump.push(key, value)?;
+                }
+            }
+            ColumnarSource::Flat => {
+                match store_helper::get_flat_storage_status(store, shard_uid)? {
+                    FlatStorageStatus::Ready(status) if status.flat_head.hash == block_hash => {}
+                    status => anyhow::bail!(
+                        "flat storage of {shard_uid} is not at block {block_hash}: {status:?}"
+                    ),
+                }
+                for item in store_helper::iter_flat_state_entries(shard_uid, store, None, None) {
+                    let (key, value) = item?;
+                    match value {
+                        // Contract code is only needed for its hash, which
+                        // the reference already has.
+                        FlatStateValue::Ref(value_ref) if key[0] == col::CONTRACT_CODE => {
+                            let account_id = parse_account_id_from_contract_code_key(&key)?;
+                            dump.contract_code.push(
+                                &account_id,
+                                value_ref.hash,
+                                value_ref.length,
+                            )?;
+                        }
+                        FlatStateValue::Ref(value_ref) => {
+                            let value = trie_storage.retrieve_raw_bytes(&value_ref.hash)?;
+                            dump.push(key, value.to_vec())?;
+                        }
+                        FlatStateValue::Inlined(value) => dump.push(key, value)?,
+                    }
+                }
+            }
+        }
+        dump.finish()
+    }
+}
+
+const TABLES: [&str; 5] =
+    ["accounts", "access_keys", "contract_data", "contract_code", "postponed_receipts"];
+
+/// Tables of a single shard being written.
+struct ShardDump {
+    accounts: TableFile<AccountsTable>,
+    access_keys: TableFile<AccessKeysTable>,
+    contract_data: TableFile<ContractDataTable>,
+    contract_code: TableFile<ContractCodeTable>,
+    postponed_receipts: TableFile<PostponedReceiptsTable>,
+}
+
+impl ShardDump {
+    fn new(
+        output: &Path,
+        shard_uid: ShardUId,
+        format: ColumnarFormat,
+        batch_size: usize,
+    ) -> anyhow::Result<Self> {
+        let path =
+            |table: &str| output.join(table).join(format!("{shard_uid}.{}", format.extension()));
+        Ok(Self {
+            accounts: TableFile::create(&path(TABLES[0]), format, batch_size)?,
+            access_keys: TableFile::create(&path(TABLES[1]), format, batch_size)?,
+            contract_data: TableFile::create(&path(TABLES[2]), format, batch_size)?,
+            contract_code: TableFile::create(&path(TABLES[3]), format, batch_size)?,
+            postponed_receipts: TableFile::create(&path(TABLES[4]), format, batch_size)?,
+        })
+    }
+
+    /// Adds a raw trie key and value to its table.  Records of other types,
+    /// e.g. delayed receipts, are skipped.
+    fn push(&mut self, key: Vec<u8>, value: Vec<u8>) -> anyhow::Result<()> {
+        let Some(record) = StateRecord::from_raw_key_value(key, value) else {
+            return Ok(());
+        };
+        match record {
+            StateRecord::Account { account_id, account } => {
+                self.accounts.push(&account_id, &account)
+            }
+            StateRecord::AccessKey { account_id, public_key, access_key } => {
+                self.access_keys.push(&account_id, &public_key.to_string(), &access_key)
+            }
+            StateRecord::Data { account_id, data_key, value } => {
+                self.contract_data.push(&account_id, data_key.as_ref(), value.as_ref())
+            }
+            StateRecord::Contract { account_id, code } => {
+                self.contract_code.push(&account_id, hash(&code), code.len() as u32)
+            }
+            StateRecord::PostponedReceipt(receipt) => self.postponed_receipts.push(&receipt),
+            StateRecord::ReceivedData { .. } | StateRecord::DelayedReceipt(_) => Ok(()),
+        }
+    }
+
+    fn finish(self) -> anyhow::Result<usize> {
+        Ok(self.accounts.finish()?
+            + self.access_keys.finish()?
+            + self.contract_data.finish()?
+            + self.contract_code.finish()?
+            + self.postponed_receipts.finish()?)
+    }
+}
+
+/// Column builders of a table.
+trait Table: Default {
+    fn schema() -> Schema;
+    fn finish(&mut self) -> Vec<ArrayRef>;
+}
+
+enum TableWriter {
+    Parquet(parquet::arrow::ArrowWriter<File>),
+    Arrow(arrow::ipc::writer::FileWriter<File>),
+}
+
+/// A table written to a file in batches of up to `batch_size` rows.
+struct TableFile<T> {
+    table: T,
+    schema: SchemaRef,
+    writer: TableWriter,
+    batch_size: usize,
+    batch_rows: usize,
+    rows: usize,
+}
+
+impl<T: Table> TableFile<T> {
+    fn create(path: &Path, format: ColumnarFormat, batch_size: usize) -> anyhow::Result<Self> {
+        let schema = SchemaRef::new(T::schema());
+        let file = File::create(path).with_context(|| format!("failed to create {path:?}"))?;
+        let writer = match format {
+            ColumnarFormat::Parquet => {
+                let props = parquet::file::properties::WriterProperties::builder()
+                    .set_compression(parquet::basic::Compression::ZSTD(Default::default()))
+                    .build();
+                TableWriter::Parquet(parquet::arrow::ArrowWriter::try_new(
+                    file,
+                    schema.clone(),
+                    Some(props),
+                )?)
+            }
+            ColumnarFormat::Arrow => {
+                TableWriter::Arrow(arrow::ipc::writer::FileWriter::try_new(file, &schema)?)
+            }
+        };
+        Ok(Self { table: T::default(), schema, writer, batch_size, batch_rows: 0, rows: 0 })
+    }
+
+    /// Counts a row appended to the builders and writes out the batch once
+    /// it's full.
+    fn row_added(&mut self) -> anyhow::Result<()> {
+        self.batch_rows += 1;
+        self.rows += 1;
+        if self.batch_rows >= self.batch_size {
+            self.flush()?;
+        }
+        Ok(())
+    }
+
+    fn flush(&mut self) -> anyhow::Result<()> {
+        if self.batch_rows == 0 {
+            return Ok(());
+        }
+        let batch = RecordBatch::try_new(self.schema.clone(), self.table.finish())?;
+        match &mut self.writer {
+            TableWriter::Parquet(writer) => writer.write(&batch)?,
+            TableWriter::Arrow(writer) => writer.write(&batch)?,
+        }
+        self.batch_rows = 0;
+        Ok(())
+    }
+
+    /// Writes the remaining rows and the file footer.  Returns the number of
+    /// rows in the table.
+    fn finish(mut self) -> anyhow::Result<usize> {
+        self.flush()?;
+        match self.writer {
+            TableWriter::Parquet(writer) => {
+                writer.close()?;
+            }
+            TableWriter::Arrow(mut writer) => writer.finish()?,
+        }
+        Ok(self.rows)
+    }
+}
+
+fn balance_builder() -> Decimal128Builder {
+    Decimal128Builder::new().with_precision_and_scale(38, 0).unwrap()
+}
+
+fn balance_field(name: &str, nullable: bool) -> Field {
+    Field::new(name, DataType::Decimal128(38, 0), nullable)
+}
+
+/// Largest balance with at most 38 digits.  Real balances are far below it,
+/// but a `u128` may have 39 digits which `Decimal128(38, 0)` can't hold.
+const MAX_DECIMAL_BALANCE: Balance = 10u128.pow(38) - 1;
+
+fn balance_value(balance: Balance) -> anyhow::Result<i128> {
+    anyhow::ensure!(
+        balance <= MAX_DECIMAL_BALANCE,
+        "balance {balance} doesn't fit in Decimal128(38, 0)"
+    );
+    Ok(balance as i128)
+}
+
+fn finish_builder(builder: &mut impl ArrayBuilder) -> ArrayRef {
+    builder.finish()
+}
+
+struct AccountsTable {
+    account_id: StringBuilder,
+    amount: Decimal128Builder,
+    locked: Decimal128Builder,
+    code_hash: StringBuilder,
+    storage_usage: UInt64Builder,
+}
+
+impl Default for AccountsTable {
+    fn default() -> Self {
+        Self {
+            account_id: Default::default(),
+            amount: balance_builder(),
+            locked: balance_builder(),
+            code_hash: Default::default(),
+            storage_usage: Default::default(),
+        }
+    }
+}
+
+impl Table for AccountsTable {
+    fn schema() -> Schema {
+        Schema::new(vec![
+            Field::new("account_id", DataType::Utf8, false),
+            balance_field("amount", false),
+            balance_field("locked", false),
+            Field::new("code_hash", DataType::Utf8, false),
+            Field::new("storage_usage", DataType::UInt64, false),
+        ])
+    }
+
+    fn finish(&mut self) -> Vec<ArrayRef> {
+        vec![
+            finish_builder(&mut self.account_id),
+            finish_builder(&mut self.amount),
+            finish_builder(&mut self.locked),
+            finish_builder(&mut self.code_hash),
+            finish_builder(&mut self.storage_usage),
+        ]
+    }
+}
+
+impl TableFile<AccountsTable> {
+    fn push(&mut self, account_id: &AccountId, account: &Account) -> anyhow::Result<()> {
+        let table = &mut self.table;
+        table.account_id.append_value(account_id);
+        table.amount.append_value(balance_value(account.amount())?);
+        table.locked.append_value(balance_value(account.locked())?);
+        table.code_hash.append_value(account.code_hash().to_string());
+        table.storage_usage.append_value(account.storage_usage());
+        self.row_added()
+    }
+}
+
+struct AccessKeysTable {
+    account_id: StringBuilder,
+    public_key: StringBuilder,
+    nonce: UInt64Builder,
+    permission: StringBuilder,
+    allowance: Decimal128Builder,
+    receiver_id: StringBuilder,
+    method_names: ListBuilder<StringBuilder>,
+}
+
+impl Default for AccessKeysTable {
+    fn default() -> Self {
+        Self {
+            account_id: Default::default(),
+            public_key: Default::default(),
+            nonce: Default::default(),
+            permission: Default::default(),
+            allowance: balance_builder(),
+            receiver_id: Default::default(),
+            method_names: ListBuilder::new(StringBuilder::new()),
+        }
+    }
+}
+
+impl Table for AccessKeysTable {
+    fn schema() -> Schema {
+        Schema::new(vec![
+            Field::new("account_id", DataType::Utf8, false),
+            Field::new("public_key", DataType::Utf8, false),
+            Field::new("nonce", DataType::UInt64, false),
+            // Either `FullAccess` or `FunctionCall`.  The remaining columns
+            // are only set for function call keys.
+            Field::new("permission", DataType::Utf8, false),
+            balance_field("allowance", true),
+            Field::new("receiver_id", DataType::Utf8, true),
+            Field::new(
+                "method_names",
+                DataType::List(Arc::new(Field::new("item", DataType::Utf8, true))),
+                true,
+            ),
+        ])
+    }
+
+    fn finish(&mut self) -> Vec<ArrayRef> {
+        vec![
+            finish_builder(&mut self.account_id),
+            finish_builder(&mut self.public_key),
+            finish_builder(&mut self.nonce),
+            finish_builder(&mut self.permission),
+            finish_builder(&mut self.allowance),
+            finish_builder(&mut self.receiver_id),
+            finish_builder(&mut self.method_names),
+        ]
+    }
+}
+
+impl TableFile<AccessKeysTable> {
+    fn push(
+        &mut self,
+        account_id: &AccountId,
+        public_key: &str,
+        access_key: &AccessKey,
+    ) -> anyhow::Result<()> {
+        let table = &mut self.table;
+        table.account_id.append_value(account_id);
+        table.public_key.append_value(public_key);
+        table.nonce.append_value(access_key.nonce);
+        match &access_key.permission {
+            AccessKeyPermission::FullAccess => {
+                table.permission.append_value("FullAccess");
+                table.allowance.append_null();
+                table.receiver_id.append_null();
+                table.method_names.append_null();
+            }
+            AccessKeyPermission::FunctionCall(permission) => {
+                table.permission.append_value("FunctionCall");
+                table.allowance.append_option(permission.allowance.map(balance_value).transpose()?);
+                table.receiver_id.append_value(&permission.receiver_id);
+                for method_name in &permission.method_names {
+                    table.method_names.values().append_value(method_name);
+                }
+                table.method_names.append(true);
+            }
+        }
+        self.row_added()
+    }
+}
+
+#[derive(Default)]
+struct ContractDataTable {
+    account_id: StringBuilder,
+    key: BinaryBuilder,
+    value: BinaryBuilder,
+}
+
+impl Table for ContractDataTable {
+    fn schema() -> Schema {
+        Schema::new(vec![
+            Field::new("account_id", DataType::Utf8, false),
+            Field::new("key", DataType::Binary, false),
+            Field::new("value", DataType::Binary, false),
+        ])
+    }
+
+    fn finish(&mut self) -> Vec<ArrayRef> {
+        vec![
+            finish_builder(&mut self.account_id),
+            finish_builder(&mut self.key),
+            finish_builder(&mut self.value),
+        ]
+    }
+}
+
+impl TableFile<ContractDataTable> {
+    fn push(&mut self, account_id: &AccountId, key: &[u8], value: &[u8]) -> anyhow::Result<()> {
+        self.table.account_id.append_value(account_id);
+        self.table.key.append_value(key);
+        self.table.value.append_value(value);
+        self.row_added()
+    }
+}
+
+#[derive(Default)]
+struct ContractCodeTable {
+    account_id: StringBuilder,
+    code_hash: StringBuilder,
+    code_size: UInt64Builder,
+}
+
+impl Table for ContractCodeTable {
+    fn schema() -> Schema {
+        Schema::new(vec![
+            Field::new("account_id", DataType::Utf8, false),
+            Field::new("code_hash", DataType::Utf8, false),
+            Field::new("code_size", DataType::UInt64, false),
+        ])
+    }
+
+    fn finish(&mut self) -> Vec<ArrayRef> {
+        vec![
+            finish_builder(&mut self.account_id),
+            finish_builder(&mut self.code_hash),
+            finish_builder(&mut self.code_size),
+        ]
+    }
+}
+
+impl TableFile<ContractCodeTable> {
+    fn push(
+        &mut self,
+        account_id: &AccountId,
+        code_hash: CryptoHash,
+        code_size: u32,
+    ) -> anyhow::Result<()> {
+        self.table.account_id.append_value(account_id);
+        self.table.code_hash.append_value(code_hash.to_string());
+        self.table.code_size.append_value(code_size.into());
+        self.row_added()
+    }
+}
+
+#[derive(Default)]
+struct PostponedReceiptsTable {
+    receipt_id: StringBuilder,
+    predecessor_id: StringBuilder,
+    receiver_id: StringBuilder,
+    receipt: StringBuilder,
+}
+
+impl Table for PostponedReceiptsTable {
+    fn schema() -> Schema {
+        Schema::new(vec![
+            Field::new("receipt_id", DataType::Utf8, false),
+            Field::new("predecessor_id", DataType::Utf8, false),
+            Field::new("receiver_id", DataType::Utf8, false),
+            // The whole receipt as JSON.
+            Field::new("receipt", DataType::Utf8, false),
+        ])
+    }
+
+    fn finish(&mut self) -> Vec<ArrayRef> {
+        vec![
+            finish_builder(&mut self.receipt_id),
+            finish_builder(&mut self.predecessor_id),
+            finish_builder(&mut self.receiver_id),
+            finish_builder(&mut self.receipt),
+        ]
+    }
+}
+
+impl TableFile<PostponedReceiptsTable> {
+    fn push(&mut self, receipt: &Receipt) -> anyhow::Result<()> {
+        self.table.receipt_id.append_value(receipt.receipt_id().to_string());
+        self.table.predecessor_id.append_value(receipt.predecessor_id());
+        self.table.receiver_id.append_value(receipt.receiver_id());
+        self.table.receipt.append_value(serde_json::to_string(receipt)?);
+        self.row_added()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{balance_value, ColumnarFormat, ColumnarSource, DumpStateColumnarCmd};
+    use arrow::array::{Array, Decimal128Array, ListArray, StringArray, UInt64Array};
+    use arrow::datatypes::DataType;
+    use arrow::record_batch::RecordBatch;
+    use borsh::to_vec;
+    use near_crypto::{KeyType, PublicKey};
+    use near_primitives::account::{
+        AccessKey, AccessKeyPermission, Account, FunctionCallPermission,
+    };
+    use near_primitives::hash::CryptoHash;
+    use near_primitives::trie_key::TrieKey;
+    use near_primitives::version::PROTOCOL_VERSION;
+    use near_store::test_utils::{test_populate_trie, TestTriesBuilder};
+    use near_store::{ShardUId, Trie};
+    use std::path::Path;
+
+    fn read_parquet(path: &Path) -> Vec<RecordBatch> {
+        let file = std::fs::File::open(path).unwrap();
+        parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder::try_new(file)
+            .unwrap()
+            .build()
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap()
+    }
+
+    fn column<'a, T: 'static>(batch: &'a RecordBatch, name: &str) -> &'a T {
+        batch.column_by_name(name).unwrap().as_any().downcast_ref::<T>().unwrap()
+    }
+
+    /// Dumps a trie with accounts and access keys to Parquet and reads the
+    /// tables back.
+    #[test]
+    fn test_dump_parquet_round_trip() {
+        let tries = TestTriesBuilder::new().build();
+        let shard_uid = ShardUId::single_shard();
+        let max_balance = 10u128.pow(38) - 1;
+        let accounts = [("alice.near", 100, 0), ("bob.near", max_balance, 7)];
+        let public_key = PublicKey::from_seed(KeyType::ED25519, "alice.near");
+        let function_call_key = AccessKey {
+            nonce: 3,
+            permission: AccessKeyPermission::FunctionCall(FunctionCallPermission {
+                allowance: Some(max_balance),
+                receiver_id: "contract.near".to_string(),
+                method_names: vec!["foo".to_string(), "bar".to_string()],
+            }),
+        };
+        let mut changes = vec![];
+        for (account_id, amount, locked) in accounts {
+            let account =
+                Account::new(amount, locked, 0, CryptoHash::default(), 100, PROTOCOL_VERSION);
+            let key = TrieKey::Account { account_id: account_id.parse().unwrap() };
+            changes.push((key.to_vec(), Some(to_vec(&account).unwrap())));
+        }
+        for (account_id, access_key) in
+            [("alice.near", AccessKey::full_access()), ("bob.near", function_call_key)]
+        {
+            let key = TrieKey::AccessKey {
+                account_id: account_id.parse().unwrap(),
+                public_key: public_key.clone(),
+            };
+            changes.push((key.to_vec(), Some(to_vec(&access_key).unwrap())));
+        }
+        let state_root = test_populate_trie(&tries, &Trie::EMPTY_ROOT, shard_uid, changes);
+
+        let output = tempfile::tempdir().unwrap();
+        let cmd = DumpStateColumnarCmd {
+            height: None,
+            output: None,
+            format: ColumnarFormat::Parquet,
+            source: ColumnarSource::Trie,
+            batch_size: 1,
+        };
+        for table in super::TABLES {
+            std::fs::create_dir_all(output.path().join(table)).unwrap();
+        }
+        let rows = cmd
+            .dump_shard(
+                &tries.get_store(),
+                output.path(),
+                shard_uid,
+                CryptoHash::default(),
+                state_root,
+            )
+            .unwrap();
+        assert_eq!(rows, 4);
+
+        let batches = read_parquet(&output.path().join("accounts").join("s0.v0.parquet"));
+        let mut rows = vec![];
+        for batch in &batches {
+            let schema = batch.schema();
+            assert_eq!(
+                schema.field_with_name("amount").unwrap().data_type(),
+                &DataType::Decimal128(38, 0)
+            );
+            assert_eq!(
+                schema.field_with_name("locked").unwrap().data_type(),
+                &DataType::Decimal128(38, 0)
+            );
+            let account_ids = column::<StringArray>(batch, "account_id");
+            let amounts = column::<Decimal128Array>(batch, "amount");
+            let locked = column::<Decimal128Array>(batch, "locked");
+            let storage_usage = column::<UInt64Array>(batch, "storage_usage");
+            for i in 0..batch.num_rows() {
+                rows.push((
+                    account_ids.value(i).to_string(),
+                    amounts.value(i) as u128,
+                    locked.value(i) as u128,
+                    storage_usage.value(i),
+                ));
+            }
+        }
+        assert_eq!(
+            rows,
+            vec![
+                ("alice.near".to_string(), 100, 0, 100),
+                ("bob.near".to_string(), max_balance, 7, 100),
+            ]
+        );
+
+        let batches = read_parquet(&output.path().join("access_keys").join("s0.v0.parquet"));
+        let batch = arrow::compute::concat_batches(&batches[0].schema(), &batches).unwrap();
+        assert_eq!(
+            column::<StringArray>(&batch, "permission").iter().collect::<Vec<_>>(),
+            vec![Some("FullAccess"), Some("FunctionCall")]
+        );
+        let allowances = column::<Decimal128Array>(&batch, "allowance");
+        assert!(allowances.is_null(0));
+        assert_eq!(allowances.value(1) as u128, max_balance);
+        let method_names = column::<ListArray>(&batch, "method_names");
+        assert!(method_names.is_null(0));
+        let method_names = method_names.value(1);
+        let method_names = method_names.as_any().downcast_ref::<StringArray>().unwrap();
+        assert_eq!(method_names.iter().collect::<Vec<_>>(), vec![Some("foo"), Some("bar")]);
+    }
+
+    /// `u128` balances with 39 digits don't fit in `Decimal128(38, 0)`.
+    #[test]
+    fn test_balance_out_of_decimal_range() {
+        assert_eq!(balance_value(10u128.pow(38) - 1).unwrap(), 10i128.pow(38) - 1);
+        assert!(balance_value(10u128.pow(38)).is_err());
+        assert!(balance_value(i128::MAX as u128).is_err());
+        assert!(balance_value(u128::MAX).is_err());
+    }
+}
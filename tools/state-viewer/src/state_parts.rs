@@ -4,7 +4,7 @@ use near_chain::{Chain, ChainGenesis, ChainStoreAccess, DoomslugThresholdMode};
 use near_client::sync::external::{
     create_bucket_readonly, create_bucket_readwrite, external_storage_location,
     external_storage_location_directory, get_num_parts_from_filename, ExternalConnection,
-    StateFileType,
+    S3BucketLocation, StateFileType,
 };
 use near_client::sync::state::StateSync;
 use near_epoch_manager::shard_tracker::{ShardTracker, TrackedConfig};
@@ -215,10 +215,11 @@ fn create_external_connection(
     if let Some(root_dir) = root_dir {
         ExternalConnection::Filesystem { root_dir }
     } else if let (Some(bucket), Some(region)) = (bucket, region) {
+        let location = S3BucketLocation::aws(&bucket, &region);
         let bucket = match mode {
-            Mode::Readonly => create_bucket_readonly(&bucket, &region, Duration::from_secs(5)),
+            Mode::Readonly => create_bucket_readonly(&location, Duration::from_secs(5), None),
             Mode::Readwrite => {
-                create_bucket_readwrite(&bucket, &region, Duration::from_secs(5), credentials_file)
+                create_bucket_readwrite(&location, Duration::from_secs(5), credentials_file)
             }
         }
         .expect("Failed to create an S3 bucket");